@@ -801,7 +801,7 @@ mod tests {
     use crate::merkle_tree::MerkleTreeRead;
 
     use crate::sha3::Sha3Algorithm;
-    use crate::AppendMerkleTree;
+    use crate::{Algorithm, AppendMerkleTree};
     use ethereum_types::H256;
 
     #[test]
@@ -891,6 +891,37 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_node_proof() {
+        let mut leaves = vec![H256::zero()];
+        for _ in 0..7 {
+            leaves.push(H256::random());
+        }
+        let merkle = AppendMerkleTree::<H256, Sha3Algorithm>::new(leaves.clone(), 0, None);
+
+        // node of leaves [4, 8) at height 2
+        let node = Sha3Algorithm::parent(
+            &Sha3Algorithm::parent(&leaves[4], &leaves[5]),
+            &Sha3Algorithm::parent(&leaves[6], &leaves[7]),
+        );
+        let proof = merkle.gen_proof(4).unwrap();
+        assert!(proof.validate_node::<Sha3Algorithm>(&node, 2, 4).is_ok());
+        assert!(proof
+            .validate_node::<Sha3Algorithm>(&leaves[4], 0, 4)
+            .is_ok());
+        assert!(proof
+            .validate_node::<Sha3Algorithm>(&merkle.root(), 3, 4)
+            .is_err());
+        assert!(proof
+            .validate_node::<Sha3Algorithm>(&H256::random(), 2, 4)
+            .is_err());
+        assert!(proof.validate_node::<Sha3Algorithm>(&node, 2, 0).is_err());
+
+        // proof of leaf that is not the left-most one of the node
+        let proof = merkle.gen_proof(5).unwrap();
+        assert!(proof.validate_node::<Sha3Algorithm>(&node, 2, 5).is_err());
+    }
+
     fn verify(data: &[H256], merkle: &mut AppendMerkleTree<H256, Sha3Algorithm>) {
         for (i, item) in data.iter().enumerate() {
             let proof = merkle.gen_proof(i + 1).unwrap();
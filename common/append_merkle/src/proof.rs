@@ -68,6 +68,41 @@ impl<T: HashElement> Proof<T> {
         Ok(())
     }
 
+    /// Verifies that `node` is in the tree at `height` above the leaves, where this proof is
+    /// generated for the left-most leaf of `node` at `position`. The proof item is not
+    /// verified, so that the proof could be checked without the data below `node`.
+    pub fn validate_node<A: Algorithm<T>>(
+        &self,
+        node: &T,
+        height: usize,
+        position: usize,
+    ) -> Result<()> {
+        if self.lemma.len() != self.path.len() + 2
+            || self.path.len() < height
+            || self.path.len() >= usize::BITS as usize
+        {
+            bail!("Invalid proof");
+        }
+        if position != self.position() {
+            bail!("Proof position mismatch");
+        }
+        if self.path[..height].iter().any(|is_left| !is_left) {
+            bail!("Node position not aligned");
+        }
+
+        let mut h = node.clone();
+        for i in height..self.path.len() {
+            h = if self.path[i] {
+                A::parent(&h, &self.lemma[i + 1])
+            } else {
+                A::parent(&self.lemma[i + 1], &h)
+            };
+        }
+        ensure!(h == self.root(), "Invalid node proof");
+
+        Ok(())
+    }
+
     /// Returns the path of this proof.
     pub fn path(&self) -> &[bool] {
         &self.path
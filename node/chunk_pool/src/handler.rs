@@ -88,6 +88,9 @@ impl ChunkPoolHandler {
 
         // always remove file from pool after transaction finalized
         self.mem_pool.remove_file(&id.root).await;
+        if let Err(e) = self.mem_pool.remove_upload_session(&id.root).await {
+            warn!(?id, %e, "Failed to remove upload session");
+        }
 
         let msg = NetworkMessage::AnnounceLocalFile { tx_id: id.tx_id };
         if let Err(e) = self.sender.send(msg) {
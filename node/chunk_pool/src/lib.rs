@@ -5,7 +5,7 @@ mod handler;
 mod mem_pool;
 
 pub use handler::{ChunkPoolHandler, ChunkPoolMessage};
-pub use mem_pool::{FileID, MemoryChunkPool, SegmentInfo};
+pub use mem_pool::{FileID, MemoryChunkPool, SegmentInfo, UploadProgress, UploadSessionRecord};
 
 use std::sync::Arc;
 use std::time::Duration;
@@ -17,6 +17,10 @@ pub struct Config {
     pub max_cached_chunks_all: usize,
     pub max_writings: usize,
     pub expiration_time_secs: u64,
+    /// Expiration time to persist upload sessions since the last segment uploaded.
+    pub session_expiration_time_secs: u64,
+    /// Maximum number of persisted upload sessions.
+    pub max_upload_sessions: usize,
     pub shard_config: ShardConfig,
 }
 
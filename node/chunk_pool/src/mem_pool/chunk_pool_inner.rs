@@ -1,6 +1,7 @@
 use super::chunk_cache::{ChunkPoolCache, MemoryCachedFile};
 use super::chunk_write_control::ChunkPoolWriteCtrl;
-use super::FileID;
+use super::upload_session::{UploadSessionRecord, UploadSessionStore};
+use super::{FileID, UploadProgress};
 use crate::handler::ChunkPoolMessage;
 use crate::Config;
 use anyhow::{anyhow, bail, Result};
@@ -10,10 +11,14 @@ use shared_types::{
     bytes_to_chunks, compute_segment_size, ChunkArray, DataRoot, FileProof, Transaction, CHUNK_SIZE,
 };
 use std::sync::Arc;
+use std::time::Duration;
 use storage_async::{ShardConfig, Store};
-use tokio::sync::broadcast::{error::RecvError, Receiver};
+use tokio::sync::broadcast::{self, error::RecvError, Receiver};
 use tokio::sync::mpsc::UnboundedSender;
 
+const UPLOAD_PROGRESS_CHANNEL_CAPACITY: usize = 1024;
+const UPLOAD_SESSION_GC_INTERVAL: Duration = Duration::from_secs(60);
+
 struct Inner {
     config: Config,
     segment_cache: ChunkPoolCache,
@@ -84,6 +89,9 @@ pub struct MemoryChunkPool {
     inner: Mutex<Inner>,
     log_store: Arc<Store>,
     sender: UnboundedSender<ChunkPoolMessage>,
+    progress_sender: broadcast::Sender<UploadProgress>,
+    sessions: UploadSessionStore,
+    session_expiration_time_secs: u64,
 }
 
 impl MemoryChunkPool {
@@ -92,13 +100,32 @@ impl MemoryChunkPool {
         log_store: Arc<Store>,
         sender: UnboundedSender<ChunkPoolMessage>,
     ) -> Self {
+        let (progress_sender, _) = broadcast::channel(UPLOAD_PROGRESS_CHANNEL_CAPACITY);
+
         MemoryChunkPool {
             inner: Mutex::new(Inner::new(config)),
-            log_store,
+            log_store: log_store.clone(),
             sender,
+            progress_sender,
+            sessions: UploadSessionStore::new(
+                log_store,
+                config.max_upload_sessions,
+                config.max_cached_chunks_all,
+            ),
+            session_expiration_time_secs: config.session_expiration_time_secs,
         }
     }
 
+    /// Subscribes the upload progress of all files in the pool.
+    pub fn subscribe_upload_progress(&self) -> Receiver<UploadProgress> {
+        self.progress_sender.subscribe()
+    }
+
+    fn notify_upload_progress(&self, progress: UploadProgress) {
+        // Sending only fails when there is no subscriber, which is fine.
+        let _ = self.progress_sender.send(progress);
+    }
+
     pub fn validate_segment_size(&self, segment: &[u8]) -> Result<()> {
         if segment.is_empty() {
             bail!("data is empty");
@@ -111,15 +138,67 @@ impl MemoryChunkPool {
         Ok(())
     }
 
-    pub async fn cache_chunks(&self, seg_info: SegmentInfo) -> Result<()> {
+    pub async fn cache_chunks(&self, seg_info: SegmentInfo, file_size: usize) -> Result<()> {
         let root = seg_info.root;
-        debug!("cache_chunks, root={:?} index={}", root, seg_info.seg_index);
-        let should_flush = self
-            .inner
-            .lock()
+        let seg_index = seg_info.seg_index;
+        let chunks_per_segment = seg_info.chunks_per_segment;
+        debug!("cache_chunks, root={:?} index={}", root, seg_index);
+
+        // Drops the persisted session if uploaded with a different file or segment size.
+        if let Some(record) = self.sessions.get(&root).await? {
+            if !record.matches(file_size, chunks_per_segment) {
+                debug!(%root, "Remove mismatched upload session");
+                self.remove_cached_file(&root).await;
+                self.sessions.remove(&root).await?;
+            }
+        }
+
+        // Segments may be garbage collected from memory or lost due to node restart.
+        if !self.check_already_has_cache(&root).await {
+            self.restore_upload_session(&root).await?;
+        }
+
+        let (chunks, proof) = (
+            ChunkArray {
+                data: seg_info.seg_data.clone(),
+                start_index: (seg_index * chunks_per_segment) as u64,
+            },
+            seg_info.seg_proof.clone(),
+        );
+        let (should_flush, uploaded_seg_num) = {
+            let mut inner = self.inner.lock().await;
+            let should_flush = inner.segment_cache.cache_segment(seg_info)?;
+            let uploaded_seg_num = inner
+                .segment_cache
+                .get_file(&root)
+                .map_or(0, |file| file.segments.len());
+            (should_flush, uploaded_seg_num)
+        };
+
+        // the segment is still cached in memory if failed to persist, and the upload
+        // could not be resumed after restart only
+        if let Err(e) = self
+            .sessions
+            .put_cached_segment(
+                &root,
+                seg_index,
+                file_size,
+                chunks_per_segment,
+                chunks,
+                proof,
+            )
             .await
-            .segment_cache
-            .cache_segment(seg_info)?;
+        {
+            warn!(%root, %seg_index, %e, "Failed to persist upload session");
+        }
+
+        self.notify_upload_progress(UploadProgress {
+            root,
+            tx_seq: None,
+            seg_index,
+            uploaded_seg_num,
+            total_seg_num: None,
+        });
 
         // store and finalize the cached file if completed
         if should_flush {
@@ -205,12 +284,38 @@ impl MemoryChunkPool {
             }
         }
 
-        let all_uploaded = self
-            .inner
-            .lock()
+        let (all_uploaded, uploaded_seg_num) = {
+            let mut inner = self.inner.lock().await;
+            let all_uploaded = inner
+                .write_control
+                .on_write_succeeded(&seg_info.root, seg_info.seg_index);
+            let uploaded_seg_num = inner
+                .write_control
+                .get_file(&seg_info.root)
+                .map_or(total_segments, |file| file.uploaded_seg_num());
+            (all_uploaded, uploaded_seg_num)
+        };
+
+        if let Err(e) = self
+            .sessions
+            .on_segment_written(
+                &seg_info.root,
+                seg_info.seg_index,
+                file_size,
+                seg_info.chunks_per_segment,
+            )
             .await
-            .write_control
-            .on_write_succeeded(&seg_info.root, seg_info.seg_index);
+        {
+            warn!(root = %seg_info.root, seg_index = %seg_info.seg_index, %e, "Failed to persist upload session");
+        }
+
+        self.notify_upload_progress(UploadProgress {
+            root: seg_info.root,
+            tx_seq: Some(file_id.tx_id.seq),
+            seg_index: seg_info.seg_index,
+            uploaded_seg_num,
+            total_seg_num: Some(total_segments),
+        });
 
         // Notify to finalize transaction asynchronously.
         if all_uploaded {
@@ -227,6 +332,9 @@ impl MemoryChunkPool {
             "start to flush cached segments to log store. data root: {}, tx_seq:{}",
             tx.data_merkle_root, tx.seq
         );
+        if !self.check_already_has_cache(&tx.data_merkle_root).await {
+            self.restore_upload_session(&tx.data_merkle_root).await?;
+        }
         let maybe_file = self
             .inner
             .lock()
@@ -289,6 +397,117 @@ impl MemoryChunkPool {
         }
     }
 
+    /// Restores the persisted upload sessions after node restarted.
+    pub async fn restore_upload_sessions(&self) -> Result<()> {
+        // sessions may be expired during node stopped
+        self.gc_upload_sessions().await?;
+
+        for root in self.sessions.roots().await? {
+            match self.log_store.get_tx_by_data_root(&root).await? {
+                Some(tx) if self.log_store.check_tx_completed(tx.seq).await? => {
+                    self.sessions.remove(&root).await?;
+                }
+                // flush the persisted segments into store if log entry already retrieved
+                Some(tx) => {
+                    self.update_file_info(&tx).await?;
+                }
+                None => self.restore_upload_session(&root).await?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Restores the persisted segments that not written into store into memory cache.
+    async fn restore_upload_session(&self, root: &DataRoot) -> Result<()> {
+        let record = match self.sessions.get(root).await? {
+            Some(record) if !record.cached_segments.is_empty() => record,
+            _ => return Ok(()),
+        };
+
+        let segments = self.sessions.get_cached_segments(root, &record).await?;
+
+        debug!(%root, num_segments = segments.len(), "Restore upload session");
+
+        let mut inner = self.inner.lock().await;
+        for (seg_index, seg, proof) in segments {
+            inner.segment_cache.cache_segment(SegmentInfo {
+                root: *root,
+                seg_data: seg.data,
+                seg_proof: proof,
+                seg_index,
+                chunks_per_segment: record.chunks_per_segment as usize,
+            })?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn get_upload_session(&self, root: &DataRoot) -> Result<Option<UploadSessionRecord>> {
+        self.sessions.get(root).await
+    }
+
+    /// Returns the indexes of segments that not uploaded yet for the specified file,
+    /// where `in_shard` filters segments that should be stored in this node.
+    pub async fn get_missing_segments(
+        &self,
+        root: &DataRoot,
+        record: &UploadSessionRecord,
+        in_shard: impl Fn(usize) -> bool,
+    ) -> Result<Vec<usize>> {
+        let mut missing = Vec::new();
+        for seg_index in (0..record.total_segments()).filter(|x| in_shard(*x)) {
+            if record.cached_segments.contains(&(seg_index as u64)) {
+                continue;
+            }
+
+            if !self.sessions.is_segment_written(root, seg_index).await? {
+                missing.push(seg_index);
+            }
+        }
+
+        Ok(missing)
+    }
+
+    pub(crate) async fn remove_upload_session(&self, root: &DataRoot) -> Result<()> {
+        self.sessions.remove(root).await
+    }
+
+    /// Removes the upload sessions that no new segment uploaded for a long time.
+    pub async fn monitor_upload_sessions(chunk_pool: Arc<Self>) {
+        info!("Start to monitor upload sessions");
+
+        let mut interval = tokio::time::interval(UPLOAD_SESSION_GC_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            if let Err(e) = chunk_pool.gc_upload_sessions().await {
+                warn!(%e, "Failed to garbage collect upload sessions");
+            }
+        }
+    }
+
+    async fn gc_upload_sessions(&self) -> Result<()> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+
+        for root in self.sessions.roots().await? {
+            let expired = match self.sessions.get(&root).await? {
+                Some(record) => record.updated_at + self.session_expiration_time_secs <= now,
+                None => true,
+            };
+
+            if expired {
+                debug!(%root, "Garbage collected for upload session");
+                self.sessions.remove(&root).await?;
+            }
+        }
+
+        Ok(())
+    }
+
     pub(crate) async fn remove_cached_file(&self, root: &DataRoot) -> Option<MemoryCachedFile> {
         self.inner.lock().await.segment_cache.remove_file(root)
     }
@@ -381,3 +600,206 @@ impl MemoryChunkPool {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use storage::log_store::log_manager::{LogConfig, LogManager};
+    use task_executor::test_utils::TestRuntime;
+
+    const CHUNKS_PER_SEGMENT: usize = 2;
+    const FILE_SIZE: usize = 4 * CHUNK_SIZE;
+
+    fn new_store(runtime: &TestRuntime) -> Arc<Store> {
+        let store = LogManager::memorydb(LogConfig::default()).unwrap();
+        Arc::new(Store::new(Arc::new(store), runtime.task_executor.clone()))
+    }
+
+    fn new_pool(store: Arc<Store>, session_expiration_time_secs: u64) -> MemoryChunkPool {
+        let config = Config {
+            write_window_size: 4,
+            max_cached_chunks_all: 1024,
+            max_writings: 4,
+            expiration_time_secs: 300,
+            session_expiration_time_secs,
+            max_upload_sessions: 2,
+            shard_config: ShardConfig::default(),
+        };
+        let (sender, _) = tokio::sync::mpsc::unbounded_channel();
+        MemoryChunkPool::new(config, store, sender)
+    }
+
+    fn new_segment(root: DataRoot, seg_index: usize, chunks_per_segment: usize) -> SegmentInfo {
+        SegmentInfo {
+            root,
+            seg_data: vec![seg_index as u8; chunks_per_segment * CHUNK_SIZE],
+            seg_proof: FileProof::new(vec![], vec![]),
+            seg_index,
+            chunks_per_segment,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resume_after_restart() {
+        let runtime = TestRuntime::default();
+        let store = new_store(&runtime);
+        let root = DataRoot::repeat_byte(1);
+
+        let pool = new_pool(store.clone(), 3600);
+        pool.cache_chunks(new_segment(root, 0, CHUNKS_PER_SEGMENT), FILE_SIZE)
+            .await
+            .unwrap();
+        drop(pool);
+
+        // restart with the same store
+        let pool = new_pool(store, 3600);
+        assert!(!pool.check_already_has_cache(&root).await);
+
+        let record = pool.get_upload_session(&root).await.unwrap().unwrap();
+        assert_eq!(record.cached_segments, vec![0]);
+        assert_eq!(record.total_segments(), 2);
+
+        pool.restore_upload_sessions().await.unwrap();
+        assert!(pool.check_already_has_cache(&root).await);
+        let missing = pool
+            .get_missing_segments(&root, &record, |_| true)
+            .await
+            .unwrap();
+        assert_eq!(missing, vec![1]);
+    }
+
+    #[tokio::test]
+    async fn test_session_expired() {
+        let runtime = TestRuntime::default();
+        let store = new_store(&runtime);
+        let root = DataRoot::repeat_byte(1);
+
+        let pool = new_pool(store.clone(), 3600);
+        pool.cache_chunks(new_segment(root, 0, CHUNKS_PER_SEGMENT), FILE_SIZE)
+            .await
+            .unwrap();
+        pool.gc_upload_sessions().await.unwrap();
+        assert!(pool.get_upload_session(&root).await.unwrap().is_some());
+
+        let pool = new_pool(store, 0);
+        pool.gc_upload_sessions().await.unwrap();
+        assert!(pool.get_upload_session(&root).await.unwrap().is_none());
+        assert!(pool.sessions.roots().await.unwrap().is_empty());
+        assert!(pool
+            .sessions
+            .get_cached_segments(
+                &root,
+                &UploadSessionRecord {
+                    cached_segments: vec![0],
+                    ..Default::default()
+                }
+            )
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_session_mismatched() {
+        let runtime = TestRuntime::default();
+        let store = new_store(&runtime);
+        let root = DataRoot::repeat_byte(1);
+
+        let pool = new_pool(store.clone(), 3600);
+        pool.cache_chunks(new_segment(root, 1, CHUNKS_PER_SEGMENT), FILE_SIZE)
+            .await
+            .unwrap();
+
+        // upload the same root again with a different segment size after restart
+        let pool = new_pool(store, 3600);
+        pool.cache_chunks(new_segment(root, 0, 1), FILE_SIZE)
+            .await
+            .unwrap();
+
+        let record = pool.get_upload_session(&root).await.unwrap().unwrap();
+        assert_eq!(record.chunks_per_segment, 1);
+        assert_eq!(record.cached_segments, vec![0]);
+        assert_eq!(record.total_segments(), 4);
+
+        let segments = pool
+            .sessions
+            .get_cached_segments(&root, &record)
+            .await
+            .unwrap();
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].1.data, vec![0; CHUNK_SIZE]);
+        assert_eq!(pool.sessions.roots().await.unwrap(), vec![root]);
+    }
+
+    #[tokio::test]
+    async fn test_session_limits() {
+        let runtime = TestRuntime::default();
+        let store = new_store(&runtime);
+        let pool = new_pool(store.clone(), 3600);
+
+        // exceeds the max number of sessions
+        for i in 1..=3 {
+            pool.cache_chunks(
+                new_segment(DataRoot::repeat_byte(i), 0, CHUNKS_PER_SEGMENT),
+                FILE_SIZE,
+            )
+            .await
+            .unwrap();
+        }
+        assert!(pool
+            .get_upload_session(&DataRoot::repeat_byte(3))
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(pool.sessions.roots().await.unwrap().len(), 2);
+
+        // exceeds the max number of persisted chunks
+        let root = DataRoot::repeat_byte(1);
+        let sessions = UploadSessionStore::new(store, 2, 4);
+        let put_segment_1 = || {
+            let seg = new_segment(root, 1, CHUNKS_PER_SEGMENT);
+            sessions.put_cached_segment(
+                &root,
+                1,
+                FILE_SIZE,
+                CHUNKS_PER_SEGMENT,
+                ChunkArray {
+                    data: seg.seg_data,
+                    start_index: CHUNKS_PER_SEGMENT as u64,
+                },
+                seg.seg_proof,
+            )
+        };
+        assert!(put_segment_1().await.is_err());
+
+        // written segments release the quota, and only counted once
+        for _ in 0..2 {
+            sessions
+                .on_segment_written(&root, 0, FILE_SIZE, CHUNKS_PER_SEGMENT)
+                .await
+                .unwrap();
+        }
+        let record = sessions.get(&root).await.unwrap().unwrap();
+        assert!(record.cached_segments.is_empty());
+        assert_eq!(record.written_seg_num, 1);
+
+        put_segment_1().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_expired_session_not_restored() {
+        let runtime = TestRuntime::default();
+        let store = new_store(&runtime);
+        let root = DataRoot::repeat_byte(1);
+
+        let pool = new_pool(store.clone(), 3600);
+        pool.cache_chunks(new_segment(root, 0, CHUNKS_PER_SEGMENT), FILE_SIZE)
+            .await
+            .unwrap();
+
+        let pool = new_pool(store, 0);
+        pool.restore_upload_sessions().await.unwrap();
+        assert!(!pool.check_already_has_cache(&root).await);
+        assert!(pool.get_upload_session(&root).await.unwrap().is_none());
+    }
+}
@@ -1,9 +1,11 @@
 mod chunk_cache;
 mod chunk_pool_inner;
 mod chunk_write_control;
+mod upload_session;
 
 pub use chunk_pool_inner::MemoryChunkPool;
 pub use chunk_pool_inner::SegmentInfo;
+pub use upload_session::UploadSessionRecord;
 
 use shared_types::DataRoot;
 use shared_types::TxID;
@@ -13,3 +15,15 @@ pub struct FileID {
     pub root: DataRoot,
     pub tx_id: TxID,
 }
+
+/// Progress of a file upload, notified once a segment is accepted by the pool.
+#[derive(Clone, Copy, Debug)]
+pub struct UploadProgress {
+    pub root: DataRoot,
+    /// Unavailable if the file is cached before log entry retrieved.
+    pub tx_seq: Option<u64>,
+    pub seg_index: usize,
+    pub uploaded_seg_num: usize,
+    /// Unavailable if the file is cached before log entry retrieved.
+    pub total_seg_num: Option<usize>,
+}
@@ -0,0 +1,304 @@
+use anyhow::{bail, Result};
+use async_lock::{Mutex, MutexGuard};
+use shared_types::{
+    bytes_to_chunks, compute_segment_size, ChunkArray, DataRoot, FileProof, CHUNK_SIZE,
+};
+use ssz_derive::{Decode as DeriveDecode, Encode as DeriveEncode};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use storage::log_store::config::ConfigTx;
+use storage::log_store::log_manager::DATA_DB_KEY;
+use storage_async::Store;
+
+const KEY_SESSIONS: &str = "chunk_pool.sessions";
+
+/// Persisted upload progress of a file.
+#[derive(Clone, Debug, Default, DeriveEncode, DeriveDecode)]
+pub struct UploadSessionRecord {
+    pub file_size: u64,
+    pub chunks_per_segment: u64,
+    /// Segments cached in pool but not written into store yet.
+    pub cached_segments: Vec<u64>,
+    /// Number of segments already written into store.
+    pub written_seg_num: u64,
+    /// Unix timestamp in seconds when the last segment uploaded.
+    pub updated_at: u64,
+}
+
+impl UploadSessionRecord {
+    pub fn total_segments(&self) -> usize {
+        let total_chunks = bytes_to_chunks(self.file_size as usize);
+        compute_segment_size(total_chunks, self.chunks_per_segment as usize).0
+    }
+
+    /// Returns the number of chunks of the specified segment.
+    pub fn segment_chunks(&self, seg_index: u64) -> u64 {
+        let total_chunks = bytes_to_chunks(self.file_size as usize) as u64;
+        let start = seg_index.saturating_mul(self.chunks_per_segment);
+        total_chunks
+            .saturating_sub(start)
+            .min(self.chunks_per_segment)
+    }
+
+    /// Returns whether the session is created for the file of same size and segment size.
+    pub fn matches(&self, file_size: usize, chunks_per_segment: usize) -> bool {
+        self.file_size == file_size as u64 && self.chunks_per_segment == chunks_per_segment as u64
+    }
+}
+
+#[derive(DeriveEncode, DeriveDecode)]
+struct PersistedSegment {
+    chunks: ChunkArray,
+    proof: FileProof,
+}
+
+fn key_session(root: &DataRoot) -> String {
+    format!("chunk_pool.session.{:?}", root)
+}
+
+fn key_cached_segment(root: &DataRoot, seg_index: u64) -> String {
+    format!("chunk_pool.session.{:?}.cached.{}", root, seg_index)
+}
+
+fn key_written_segment(root: &DataRoot, seg_index: u64) -> String {
+    format!("chunk_pool.session.{:?}.written.{}", root, seg_index)
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Persists the upload sessions in chunk pool, so that clients could resume the upload
+/// after disconnected or node restarted.
+///
+/// Segments that cached in pool are persisted along with the session, and only segment
+/// indexes are persisted once written into store.
+///
+/// Both the number of sessions and the number of persisted chunks are bounded, so that
+/// clients could not fill up the disk with files that never submitted on chain.
+pub struct UploadSessionStore {
+    store: Arc<Store>,
+    max_sessions: usize,
+    max_cached_chunks: u64,
+    /// Serializes the read-modify-write of session records, and holds the number of
+    /// persisted chunks of all sessions, which is lazily counted at the first write.
+    lock: Mutex<Option<u64>>,
+}
+
+impl UploadSessionStore {
+    pub fn new(store: Arc<Store>, max_sessions: usize, max_cached_chunks: usize) -> Self {
+        UploadSessionStore {
+            store,
+            max_sessions,
+            max_cached_chunks: max_cached_chunks as u64,
+            lock: Mutex::new(None),
+        }
+    }
+
+    async fn lock(&self) -> Result<MutexGuard<'_, Option<u64>>> {
+        let mut guard = self.lock.lock().await;
+        if guard.is_none() {
+            let mut cached_chunks = 0;
+            for root in self.roots().await? {
+                if let Some(record) = self.get(&root).await? {
+                    cached_chunks += record
+                        .cached_segments
+                        .iter()
+                        .map(|seg_index| record.segment_chunks(*seg_index))
+                        .sum::<u64>();
+                }
+            }
+            *guard = Some(cached_chunks);
+        }
+
+        Ok(guard)
+    }
+
+    pub async fn get(&self, root: &DataRoot) -> Result<Option<UploadSessionRecord>> {
+        self.store
+            .get_config_decoded(&key_session(root), DATA_DB_KEY)
+            .await
+    }
+
+    pub async fn roots(&self) -> Result<Vec<DataRoot>> {
+        Ok(self
+            .store
+            .get_config_decoded(&KEY_SESSIONS, DATA_DB_KEY)
+            .await?
+            .unwrap_or_default())
+    }
+
+    pub async fn is_segment_written(&self, root: &DataRoot, seg_index: usize) -> Result<bool> {
+        Ok(self
+            .store
+            .get_config_decoded::<_, bool>(
+                &key_written_segment(root, seg_index as u64),
+                DATA_DB_KEY,
+            )
+            .await?
+            .is_some())
+    }
+
+    /// Returns the persisted segments that not written into store yet.
+    pub async fn get_cached_segments(
+        &self,
+        root: &DataRoot,
+        record: &UploadSessionRecord,
+    ) -> Result<Vec<(usize, ChunkArray, FileProof)>> {
+        let mut segments = Vec::with_capacity(record.cached_segments.len());
+        for &seg_index in record.cached_segments.iter() {
+            let key = key_cached_segment(root, seg_index);
+            if let Some(seg) = self
+                .store
+                .get_config_decoded::<_, PersistedSegment>(&key, DATA_DB_KEY)
+                .await?
+            {
+                segments.push((seg_index as usize, seg.chunks, seg.proof));
+            }
+        }
+
+        Ok(segments)
+    }
+
+    pub async fn put_cached_segment(
+        &self,
+        root: &DataRoot,
+        seg_index: usize,
+        file_size: usize,
+        chunks_per_segment: usize,
+        chunks: ChunkArray,
+        proof: FileProof,
+    ) -> Result<()> {
+        let mut guard = self.lock().await?;
+        let cached_chunks = guard.expect("counted in lock");
+
+        let mut tx = ConfigTx::default();
+        let mut record = self
+            .get_or_create(&mut tx, root, file_size, chunks_per_segment)
+            .await?;
+
+        let seg_index = seg_index as u64;
+        let mut new_chunks = 0;
+        if !record.cached_segments.contains(&seg_index) {
+            new_chunks = (chunks.data.len() / CHUNK_SIZE) as u64;
+            if cached_chunks + new_chunks > self.max_cached_chunks {
+                bail!(
+                    "persisted chunks of upload sessions exceed the limit {}",
+                    self.max_cached_chunks
+                );
+            }
+            record.cached_segments.push(seg_index);
+        }
+        record.updated_at = now_secs();
+
+        tx.set_config(
+            &key_cached_segment(root, seg_index),
+            &PersistedSegment { chunks, proof },
+        );
+        tx.set_config(&key_session(root), &record);
+
+        self.store.exec_configs(tx, DATA_DB_KEY).await?;
+        *guard = Some(cached_chunks + new_chunks);
+
+        Ok(())
+    }
+
+    pub async fn on_segment_written(
+        &self,
+        root: &DataRoot,
+        seg_index: usize,
+        file_size: usize,
+        chunks_per_segment: usize,
+    ) -> Result<()> {
+        // check under the lock, otherwise concurrent writes of the same segment would count
+        // the written segment more than once
+        let mut guard = self.lock().await?;
+        let cached_chunks = guard.expect("counted in lock");
+
+        if self.is_segment_written(root, seg_index).await? {
+            return Ok(());
+        }
+
+        let mut tx = ConfigTx::default();
+        let mut record = self
+            .get_or_create(&mut tx, root, file_size, chunks_per_segment)
+            .await?;
+
+        let seg_index = seg_index as u64;
+        let mut removed_chunks = 0;
+        if let Some(pos) = record.cached_segments.iter().position(|x| *x == seg_index) {
+            record.cached_segments.swap_remove(pos);
+            tx.remove_config(&key_cached_segment(root, seg_index));
+            removed_chunks = record.segment_chunks(seg_index);
+        }
+        record.written_seg_num += 1;
+        record.updated_at = now_secs();
+
+        tx.set_config(&key_written_segment(root, seg_index), &true);
+        tx.set_config(&key_session(root), &record);
+
+        self.store.exec_configs(tx, DATA_DB_KEY).await?;
+        *guard = Some(cached_chunks.saturating_sub(removed_chunks));
+
+        Ok(())
+    }
+
+    /// Removes the session and all persisted segments, e.g. when file finalized or
+    /// session expired.
+    pub async fn remove(&self, root: &DataRoot) -> Result<()> {
+        let mut guard = self.lock().await?;
+        let cached_chunks = guard.expect("counted in lock");
+
+        let mut tx = ConfigTx::default();
+        let mut removed_chunks = 0;
+        if let Some(record) = self.get(root).await? {
+            for seg_index in record.cached_segments.iter() {
+                tx.remove_config(&key_cached_segment(root, *seg_index));
+                removed_chunks += record.segment_chunks(*seg_index);
+            }
+            if record.written_seg_num > 0 {
+                for seg_index in 0..record.total_segments() as u64 {
+                    tx.remove_config(&key_written_segment(root, seg_index));
+                }
+            }
+            tx.remove_config(&key_session(root));
+        }
+
+        let mut roots = self.roots().await?;
+        roots.retain(|x| x != root);
+        tx.set_config(&KEY_SESSIONS, &roots);
+
+        self.store.exec_configs(tx, DATA_DB_KEY).await?;
+        *guard = Some(cached_chunks.saturating_sub(removed_chunks));
+
+        Ok(())
+    }
+
+    async fn get_or_create(
+        &self,
+        tx: &mut ConfigTx,
+        root: &DataRoot,
+        file_size: usize,
+        chunks_per_segment: usize,
+    ) -> Result<UploadSessionRecord> {
+        if let Some(record) = self.get(root).await? {
+            return Ok(record);
+        }
+
+        let mut roots = self.roots().await?;
+        if roots.len() >= self.max_sessions {
+            bail!("too many upload sessions, max = {}", self.max_sessions);
+        }
+        roots.push(*root);
+        tx.set_config(&KEY_SESSIONS, &roots);
+
+        Ok(UploadSessionRecord {
+            file_size: file_size as u64,
+            chunks_per_segment: chunks_per_segment as u64,
+            ..Default::default()
+        })
+    }
+}
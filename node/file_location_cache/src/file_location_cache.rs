@@ -1,7 +1,7 @@
 use crate::Config;
 use metrics::{register_meter_with_group, Histogram, Meter, Sample};
-use network::types::SignedAnnounceFile;
-use network::PeerId;
+use network::types::{SignedAnnounceFile, SignedAnnounceFileRanges};
+use network::{Multiaddr, PeerId};
 use parking_lot::Mutex;
 use priority_queue::PriorityQueue;
 use rand::seq::IteratorRandom;
@@ -14,9 +14,38 @@ use storage::config::ShardConfig;
 lazy_static::lazy_static! {
     pub static ref INSERT_QPS: Arc<dyn Meter> = register_meter_with_group("file_location_cache_insert", "qps");
     pub static ref INSERT_BATCH: Arc<dyn Histogram> = Sample::ExpDecay(0.015).register_with_group("file_location_cache_insert", "batch", 1024);
+    pub static ref INSERT_RANGES_QPS: Arc<dyn Meter> = register_meter_with_group("file_location_cache_insert_ranges", "qps");
     pub static ref TOTAL_CACHED: Arc<dyn Histogram> = Sample::ExpDecay(0.015).register("file_location_cache_size", 1024);
 }
 
+/// Location of a file announced by a peer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileLocation {
+    pub peer_id: PeerId,
+    pub at: Multiaddr,
+    pub timestamp: u32,
+}
+
+impl From<&SignedAnnounceFile> for FileLocation {
+    fn from(announcement: &SignedAnnounceFile) -> Self {
+        FileLocation {
+            peer_id: announcement.peer_id.clone().into(),
+            at: announcement.at.clone().into(),
+            timestamp: announcement.timestamp,
+        }
+    }
+}
+
+impl From<&SignedAnnounceFileRanges> for FileLocation {
+    fn from(announcement: &SignedAnnounceFileRanges) -> Self {
+        FileLocation {
+            peer_id: announcement.peer_id.clone().into(),
+            at: announcement.at.clone().into(),
+            timestamp: announcement.timestamp,
+        }
+    }
+}
+
 /// Caches limited announcements of specified file from different peers.
 struct AnnouncementCache {
     /// Maximum number of announcements in cache.
@@ -255,6 +284,96 @@ impl FileCache {
     }
 }
 
+/// Caches the latest range announcement of each peer.
+struct RangeAnnouncementCache {
+    /// Maximum number of announcements in cache.
+    capacity: usize,
+
+    /// Timeout in seconds to expire the cached announcement.
+    timeout_secs: u32,
+
+    /// All cached announcements that mapped from peer id to announcement.
+    items: HashMap<PeerId, SignedAnnounceFileRanges>,
+
+    /// All announcements are prioritized by timestamp.
+    /// The top element is the oldest announcement.
+    priorities: PriorityQueue<PeerId, Reverse<u32>>,
+}
+
+impl RangeAnnouncementCache {
+    fn new(capacity: usize, timeout_secs: u32) -> Self {
+        RangeAnnouncementCache {
+            capacity,
+            timeout_secs,
+            items: Default::default(),
+            priorities: Default::default(),
+        }
+    }
+
+    /// Garbage collects expired announcements.
+    fn garbage_collect(&mut self) {
+        let now = timestamp_now();
+
+        while let Some((_, &Reverse(ts))) = self.priorities.peek() {
+            if ts + self.timeout_secs > now {
+                break;
+            }
+
+            self.pop();
+        }
+    }
+
+    /// Removes the oldest announcement if any.
+    fn pop(&mut self) -> Option<SignedAnnounceFileRanges> {
+        let (peer_id, _) = self.priorities.pop()?;
+        self.items.remove(&peer_id)
+    }
+
+    /// Insert the specified `announcement` into cache, which replaces the older one of the
+    /// same peer.
+    fn insert(&mut self, announcement: SignedAnnounceFileRanges) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        self.garbage_collect();
+
+        let peer_id = announcement.peer_id.clone().into();
+
+        if let Some(existing) = self.items.get(&peer_id) {
+            // ignore older announcement
+            if announcement.timestamp <= existing.timestamp {
+                return;
+            }
+        }
+
+        self.priorities
+            .push(peer_id, Reverse(announcement.timestamp));
+        self.items.insert(peer_id, announcement);
+
+        // remove oldest one if capacity exceeded
+        if self.items.len() > self.capacity {
+            self.pop();
+        }
+    }
+
+    /// Removes the announcement of specified peer.
+    fn remove(&mut self, peer_id: &PeerId) -> Option<SignedAnnounceFileRanges> {
+        self.priorities.remove(peer_id);
+        self.items.remove(peer_id)
+    }
+
+    /// Returns all announcements that cover the specified `tx_seq`.
+    fn covering(&mut self, tx_seq: u64) -> Vec<&SignedAnnounceFileRanges> {
+        self.garbage_collect();
+
+        self.items
+            .values()
+            .filter(|announcement| announcement.contains(tx_seq))
+            .collect()
+    }
+}
+
 #[derive(Default)]
 pub struct PeerShardConfigCache {
     peers: HashMap<PeerId, ShardConfig>,
@@ -272,15 +391,13 @@ impl PeerShardConfigCache {
 
 pub struct FileLocationCache {
     cache: Mutex<FileCache>,
+    range_cache: Mutex<RangeAnnouncementCache>,
     peer_cache: Mutex<PeerShardConfigCache>,
 }
 
 impl Default for FileLocationCache {
     fn default() -> Self {
-        FileLocationCache {
-            cache: Mutex::new(FileCache::new(Default::default())),
-            peer_cache: Mutex::new(Default::default()),
-        }
+        FileLocationCache::new(Default::default())
     }
 }
 
@@ -288,6 +405,10 @@ impl FileLocationCache {
     pub fn new(config: Config) -> Self {
         FileLocationCache {
             cache: Mutex::new(FileCache::new(config)),
+            range_cache: Mutex::new(RangeAnnouncementCache::new(
+                config.max_range_entries,
+                config.entry_expiration_time_secs,
+            )),
             peer_cache: Mutex::new(Default::default()),
         }
     }
@@ -315,14 +436,52 @@ impl FileLocationCache {
         self.cache.lock().random(tx_id)
     }
 
-    pub fn get_all(&self, tx_id: TxID) -> Vec<SignedAnnounceFile> {
-        self.cache.lock().all(tx_id).unwrap_or_default()
+    /// Inserts the announcement of files in tx_seq ranges.
+    pub fn insert_ranges(&self, announcement: SignedAnnounceFileRanges) {
+        INSERT_RANGES_QPS.mark(1);
+
+        let peer_id = *announcement.peer_id;
+        let shard_config = match ShardConfig::try_from(announcement.shard_config) {
+            Ok(v) => v,
+            Err(_) => return,
+        };
+        self.insert_peer_config(peer_id, shard_config);
+
+        self.range_cache.lock().insert(announcement);
+    }
+
+    /// Returns locations of the specified file, announced either for the file or in ranges that
+    /// cover the file.
+    pub fn get_all(&self, tx_id: TxID) -> Vec<FileLocation> {
+        let mut locations: Vec<FileLocation> = self
+            .cache
+            .lock()
+            .all(tx_id)
+            .unwrap_or_default()
+            .iter()
+            .map(FileLocation::from)
+            .collect();
+
+        for announcement in self.range_cache.lock().covering(tx_id.seq) {
+            let peer_id: PeerId = announcement.peer_id.clone().into();
+            if locations.iter().all(|location| location.peer_id != peer_id) {
+                locations.push(announcement.into());
+            }
+        }
+
+        locations
     }
 
     pub fn remove(&self, tx_id: &TxID, peer_id: &PeerId) -> Option<SignedAnnounceFile> {
         self.cache.lock().remove(tx_id, peer_id)
     }
 
+    /// Removes the range announcement of specified peer, e.g. the peer failed to prove that it
+    /// stores an announced file.
+    pub fn remove_ranges(&self, peer_id: &PeerId) -> Option<SignedAnnounceFileRanges> {
+        self.range_cache.lock().remove(peer_id)
+    }
+
     /// TODO: Trigger chunk_pool/sync to reconstruct if it changes?
     pub fn insert_peer_config(
         &self,
@@ -341,12 +500,19 @@ impl FileLocationCache {
 mod tests {
     use std::cmp::Reverse;
 
-    use network::{types::SignedAnnounceFile, PeerId};
+    use network::{
+        libp2p::identity,
+        types::{
+            AnnounceFileRanges, SignedAnnounceFile, SignedAnnounceFileRanges, SignedMessage,
+            TimedMessage, TxSeqRange,
+        },
+        Multiaddr, PeerId,
+    };
     use shared_types::{timestamp_now, TxID};
 
-    use crate::{test_util::AnnounceFileBuilder, Config};
+    use crate::{test_util::AnnounceFileBuilder, Config, FileLocationCache};
 
-    use super::{AnnouncementCache, FileCache};
+    use super::{AnnouncementCache, FileCache, RangeAnnouncementCache};
 
     fn create_file(peer_id: Option<PeerId>, timestamp: u32) -> SignedAnnounceFile {
         let builder = AnnounceFileBuilder::default().with_timestamp(timestamp);
@@ -539,6 +705,7 @@ mod tests {
             max_entries_total: total_entries,
             max_entries_per_file: file_entries,
             entry_expiration_time_secs: timeout,
+            ..Default::default()
         })
     }
 
@@ -609,4 +776,101 @@ mod tests {
             vec![now - 3, now - 2, now - 1],
         );
     }
+
+    fn create_file_ranges(
+        peer_id: PeerId,
+        ranges: Vec<(u64, u64)>,
+        timestamp: u32,
+    ) -> SignedAnnounceFileRanges {
+        let at: Multiaddr = "/ip4/127.0.0.1/tcp/10000".parse().unwrap();
+        let msg = TimedMessage {
+            inner: AnnounceFileRanges {
+                ranges: ranges
+                    .into_iter()
+                    .map(|(start, end)| TxSeqRange { start, end })
+                    .collect(),
+                shard_config: Default::default(),
+                peer_id: peer_id.into(),
+                at: at.into(),
+            },
+            timestamp,
+        };
+
+        let keypair = identity::Keypair::generate_secp256k1();
+        SignedMessage::sign_message(msg, &keypair).unwrap()
+    }
+
+    #[test]
+    fn test_range_cache_covering() {
+        let mut cache = RangeAnnouncementCache::new(2, 3600);
+        let now = timestamp_now();
+
+        let (peer1, peer2, peer3) = (PeerId::random(), PeerId::random(), PeerId::random());
+        cache.insert(create_file_ranges(peer1, vec![(0, 10), (20, 30)], now - 3));
+        cache.insert(create_file_ranges(peer2, vec![(5, 25)], now - 2));
+
+        assert_eq!(cache.covering(0).len(), 1);
+        assert_eq!(cache.covering(5).len(), 2);
+        assert_eq!(cache.covering(10).len(), 1);
+        assert_eq!(cache.covering(25).len(), 1);
+        assert!(cache.covering(30).is_empty());
+
+        // older announcement of the same peer ignored
+        cache.insert(create_file_ranges(peer2, vec![(30, 40)], now - 3));
+        assert!(cache.covering(30).is_empty());
+
+        // oldest announcement removed if capacity exceeded
+        cache.insert(create_file_ranges(peer3, vec![(30, 40)], now - 1));
+        assert_eq!(cache.covering(30).len(), 1);
+        assert!(cache.covering(0).is_empty());
+    }
+
+    #[test]
+    fn test_range_cache_garbage_collect() {
+        let mut cache = RangeAnnouncementCache::new(100, 3600);
+        let now = timestamp_now();
+
+        cache.insert(create_file_ranges(
+            PeerId::random(),
+            vec![(0, 10)],
+            now - 5000,
+        ));
+        cache.insert(create_file_ranges(PeerId::random(), vec![(0, 10)], now - 1));
+        assert_eq!(cache.covering(0).len(), 1);
+    }
+
+    #[test]
+    fn test_get_all_with_ranges() {
+        let cache = FileLocationCache::default();
+        let now = timestamp_now();
+
+        let (peer1, peer2) = (PeerId::random(), PeerId::random());
+        let tx1 = TxID::random_hash(1);
+        cache.insert(create_file_2(tx1, peer1, now - 1));
+        cache.insert_ranges(create_file_ranges(peer1, vec![(0, 10)], now - 2));
+        cache.insert_ranges(create_file_ranges(peer2, vec![(1, 2)], now - 3));
+
+        // peer announced in both ways returned only once
+        let locations = cache.get_all(tx1);
+        assert_eq!(locations.len(), 2);
+        assert_eq!(locations[0].peer_id, peer1);
+        assert_eq!(locations[0].timestamp, now - 1);
+        assert_eq!(locations[1].peer_id, peer2);
+
+        // file only covered by ranges
+        let locations = cache.get_all(TxID::random_hash(5));
+        assert_eq!(locations.len(), 1);
+        assert_eq!(locations[0].peer_id, peer1);
+        assert!(cache.get_peer_config(&peer2).is_some());
+
+        assert!(cache.get_all(TxID::random_hash(10)).is_empty());
+
+        // peer removed for both ways
+        cache.remove(&tx1, &peer1);
+        assert!(cache.remove_ranges(&peer1).is_some());
+        let locations = cache.get_all(tx1);
+        assert_eq!(locations.len(), 1);
+        assert_eq!(locations[0].peer_id, peer2);
+        assert!(cache.remove_ranges(&peer1).is_none());
+    }
 }
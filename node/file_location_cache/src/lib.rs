@@ -3,7 +3,7 @@ pub mod test_util;
 
 use serde::Deserialize;
 
-pub use crate::file_location_cache::FileLocationCache;
+pub use crate::file_location_cache::{FileLocation, FileLocationCache};
 
 #[derive(Clone, Copy, Debug, Deserialize)]
 #[serde(default)]
@@ -11,6 +11,8 @@ pub struct Config {
     pub max_entries_total: usize,
     pub max_entries_per_file: usize,
     pub entry_expiration_time_secs: u32,
+    /// Maximum number of peers to cache announcements of files in tx_seq ranges.
+    pub max_range_entries: usize,
 }
 
 impl Default for Config {
@@ -19,6 +21,7 @@ impl Default for Config {
             max_entries_total: 1000000,
             max_entries_per_file: 4,
             entry_expiration_time_secs: 86400,
+            max_range_entries: 10000,
         }
     }
 }
@@ -0,0 +1,245 @@
+//! Encoding of the key-value batch carried by a log entry.
+//!
+//! All integers are encoded in big endian:
+//!
+//! ```text
+//! batch     := version(u8) | num_writes(u32) | write* | num_acls(u32) | acl* | signature(65)
+//! write     := stream_id(32) | key_len(u32) | key | value_len(u32) | value
+//! acl       := op(u8) | stream_id(32) | key_len(u32) | key | account(20)
+//! signature := r(32) | s(32) | v(1)
+//! ```
+//!
+//! The signature is signed by the batch sender over the keccak256 hash of all bytes before it,
+//! and the recovered address is used to check the key ownership.
+
+use anyhow::{anyhow, bail, Result};
+use ethereum_types::{Address, H256, U256};
+use ethers::signers::LocalWallet;
+use ethers::types::Signature;
+use ethers::utils::keccak256;
+
+pub const BATCH_VERSION: u8 = 1;
+
+const STREAM_ID_SIZE: usize = 32;
+const SIGNATURE_SIZE: usize = 65;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KvWrite {
+    pub stream_id: U256,
+    pub key: Vec<u8>,
+    pub value: Vec<u8>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AclOp {
+    /// Allows the account to write the key.
+    GrantWriter = 0,
+    /// Disallows the account to write the key.
+    RevokeWriter = 1,
+    /// Transfers the key ownership to the account.
+    TransferOwner = 2,
+}
+
+impl TryFrom<u8> for AclOp {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u8) -> Result<Self> {
+        match value {
+            0 => Ok(AclOp::GrantWriter),
+            1 => Ok(AclOp::RevokeWriter),
+            2 => Ok(AclOp::TransferOwner),
+            _ => Err(anyhow!("invalid acl op {}", value)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KvAcl {
+    pub op: AclOp,
+    pub stream_id: U256,
+    pub key: Vec<u8>,
+    pub account: Address,
+}
+
+/// Key-value updates submitted in a single log entry, which are applied atomically.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct KvBatch {
+    pub writes: Vec<KvWrite>,
+    pub acls: Vec<KvAcl>,
+}
+
+/// Batch decoded from a log entry along with the sender recovered from signature.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignedKvBatch {
+    pub sender: Address,
+    pub batch: KvBatch,
+}
+
+impl KvBatch {
+    /// Encodes the batch without signature, which is the message to sign.
+    pub fn encode_unsigned(&self) -> Vec<u8> {
+        let mut buf = vec![BATCH_VERSION];
+
+        buf.extend_from_slice(&(self.writes.len() as u32).to_be_bytes());
+        for write in self.writes.iter() {
+            buf.extend_from_slice(&stream_id_to_bytes(&write.stream_id));
+            put_bytes(&mut buf, &write.key);
+            put_bytes(&mut buf, &write.value);
+        }
+
+        buf.extend_from_slice(&(self.acls.len() as u32).to_be_bytes());
+        for acl in self.acls.iter() {
+            buf.push(acl.op as u8);
+            buf.extend_from_slice(&stream_id_to_bytes(&acl.stream_id));
+            put_bytes(&mut buf, &acl.key);
+            buf.extend_from_slice(acl.account.as_bytes());
+        }
+
+        buf
+    }
+
+    /// Returns the hash to sign for the encoded unsigned batch.
+    pub fn signing_hash(unsigned: &[u8]) -> H256 {
+        H256::from(keccak256(unsigned))
+    }
+
+    /// Signs and encodes the batch, which could be submitted as a log entry.
+    pub fn encode_signed(&self, wallet: &LocalWallet) -> Result<Vec<u8>> {
+        let mut buf = self.encode_unsigned();
+        let signature = wallet.sign_hash(Self::signing_hash(&buf))?;
+        buf.extend_from_slice(&signature.to_vec());
+        Ok(buf)
+    }
+}
+
+impl SignedKvBatch {
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        if data.len() < SIGNATURE_SIZE {
+            bail!("batch too short, len = {}", data.len());
+        }
+
+        let (unsigned, signature) = data.split_at(data.len() - SIGNATURE_SIZE);
+        let signature = Signature::try_from(signature)?;
+        let sender = signature.recover(KvBatch::signing_hash(unsigned))?;
+
+        let mut reader = Reader { data: unsigned };
+        let version = reader.read_u8()?;
+        if version != BATCH_VERSION {
+            bail!("unsupported batch version {}", version);
+        }
+
+        let mut batch = KvBatch::default();
+
+        for _ in 0..reader.read_u32()? {
+            batch.writes.push(KvWrite {
+                stream_id: reader.read_stream_id()?,
+                key: reader.read_bytes()?,
+                value: reader.read_bytes()?,
+            });
+        }
+
+        for _ in 0..reader.read_u32()? {
+            batch.acls.push(KvAcl {
+                op: AclOp::try_from(reader.read_u8()?)?,
+                stream_id: reader.read_stream_id()?,
+                key: reader.read_bytes()?,
+                account: Address::from_slice(reader.read(Address::len_bytes())?),
+            });
+        }
+
+        if !reader.data.is_empty() {
+            bail!("{} bytes left after batch decoded", reader.data.len());
+        }
+
+        Ok(SignedKvBatch { sender, batch })
+    }
+}
+
+pub(crate) fn stream_id_to_bytes(stream_id: &U256) -> [u8; STREAM_ID_SIZE] {
+    let mut buf = [0u8; STREAM_ID_SIZE];
+    stream_id.to_big_endian(&mut buf);
+    buf
+}
+
+fn put_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    buf.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+    buf.extend_from_slice(bytes);
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn read(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.data.len() < len {
+            bail!("unexpected end of batch");
+        }
+
+        let (value, left) = self.data.split_at(len);
+        self.data = left;
+        Ok(value)
+    }
+
+    fn read_u8(&mut self) -> Result<u8> {
+        Ok(self.read(1)?[0])
+    }
+
+    fn read_u32(&mut self) -> Result<u32> {
+        Ok(u32::from_be_bytes(self.read(4)?.try_into()?))
+    }
+
+    fn read_stream_id(&mut self) -> Result<U256> {
+        Ok(U256::from_big_endian(self.read(STREAM_ID_SIZE)?))
+    }
+
+    fn read_bytes(&mut self) -> Result<Vec<u8>> {
+        let len = self.read_u32()? as usize;
+        Ok(self.read(len)?.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AclOp, KvAcl, KvBatch, KvWrite, SignedKvBatch};
+    use ethereum_types::{Address, U256};
+    use ethers::signers::{LocalWallet, Signer};
+
+    #[test]
+    fn test_batch_codec() {
+        let wallet = LocalWallet::new(&mut rand::thread_rng());
+        let batch = KvBatch {
+            writes: vec![
+                KvWrite {
+                    stream_id: U256::from(1),
+                    key: b"foo".to_vec(),
+                    value: b"bar".to_vec(),
+                },
+                KvWrite {
+                    stream_id: U256::from(2),
+                    key: b"empty".to_vec(),
+                    value: vec![],
+                },
+            ],
+            acls: vec![KvAcl {
+                op: AclOp::GrantWriter,
+                stream_id: U256::from(1),
+                key: b"foo".to_vec(),
+                account: Address::repeat_byte(7),
+            }],
+        };
+
+        let data = batch.encode_signed(&wallet).unwrap();
+        let decoded = SignedKvBatch::decode(&data).unwrap();
+        assert_eq!(decoded.sender, wallet.address());
+        assert_eq!(decoded.batch, batch);
+
+        // truncated or tampered data
+        assert!(SignedKvBatch::decode(&data[1..]).is_err());
+        let mut tampered = data.clone();
+        tampered[10] ^= 1;
+        assert!(SignedKvBatch::decode(&tampered)
+            .map(|v| v.sender != wallet.address())
+            .unwrap_or(true));
+    }
+}
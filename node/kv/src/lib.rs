@@ -0,0 +1,41 @@
+//! Key-value runtime upon the log layer, see `docs/k-v-store.md`.
+//!
+//! Key-value batches are submitted as log entries tagged with stream ids, and applied in the
+//! order of transaction sequence number once finalized.
+
+mod batch;
+mod runtime;
+mod store;
+
+use ethereum_types::U256;
+use serde::Deserialize;
+
+pub use batch::{AclOp, KvAcl, KvBatch, KvWrite, SignedKvBatch, BATCH_VERSION};
+pub use runtime::KvRuntime;
+pub use store::{KeyValue, KeyValuePage, KvStore};
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub enabled: bool,
+    /// Streams to replay, or all streams if empty.
+    pub stream_ids: Vec<U256>,
+    /// Maximum size of a key-value batch, and larger ones will be skipped.
+    pub max_batch_size: usize,
+    /// Interval to check the next transaction if not finalized yet.
+    pub poll_interval_ms: u64,
+    /// Maximum time to wait for a transaction to be finalized, and it will be skipped then.
+    pub finalize_timeout_secs: u64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            enabled: false,
+            stream_ids: vec![],
+            max_batch_size: 16 * 1024 * 1024,
+            poll_interval_ms: 1000,
+            finalize_timeout_secs: 24 * 3600,
+        }
+    }
+}
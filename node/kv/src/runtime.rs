@@ -0,0 +1,159 @@
+use crate::batch::SignedKvBatch;
+use crate::store::KvStore;
+use crate::Config;
+use anyhow::{bail, Result};
+use ethereum_types::U256;
+use shared_types::{bytes_to_chunks, Transaction};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use storage_async::Store;
+use task_executor::TaskExecutor;
+use tracing::{debug, error, info, warn};
+
+/// Replays finalized transactions from the log store in order, and applies the key-value
+/// batches into `KvStore`.
+pub struct KvRuntime {
+    config: Config,
+    store: Arc<KvStore>,
+    log_store: Arc<Store>,
+    /// The transaction waiting to be finalized, and since when.
+    waiting: Option<(u64, Instant)>,
+}
+
+impl KvRuntime {
+    pub fn spawn(
+        executor: TaskExecutor,
+        config: Config,
+        store: Arc<KvStore>,
+        log_store: Arc<Store>,
+    ) -> Result<()> {
+        // key-value batches out of shard could not be replayed
+        let shard_config = log_store.get_store().get_shard_config();
+        if shard_config.num_shard > 1 {
+            bail!(
+                "Key-value runtime requires all data, but node is sharded: {:?}",
+                shard_config
+            );
+        }
+
+        info!(next_tx_seq = %store.next_tx_seq()?, "Start to replay key-value batches");
+
+        let runtime = KvRuntime {
+            config,
+            store,
+            log_store,
+            waiting: None,
+        };
+        executor.spawn(runtime.start(), "kv_runtime");
+
+        Ok(())
+    }
+
+    async fn start(mut self) {
+        let poll_interval = Duration::from_millis(self.config.poll_interval_ms);
+
+        loop {
+            match self.replay_next().await {
+                Ok(true) => {}
+                Ok(false) => tokio::time::sleep(poll_interval).await,
+                Err(e) => {
+                    error!(%e, "Failed to replay key-value batch");
+                    tokio::time::sleep(poll_interval).await;
+                }
+            }
+        }
+    }
+
+    /// Replays the next transaction, and returns `false` if it is not ready yet.
+    async fn replay_next(&mut self) -> Result<bool> {
+        let tx_seq = self.store.next_tx_seq()?;
+        let tx = match self.log_store.get_tx_by_seq_number(tx_seq).await? {
+            Some(tx) => tx,
+            None => return Ok(false),
+        };
+
+        let stream_ids = self.interested_stream_ids(&tx);
+        if stream_ids.is_empty() {
+            self.store.skip_tx(tx_seq)?;
+            return Ok(true);
+        }
+
+        if !self.log_store.check_tx_completed(tx_seq).await? {
+            if self.log_store.check_tx_pruned(tx_seq).await? {
+                warn!(%tx_seq, "Skip key-value batch that already pruned");
+                self.store.skip_tx(tx_seq)?;
+                return Ok(true);
+            }
+
+            // wait for the file to be finalized, but not forever
+            let since = match self.waiting {
+                Some((seq, since)) if seq == tx_seq => since,
+                _ => self.waiting.insert((tx_seq, Instant::now())).1,
+            };
+            let timeout = Duration::from_secs(self.config.finalize_timeout_secs);
+            if since.elapsed() < timeout {
+                return Ok(false);
+            }
+
+            warn!(%tx_seq, ?timeout, "Skip key-value batch that not finalized in time");
+            self.store.skip_tx(tx_seq)?;
+            return Ok(true);
+        }
+
+        if tx.size as usize > self.config.max_batch_size {
+            warn!(%tx_seq, size = %tx.size, "Skip key-value batch that is too large");
+            self.store.skip_tx(tx_seq)?;
+            return Ok(true);
+        }
+
+        // Skipping a finalized batch leads to inconsistent state, so retry until available,
+        // e.g. the shard config changed.
+        let data = match self.read_data(&tx).await? {
+            Some(data) => data,
+            None => bail!("Data of key-value batch unavailable, tx_seq = {}", tx_seq),
+        };
+
+        match SignedKvBatch::decode(&data) {
+            Ok(batch) => {
+                debug!(%tx_seq, sender = ?batch.sender, "Apply key-value batch");
+                self.store.apply(tx_seq, &stream_ids, &batch)?;
+            }
+            Err(e) => {
+                debug!(%tx_seq, %e, "Skip invalid key-value batch");
+                self.store.skip_tx(tx_seq)?;
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Returns the stream ids of transaction to replay, or empty if none of them is concerned.
+    fn interested_stream_ids(&self, tx: &Transaction) -> Vec<U256> {
+        tx.stream_ids
+            .iter()
+            .filter(|id| self.config.stream_ids.is_empty() || self.config.stream_ids.contains(id))
+            .cloned()
+            .collect()
+    }
+
+    async fn read_data(&self, tx: &Transaction) -> Result<Option<Vec<u8>>> {
+        let num_chunks = bytes_to_chunks(tx.size as usize);
+        if num_chunks == 0 {
+            return Ok(Some(vec![]));
+        }
+
+        let mut data = match self
+            .log_store
+            .get_chunks_by_tx_and_index_range(tx.seq, 0, num_chunks)
+            .await?
+        {
+            Some(chunks) => chunks.data,
+            None => return Ok(None),
+        };
+
+        // strip the padding of the last chunk
+        data.truncate(tx.size as usize);
+
+        Ok(Some(data))
+    }
+}
@@ -0,0 +1,682 @@
+use crate::batch::{stream_id_to_bytes, AclOp, KvAcl, KvWrite, SignedKvBatch};
+use anyhow::{anyhow, bail, Result};
+use ethereum_types::{Address, U256};
+use kvdb::{DBTransaction, KeyValueDB};
+use kvdb_rocksdb::{Database, DatabaseConfig};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+pub const COL_DATA: u32 = 0;
+pub const COL_OWNER: u32 = 1;
+pub const COL_WRITER: u32 = 2;
+pub const COL_MISC: u32 = 3;
+pub const COL_NUM: u32 = 4;
+
+const NEXT_TX_SEQ_KEY: &[u8] = b"next_tx_seq";
+
+/// Terminates the escaped key in data keys, which is less than any escaped key byte.
+const DATA_KEY_TERMINATOR: [u8; 2] = [0, 0];
+const STREAM_ID_SIZE: usize = 32;
+const VERSION_SIZE: usize = 8;
+
+/// Maximum number of seeks and entries scanned by `get_range` at a time.
+const MAX_RANGE_SCAN: usize = 10_000;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyValue {
+    pub key: Vec<u8>,
+    pub value: Vec<u8>,
+    /// Sequence number of the transaction that writes the value.
+    pub version: u64,
+}
+
+/// A page of key-values in ascending order.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct KeyValuePage {
+    pub values: Vec<KeyValue>,
+    /// The key to continue the scan if there may be more key-values.
+    pub next_key: Option<Vec<u8>>,
+}
+
+/// Materialized key-value state, which is versioned by the transaction sequence number.
+///
+/// Columns:
+/// - `COL_DATA`: `stream_id | escaped key | 0x0000 | version(u64)` => value, where `0x00` in
+///   key is escaped as `0x00ff`, so that data keys are sorted by key and then version
+/// - `COL_OWNER`: `stream_id | key` => owner
+/// - `COL_WRITER`: `stream_id | key | account` => empty
+/// - `COL_MISC`: replay progress
+pub struct KvStore {
+    db: Arc<dyn KeyValueDB>,
+}
+
+impl KvStore {
+    pub fn rocksdb(path: impl AsRef<Path>) -> Result<Self> {
+        let mut db_config = DatabaseConfig::with_columns(COL_NUM);
+        db_config.enable_statistics = true;
+        let db = Arc::new(Database::open(&db_config, path)?);
+        Ok(KvStore { db })
+    }
+
+    pub fn memorydb() -> Self {
+        KvStore {
+            db: Arc::new(kvdb_memorydb::create(COL_NUM)),
+        }
+    }
+
+    /// Returns the sequence number of the next transaction to replay.
+    pub fn next_tx_seq(&self) -> Result<u64> {
+        match self.db.get(COL_MISC, NEXT_TX_SEQ_KEY)? {
+            Some(v) => Ok(u64::from_be_bytes(
+                v.as_slice().try_into().map_err(|e| anyhow!("{:?}", e))?,
+            )),
+            None => Ok(0),
+        }
+    }
+
+    /// Skips the transaction which has nothing to apply.
+    pub fn skip_tx(&self, tx_seq: u64) -> Result<()> {
+        let mut db_tx = self.db.transaction();
+        self.put_next_tx_seq(&mut db_tx, tx_seq)?;
+        Ok(self.db.write(db_tx)?)
+    }
+
+    /// Applies the batch of transaction `tx_seq` to the specified streams.
+    ///
+    /// Updates of a stream are applied atomically, and are rejected altogether if any of them
+    /// is not authorized. So, nodes that replay different sets of streams always agree on the
+    /// state of common streams.
+    pub fn apply(&self, tx_seq: u64, stream_ids: &[U256], batch: &SignedKvBatch) -> Result<()> {
+        let mut db_tx = self.db.transaction();
+        self.put_next_tx_seq(&mut db_tx, tx_seq)?;
+
+        let mut updates: BTreeMap<U256, (Vec<&KvWrite>, Vec<&KvAcl>)> = BTreeMap::new();
+        for write in batch.batch.writes.iter() {
+            updates.entry(write.stream_id).or_default().0.push(write);
+        }
+        for acl in batch.batch.acls.iter() {
+            updates.entry(acl.stream_id).or_default().1.push(acl);
+        }
+
+        for (stream_id, (writes, acls)) in updates {
+            if !stream_ids.contains(&stream_id) {
+                debug!(%tx_seq, %stream_id, "Ignore updates of stream");
+                continue;
+            }
+
+            let mut acl_view = AclView::new(self);
+            if let Err(e) = acl_view.check(batch.sender, &writes, &acls) {
+                warn!(%tx_seq, %stream_id, sender = ?batch.sender, %e, "Reject updates of stream");
+                continue;
+            }
+
+            acl_view.commit(&mut db_tx);
+            for write in writes {
+                db_tx.put(
+                    COL_DATA,
+                    &data_key(&write.stream_id, &write.key, tx_seq),
+                    &write.value,
+                );
+            }
+        }
+
+        Ok(self.db.write(db_tx)?)
+    }
+
+    /// Returns the latest value of key no later than `version`.
+    pub fn get(&self, stream_id: &U256, key: &[u8], version: u64) -> Result<Option<KeyValue>> {
+        let mut result: Option<KeyValue> = None;
+        // Versions of the same key are sorted in ascending order.
+        for item in self
+            .db
+            .iter_with_prefix(COL_DATA, &data_key_prefix(stream_id, key))
+        {
+            let (db_key, value) = item?;
+            let (_, entry_version) = decode_data_key(&db_key)?;
+            if entry_version > version {
+                break;
+            }
+
+            result = Some(KeyValue {
+                key: key.to_vec(),
+                value,
+                version: entry_version,
+            });
+        }
+
+        Ok(result)
+    }
+
+    /// Returns the latest values no later than `version` for keys within
+    /// `[start_key, end_key)` in ascending order.
+    ///
+    /// The scan is bounded, so the page may contain less than `limit` values, and `next_key`
+    /// is returned to continue the scan if there may be more values.
+    pub fn get_range(
+        &self,
+        stream_id: &U256,
+        start_key: &[u8],
+        end_key: Option<&[u8]>,
+        version: u64,
+        limit: usize,
+    ) -> Result<KeyValuePage> {
+        self.get_range_bounded(
+            stream_id,
+            start_key,
+            end_key,
+            version,
+            limit,
+            MAX_RANGE_SCAN,
+        )
+    }
+
+    fn get_range_bounded(
+        &self,
+        stream_id: &U256,
+        start_key: &[u8],
+        end_key: Option<&[u8]>,
+        version: u64,
+        limit: usize,
+        max_scan: usize,
+    ) -> Result<KeyValuePage> {
+        let mut page = KeyValuePage::default();
+        if limit == 0 {
+            return Ok(page);
+        }
+
+        // Number of seeks and entries scanned.
+        let mut scanned = 0;
+        let mut prefixes = data_key_seek_prefixes(stream_id, start_key).peekable();
+        while let Some((prefix, _)) = prefixes.next() {
+            scanned += 1;
+
+            // The key being iterated and its latest value, since versions are sorted in
+            // ascending order under the same key.
+            let mut current_key: Option<Vec<u8>> = None;
+            let mut current: Option<KeyValue> = None;
+            for item in self.db.iter_with_prefix(COL_DATA, &prefix) {
+                let (db_key, value) = item?;
+                let (entry_key, entry_version) = decode_data_key(&db_key)?;
+                if end_key.map_or(false, |end| entry_key.as_slice() >= end) {
+                    page.values.extend(current);
+                    return Ok(page);
+                }
+
+                if current_key.as_ref() != Some(&entry_key) {
+                    page.values.extend(current.take());
+                    if page.values.len() >= limit || scanned >= max_scan {
+                        page.next_key = Some(entry_key);
+                        return Ok(page);
+                    }
+                    current_key = Some(entry_key.clone());
+                }
+
+                scanned += 1;
+                if entry_version <= version {
+                    current = Some(KeyValue {
+                        key: entry_key,
+                        value,
+                        version: entry_version,
+                    });
+                }
+            }
+            page.values.extend(current);
+
+            // keys never span across prefixes
+            if let Some((_, next_key)) = prefixes.peek() {
+                if end_key.map_or(false, |end| next_key.as_slice() >= end) {
+                    return Ok(page);
+                }
+
+                if page.values.len() >= limit || scanned >= max_scan {
+                    page.next_key = Some(next_key.clone());
+                    return Ok(page);
+                }
+            }
+        }
+
+        Ok(page)
+    }
+
+    pub fn get_owner(&self, stream_id: &U256, key: &[u8]) -> Result<Option<Address>> {
+        Ok(self
+            .db
+            .get(COL_OWNER, &owner_key(stream_id, key))?
+            .map(|v| Address::from_slice(&v)))
+    }
+
+    pub fn is_writer(&self, stream_id: &U256, key: &[u8], account: &Address) -> Result<bool> {
+        Ok(self
+            .db
+            .get(COL_WRITER, &writer_key(stream_id, key, account))?
+            .is_some())
+    }
+
+    fn put_next_tx_seq(&self, db_tx: &mut DBTransaction, tx_seq: u64) -> Result<()> {
+        let next_tx_seq = self.next_tx_seq()?;
+        if tx_seq != next_tx_seq {
+            bail!(
+                "unexpected tx seq to apply, expected = {}, actual = {}",
+                next_tx_seq,
+                tx_seq
+            );
+        }
+
+        db_tx.put(COL_MISC, NEXT_TX_SEQ_KEY, &(tx_seq + 1).to_be_bytes());
+        Ok(())
+    }
+}
+
+/// Ownership of keys with uncommitted updates.
+struct AclView<'a> {
+    store: &'a KvStore,
+    owners: HashMap<(U256, Vec<u8>), Option<Address>>,
+    writers: HashMap<(U256, Vec<u8>, Address), bool>,
+}
+
+impl<'a> AclView<'a> {
+    fn new(store: &'a KvStore) -> Self {
+        AclView {
+            store,
+            owners: HashMap::new(),
+            writers: HashMap::new(),
+        }
+    }
+
+    /// Checks updates in order, where the first account that updates a key becomes the owner.
+    /// Values could be written by the owner or granted writers, and ACLs could only be updated
+    /// by the owner.
+    fn check(&mut self, sender: Address, writes: &[&KvWrite], acls: &[&KvAcl]) -> Result<()> {
+        for write in writes {
+            match self.owner(&write.stream_id, &write.key)? {
+                None => self.set_owner(&write.stream_id, &write.key, sender),
+                Some(owner) if owner == sender => {}
+                Some(_) => {
+                    if !self.is_writer(&write.stream_id, &write.key, &sender)? {
+                        bail!("not allowed to write key {:?}", write.key);
+                    }
+                }
+            }
+        }
+
+        for acl in acls {
+            match self.owner(&acl.stream_id, &acl.key)? {
+                None => self.set_owner(&acl.stream_id, &acl.key, sender),
+                Some(owner) if owner == sender => {}
+                Some(_) => bail!("not owner of key {:?}", acl.key),
+            }
+
+            match acl.op {
+                AclOp::GrantWriter => self.set_writer(acl, true),
+                AclOp::RevokeWriter => self.set_writer(acl, false),
+                AclOp::TransferOwner => self.set_owner(&acl.stream_id, &acl.key, acl.account),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn commit(self, db_tx: &mut DBTransaction) {
+        for ((stream_id, key), owner) in self.owners {
+            if let Some(owner) = owner {
+                db_tx.put(COL_OWNER, &owner_key(&stream_id, &key), owner.as_bytes());
+            }
+        }
+
+        for ((stream_id, key, account), allowed) in self.writers {
+            let db_key = writer_key(&stream_id, &key, &account);
+            if allowed {
+                db_tx.put(COL_WRITER, &db_key, &[]);
+            } else {
+                db_tx.delete(COL_WRITER, &db_key);
+            }
+        }
+    }
+
+    fn owner(&mut self, stream_id: &U256, key: &[u8]) -> Result<Option<Address>> {
+        let cache_key = (*stream_id, key.to_vec());
+        if let Some(owner) = self.owners.get(&cache_key) {
+            return Ok(*owner);
+        }
+
+        let owner = self.store.get_owner(stream_id, key)?;
+        self.owners.insert(cache_key, owner);
+        Ok(owner)
+    }
+
+    fn set_owner(&mut self, stream_id: &U256, key: &[u8], owner: Address) {
+        self.owners.insert((*stream_id, key.to_vec()), Some(owner));
+    }
+
+    fn is_writer(&mut self, stream_id: &U256, key: &[u8], account: &Address) -> Result<bool> {
+        let cache_key = (*stream_id, key.to_vec(), *account);
+        if let Some(allowed) = self.writers.get(&cache_key) {
+            return Ok(*allowed);
+        }
+
+        let allowed = self.store.is_writer(stream_id, key, account)?;
+        self.writers.insert(cache_key, allowed);
+        Ok(allowed)
+    }
+
+    fn set_writer(&mut self, acl: &KvAcl, allowed: bool) {
+        self.writers
+            .insert((acl.stream_id, acl.key.clone(), acl.account), allowed);
+    }
+}
+
+/// Returns the prefix of data keys for all versions of `key`.
+fn data_key_prefix(stream_id: &U256, key: &[u8]) -> Vec<u8> {
+    let mut db_key = stream_id_to_bytes(stream_id).to_vec();
+    for b in key {
+        db_key.push(*b);
+        if *b == 0 {
+            db_key.push(0xff);
+        }
+    }
+    db_key.extend_from_slice(&DATA_KEY_TERMINATOR);
+    db_key
+}
+
+fn data_key(stream_id: &U256, key: &[u8], version: u64) -> Vec<u8> {
+    let mut db_key = data_key_prefix(stream_id, key);
+    db_key.extend_from_slice(&version.to_be_bytes());
+    db_key
+}
+
+/// Returns the data key prefixes in ascending order that cover all keys no less than
+/// `start_key`, since `kvdb` could not seek. That is the prefix of `start_key` itself, and
+/// then for each byte from the last one, the prefixes with a greater byte at that position.
+///
+/// Each prefix is returned along with the smallest key it covers, and prefixes that could not
+/// be a valid escaped key, e.g. `0x00` followed by a byte other than `0xff`, are skipped
+/// without a seek.
+fn data_key_seek_prefixes(
+    stream_id: &U256,
+    start_key: &[u8],
+) -> impl Iterator<Item = (Vec<u8>, Vec<u8>)> {
+    let start = data_key_prefix(stream_id, start_key);
+    let greater_start = start.clone();
+    let key_positions = (STREAM_ID_SIZE..greater_start.len()).rev();
+    std::iter::once(start)
+        .chain(key_positions.flat_map(move |i| {
+            let base = greater_start[..i].to_vec();
+            (greater_start[i] as u16 + 1..=u8::MAX as u16).map(move |b| {
+                let mut prefix = base.clone();
+                prefix.push(b as u8);
+                prefix
+            })
+        }))
+        .filter_map(|prefix| {
+            let key = decode_key_prefix(&prefix[STREAM_ID_SIZE..])?;
+            Some((prefix, key))
+        })
+}
+
+/// Decodes the smallest key whose escaped form starts with `prefix`, or `None` if no key
+/// could match the prefix.
+fn decode_key_prefix(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut key = Vec::with_capacity(prefix.len());
+    let mut bytes = prefix.iter();
+    loop {
+        match (bytes.next(), bytes.as_slice()) {
+            (None, _) => return Some(key),
+            (Some(0), [0xff, ..]) => {
+                key.push(0);
+                bytes.next();
+            }
+            (Some(0), [] | [0]) => return Some(key),
+            (Some(0), _) => return None,
+            (Some(b), _) => key.push(*b),
+        }
+    }
+}
+
+/// Decodes the key and version from the data key.
+fn decode_data_key(db_key: &[u8]) -> Result<(Vec<u8>, u64)> {
+    if db_key.len() < STREAM_ID_SIZE + DATA_KEY_TERMINATOR.len() + VERSION_SIZE {
+        bail!("invalid data key length {}", db_key.len());
+    }
+
+    let (rest, version) = db_key.split_at(db_key.len() - VERSION_SIZE);
+    let mut key = Vec::with_capacity(rest.len() - STREAM_ID_SIZE);
+    let mut bytes = rest[STREAM_ID_SIZE..].iter();
+    loop {
+        match (bytes.next(), bytes.as_slice()) {
+            (Some(0), [0xff, ..]) => {
+                key.push(0);
+                bytes.next();
+            }
+            (Some(0), [0]) => break,
+            (Some(0), _) | (None, _) => bail!("invalid data key {:?}", db_key),
+            (Some(b), _) => key.push(*b),
+        }
+    }
+
+    Ok((key, u64::from_be_bytes(version.try_into()?)))
+}
+
+fn owner_key(stream_id: &U256, key: &[u8]) -> Vec<u8> {
+    let mut db_key = stream_id_to_bytes(stream_id).to_vec();
+    db_key.extend_from_slice(key);
+    db_key
+}
+
+fn writer_key(stream_id: &U256, key: &[u8], account: &Address) -> Vec<u8> {
+    let mut db_key = owner_key(stream_id, key);
+    db_key.extend_from_slice(account.as_bytes());
+    db_key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KvStore;
+    use crate::batch::{AclOp, KvAcl, KvBatch, KvWrite, SignedKvBatch};
+    use ethereum_types::{Address, U256};
+
+    fn write(stream_id: u64, key: &[u8], value: &[u8]) -> KvWrite {
+        KvWrite {
+            stream_id: U256::from(stream_id),
+            key: key.to_vec(),
+            value: value.to_vec(),
+        }
+    }
+
+    fn signed(sender: Address, writes: Vec<KvWrite>, acls: Vec<KvAcl>) -> SignedKvBatch {
+        SignedKvBatch {
+            sender,
+            batch: KvBatch { writes, acls },
+        }
+    }
+
+    #[test]
+    fn test_versioned_get() {
+        let store = KvStore::memorydb();
+        let alice = Address::repeat_byte(1);
+        let streams = [U256::from(1)];
+
+        let batch = signed(
+            alice,
+            vec![write(1, b"a", b"1"), write(1, b"ab", b"2")],
+            vec![],
+        );
+        store.apply(0, &streams, &batch).unwrap();
+        store.skip_tx(1).unwrap();
+        let batch = signed(alice, vec![write(1, b"a", b"3")], vec![]);
+        store.apply(2, &streams, &batch).unwrap();
+        assert_eq!(store.next_tx_seq().unwrap(), 3);
+
+        let get = |key: &[u8], version| store.get(&streams[0], key, version).unwrap();
+        assert_eq!(get(b"a", u64::MAX).unwrap().value, b"3");
+        assert_eq!(get(b"a", 1).unwrap().value, b"1");
+        assert_eq!(get(b"ab", u64::MAX).unwrap().version, 0);
+        assert!(get(b"b", u64::MAX).is_none());
+
+        let range = store
+            .get_range(&streams[0], b"a", None, u64::MAX, 10)
+            .unwrap()
+            .values;
+        assert_eq!(range.len(), 2);
+        assert_eq!((range[0].key.as_slice(), range[0].version), (&b"a"[..], 2));
+        assert_eq!((range[1].key.as_slice(), range[1].version), (&b"ab"[..], 0));
+
+        let range = store
+            .get_range(&streams[0], b"a", Some(b"ab"), 1, 10)
+            .unwrap()
+            .values;
+        assert_eq!(range.len(), 1);
+        assert_eq!(range[0].value, b"1");
+
+        // keys are sorted by raw bytes, including the ones with zero bytes
+        let batch = signed(
+            alice,
+            vec![write(1, b"a\0", b"4"), write(1, b"\0", b"5")],
+            vec![],
+        );
+        store.apply(3, &streams, &batch).unwrap();
+        let keys = |start: &[u8], limit| {
+            store
+                .get_range(&streams[0], start, None, u64::MAX, limit)
+                .unwrap()
+                .values
+                .into_iter()
+                .map(|v| v.key)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            keys(b"", 10),
+            vec![
+                b"\0".to_vec(),
+                b"a".to_vec(),
+                b"a\0".to_vec(),
+                b"ab".to_vec()
+            ]
+        );
+        assert_eq!(keys(b"\x01", 2), vec![b"a".to_vec(), b"a\0".to_vec()]);
+        assert_eq!(keys(b"a\x01", 10), vec![b"ab".to_vec()]);
+        assert!(keys(b"b", 10).is_empty());
+        assert!(keys(b"", 0).is_empty());
+        assert_eq!(get(b"a\0", u64::MAX).unwrap().value, b"4");
+
+        // pages of bounded scan could continue with the next key
+        let mut pages = vec![];
+        let mut start = Some(vec![]);
+        while let Some(start_key) = start {
+            let page = store
+                .get_range_bounded(&streams[0], &start_key, None, u64::MAX, 10, 4)
+                .unwrap();
+            pages.push(page.values.into_iter().map(|v| v.key).collect::<Vec<_>>());
+            start = page.next_key;
+        }
+        assert!(pages.len() > 1);
+        assert_eq!(pages.concat(), keys(b"", 10));
+
+        // tx seq must be applied in order
+        assert!(store.skip_tx(5).is_err());
+    }
+
+    #[test]
+    fn test_acl() {
+        let store = KvStore::memorydb();
+        let (alice, bob) = (Address::repeat_byte(1), Address::repeat_byte(2));
+        let streams = [U256::from(1), U256::from(2)];
+        let stream_id = streams[0];
+
+        // alice owns key and bob is not allowed to write
+        store
+            .apply(
+                0,
+                &streams,
+                &signed(alice, vec![write(1, b"k", b"a")], vec![]),
+            )
+            .unwrap();
+        assert_eq!(store.get_owner(&stream_id, b"k").unwrap(), Some(alice));
+        store
+            .apply(
+                1,
+                &streams,
+                &signed(bob, vec![write(1, b"k", b"b")], vec![]),
+            )
+            .unwrap();
+        assert_eq!(
+            store
+                .get(&stream_id, b"k", u64::MAX)
+                .unwrap()
+                .unwrap()
+                .value,
+            b"a"
+        );
+
+        // updates of stream are rejected altogether, but other streams are not affected
+        let batch = signed(
+            bob,
+            vec![
+                write(1, b"x", b"b"),
+                write(1, b"k", b"b"),
+                write(2, b"k", b"b"),
+            ],
+            vec![],
+        );
+        store.apply(2, &streams, &batch).unwrap();
+        assert!(store.get(&stream_id, b"x", u64::MAX).unwrap().is_none());
+        assert!(store.get(&streams[1], b"k", u64::MAX).unwrap().is_some());
+
+        // grant bob to write
+        let grant = KvAcl {
+            op: AclOp::GrantWriter,
+            stream_id,
+            key: b"k".to_vec(),
+            account: bob,
+        };
+        store
+            .apply(3, &streams, &signed(bob, vec![], vec![grant.clone()]))
+            .unwrap();
+        assert!(!store.is_writer(&stream_id, b"k", &bob).unwrap());
+        store
+            .apply(4, &streams, &signed(alice, vec![], vec![grant.clone()]))
+            .unwrap();
+        assert!(store.is_writer(&stream_id, b"k", &bob).unwrap());
+        store
+            .apply(
+                5,
+                &streams,
+                &signed(bob, vec![write(1, b"k", b"b")], vec![]),
+            )
+            .unwrap();
+        assert_eq!(
+            store
+                .get(&stream_id, b"k", u64::MAX)
+                .unwrap()
+                .unwrap()
+                .value,
+            b"b"
+        );
+
+        // revoke and transfer ownership
+        let revoke = KvAcl {
+            op: AclOp::RevokeWriter,
+            ..grant.clone()
+        };
+        let transfer = KvAcl {
+            op: AclOp::TransferOwner,
+            ..grant
+        };
+        store
+            .apply(6, &streams, &signed(alice, vec![], vec![revoke, transfer]))
+            .unwrap();
+        assert!(!store.is_writer(&stream_id, b"k", &bob).unwrap());
+        assert_eq!(store.get_owner(&stream_id, b"k").unwrap(), Some(bob));
+
+        // updates of streams not specified are ignored
+        store
+            .apply(
+                7,
+                &streams[..1],
+                &signed(bob, vec![write(2, b"y", b"b")], vec![]),
+            )
+            .unwrap();
+        assert!(store.get(&streams[1], b"y", u64::MAX).unwrap().is_none());
+    }
+}
@@ -5,7 +5,7 @@ mod sync_manager;
 use ethers::prelude::H160;
 pub use sync_manager::{
     config::{CacheConfig, LogSyncConfig},
-    LogSyncEvent, LogSyncManager,
+    FlowReader, LogSyncEvent, LogSyncManager,
 };
 
 pub type ContractAddress = H160;
@@ -0,0 +1,37 @@
+use crate::sync_manager::log_entry_fetcher::LogEntryFetcher;
+use crate::LogSyncConfig;
+use anyhow::Result;
+use contract_interface::ZgsFlow;
+use ethereum_types::H256;
+use ethers::prelude::{Http, Provider};
+use ethers::providers::RetryClient;
+
+/// Reads the state of the flow contract on chain, so that other services could verify
+/// transactions received from peers before they are synced from blockchain.
+#[derive(Clone)]
+pub struct FlowReader {
+    flow_contract: ZgsFlow<Provider<RetryClient<Http>>>,
+}
+
+impl FlowReader {
+    pub async fn new(config: &LogSyncConfig) -> Result<Self> {
+        let flow_contract = LogEntryFetcher::new(config).await?.flow_contract();
+        Ok(Self { flow_contract })
+    }
+
+    /// Returns the number of transactions submitted on chain.
+    pub async fn num_submissions(&self) -> Result<u64> {
+        Ok(self.flow_contract.num_submissions().call().await?.as_u64())
+    }
+
+    /// Returns the flow root after the specified transaction is appended, which is zero for
+    /// transactions submitted before the contract upgrade.
+    pub async fn flow_root_by_tx_seq(&self, tx_seq: u64) -> Result<H256> {
+        let root = self
+            .flow_contract
+            .get_flow_root_by_tx_seq(tx_seq.into())
+            .call()
+            .await?;
+        Ok(H256::from_slice(&root))
+    }
+}
@@ -4,6 +4,7 @@ use crate::{ContractAddress, LogSyncConfig};
 use anyhow::{anyhow, bail, Result};
 use append_merkle::{Algorithm, Sha3Algorithm};
 use contract_interface::{SubmissionNode, SubmitFilter, ZgsFlow};
+use ethereum_types::U256;
 use ethers::abi::RawLog;
 use ethers::prelude::{BlockNumber, EthLogDecode, Http, Middleware, Provider};
 use ethers::providers::{HttpRateLimitRetryPolicy, RetryClient, RetryClientBuilder};
@@ -21,6 +22,8 @@ use tokio::sync::{
     RwLock,
 };
 
+const STREAM_ID_SIZE: usize = 32;
+
 pub struct LogEntryFetcher {
     contract_address: ContractAddress,
     log_page_size: u64,
@@ -761,7 +764,7 @@ pub enum LogFetchProgress {
 fn submission_event_to_transaction(e: SubmitFilter, block_number: u64) -> LogFetchProgress {
     LogFetchProgress::Transaction((
         Transaction {
-            stream_ids: vec![],
+            stream_ids: tags_to_stream_ids(&e.submission.tags),
             data: vec![],
             data_merkle_root: nodes_to_root(&e.submission.nodes),
             merkle_nodes: e
@@ -779,6 +782,18 @@ fn submission_event_to_transaction(e: SubmitFilter, block_number: u64) -> LogFet
     ))
 }
 
+/// Decodes the stream ids from submission tags, which are concatenated 32-byte stream ids.
+/// Tags in other formats are not regarded as stream ids.
+fn tags_to_stream_ids(tags: &[u8]) -> Vec<U256> {
+    if tags.is_empty() || tags.len() % STREAM_ID_SIZE != 0 {
+        return vec![];
+    }
+
+    tags.chunks_exact(STREAM_ID_SIZE)
+        .map(U256::from_big_endian)
+        .collect()
+}
+
 fn nodes_to_root(node_list: &[SubmissionNode]) -> DataRoot {
     let mut root: DataRoot = node_list.last().expect("not empty").root.into();
     for next_node in node_list[..node_list.len() - 1].iter().rev() {
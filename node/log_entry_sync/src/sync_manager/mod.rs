@@ -20,6 +20,8 @@ use tokio::sync::broadcast;
 use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
 use tokio::sync::{oneshot, RwLock};
 
+pub use flow_reader::FlowReader;
+
 const RETRY_WAIT_MS: u64 = 500;
 
 // A RPC query can return at most 10000 entries.
@@ -298,7 +300,15 @@ impl LogSyncManager {
     async fn put_tx(&mut self, tx: Transaction) -> Option<bool> {
         // We call this after process chain reorg, so the sequence number should match.
         match tx.seq.cmp(&self.next_tx_seq) {
-            std::cmp::Ordering::Less => Some(true),
+            // Transactions prefetched from peers before restart are confirmed here.
+            std::cmp::Ordering::Less => match self.store.get_tx_by_seq_number(tx.seq) {
+                Ok(Some(stored)) if stored != tx => {
+                    warn!("prefetched tx mismatch with chain: seq={}", tx.seq);
+                    self.process_reverted(tx.seq).await;
+                    Some(self.put_tx_inner(tx).await)
+                }
+                _ => Some(true),
+            },
             std::cmp::Ordering::Equal => {
                 debug!("log entry sync get entry: {:?}", tx);
                 Some(self.put_tx_inner(tx).await)
@@ -313,6 +323,28 @@ impl LogSyncManager {
         }
     }
 
+    /// Puts the transaction from chain into store. If the transaction has been prefetched from
+    /// peers, it is confirmed if the same, or the prefetched ones are reverted otherwise.
+    async fn put_or_confirm_tx(&mut self, tx: &Transaction) -> Result<()> {
+        if self.store.next_tx_seq() <= tx.seq {
+            match self.store.put_tx(tx.clone()) {
+                Ok(()) => return Ok(()),
+                // The transaction may be prefetched meanwhile.
+                Err(e) if self.store.next_tx_seq() <= tx.seq => return Err(e),
+                Err(_) => {}
+            }
+        }
+
+        match self.store.get_tx_by_seq_number(tx.seq)? {
+            Some(stored) if stored == *tx => Ok(()),
+            _ => {
+                warn!("prefetched tx mismatch with chain: seq={}", tx.seq);
+                self.process_reverted(tx.seq).await;
+                self.store.put_tx(tx.clone())
+            }
+        }
+    }
+
     /// `tx_seq` is the first reverted tx seq.
     async fn process_reverted(&mut self, tx_seq: u64) {
         warn!("revert for chain reorg: seq={}", tx_seq);
@@ -450,7 +482,7 @@ impl LogSyncManager {
 
     async fn put_tx_inner(&mut self, tx: Transaction) -> bool {
         let start_time = Instant::now();
-        let result = self.store.put_tx(tx.clone());
+        let result = self.put_or_confirm_tx(&tx).await;
 
         if let Err(e) = result {
             error!("put_tx error: e={:?}", e);
@@ -654,6 +686,7 @@ where
 
 pub(crate) mod config;
 mod data_cache;
+mod flow_reader;
 mod log_entry_fetcher;
 mod log_query;
 mod metrics;
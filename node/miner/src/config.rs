@@ -16,14 +16,37 @@ use ethers::signers::LocalWallet;
 use ethers::signers::Signer;
 use storage::config::ShardConfig;
 
+/// Config of PoRA mining workers, which is shared by the node and remote workers.
+#[derive(Debug, Clone)]
+pub struct MineWorkerConfig {
+    pub cpu_percentage: u64,
+    pub iter_batch: usize,
+    /// Number of mining workers, where 0 means all available CPU cores.
+    pub threads: usize,
+    pub cpu_affinity: Vec<usize>,
+    pub pora_hasher: String,
+}
+
+impl MineWorkerConfig {
+    /// Returns the number of mining workers.
+    pub(crate) fn num_workers(&self) -> usize {
+        if self.threads > 0 {
+            return self.threads;
+        }
+
+        std::thread::available_parallelism().map_or(1, |n| n.get())
+    }
+}
+
 pub struct MinerConfig {
     pub(crate) miner_id: Option<H256>,
     pub(crate) miner_key: H256,
     pub(crate) rpc_endpoint_url: String,
     pub(crate) mine_address: Address,
     pub(crate) flow_address: Address,
-    pub(crate) cpu_percentage: u64,
-    pub(crate) iter_batch: usize,
+    pub(crate) worker_config: MineWorkerConfig,
+    /// Maximum number of blocking threads to seal data in parallel.
+    pub(crate) seal_threads: usize,
     pub(crate) shard_config: ShardConfig,
     pub(crate) context_query_interval: Duration,
     pub(crate) rate_limit_retries: u32,
@@ -42,8 +65,8 @@ impl MinerConfig {
         rpc_endpoint_url: String,
         mine_address: Address,
         flow_address: Address,
-        cpu_percentage: u64,
-        iter_batch: usize,
+        worker_config: MineWorkerConfig,
+        seal_threads: usize,
         context_query_seconds: u64,
         shard_config: ShardConfig,
         rate_limit_retries: u32,
@@ -57,8 +80,8 @@ impl MinerConfig {
             rpc_endpoint_url,
             mine_address,
             flow_address,
-            cpu_percentage,
-            iter_batch,
+            worker_config,
+            seal_threads: seal_threads.max(1),
             shard_config,
             context_query_interval: Duration::from_secs(context_query_seconds),
             rate_limit_retries,
@@ -68,6 +91,12 @@ impl MinerConfig {
         })
     }
 
+    /// Returns the config with the given shard config, e.g. the one stored in db.
+    pub fn with_shard_config(mut self, shard_config: ShardConfig) -> Self {
+        self.shard_config = shard_config;
+        self
+    }
+
     pub(crate) fn make_provider(&self) -> Result<Arc<Provider<RetryClient<Http>>>, String> {
         Ok(Arc::new(Provider::new(
             RetryClientBuilder::default()
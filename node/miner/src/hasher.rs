@@ -0,0 +1,207 @@
+//! Hashing backends of PoRA, i.e. the scratch pad generation and the quality computation.
+//!
+//! Every backend must produce exactly the same results as the PoRA verification of the mine
+//! contract, which is guarded by the conformance tests against `ReferencePoraHasher`.
+use blake2::{Blake2b512, Digest};
+use ethereum_types::{H256, U256};
+use ethers::utils::keccak256;
+use std::sync::Arc;
+use tiny_keccak::{Hasher, Keccak};
+use zgs_spec::{BYTES_PER_SCRATCHPAD, BYTES_PER_SEAL};
+
+pub const BLAKE2B_OUTPUT_BYTES: usize = 64;
+pub const KECCAK256_OUTPUT_BYTES: usize = 32;
+
+const SCRATCH_PAD_CELLS: usize = BYTES_PER_SCRATCHPAD / BLAKE2B_OUTPUT_BYTES;
+
+/// Inputs of scratch pad generation that are shared by all nonces of a mining job.
+#[derive(Debug, Clone, Copy)]
+pub struct ScratchPadSeed {
+    pub miner_id: H256,
+    pub subtask_digest: H256,
+    pub range_digest: [u8; 32],
+}
+
+pub struct ScratchPad {
+    pub scratch_pad: [u8; BYTES_PER_SCRATCHPAD],
+    pub recall_seed: [u8; KECCAK256_OUTPUT_BYTES],
+    pub pad_seed: [u8; BLAKE2B_OUTPUT_BYTES],
+}
+
+/// A hashing backend of PoRA.
+pub trait PoraHasher: Send + Sync {
+    /// Returns the scratch pad of the nonce.
+    fn scratch_pad(&self, seed: &ScratchPadSeed, nonce: &H256) -> ScratchPad;
+
+    /// Returns the quality of sealed data mixed with the scratch pad, where a lower value
+    /// is better.
+    fn pora(
+        &self,
+        seal_index: usize,
+        mixed_data: &[u8; BYTES_PER_SEAL],
+        pad_seed: &[u8; BLAKE2B_OUTPUT_BYTES],
+    ) -> U256;
+}
+
+/// Returns the hasher of the given name in config.
+pub fn make_pora_hasher(name: &str) -> Result<Arc<dyn PoraHasher>, String> {
+    match name {
+        "reference" => Ok(Arc::new(ReferencePoraHasher)),
+        _ => Err(format!("Unknown PoRA hasher: {}", name)),
+    }
+}
+
+fn keccak(input: impl AsRef<[u8]>) -> [u8; KECCAK256_OUTPUT_BYTES] {
+    let mut hasher = Keccak::v256();
+    let mut output = [0u8; 32];
+    hasher.update(input.as_ref());
+    hasher.finalize(&mut output);
+    output
+}
+
+fn pad_seed(seed: &ScratchPadSeed, nonce: &H256) -> [u8; BLAKE2B_OUTPUT_BYTES] {
+    let mut hasher = Blake2b512::new();
+    hasher.update(seed.miner_id);
+    hasher.update(nonce);
+    hasher.update(seed.subtask_digest);
+    hasher.update(seed.range_digest);
+    hasher.finalize().into()
+}
+
+/// The straightforward implementation which follows the mine contract step by step.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReferencePoraHasher;
+
+impl PoraHasher for ReferencePoraHasher {
+    fn scratch_pad(&self, seed: &ScratchPadSeed, nonce: &H256) -> ScratchPad {
+        let mut digest = pad_seed(seed, nonce);
+        let pad_seed = digest;
+
+        let mut scratch_pad = [[0u8; BLAKE2B_OUTPUT_BYTES]; SCRATCH_PAD_CELLS];
+        for scratch_pad_cell in scratch_pad.iter_mut() {
+            let output0 = keccak256(digest);
+            digest[..32].copy_from_slice(&output0);
+            let output1 = keccak256(digest);
+            digest[32..].copy_from_slice(&output1);
+
+            *scratch_pad_cell = digest;
+        }
+
+        let scratch_pad: [u8; BYTES_PER_SCRATCHPAD] = unsafe { std::mem::transmute(scratch_pad) };
+        let recall_seed: [u8; KECCAK256_OUTPUT_BYTES] = keccak(digest);
+
+        ScratchPad {
+            scratch_pad,
+            recall_seed,
+            pad_seed,
+        }
+    }
+
+    #[inline]
+    fn pora(
+        &self,
+        seal_index: usize,
+        mixed_data: &[u8; BYTES_PER_SEAL],
+        pad_seed: &[u8; BLAKE2B_OUTPUT_BYTES],
+    ) -> U256 {
+        let mut hasher = Blake2b512::new();
+        hasher.update([0u8; 24]);
+        hasher.update((seal_index as u64).to_be_bytes());
+
+        hasher.update(pad_seed);
+        hasher.update([0u8; 32]);
+
+        hasher.update(mixed_data);
+
+        let digest = hasher.finalize();
+
+        U256::from_big_endian(&digest[0..32])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    fn test_seed() -> ScratchPadSeed {
+        ScratchPadSeed {
+            miner_id: H256::repeat_byte(1),
+            subtask_digest: H256::repeat_byte(2),
+            range_digest: [3u8; 32],
+        }
+    }
+
+    fn random_seed() -> ScratchPadSeed {
+        ScratchPadSeed {
+            miner_id: H256::random(),
+            subtask_digest: H256::random(),
+            range_digest: rand::thread_rng().gen(),
+        }
+    }
+
+    /// Checks that the hasher produces the same results as `ReferencePoraHasher`.
+    fn check_conformance(hasher: &dyn PoraHasher) {
+        let reference = ReferencePoraHasher;
+        let seed = random_seed();
+
+        for _ in 0..4 {
+            let nonce = H256::random();
+            let pad = hasher.scratch_pad(&seed, &nonce);
+            let expected = reference.scratch_pad(&seed, &nonce);
+            assert_eq!(pad.pad_seed, expected.pad_seed);
+            assert_eq!(pad.recall_seed, expected.recall_seed);
+            assert_eq!(pad.scratch_pad, expected.scratch_pad);
+        }
+
+        let mut mixed_data = [0u8; BYTES_PER_SEAL];
+        rand::thread_rng().fill(&mut mixed_data[..]);
+        let pad_seed = reference.scratch_pad(&seed, &H256::random()).pad_seed;
+        for seal_index in [0, 1, 15] {
+            assert_eq!(
+                hasher.pora(seal_index, &mixed_data, &pad_seed),
+                reference.pora(seal_index, &mixed_data, &pad_seed)
+            );
+        }
+    }
+
+    #[test]
+    fn test_reference_vectors() {
+        let hasher = ReferencePoraHasher;
+        let pad = hasher.scratch_pad(&test_seed(), &H256::repeat_byte(4));
+
+        assert_eq!(
+            hex::encode(pad.pad_seed),
+            "9f505b08fa372ff4acf9d83ac2ccbb3aa436e59307b4e78eb174b44750216ff7\
+             d9b928cac64af5a24ab164fd36a5a9f4fd12af8029bbcb7d82bf45f4a336fdcb"
+        );
+        assert_eq!(
+            hex::encode(pad.recall_seed),
+            "930f762abf3c16bb3b179b19a07ffe68ecba41ed08bbe76b8854be44c05da8e2"
+        );
+        assert_eq!(
+            hex::encode(keccak(pad.scratch_pad)),
+            "23b98b34d2420bfdce873f17ce63f3511a39436a159771a981c53078b097fee3"
+        );
+
+        let mut quality = [0u8; 32];
+        hasher
+            .pora(7, &[5u8; BYTES_PER_SEAL], &pad.pad_seed)
+            .to_big_endian(&mut quality);
+        assert_eq!(
+            hex::encode(quality),
+            "aba10847477e1b79f17adfd63ab6ad454759e52585f729e5c00d1761cdfbc359"
+        );
+    }
+
+    #[test]
+    fn test_reference_conformance() {
+        check_conformance(&ReferencePoraHasher);
+    }
+
+    #[test]
+    fn test_make_pora_hasher() {
+        assert!(make_pora_hasher("reference").is_ok());
+        assert!(make_pora_hasher("unknown").is_err());
+    }
+}
@@ -5,20 +5,25 @@ extern crate contract_interface;
 extern crate lazy_static;
 
 mod config;
+pub mod hasher;
 mod loader;
-mod metrics;
+pub mod metrics;
 mod mine;
 mod miner_id;
 mod monitor;
 pub mod pora;
 mod recall_range;
+pub mod remote;
 mod sealer;
 mod service;
+pub mod simulate;
 mod submitter;
 mod watcher;
+mod worker;
 
-pub use config::MinerConfig;
+pub use config::{MineWorkerConfig, MinerConfig};
 pub use loader::PoraLoader;
+pub use metrics::{mining_stats, MiningStats};
 pub use mine::MineRangeConfig;
 pub use miner_id::load_miner_id;
 pub use service::{MineService, MinerMessage};
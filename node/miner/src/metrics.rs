@@ -1,4 +1,8 @@
-use lighthouse_metrics::{try_create_int_counter, IntCounter, Result};
+use lighthouse_metrics::{
+    try_create_float_gauge, try_create_int_counter, try_create_int_gauge, Gauge, IntCounter,
+    IntGauge, Result,
+};
+use serde::{Deserialize, Serialize};
 
 lazy_static! {
     pub static ref SCRATCH_PAD_ITER_COUNT: Result<IntCounter> = try_create_int_counter(
@@ -13,8 +17,56 @@ lazy_static! {
         "miner_mix_iter",
         "Number of mix sealed data with scratch pad iterations for PoRA"
     );
+    pub static ref LOAD_MISSING_COUNT: Result<IntCounter> = try_create_int_counter(
+        "miner_load_missing",
+        "Number of loads for PoRA whose data is not stored locally"
+    );
+    pub static ref UNSEALED_COUNT: Result<IntCounter> = try_create_int_counter(
+        "miner_unsealed",
+        "Number of loaded seals for PoRA that are not sealed yet"
+    );
     pub static ref HIT_COUNT: Result<IntCounter> =
         try_create_int_counter("miner_hit", "Number of hit for PoRA");
+    pub static ref WORKER_COUNT: Result<IntGauge> =
+        try_create_int_gauge("miner_workers", "Number of PoRA mining workers");
+    pub static ref HASH_RATE: Result<Gauge> = try_create_float_gauge(
+        "miner_hash_rate",
+        "Number of scratch pad iterations per second for PoRA"
+    );
+}
+
+/// Mining statistics since the node started, exposed to the `miner` RPC namespace.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MiningStats {
+    pub workers: u64,
+    /// Scratch pad iterations per second of all workers.
+    pub hash_rate: f64,
+    pub hashes: u64,
+    /// Number of recall positions within the mine range, which are loaded to compute PoRA.
+    pub recall_hits: u64,
+    /// Number of recall hits whose data is not stored locally.
+    pub loads_missing: u64,
+    /// Number of loaded seals that are not sealed yet, which are skipped.
+    pub seals_unsealed: u64,
+    /// Number of valid PoRA answers found.
+    pub answers: u64,
+}
+
+fn counter_value(counter: &Result<IntCounter>) -> u64 {
+    counter.as_ref().map_or(0, |x| x.get())
+}
+
+pub fn mining_stats() -> MiningStats {
+    MiningStats {
+        workers: WORKER_COUNT.as_ref().map_or(0, |x| x.get().max(0) as u64),
+        hash_rate: HASH_RATE.as_ref().map_or(0.0, |x| x.get()),
+        hashes: counter_value(&SCRATCH_PAD_ITER_COUNT),
+        recall_hits: counter_value(&LOADING_COUNT),
+        loads_missing: counter_value(&LOAD_MISSING_COUNT),
+        seals_unsealed: counter_value(&UNSEALED_COUNT),
+        answers: counter_value(&HIT_COUNT),
+    }
 }
 
 pub fn report() -> String {
@@ -23,10 +75,11 @@ pub fn report() -> String {
         Err(_) => "n/a".to_string(),
     };
     format!(
-        "scratch pad: {}, loading: {}, pad_mix: {}, hit: {}",
+        "scratch pad: {}, loading: {}, pad_mix: {}, hit: {}, hash rate: {:.1}/s",
         s(&SCRATCH_PAD_ITER_COUNT),
         s(&LOADING_COUNT),
         s(&PAD_MIX_COUNT),
-        s(&HIT_COUNT)
+        s(&HIT_COUNT),
+        mining_stats().hash_rate
     )
 }
@@ -1,35 +1,66 @@
 use contract_interface::pora_mine::MineContext;
 use ethereum_types::{H256, U256};
+use lighthouse_metrics::set_gauge;
 use rand::{self, Rng};
-use std::time;
 use task_executor::TaskExecutor;
-use tokio::sync::{broadcast, mpsc};
-use tokio::time::{sleep, Duration, Instant};
+use tokio::sync::{broadcast, mpsc, watch};
 
 use storage::config::ShardConfig;
 use zgs_spec::{SECTORS_PER_LOAD, SECTORS_PER_MAX_MINING_RANGE, SECTORS_PER_PRICING};
 
+use crate::hasher::PoraHasher;
+use crate::metrics::WORKER_COUNT;
 use crate::recall_range::RecallRange;
+use crate::worker::MineWorker;
 use crate::{
     pora::{AnswerWithoutProof, Miner},
     watcher::MineContextMessage,
-    MinerConfig, MinerMessage, PoraLoader,
+    MineWorkerConfig, MinerMessage, PoraLoader,
 };
 
 use std::sync::Arc;
 
+/// Tracks the mine context and mine range, and dispatches mining jobs to the worker pool.
 pub struct PoraService {
     mine_context_receiver: broadcast::Receiver<MineContextMessage>,
-    mine_answer_sender: mpsc::UnboundedSender<AnswerWithoutProof>,
     msg_recv: broadcast::Receiver<MinerMessage>,
-    loader: Arc<dyn PoraLoader>,
+    job_sender: watch::Sender<Option<MineJob>>,
 
+    mining_enabled: bool,
     puzzle: Option<PoraPuzzle>,
     mine_range: MineRangeConfig,
     miner_id: H256,
+}
 
-    cpu_percentage: u64,
-    iter_batch: usize,
+/// A mining job shared by all workers, which is replaced once the mine context or mine range
+/// changes.
+#[derive(Debug, Clone)]
+pub(crate) struct MineJob {
+    pub puzzle: PoraPuzzle,
+    pub mine_range: MineRangeConfig,
+    pub range: RecallRange,
+    pub miner_id: H256,
+    /// The random nonce that workers derive nonces of their batches from.
+    pub nonce: H256,
+}
+
+impl MineJob {
+    pub fn as_miner<'a>(
+        &'a self,
+        loader: &'a dyn PoraLoader,
+        hasher: &'a dyn PoraHasher,
+    ) -> Miner<'a> {
+        Miner {
+            range: self.range,
+            miner_id: &self.miner_id,
+            mine_range_config: &self.mine_range,
+            context: &self.puzzle.context,
+            subtask_digest: &self.puzzle.subtask_digest,
+            pora_target: &self.puzzle.pora_target,
+            loader,
+            hasher,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -62,6 +93,18 @@ impl PoraPuzzle {
     pub fn context_digest(&self) -> H256 {
         H256(self.context.digest)
     }
+
+    pub fn context(&self) -> &MineContext {
+        &self.context
+    }
+
+    pub fn pora_target(&self) -> U256 {
+        self.pora_target
+    }
+
+    pub fn subtask_digest(&self) -> H256 {
+        self.subtask_digest
+    }
 }
 #[derive(Clone, Debug, Default)]
 pub struct MineRangeConfig {
@@ -71,8 +114,20 @@ pub struct MineRangeConfig {
 }
 
 impl MineRangeConfig {
+    pub(crate) fn new(
+        start_position: Option<u64>,
+        end_position: Option<u64>,
+        shard_config: ShardConfig,
+    ) -> Self {
+        Self {
+            start_position,
+            end_position,
+            shard_config,
+        }
+    }
+
     #[inline]
-    fn to_valid_range(&self, context: &MineContext) -> Option<RecallRange> {
+    pub(crate) fn to_valid_range(&self, context: &MineContext) -> Option<RecallRange> {
         let self_start_position = self.start_position?;
         let self_end_position = self.end_position?;
 
@@ -100,6 +155,18 @@ impl MineRangeConfig {
         })
     }
 
+    pub(crate) fn start_position(&self) -> Option<u64> {
+        self.start_position
+    }
+
+    pub(crate) fn end_position(&self) -> Option<u64> {
+        self.end_position
+    }
+
+    pub(crate) fn shard_config(&self) -> ShardConfig {
+        self.shard_config
+    }
+
     #[inline]
     pub(crate) fn is_covered(&self, recall_position: u64) -> Option<bool> {
         let self_start_position = self.start_position?;
@@ -116,44 +183,59 @@ impl MineRangeConfig {
 }
 
 impl PoraService {
+    /// Spawns the service along with local mining workers, and returns the receiver of mining
+    /// jobs, which could be served to remote workers as well.
+    #[allow(clippy::too_many_arguments)]
     pub fn spawn(
         executor: TaskExecutor,
         msg_recv: broadcast::Receiver<MinerMessage>,
         mine_context_receiver: broadcast::Receiver<MineContextMessage>,
+        mine_answer_sender: mpsc::UnboundedSender<AnswerWithoutProof>,
         loader: Arc<dyn PoraLoader>,
-        config: &MinerConfig,
+        hasher: Arc<dyn PoraHasher>,
+        worker_config: &MineWorkerConfig,
+        shard_config: ShardConfig,
         miner_id: H256,
-    ) -> mpsc::UnboundedReceiver<AnswerWithoutProof> {
-        let (mine_answer_sender, mine_answer_receiver) =
-            mpsc::unbounded_channel::<AnswerWithoutProof>();
-        let mine_range = MineRangeConfig {
-            start_position: Some(0),
-            end_position: Some(u64::MAX),
-            shard_config: config.shard_config,
-        };
+    ) -> watch::Receiver<Option<MineJob>> {
+        let (job_sender, job_receiver) = watch::channel(None);
+        let mine_range = MineRangeConfig::new(Some(0), Some(u64::MAX), shard_config);
+
+        if worker_config.cpu_percentage > 0 {
+            let num_workers = worker_config.num_workers();
+            for id in 0..num_workers {
+                MineWorker::spawn(
+                    executor.clone(),
+                    id,
+                    num_workers,
+                    job_receiver.clone(),
+                    mine_answer_sender.clone(),
+                    loader.clone(),
+                    hasher.clone(),
+                    worker_config,
+                );
+            }
+            set_gauge(&WORKER_COUNT, num_workers as i64);
+            info!("Spawned {} PoRA mining workers", num_workers);
+        } else {
+            warn!("Local PoRA mining is disabled since miner_cpu_percentage is 0");
+        }
+
         let pora = PoraService {
             mine_context_receiver,
-            mine_answer_sender,
             msg_recv,
+            job_sender,
+            mining_enabled: true,
             puzzle: None,
             mine_range,
             miner_id,
-            loader,
-            cpu_percentage: config.cpu_percentage,
-            iter_batch: config.iter_batch,
         };
         executor.spawn(async move { Box::pin(pora.start()).await }, "pora_master");
-        mine_answer_receiver
+        job_receiver
     }
 
     async fn start(mut self) {
-        let mut mining_enabled = true;
         let mut channel_opened = true;
 
-        let cpu_percent: u64 = self.cpu_percentage;
-        let diastole = sleep(Duration::from_secs(0));
-        tokio::pin!(diastole);
-
         loop {
             tokio::select! {
                 biased;
@@ -163,22 +245,23 @@ impl PoraService {
                     match v {
                         Ok(MinerMessage::ToggleMining(enable)) => {
                             info!("Toggle mining: {}", if enable { "on" } else { "off" });
-                            mining_enabled = enable;
+                            self.mining_enabled = enable;
+                            self.update_job("toggle mining");
                         }
                         Ok(MinerMessage::SetStartPosition(pos)) => {
                             info!("Change start position to: {:?}", pos);
                             self.mine_range.start_position = pos;
-                            self.report_reason_if_mine_stop("update mine range");
+                            self.update_job("update mine range");
 
                         }
                         Ok(MinerMessage::SetEndPosition(pos)) => {
                             info!("Change end position to: {:?}", pos);
                             self.mine_range.end_position = pos;
-                            self.report_reason_if_mine_stop("update mine range");
+                            self.update_job("update mine range");
                         }
                         Ok(MinerMessage::SetShardConfig(shard_config)) => {
                             self.mine_range.shard_config = shard_config;
-                            self.report_reason_if_mine_stop("update shard");
+                            self.update_job("update shard");
                         }
                         Err(broadcast::error::RecvError::Closed) => {
                             warn!("Unexpected: Mine service config channel closed.");
@@ -195,7 +278,7 @@ impl PoraService {
                         Ok(msg) => {
                             info!("Update mine service: {:?}", msg);
                             self.puzzle = msg;
-                            self.report_reason_if_mine_stop("update mine context");
+                            self.update_job("update mine context");
                         },
                         Err(broadcast::error::RecvError::Closed) => {
                             warn!("Mine context channel closed.");
@@ -203,37 +286,28 @@ impl PoraService {
                         Err(_) => {}
                     }
                 }
+            }
+        }
+    }
 
-                () = &mut diastole, if !diastole.is_elapsed() => {
-                }
-
-                _ = async {}, if mining_enabled
-                                && cpu_percent > 0
-                                && self.as_miner().is_ok()
-                                && diastole.is_elapsed() => {
-                    let nonce = H256(rand::thread_rng().gen());
-                    let miner = self.as_miner().unwrap();
+    /// Dispatches a new mining job to workers, or stops workers if mining is not available.
+    fn update_job(&mut self, event: &'static str) {
+        let job = match self.make_job() {
+            Ok(job) => Some(job),
+            Err(reason) => {
+                info!(reason, "Mine stopped on {}", event);
+                None
+            }
+        };
 
-                    let timer = time::Instant::now();
+        self.job_sender.send_replace(job);
+    }
 
-                    if let Some(answer) = miner.batch_iteration(nonce, self.iter_batch).await {
-                        info!("Hit Pora answer {:?}", answer);
-                        if self.mine_answer_sender.send(answer).is_err() {
-                            warn!("Mine submitter channel closed");
-                        }
-                    } else if cpu_percent < 100 {
-                        // 2^64 ns = 500 years
-                        let elapsed = timer.elapsed().as_nanos() as u64;
-                        let diastole_time = elapsed / cpu_percent * (100 - cpu_percent);
-                        diastole.as_mut().reset(Instant::now() + Duration::from_nanos(diastole_time));
-                    }
-                }
-            }
+    fn make_job(&self) -> Result<MineJob, &'static str> {
+        if !self.mining_enabled {
+            return Err("mining disabled");
         }
-    }
 
-    #[inline]
-    fn as_miner(&self) -> Result<Miner, &'static str> {
         let puzzle = self.puzzle.as_ref().ok_or("no mine context")?;
 
         let range = self
@@ -257,20 +331,12 @@ impl PoraService {
             return Err("Not enough flow length to shard");
         }
 
-        Ok(Miner {
+        Ok(MineJob {
+            puzzle: puzzle.clone(),
+            mine_range: self.mine_range.clone(),
             range,
-            miner_id: &self.miner_id,
-            mine_range_config: &self.mine_range,
-            context: &puzzle.context,
-            subtask_digest: &puzzle.subtask_digest,
-            pora_target: &puzzle.pora_target,
-            loader: &*self.loader,
+            miner_id: self.miner_id,
+            nonce: H256(rand::thread_rng().gen()),
         })
     }
-
-    fn report_reason_if_mine_stop(&self, event: &'static str) {
-        if let Err(reason) = self.as_miner() {
-            info!(reason, "Mine stopped on {}", event);
-        }
-    }
 }
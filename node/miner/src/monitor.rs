@@ -1,7 +1,8 @@
 use std::time::Duration;
 
+use lighthouse_metrics::set_float_gauge;
 use task_executor::TaskExecutor;
-use tokio::time::sleep;
+use tokio::time::{sleep, Instant};
 
 use super::metrics;
 
@@ -19,9 +20,23 @@ impl Monitor {
     }
 
     async fn start(&self) {
+        let mut last_hashes = metrics::mining_stats().hashes;
+        let mut last_time = Instant::now();
+
         loop {
-            info!("Mine iterations statistics: {}", metrics::report());
             let _ = sleep(self.period).await;
+
+            // update the hash rate of the last period
+            let hashes = metrics::mining_stats().hashes;
+            let elapsed = last_time.elapsed().as_secs_f64();
+            if elapsed > 0.0 {
+                let hash_rate = hashes.saturating_sub(last_hashes) as f64 / elapsed;
+                set_float_gauge(&metrics::HASH_RATE, hash_rate);
+            }
+            last_hashes = hashes;
+            last_time = Instant::now();
+
+            info!("Mine iterations statistics: {}", metrics::report());
         }
     }
 }
@@ -1,25 +1,14 @@
 use super::metrics::*;
+use crate::hasher::{PoraHasher, ScratchPad, ScratchPadSeed};
 use crate::recall_range::RecallRange;
 use crate::{MineRangeConfig, PoraLoader};
-use blake2::{Blake2b512, Digest};
 use contract_interface::pora_mine::MineContext;
 use ethereum_types::{H256, U256};
-use ethers::utils::keccak256;
-use lighthouse_metrics::inc_counter;
+use lighthouse_metrics::{inc_counter, inc_counter_by};
 use storage::log_store::MineLoadChunk;
-use tiny_keccak::{Hasher, Keccak};
 use zgs_spec::{BYTES_PER_SCRATCHPAD, BYTES_PER_SEAL, SECTORS_PER_LOAD, SECTORS_PER_SEAL};
 
-pub const BLAKE2B_OUTPUT_BYTES: usize = 64;
-pub const KECCAK256_OUTPUT_BYTES: usize = 32;
-
-fn keccak(input: impl AsRef<[u8]>) -> [u8; KECCAK256_OUTPUT_BYTES] {
-    let mut hasher = Keccak::v256();
-    let mut output = [0u8; 32];
-    hasher.update(input.as_ref());
-    hasher.finalize(&mut output);
-    output
-}
+pub use crate::hasher::{BLAKE2B_OUTPUT_BYTES, KECCAK256_OUTPUT_BYTES};
 
 pub(crate) struct Miner<'a> {
     pub range: RecallRange,
@@ -28,6 +17,7 @@ pub(crate) struct Miner<'a> {
     pub subtask_digest: &'a H256,
     pub pora_target: &'a U256,
     pub loader: &'a dyn PoraLoader,
+    pub hasher: &'a dyn PoraHasher,
     pub mine_range_config: &'a MineRangeConfig,
 }
 #[derive(Debug)]
@@ -48,26 +38,40 @@ impl<'a> Miner<'a> {
         nonce: H256,
         batch_size: usize,
     ) -> Option<AnswerWithoutProof> {
+        let seed = self.scratch_pad_seed();
         for i in 0..batch_size {
             let bytes = i.to_ne_bytes();
             let mut current_nonce = nonce;
             for (pos, b) in bytes.into_iter().enumerate() {
                 current_nonce.0[pos] ^= b;
             }
-            if let Some(answer) = self.iteration(current_nonce).await {
+
+            let scratch_pad = self.hasher.scratch_pad(&seed, &current_nonce);
+            if let Some(answer) = self.iteration(current_nonce, scratch_pad).await {
                 return Some(answer);
             }
         }
         None
     }
 
-    pub async fn iteration(&self, nonce: H256) -> Option<AnswerWithoutProof> {
+    /// Computes the answer of a single nonce, which is used to verify answers of remote workers.
+    pub async fn compute_answer(&self, nonce: H256) -> Option<AnswerWithoutProof> {
+        let seed = self.scratch_pad_seed();
+        let scratch_pad = self.hasher.scratch_pad(&seed, &nonce);
+        self.iteration(nonce, scratch_pad).await
+    }
+
+    pub async fn iteration(
+        &self,
+        nonce: H256,
+        scratch_pad: ScratchPad,
+    ) -> Option<AnswerWithoutProof> {
         inc_counter(&SCRATCH_PAD_ITER_COUNT);
         let ScratchPad {
             scratch_pad,
             recall_seed,
             pad_seed,
-        } = self.make_scratch_pad(&nonce);
+        } = scratch_pad;
 
         let recall_position = self.range.load_position(recall_seed)?;
         if !self.mine_range_config.is_covered(recall_position).unwrap() {
@@ -82,10 +86,19 @@ impl<'a> Miner<'a> {
         let MineLoadChunk {
             loaded_chunk,
             availabilities,
-        } = self
+        } = match self
             .loader
             .load_sealed_data(recall_position / SECTORS_PER_LOAD as u64)
-            .await?;
+            .await
+        {
+            Some(chunk) => chunk,
+            None => {
+                inc_counter(&LOAD_MISSING_COUNT);
+                return None;
+            }
+        };
+        let unsealed = availabilities.iter().filter(|x| !**x).count();
+        inc_counter_by(&UNSEALED_COUNT, unsealed as u64);
 
         let scratch_pad: [[u8; BYTES_PER_SEAL]; BYTES_PER_SCRATCHPAD / BYTES_PER_SEAL] =
             unsafe { std::mem::transmute(scratch_pad) };
@@ -103,7 +116,7 @@ impl<'a> Miner<'a> {
                 *x ^= y;
             }
 
-            let quality = self.pora(idx, &sealed_data, pad_seed);
+            let quality = self.hasher.pora(idx, &sealed_data, &pad_seed);
             let difficulty_scale_x64 = self
                 .range
                 .difficulty_scale_x64(self.context.flow_length.as_u64());
@@ -135,63 +148,11 @@ impl<'a> Miner<'a> {
         None
     }
 
-    fn make_scratch_pad(&self, nonce: &H256) -> ScratchPad {
-        let mut digest: [u8; BLAKE2B_OUTPUT_BYTES] = {
-            let mut hasher = Blake2b512::new();
-            hasher.update(self.miner_id);
-            hasher.update(nonce);
-            hasher.update(self.subtask_digest);
-            hasher.update(self.range.digest());
-            hasher.finalize().into()
-        };
-
-        let pad_seed = digest;
-
-        let mut scratch_pad =
-            [[0u8; BLAKE2B_OUTPUT_BYTES]; BYTES_PER_SCRATCHPAD / BLAKE2B_OUTPUT_BYTES];
-        for scratch_pad_cell in scratch_pad.iter_mut() {
-            let output0 = keccak256(digest);
-            digest[..32].copy_from_slice(&output0);
-            let output1 = keccak256(digest);
-            digest[32..].copy_from_slice(&output1);
-
-            *scratch_pad_cell = digest;
-        }
-
-        let scratch_pad: [u8; BYTES_PER_SCRATCHPAD] = unsafe { std::mem::transmute(scratch_pad) };
-        let recall_seed: [u8; KECCAK256_OUTPUT_BYTES] = keccak(digest);
-
-        ScratchPad {
-            scratch_pad,
-            recall_seed,
-            pad_seed,
+    fn scratch_pad_seed(&self) -> ScratchPadSeed {
+        ScratchPadSeed {
+            miner_id: *self.miner_id,
+            subtask_digest: *self.subtask_digest,
+            range_digest: self.range.digest(),
         }
     }
-
-    #[inline]
-    fn pora(
-        &self,
-        seal_index: usize,
-        mixed_data: &[u8; BYTES_PER_SEAL],
-        pad_seed: [u8; BLAKE2B_OUTPUT_BYTES],
-    ) -> U256 {
-        let mut hasher = Blake2b512::new();
-        hasher.update([0u8; 24]);
-        hasher.update((seal_index as u64).to_be_bytes());
-
-        hasher.update(pad_seed);
-        hasher.update([0u8; 32]);
-
-        hasher.update(mixed_data);
-
-        let digest = hasher.finalize();
-
-        U256::from_big_endian(&digest[0..32])
-    }
-}
-
-struct ScratchPad {
-    scratch_pad: [u8; BYTES_PER_SCRATCHPAD],
-    recall_seed: [u8; KECCAK256_OUTPUT_BYTES],
-    pad_seed: [u8; BLAKE2B_OUTPUT_BYTES],
 }
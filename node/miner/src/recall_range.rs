@@ -1,8 +1,10 @@
 use ethereum_types::U256;
+use serde::{Deserialize, Serialize};
 use tiny_keccak::{Hasher, Keccak};
 use zgs_spec::{SECTORS_PER_LOAD, SECTORS_PER_MAX_MINING_RANGE};
 
-#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct RecallRange {
     pub start_position: u64,
     pub mining_length: u64,
@@ -0,0 +1,608 @@
+//! Remote mining protocol, which separates storage nodes from hashing workers.
+//!
+//! A storage node serves the current mining job and sealed loads to remote workers through the
+//! `miner` RPC namespace, and verifies answers of remote workers against the local log store
+//! before passing them to the submitter. A remote worker runs the same `PoraService` as the
+//! node, except that sealed loads are fetched from the storage node on recall hits.
+//!
+//! Note, every recall hit transfers a load of `BYTES_PER_LOAD` bytes, so the hash rate of a
+//! remote worker is bounded by its bandwidth to the storage node.
+use async_trait::async_trait;
+use ethereum_types::H256;
+use lru::LruCache;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use storage::config::ShardConfig;
+use storage::log_store::MineLoadChunk;
+use task_executor::TaskExecutor;
+use tokio::sync::{broadcast, mpsc, watch};
+use tokio::time::sleep;
+use zgs_spec::{BYTES_PER_SEAL, SEALS_PER_LOAD};
+
+use crate::hasher::{make_pora_hasher, PoraHasher};
+use crate::mine::{MineJob, PoraService};
+use crate::monitor::Monitor;
+use crate::pora::AnswerWithoutProof;
+use crate::recall_range::RecallRange;
+use crate::simulate::RecordedPuzzle;
+use crate::watcher::MineContextMessage;
+use crate::{MineWorkerConfig, MinerMessage, PoraLoader};
+
+/// Maximum number of sealed loads cached by a remote worker.
+const LOAD_CACHE_CAPACITY: usize = 64;
+
+/// The mining job served to remote workers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteMineJob {
+    pub miner_id: H256,
+    pub puzzle: RecordedPuzzle,
+    pub start_position: Option<u64>,
+    pub end_position: Option<u64>,
+    pub shard_config: ShardConfig,
+}
+
+impl From<&MineJob> for RemoteMineJob {
+    fn from(job: &MineJob) -> Self {
+        Self {
+            miner_id: job.miner_id,
+            puzzle: RecordedPuzzle::from(&job.puzzle),
+            start_position: job.mine_range.start_position(),
+            end_position: job.mine_range.end_position(),
+            shard_config: job.mine_range.shard_config(),
+        }
+    }
+}
+
+/// Sealed data of a load, i.e. `SEALS_PER_LOAD` seals.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SealedLoad {
+    #[serde(with = "base64")]
+    pub data: Vec<u8>,
+    pub availabilities: Vec<bool>,
+}
+
+impl From<MineLoadChunk> for SealedLoad {
+    fn from(chunk: MineLoadChunk) -> Self {
+        Self {
+            data: chunk.loaded_chunk.concat(),
+            availabilities: chunk.availabilities.to_vec(),
+        }
+    }
+}
+
+impl TryFrom<SealedLoad> for MineLoadChunk {
+    type Error = String;
+
+    fn try_from(load: SealedLoad) -> Result<Self, Self::Error> {
+        if load.data.len() != BYTES_PER_SEAL * SEALS_PER_LOAD {
+            return Err(format!("invalid load size {}", load.data.len()));
+        }
+
+        let availabilities = load
+            .availabilities
+            .try_into()
+            .map_err(|v: Vec<bool>| format!("invalid availabilities length {}", v.len()))?;
+        let loaded_chunk = load
+            .data
+            .chunks_exact(BYTES_PER_SEAL)
+            .map(|seal| seal.try_into().unwrap())
+            .collect();
+
+        Ok(MineLoadChunk {
+            loaded_chunk,
+            availabilities,
+        })
+    }
+}
+
+/// An answer found by a remote worker.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteAnswer {
+    pub context_digest: H256,
+    pub context_flow_root: H256,
+    pub nonce: H256,
+    pub miner_id: H256,
+    pub range: RecallRange,
+    pub recall_position: u64,
+    pub seal_offset: usize,
+    #[serde(with = "base64")]
+    pub sealed_data: Vec<u8>,
+}
+
+impl From<AnswerWithoutProof> for RemoteAnswer {
+    fn from(answer: AnswerWithoutProof) -> Self {
+        Self {
+            context_digest: answer.context_digest,
+            context_flow_root: answer.context_flow_root,
+            nonce: answer.nonce,
+            miner_id: answer.miner_id,
+            range: answer.range,
+            recall_position: answer.recall_position,
+            seal_offset: answer.seal_offset,
+            sealed_data: answer.sealed_data.to_vec(),
+        }
+    }
+}
+
+/// Nonces of the answers accepted for the current mine context.
+#[derive(Default)]
+struct AcceptedNonces {
+    context_digest: H256,
+    nonces: HashSet<H256>,
+}
+
+impl AcceptedNonces {
+    /// Returns `false` if the nonce has been accepted for the context, and forgets nonces of
+    /// previous contexts once the context changes.
+    fn insert(&mut self, context_digest: H256, nonce: H256) -> bool {
+        if self.context_digest != context_digest {
+            self.context_digest = context_digest;
+            self.nonces.clear();
+        }
+        self.nonces.insert(nonce)
+    }
+}
+
+/// Serves mining jobs and sealed loads to remote workers on the storage node.
+pub struct RemoteMineServer {
+    job_receiver: watch::Receiver<Option<MineJob>>,
+    mine_answer_sender: mpsc::UnboundedSender<AnswerWithoutProof>,
+    loader: Arc<dyn PoraLoader>,
+    hasher: Arc<dyn PoraHasher>,
+    accepted_nonces: Mutex<AcceptedNonces>,
+}
+
+impl RemoteMineServer {
+    pub(crate) fn new(
+        job_receiver: watch::Receiver<Option<MineJob>>,
+        mine_answer_sender: mpsc::UnboundedSender<AnswerWithoutProof>,
+        loader: Arc<dyn PoraLoader>,
+        hasher: Arc<dyn PoraHasher>,
+    ) -> Self {
+        Self {
+            job_receiver,
+            mine_answer_sender,
+            loader,
+            hasher,
+            accepted_nonces: Default::default(),
+        }
+    }
+
+    /// Returns the current mining job, or `None` if mining is stopped.
+    pub fn mine_job(&self) -> Option<RemoteMineJob> {
+        self.job_receiver.borrow().as_ref().map(RemoteMineJob::from)
+    }
+
+    pub async fn load_sealed_data(&self, load_index: u64) -> Option<SealedLoad> {
+        self.loader
+            .load_sealed_data(load_index)
+            .await
+            .map(SealedLoad::from)
+    }
+
+    /// Verifies the answer of a remote worker by mining the same nonce against the local log
+    /// store, and passes the answer to the submitter if valid. An answer is only accepted once
+    /// per mine context, so that workers could not replay answers to flood the submitter.
+    pub async fn submit_answer(&self, answer: RemoteAnswer) -> Result<(), String> {
+        let job = self
+            .job_receiver
+            .borrow()
+            .clone()
+            .ok_or("mining is stopped")?;
+
+        if answer.context_digest != job.puzzle.context_digest() {
+            return Err("inconsistent context digest".into());
+        }
+
+        if answer.miner_id != job.miner_id {
+            return Err("inconsistent miner id".into());
+        }
+
+        if answer.range != job.range {
+            return Err("inconsistent recall range".into());
+        }
+
+        let miner = job.as_miner(&*self.loader, &*self.hasher);
+        let expected = miner
+            .compute_answer(answer.nonce)
+            .await
+            .ok_or("no valid answer for the nonce")?;
+        if expected.recall_position != answer.recall_position
+            || expected.seal_offset != answer.seal_offset
+            || expected.sealed_data[..] != answer.sealed_data[..]
+        {
+            return Err("inconsistent answer".into());
+        }
+
+        if !self
+            .accepted_nonces
+            .lock()
+            .unwrap()
+            .insert(answer.context_digest, answer.nonce)
+        {
+            return Err("duplicate answer".into());
+        }
+
+        info!(nonce = ?answer.nonce, "Receive valid Pora answer from remote worker");
+        self.mine_answer_sender
+            .send(expected)
+            .map_err(|_| "mine submitter channel closed".to_string())
+    }
+}
+
+/// Client of a storage node, which is used by remote workers.
+#[async_trait]
+pub trait RemoteMineClient: Send + Sync {
+    async fn get_mine_job(&self) -> Result<Option<RemoteMineJob>, String>;
+
+    async fn load_sealed_data(&self, load_index: u64) -> Result<Option<SealedLoad>, String>;
+
+    async fn submit_answer(&self, answer: RemoteAnswer) -> Result<(), String>;
+}
+
+/// Loads sealed data from the storage node, and caches recently loaded data of the current
+/// mining context, since recall positions of different nonces may hit the same load.
+struct RemoteLoader {
+    client: Arc<dyn RemoteMineClient>,
+    /// The context digest of the current mining job.
+    context_digest: watch::Receiver<H256>,
+    cache: Mutex<LruCache<(H256, u64), MineLoadChunk>>,
+}
+
+impl RemoteLoader {
+    fn new(client: Arc<dyn RemoteMineClient>, context_digest: watch::Receiver<H256>) -> Self {
+        Self {
+            client,
+            context_digest,
+            cache: Mutex::new(LruCache::new(LOAD_CACHE_CAPACITY)),
+        }
+    }
+}
+
+#[async_trait]
+impl PoraLoader for RemoteLoader {
+    async fn load_sealed_data(&self, load_index: u64) -> Option<MineLoadChunk> {
+        let key = (*self.context_digest.borrow(), load_index);
+        if let Some(chunk) = self.cache.lock().unwrap().get(&key) {
+            return Some(chunk.clone());
+        }
+
+        match self.client.load_sealed_data(load_index).await {
+            Ok(Some(load)) => match MineLoadChunk::try_from(load) {
+                Ok(chunk) => {
+                    self.cache.lock().unwrap().put(key, chunk.clone());
+                    Some(chunk)
+                }
+                Err(e) => {
+                    warn!(%load_index, "Invalid sealed load from storage node: {}", e);
+                    None
+                }
+            },
+            Ok(None) => None,
+            Err(e) => {
+                warn!(%load_index, "Failed to load sealed data from storage node: {}", e);
+                None
+            }
+        }
+    }
+}
+
+/// A hashing worker that mines the jobs of a storage node.
+pub struct RemoteMineWorker {
+    executor: TaskExecutor,
+    client: Arc<dyn RemoteMineClient>,
+    hasher: Arc<dyn PoraHasher>,
+    worker_config: MineWorkerConfig,
+    poll_interval: Duration,
+}
+
+impl RemoteMineWorker {
+    pub fn spawn(
+        executor: TaskExecutor,
+        client: Arc<dyn RemoteMineClient>,
+        worker_config: MineWorkerConfig,
+        poll_interval: Duration,
+    ) -> Result<(), String> {
+        let hasher = make_pora_hasher(&worker_config.pora_hasher)?;
+        let worker = RemoteMineWorker {
+            executor: executor.clone(),
+            client,
+            hasher,
+            worker_config,
+            poll_interval,
+        };
+        executor.spawn(
+            async move { Box::pin(worker.start()).await },
+            "remote_mine_worker",
+        );
+        Monitor::spawn(executor, Duration::from_secs(5));
+        Ok(())
+    }
+
+    async fn start(self) {
+        // The miner id of the storage node is required to spawn the mining service.
+        let mut current = loop {
+            match self.client.get_mine_job().await {
+                Ok(Some(job)) => break job,
+                Ok(None) => debug!("No mine job from storage node"),
+                Err(e) => warn!("Failed to get mine job from storage node: {}", e),
+            }
+            sleep(self.poll_interval).await;
+        };
+        info!(miner_id = ?current.miner_id, "Start to mine jobs of storage node");
+
+        let (msg_send, msg_recv) = broadcast::channel(1024);
+        let (context_send, context_recv) = broadcast::channel(1024);
+        let (mine_answer_sender, mut mine_answer_receiver) = mpsc::unbounded_channel();
+        let (context_digest_send, context_digest_recv) = watch::channel(current.puzzle.digest);
+        let _job_receiver = PoraService::spawn(
+            self.executor.clone(),
+            msg_recv,
+            context_recv,
+            mine_answer_sender,
+            Arc::new(RemoteLoader::new(self.client.clone(), context_digest_recv)),
+            self.hasher.clone(),
+            &self.worker_config,
+            current.shard_config,
+            current.miner_id,
+        );
+        apply_job(&msg_send, &context_send, Some(&current));
+
+        let mut stopped = false;
+        let mut poll = tokio::time::interval(self.poll_interval);
+        loop {
+            tokio::select! {
+                maybe_answer = mine_answer_receiver.recv() => {
+                    let answer = match maybe_answer {
+                        Some(answer) => answer,
+                        None => return,
+                    };
+                    info!(nonce = ?answer.nonce, "Submit Pora answer to storage node");
+                    if let Err(e) = self.client.submit_answer(answer.into()).await {
+                        warn!("Pora answer rejected by storage node: {}", e);
+                    }
+                }
+
+                _ = poll.tick() => {
+                    let job = match self.client.get_mine_job().await {
+                        Ok(job) => job,
+                        Err(e) => {
+                            warn!("Failed to get mine job from storage node: {}", e);
+                            continue;
+                        }
+                    };
+
+                    match job {
+                        Some(job) if job.miner_id != current.miner_id => {
+                            warn!(miner_id = ?job.miner_id, "Miner id of storage node changed, restart is required");
+                        }
+                        Some(job) if stopped || job != current => {
+                            let _ = context_digest_send.send(job.puzzle.digest);
+                            apply_job(&msg_send, &context_send, Some(&job));
+                            current = job;
+                            stopped = false;
+                        }
+                        None if !stopped => {
+                            apply_job(&msg_send, &context_send, None);
+                            stopped = true;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Updates the mining service with the job of storage node, or stops mining if `None`.
+fn apply_job(
+    msg_send: &broadcast::Sender<MinerMessage>,
+    context_send: &broadcast::Sender<MineContextMessage>,
+    job: Option<&RemoteMineJob>,
+) {
+    if let Some(job) = job {
+        for msg in [
+            MinerMessage::SetShardConfig(job.shard_config),
+            MinerMessage::SetStartPosition(job.start_position),
+            MinerMessage::SetEndPosition(job.end_position),
+        ] {
+            let _ = msg_send.send(msg);
+        }
+    }
+
+    let _ = context_send.send(job.map(|job| job.puzzle.to_puzzle()));
+}
+
+mod base64 {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(v: &[u8], s: S) -> Result<S::Ok, S::Error> {
+        let base64 = base64::encode(v);
+        String::serialize(&base64, s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<u8>, D::Error> {
+        let base64 = String::deserialize(d)?;
+        base64::decode(base64.as_bytes()).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MineRangeConfig;
+    use ethereum_types::U256;
+    use zgs_spec::SECTORS_PER_LOAD;
+
+    /// Serves the same fully sealed load for all load indices.
+    struct TestLoader;
+
+    #[async_trait]
+    impl PoraLoader for TestLoader {
+        async fn load_sealed_data(&self, _load_index: u64) -> Option<MineLoadChunk> {
+            let mut chunk = MineLoadChunk::default();
+            for (i, seal) in chunk.loaded_chunk.iter_mut().enumerate() {
+                *seal = [i as u8; BYTES_PER_SEAL];
+            }
+            chunk.availabilities = [true; SEALS_PER_LOAD];
+            Some(chunk)
+        }
+    }
+
+    fn test_job(context_digest: H256) -> MineJob {
+        let shard_config = ShardConfig::default();
+        let puzzle = RecordedPuzzle {
+            epoch: 1,
+            mine_start: 100,
+            flow_root: H256::random(),
+            flow_length: 1 << 30,
+            block_digest: H256::random(),
+            digest: context_digest,
+            // every nonce hits with the maximum target
+            pora_target: U256::MAX,
+            subtask_digest: H256::random(),
+            max_shards: 1,
+        };
+
+        MineJob {
+            puzzle: puzzle.to_puzzle(),
+            mine_range: MineRangeConfig::new(Some(0), Some(u64::MAX), shard_config),
+            range: RecallRange {
+                start_position: 0,
+                mining_length: 16 * SECTORS_PER_LOAD as u64,
+                shard_mask: shard_config.miner_shard_mask(),
+                shard_id: shard_config.miner_shard_id(),
+            },
+            miner_id: H256::random(),
+            nonce: H256::random(),
+        }
+    }
+
+    async fn mine_answer(server: &RemoteMineServer, nonce: H256) -> RemoteAnswer {
+        let job = server.job_receiver.borrow().clone().unwrap();
+        job.as_miner(&*server.loader, &*server.hasher)
+            .compute_answer(nonce)
+            .await
+            .unwrap()
+            .into()
+    }
+
+    #[tokio::test]
+    async fn test_submit_answer() {
+        let (job_sender, job_receiver) = watch::channel(Some(test_job(H256::random())));
+        let (answer_sender, mut answer_receiver) = mpsc::unbounded_channel();
+        let server = RemoteMineServer::new(
+            job_receiver,
+            answer_sender,
+            Arc::new(TestLoader),
+            make_pora_hasher("reference").unwrap(),
+        );
+
+        let answer = mine_answer(&server, H256::random()).await;
+        server.submit_answer(answer.clone()).await.unwrap();
+        let submitted = answer_receiver.try_recv().unwrap();
+        assert_eq!(submitted.nonce, answer.nonce);
+        assert_eq!(submitted.recall_position, answer.recall_position);
+
+        // replayed answers are rejected
+        assert!(server.submit_answer(answer.clone()).await.is_err());
+
+        let answer = mine_answer(&server, H256::random()).await;
+
+        let mut tampered = answer.clone();
+        tampered.sealed_data[0] ^= 1;
+        assert!(server.submit_answer(tampered).await.is_err());
+
+        let mut tampered = answer.clone();
+        tampered.recall_position += SECTORS_PER_LOAD as u64;
+        assert!(server.submit_answer(tampered).await.is_err());
+
+        let mut tampered = answer.clone();
+        tampered.range.mining_length *= 2;
+        assert!(server.submit_answer(tampered).await.is_err());
+
+        let mut tampered = answer.clone();
+        tampered.context_digest = H256::random();
+        assert!(server.submit_answer(tampered).await.is_err());
+
+        // rejected answers do not block the valid one of the same nonce
+        server.submit_answer(answer.clone()).await.unwrap();
+        assert!(answer_receiver.try_recv().is_ok());
+        assert!(answer_receiver.try_recv().is_err());
+
+        // the same nonce is accepted again for a new mine context
+        job_sender.send(Some(test_job(H256::random()))).unwrap();
+        let answer = mine_answer(&server, answer.nonce).await;
+        server.submit_answer(answer).await.unwrap();
+        assert!(answer_receiver.try_recv().is_ok());
+    }
+
+    /// Counts loads requested from the storage node.
+    #[derive(Default)]
+    struct CountingClient {
+        loads: Mutex<u64>,
+    }
+
+    #[async_trait]
+    impl RemoteMineClient for CountingClient {
+        async fn get_mine_job(&self) -> Result<Option<RemoteMineJob>, String> {
+            Ok(None)
+        }
+
+        async fn load_sealed_data(&self, _load_index: u64) -> Result<Option<SealedLoad>, String> {
+            *self.loads.lock().unwrap() += 1;
+            Ok(Some(MineLoadChunk::default().into()))
+        }
+
+        async fn submit_answer(&self, _answer: RemoteAnswer) -> Result<(), String> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_remote_loader_cache() {
+        let client = Arc::new(CountingClient::default());
+        let (context_send, context_recv) = watch::channel(H256::random());
+        let loader = RemoteLoader::new(client.clone(), context_recv);
+
+        assert!(loader.load_sealed_data(1).await.is_some());
+        assert!(loader.load_sealed_data(1).await.is_some());
+        assert_eq!(*client.loads.lock().unwrap(), 1);
+
+        assert!(loader.load_sealed_data(2).await.is_some());
+        assert_eq!(*client.loads.lock().unwrap(), 2);
+
+        // loaded again in a new mining context
+        context_send.send(H256::random()).unwrap();
+        assert!(loader.load_sealed_data(1).await.is_some());
+        assert_eq!(*client.loads.lock().unwrap(), 3);
+    }
+
+    #[test]
+    fn test_sealed_load_conversion() {
+        let mut chunk = MineLoadChunk::default();
+        chunk.loaded_chunk[1] = [7u8; BYTES_PER_SEAL];
+        chunk.availabilities[1] = true;
+
+        let load = SealedLoad::from(chunk);
+        assert_eq!(load.data.len(), BYTES_PER_SEAL * SEALS_PER_LOAD);
+
+        let json = serde_json::to_string(&load).unwrap();
+        let decoded: SealedLoad = serde_json::from_str(&json).unwrap();
+        let chunk = MineLoadChunk::try_from(decoded).unwrap();
+        assert_eq!(chunk.loaded_chunk[0], [0u8; BYTES_PER_SEAL]);
+        assert_eq!(chunk.loaded_chunk[1], [7u8; BYTES_PER_SEAL]);
+        assert_eq!(chunk.availabilities.iter().filter(|x| **x).count(), 1);
+
+        let invalid = SealedLoad {
+            data: vec![0u8; BYTES_PER_SEAL],
+            availabilities: vec![true; SEALS_PER_LOAD],
+        };
+        assert!(MineLoadChunk::try_from(invalid).is_err());
+    }
+}
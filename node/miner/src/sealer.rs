@@ -6,7 +6,7 @@ use tokio::time::{sleep, Duration, Instant};
 
 use contract_interface::{EpochRangeWithContextDigest, ZgsFlow};
 use storage::{
-    error::Result,
+    error::{Error, Result},
     log_store::{SealAnswer, SealTask},
 };
 use storage_async::Store;
@@ -20,13 +20,18 @@ const DB_QUERY_PERIOD_ON_ERROR: u64 = 5;
 const CHAIN_STATUS_QUERY_PERIOD: u64 = 5;
 
 pub struct Sealer {
+    executor: TaskExecutor,
     flow_contract: ZgsFlow<Provider<RetryClient<Http>>>,
     store: Arc<Store>,
     context_cache: BTreeMap<u128, EpochRangeWithContextDigest>,
     last_context_flow_length: u64,
     miner_id: H256,
+    seal_threads: usize,
 }
 
+/// A seal task along with its seal context digest and the end seal index of the context.
+type SealJob = (SealTask, H256, u64);
+
 impl Sealer {
     pub fn spawn(
         executor: TaskExecutor,
@@ -37,11 +42,13 @@ impl Sealer {
     ) {
         let flow_contract = ZgsFlow::new(config.flow_address, provider);
         let sealer = Sealer {
+            executor: executor.clone(),
             flow_contract,
             store,
             context_cache: Default::default(),
             last_context_flow_length: 0,
             miner_id,
+            seal_threads: config.seal_threads,
         };
 
         executor.spawn(async move { Box::pin(sealer.start()).await }, "data_sealer");
@@ -149,9 +156,13 @@ impl Sealer {
         )))
     }
 
+    /// Pulls the seal tasks of up to `seal_threads` loads, so that every sealing thread has a
+    /// load to seal in a round.
     async fn fetch_task(&self) -> Result<Option<Vec<SealTask>>> {
         let seal_index_max = self.last_context_flow_length as usize / SECTORS_PER_SEAL;
-        self.store.pull_seal_chunk(seal_index_max).await
+        self.store
+            .pull_seal_chunk(seal_index_max, self.seal_threads)
+            .await
     }
 
     async fn submit_answer(&self, answers: Vec<SealAnswer>) -> Result<()> {
@@ -171,8 +182,7 @@ impl Sealer {
             tasks.iter().map(|x| x.seal_index).collect::<Vec<u64>>()
         );
 
-        let mut answers = Vec::with_capacity(tasks.len());
-
+        let mut jobs = Vec::with_capacity(tasks.len());
         for task in tasks {
             let (context_digest, end_seal) =
                 if let Some(context) = self.fetch_context(task.seal_index).await? {
@@ -181,25 +191,77 @@ impl Sealer {
                     trace!(target: "seal", "Index {} is not ready for seal", task.seal_index);
                     continue;
                 };
+            jobs.push((task, context_digest, end_seal));
+        }
+
+        let answers = self.seal_parallel(jobs).await?;
+        self.submit_answer(answers).await?;
+
+        Ok(true)
+    }
+
+    /// Seals the jobs on at most `seal_threads` blocking threads, so that sealing does not block
+    /// the async runtime.
+    async fn seal_parallel(&self, jobs: Vec<SealJob>) -> Result<Vec<SealAnswer>> {
+        if jobs.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let chunk_size = jobs.len().div_ceil(self.seal_threads);
+        let mut jobs = jobs.into_iter();
+        let mut handles = Vec::with_capacity(self.seal_threads);
+        let mut result = Ok(());
+        loop {
+            let chunk: Vec<SealJob> = jobs.by_ref().take(chunk_size).collect();
+            if chunk.is_empty() {
+                break;
+            }
+
+            let miner_id = self.miner_id;
+            match self
+                .executor
+                .spawn_blocking_handle(move || seal_jobs(chunk, miner_id), "data_sealer_worker")
+            {
+                Some(handle) => handles.push(handle),
+                None => {
+                    result = Err(Error::Custom("runtime shutting down".into()));
+                    break;
+                }
+            }
+        }
+
+        // Await all the spawned threads before returning any error, so that no sealing is left
+        // running in the background.
+        let mut answers = Vec::new();
+        for handle in handles {
+            match handle.await {
+                Ok(chunk_answers) => answers.extend(chunk_answers),
+                Err(e) if result.is_ok() => result = Err(e.into()),
+                Err(_) => {}
+            }
+        }
+        result.map(|_| answers)
+    }
+}
+
+fn seal_jobs(jobs: Vec<SealJob>, miner_id: H256) -> Vec<SealAnswer> {
+    jobs.into_iter()
+        .map(|(task, context_digest, end_seal)| {
             let mut data = task.non_sealed_data;
             zgs_seal::seal(
                 &mut data,
-                &self.miner_id,
+                &miner_id,
                 &context_digest,
                 task.seal_index * SECTORS_PER_SEAL as u64,
             );
-            answers.push(SealAnswer {
+            SealAnswer {
                 seal_index: task.seal_index,
                 version: task.version,
                 sealed_data: data,
-                miner_id: self.miner_id,
+                miner_id,
                 seal_context: context_digest,
                 context_end_seal: end_seal,
-            });
-        }
-
-        self.submit_answer(answers).await?;
-
-        Ok(true)
-    }
+            }
+        })
+        .collect()
 }
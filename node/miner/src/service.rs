@@ -1,5 +1,7 @@
+use crate::hasher::make_pora_hasher;
 use crate::miner_id::check_and_request_miner_id;
 use crate::monitor::Monitor;
+use crate::remote::RemoteMineServer;
 use crate::sealer::Sealer;
 use crate::submitter::Submitter;
 use crate::{config::MinerConfig, mine::PoraService, watcher::MineContextWatcher};
@@ -8,7 +10,7 @@ use std::sync::Arc;
 use std::time::Duration;
 use storage::config::ShardConfig;
 use storage_async::Store;
-use tokio::sync::broadcast;
+use tokio::sync::{broadcast, mpsc};
 
 #[derive(Clone, Debug)]
 pub enum MinerMessage {
@@ -31,9 +33,10 @@ impl MineService {
         _network_send: NetworkSender,
         config: MinerConfig,
         store: Arc<Store>,
-    ) -> Result<broadcast::Sender<MinerMessage>, String> {
+    ) -> Result<(broadcast::Sender<MinerMessage>, Arc<RemoteMineServer>), String> {
         let provider = config.make_provider()?;
         let signing_provider = Arc::new(config.make_signing_provider().await?);
+        let hasher = make_pora_hasher(&config.worker_config.pora_hasher)?;
 
         let (msg_send, msg_recv) = broadcast::channel(1024);
 
@@ -49,14 +52,24 @@ impl MineService {
             miner_id,
         );
 
-        let mine_answer_receiver = PoraService::spawn(
+        let (mine_answer_sender, mine_answer_receiver) = mpsc::unbounded_channel();
+        let job_receiver = PoraService::spawn(
             executor.clone(),
             msg_recv.resubscribe(),
             mine_context_receiver.resubscribe(),
+            mine_answer_sender.clone(),
             store.clone(),
-            &config,
+            hasher.clone(),
+            &config.worker_config,
+            config.shard_config,
             miner_id,
         );
+        let remote_mine_server = Arc::new(RemoteMineServer::new(
+            job_receiver,
+            mine_answer_sender,
+            store.clone(),
+            hasher,
+        ));
 
         Submitter::spawn(
             executor.clone(),
@@ -74,6 +87,6 @@ impl MineService {
 
         debug!("Starting miner service");
 
-        Ok(msg_send)
+        Ok((msg_send, remote_mine_server))
     }
 }
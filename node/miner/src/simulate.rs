@@ -0,0 +1,348 @@
+//! Offline mining simulation, which replays recorded mine contexts against the local log store
+//! with a fake submitter, so that a miner could be debugged without a live mine contract.
+use contract_interface::pora_mine::MineContext;
+use ethereum_types::{H256, U256};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use task_executor::TaskExecutor;
+use tokio::sync::{broadcast, mpsc};
+use tokio::time::{sleep, Instant};
+use zgs_spec::{SEALS_PER_LOAD, SECTORS_PER_LOAD};
+
+use crate::hasher::make_pora_hasher;
+use crate::metrics::{mining_stats, MiningStats};
+use crate::mine::{PoraPuzzle, PoraService};
+use crate::pora::AnswerWithoutProof;
+use crate::recall_range::RecallRange;
+use crate::{MineRangeConfig, MinerConfig, MinerMessage, PoraLoader};
+
+/// Maximum number of recall positions sampled to estimate the recall coverage.
+const COVERAGE_SAMPLES: u64 = 1 << 16;
+
+/// A mine context recorded from the mine contract, i.e. the result of `computeWorkerContext`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordedPuzzle {
+    pub epoch: u64,
+    pub mine_start: u64,
+    pub flow_root: H256,
+    pub flow_length: u64,
+    pub block_digest: H256,
+    pub digest: H256,
+    pub pora_target: U256,
+    pub subtask_digest: H256,
+    pub max_shards: u64,
+}
+
+impl RecordedPuzzle {
+    pub(crate) fn to_puzzle(&self) -> PoraPuzzle {
+        let context = MineContext {
+            epoch: self.epoch.into(),
+            mine_start: self.mine_start.into(),
+            flow_root: self.flow_root.0,
+            flow_length: self.flow_length.into(),
+            block_digest: self.block_digest.0,
+            digest: self.digest.0,
+        };
+        PoraPuzzle::new(
+            context,
+            self.pora_target,
+            self.max_shards,
+            self.subtask_digest,
+        )
+    }
+}
+
+impl From<&PoraPuzzle> for RecordedPuzzle {
+    fn from(puzzle: &PoraPuzzle) -> Self {
+        let context = puzzle.context();
+        Self {
+            epoch: context.epoch.as_u64(),
+            mine_start: context.mine_start.as_u64(),
+            flow_root: H256(context.flow_root),
+            flow_length: context.flow_length.as_u64(),
+            block_digest: H256(context.block_digest),
+            digest: H256(context.digest),
+            pora_target: puzzle.pora_target(),
+            subtask_digest: puzzle.subtask_digest(),
+            max_shards: puzzle.max_shards(),
+        }
+    }
+}
+
+/// Loads recorded mine contexts from a JSON file of `RecordedPuzzle` array.
+pub fn load_recorded_puzzles(path: &Path) -> Result<Vec<RecordedPuzzle>, String> {
+    let content = std::fs::read(path).map_err(|e| format!("Failed to read {:?}: {:?}", path, e))?;
+    serde_json::from_slice(&content).map_err(|e| format!("Failed to parse {:?}: {:?}", path, e))
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SimulateConfig {
+    pub miner_id: H256,
+    pub start_position: Option<u64>,
+    pub end_position: Option<u64>,
+    /// Mining time of each recorded mine context.
+    pub duration: Duration,
+}
+
+/// Simulation result of a recorded mine context.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulationReport {
+    pub epoch: u64,
+    /// Reason that the mine context could not be mined with the local config.
+    pub skipped: Option<String>,
+    pub hashes: u64,
+    /// Fraction of recall positions covered by the mine range, estimated from the config.
+    pub expected_coverage: f64,
+    /// Fraction of hashes whose recall position is covered by the mine range.
+    pub actual_coverage: f64,
+    pub loads_missing: u64,
+    pub seals_unsealed: u64,
+    /// Expected answers per hash, assuming all covered data is stored and sealed.
+    pub expected_hit_rate: f64,
+    /// Answers received by the fake submitter per hash.
+    pub actual_hit_rate: f64,
+    pub answers: u64,
+}
+
+/// Runs `PoraService` against the loader for every recorded mine context in turn, and collects
+/// answers with a fake submitter instead of submitting them to the mine contract.
+pub async fn simulate(
+    executor: TaskExecutor,
+    loader: Arc<dyn PoraLoader>,
+    config: &MinerConfig,
+    simulate_config: SimulateConfig,
+    puzzles: Vec<RecordedPuzzle>,
+) -> Result<Vec<SimulationReport>, String> {
+    let hasher = make_pora_hasher(&config.worker_config.pora_hasher)?;
+    let (msg_send, msg_recv) = broadcast::channel(1024);
+    let (context_send, context_recv) = broadcast::channel(1024);
+    let (answer_send, mut answer_recv) = mpsc::unbounded_channel();
+    let _job_recv = PoraService::spawn(
+        executor,
+        msg_recv,
+        context_recv,
+        answer_send,
+        loader,
+        hasher,
+        &config.worker_config,
+        config.shard_config,
+        simulate_config.miner_id,
+    );
+
+    let mine_range = MineRangeConfig::new(
+        simulate_config.start_position.or(Some(0)),
+        simulate_config.end_position.or(Some(u64::MAX)),
+        config.shard_config,
+    );
+    let messages = [
+        simulate_config
+            .start_position
+            .map(|pos| MinerMessage::SetStartPosition(Some(pos))),
+        simulate_config
+            .end_position
+            .map(|pos| MinerMessage::SetEndPosition(Some(pos))),
+    ];
+    for msg in messages.into_iter().flatten() {
+        msg_send
+            .send(msg)
+            .map_err(|e| format!("Failed to configure mine range: {:?}", e))?;
+    }
+
+    let mut reports = vec![];
+    for recorded in puzzles {
+        let puzzle = recorded.to_puzzle();
+        let mut report = SimulationReport {
+            epoch: recorded.epoch,
+            ..Default::default()
+        };
+
+        let range = match mine_range.to_valid_range(puzzle.context()) {
+            Some(range) if range.mining_length > 0 => range,
+            _ => {
+                report.skipped = Some("no mine range".into());
+                reports.push(report);
+                continue;
+            }
+        };
+        report.expected_coverage = expected_coverage(&mine_range, &range);
+        report.expected_hit_rate =
+            report.expected_coverage * SEALS_PER_LOAD as f64 * hit_probability(&puzzle, &range);
+
+        let before = mining_stats();
+        context_send
+            .send(Some(puzzle.clone()))
+            .map_err(|e| format!("Failed to send mine context: {:?}", e))?;
+        report.answers = collect_answers(&mut answer_recv, &puzzle, simulate_config.duration).await;
+        context_send
+            .send(None)
+            .map_err(|e| format!("Failed to send mine context: {:?}", e))?;
+        // wait for workers to stop the current job
+        sleep(Duration::from_millis(100)).await;
+        report.answers += collect_answers(&mut answer_recv, &puzzle, Duration::ZERO).await;
+
+        report.update_stats(&before, &mining_stats());
+        reports.push(report);
+    }
+
+    Ok(reports)
+}
+
+impl SimulationReport {
+    fn update_stats(&mut self, before: &MiningStats, after: &MiningStats) {
+        self.hashes = after.hashes - before.hashes;
+        self.loads_missing = after.loads_missing - before.loads_missing;
+        self.seals_unsealed = after.seals_unsealed - before.seals_unsealed;
+
+        if self.hashes > 0 {
+            let recall_hits = after.recall_hits - before.recall_hits;
+            self.actual_coverage = recall_hits as f64 / self.hashes as f64;
+            self.actual_hit_rate = self.answers as f64 / self.hashes as f64;
+        }
+    }
+}
+
+/// Acts as the submitter for `duration`, and returns the number of answers of the puzzle.
+async fn collect_answers(
+    answer_recv: &mut mpsc::UnboundedReceiver<AnswerWithoutProof>,
+    puzzle: &PoraPuzzle,
+    duration: Duration,
+) -> u64 {
+    let deadline = Instant::now() + duration;
+    let mut answers = 0;
+
+    loop {
+        let answer = tokio::select! {
+            answer = answer_recv.recv() => answer,
+            _ = tokio::time::sleep_until(deadline) => None,
+        };
+        let answer = match answer {
+            Some(answer) => answer,
+            None => break,
+        };
+
+        if answer.context_digest == puzzle.context_digest() {
+            answers += 1;
+        } else {
+            debug!("Skip answer of inconsistent context digest");
+        }
+    }
+
+    // answers received right before the deadline
+    while let Ok(answer) = answer_recv.try_recv() {
+        if answer.context_digest == puzzle.context_digest() {
+            answers += 1;
+        }
+    }
+
+    answers
+}
+
+/// Estimates the fraction of recall positions covered by the mine range, by sampling recall
+/// offsets evenly in the same way as `RecallRange::load_position`.
+fn expected_coverage(mine_range: &MineRangeConfig, range: &RecallRange) -> f64 {
+    let num_loads = range.mining_length / SECTORS_PER_LOAD as u64;
+    if num_loads == 0 {
+        return 0.0;
+    }
+
+    let step = (num_loads / COVERAGE_SAMPLES).max(1);
+    let (mut covered, mut total) = (0u64, 0u64);
+    for origin_offset in (0..num_loads).step_by(step as usize) {
+        let recall_offset = (origin_offset & range.shard_mask) | range.shard_id;
+        let recall_position = range.start_position + recall_offset * SECTORS_PER_LOAD as u64;
+        total += 1;
+        if mine_range.is_covered(recall_position) == Some(true) {
+            covered += 1;
+        }
+    }
+
+    covered as f64 / total as f64
+}
+
+/// Returns the probability that the quality of a seal meets the target of the puzzle.
+fn hit_probability(puzzle: &PoraPuzzle, range: &RecallRange) -> f64 {
+    let scale = range.difficulty_scale_x64(puzzle.context().flow_length.as_u64());
+    if scale.is_zero() {
+        return 0.0;
+    }
+
+    // the same target as `Miner::iteration`, whose overflow bits are dropped
+    let target = (puzzle.pora_target / scale) << 64;
+    u256_to_f64(target) / 2f64.powi(256)
+}
+
+fn u256_to_f64(value: U256) -> f64 {
+    let shift = value.bits().saturating_sub(64);
+    (value >> shift).as_u64() as f64 * 2f64.powi(shift as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use storage::config::ShardConfig;
+    use zgs_spec::SECTORS_PER_PRICING;
+
+    fn recall_range(shard_config: &ShardConfig) -> RecallRange {
+        RecallRange {
+            start_position: 0,
+            mining_length: 4 * SECTORS_PER_PRICING as u64,
+            shard_mask: shard_config.miner_shard_mask(),
+            shard_id: shard_config.miner_shard_id(),
+        }
+    }
+
+    #[test]
+    fn test_expected_coverage() {
+        let shard_config = ShardConfig::default();
+        let range = recall_range(&shard_config);
+
+        let mine_range = MineRangeConfig::new(Some(0), Some(u64::MAX), shard_config);
+        assert_eq!(expected_coverage(&mine_range, &range), 1.0);
+
+        // the first half of the recall range
+        let mine_range =
+            MineRangeConfig::new(Some(0), Some(2 * SECTORS_PER_PRICING as u64), shard_config);
+        let coverage = expected_coverage(&mine_range, &range);
+        assert!((coverage - 0.5).abs() < 0.01);
+
+        let mine_range = MineRangeConfig::new(None, None, shard_config);
+        assert_eq!(expected_coverage(&mine_range, &range), 0.0);
+    }
+
+    #[test]
+    fn test_u256_to_f64() {
+        assert_eq!(u256_to_f64(U256::zero()), 0.0);
+        assert_eq!(u256_to_f64(U256::from(12345u64)), 12345.0);
+        assert_eq!(u256_to_f64(U256::one() << 200), 2f64.powi(200));
+        assert_eq!(u256_to_f64(U256::MAX), 2f64.powi(256));
+    }
+
+    #[test]
+    fn test_recorded_puzzle_json() {
+        let recorded = RecordedPuzzle {
+            epoch: 1,
+            mine_start: 100,
+            flow_root: H256::random(),
+            flow_length: 1 << 30,
+            block_digest: H256::random(),
+            digest: H256::random(),
+            pora_target: U256::MAX,
+            subtask_digest: H256::random(),
+            max_shards: 128,
+        };
+
+        let json = serde_json::to_string(&recorded).unwrap();
+        assert!(json.contains("poraTarget"));
+        let decoded: RecordedPuzzle = serde_json::from_str(&json).unwrap();
+
+        let puzzle = decoded.to_puzzle();
+        assert_eq!(puzzle, recorded.to_puzzle());
+        assert_eq!(puzzle.context_digest(), recorded.digest);
+        assert_eq!(puzzle.max_shards(), 128);
+        assert_eq!(RecordedPuzzle::from(&puzzle), recorded);
+    }
+}
@@ -0,0 +1,206 @@
+//! A pool of PoRA mining workers, each of which runs on a dedicated thread and could be pinned
+//! to a CPU core.
+//!
+//! Workers share the same mining job, and split its nonce space by batches: the worker `id`
+//! of `n` workers iterates batches `id, id + n, id + 2n, ...`, so that no nonce is computed
+//! twice.
+use ethereum_types::H256;
+use std::sync::Arc;
+use task_executor::TaskExecutor;
+use tokio::sync::{mpsc, watch};
+use tokio::time::{sleep, Duration, Instant};
+
+use crate::hasher::PoraHasher;
+use crate::mine::MineJob;
+use crate::pora::{AnswerWithoutProof, Miner};
+use crate::{MineWorkerConfig, PoraLoader};
+
+pub(crate) struct MineWorker {
+    id: usize,
+    num_workers: usize,
+    cpu_core: Option<usize>,
+    job_receiver: watch::Receiver<Option<MineJob>>,
+    mine_answer_sender: mpsc::UnboundedSender<AnswerWithoutProof>,
+    loader: Arc<dyn PoraLoader>,
+    hasher: Arc<dyn PoraHasher>,
+
+    cpu_percentage: u64,
+    iter_batch: usize,
+}
+
+impl MineWorker {
+    pub fn spawn(
+        executor: TaskExecutor,
+        id: usize,
+        num_workers: usize,
+        job_receiver: watch::Receiver<Option<MineJob>>,
+        mine_answer_sender: mpsc::UnboundedSender<AnswerWithoutProof>,
+        loader: Arc<dyn PoraLoader>,
+        hasher: Arc<dyn PoraHasher>,
+        config: &MineWorkerConfig,
+    ) {
+        let cpu_core = if config.cpu_affinity.is_empty() {
+            None
+        } else {
+            Some(config.cpu_affinity[id % config.cpu_affinity.len()])
+        };
+
+        let worker = MineWorker {
+            id,
+            num_workers,
+            cpu_core,
+            job_receiver,
+            mine_answer_sender,
+            loader,
+            hasher,
+            cpu_percentage: config.cpu_percentage,
+            iter_batch: config.iter_batch,
+        };
+
+        let handle = match executor.handle() {
+            Some(handle) => handle,
+            None => {
+                warn!("Runtime is shutting down, mining worker {} not spawned", id);
+                return;
+            }
+        };
+
+        // Mining is CPU intensive, so it runs on a blocking thread instead of the async
+        // workers, while loading sealed data is still driven by the runtime.
+        executor.spawn_blocking(
+            move || {
+                if let Some(core) = worker.cpu_core {
+                    match set_cpu_affinity(core) {
+                        Ok(()) => debug!("Mining worker {} pinned to CPU {}", worker.id, core),
+                        Err(e) => warn!(
+                            "Failed to pin mining worker {} to CPU {}: {}",
+                            worker.id, core, e
+                        ),
+                    }
+                }
+
+                handle.block_on(worker.start())
+            },
+            "pora_worker",
+        );
+    }
+
+    /// Mines the current job until the job channel is closed.
+    async fn start(mut self) {
+        loop {
+            let job = self.job_receiver.borrow_and_update().clone();
+            let keep_running = match job {
+                Some(job) => self.mine(&job).await,
+                None => self.job_receiver.changed().await.is_ok(),
+            };
+
+            if !keep_running {
+                debug!("Mining worker {} exits", self.id);
+                return;
+            }
+        }
+    }
+
+    /// Mines the job until it is replaced. Returns `false` if the job channel is closed.
+    async fn mine(&mut self, job: &MineJob) -> bool {
+        let miner = job.as_miner(&*self.loader, &*self.hasher);
+        let mut batch = self.id as u64;
+
+        loop {
+            tokio::select! {
+                biased;
+
+                changed = self.job_receiver.changed() => return changed.is_ok(),
+
+                _ = mine_batch(
+                    &miner,
+                    batch_nonce(job.nonce, batch),
+                    self.iter_batch,
+                    self.cpu_percentage,
+                    &self.mine_answer_sender,
+                ) => {}
+            }
+
+            batch += self.num_workers as u64;
+        }
+    }
+}
+
+async fn mine_batch(
+    miner: &Miner<'_>,
+    nonce: H256,
+    iter_batch: usize,
+    cpu_percentage: u64,
+    mine_answer_sender: &mpsc::UnboundedSender<AnswerWithoutProof>,
+) {
+    let timer = Instant::now();
+
+    if let Some(answer) = miner.batch_iteration(nonce, iter_batch).await {
+        info!("Hit Pora answer {:?}", answer);
+        if mine_answer_sender.send(answer).is_err() {
+            warn!("Mine submitter channel closed");
+        }
+    } else if cpu_percentage < 100 {
+        // 2^64 ns = 500 years
+        let elapsed = timer.elapsed().as_nanos() as u64;
+        let diastole_time = elapsed / cpu_percentage * (100 - cpu_percentage);
+        sleep(Duration::from_nanos(diastole_time)).await;
+    }
+}
+
+/// Returns the nonce of the batch, which has the batch index mixed into bytes `[8, 16)`, while
+/// `Miner::batch_iteration` mixes the iteration index into the lowest bytes.
+fn batch_nonce(nonce: H256, batch: u64) -> H256 {
+    let mut batch_nonce = nonce;
+    for (x, y) in batch_nonce.0[8..16].iter_mut().zip(batch.to_be_bytes()) {
+        *x ^= y;
+    }
+    batch_nonce
+}
+
+#[cfg(target_os = "linux")]
+fn set_cpu_affinity(core: usize) -> Result<(), String> {
+    if core >= libc::CPU_SETSIZE as usize {
+        return Err(format!("CPU index out of range: {}", core));
+    }
+
+    // Safe since the set is initialized and the core index is checked above.
+    let result = unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        libc::CPU_SET(core, &mut set);
+        libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set)
+    };
+
+    if result != 0 {
+        return Err(std::io::Error::last_os_error().to_string());
+    }
+
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_cpu_affinity(_core: usize) -> Result<(), String> {
+    Err("CPU affinity is only supported on Linux".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_batch_nonce() {
+        let nonce = H256::random();
+        assert_eq!(batch_nonce(nonce, 0), nonce);
+
+        // batches of workers never overlap with iterations within a batch
+        let num_workers = 4u64;
+        let nonces: HashSet<H256> = (0..num_workers)
+            .flat_map(|id| (0..8).map(move |k| id + k * num_workers))
+            .map(|batch| batch_nonce(nonce, batch))
+            .collect();
+        assert_eq!(nonces.len(), 32);
+        assert!(nonces.iter().all(|x| x.0[..8] == nonce.0[..8]));
+        assert!(nonces.iter().all(|x| x.0[16..] == nonce.0[16..]));
+    }
+}
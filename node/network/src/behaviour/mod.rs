@@ -6,7 +6,10 @@ use crate::peer_manager::{
     ConnectionDirection, PeerManager, PeerManagerEvent,
 };
 use crate::rpc::methods::DataByHashRequest;
-use crate::rpc::methods::GetChunksRequest;
+use crate::rpc::methods::{
+    GetChunksRequest, GetErasurePieceRequest, GetTransactionsRequest, StorageChallengeRequest,
+    StorageChallengeResponse, TransactionsResponse,
+};
 use crate::rpc::*;
 use crate::service::Context as ServiceContext;
 use crate::types::{GossipEncoding, GossipKind, GossipTopic, SnappyTransform};
@@ -24,16 +27,22 @@ use libp2p::{
         MessageAuthenticity, MessageId,
     },
     identify::{Identify, IdentifyConfig, IdentifyEvent},
+    kad::{
+        record::Key,
+        store::{MemoryStore, MemoryStoreConfig},
+        GetProvidersOk, Kademlia, KademliaConfig, KademliaEvent, QueryId, QueryResult,
+    },
     swarm::{
+        behaviour::toggle::Toggle,
         dial_opts::{DialOpts, PeerCondition},
         AddressScore, NetworkBehaviour, NetworkBehaviourAction as NBAction,
         NetworkBehaviourEventProcess, PollParameters,
     },
     NetworkBehaviour, PeerId,
 };
-use shared_types::{ChunkArrayWithProof, ShardedFile};
+use shared_types::{ChunkArrayWithProof, ErasurePiece, ShardConfig, ShardedFile, TxID};
 use std::{
-    collections::VecDeque,
+    collections::{HashMap, VecDeque},
     sync::Arc,
     task::{Context, Poll},
 };
@@ -42,12 +51,16 @@ use self::gossip_cache::GossipCache;
 
 mod gossip_cache;
 pub mod gossipsub_scoring_parameters;
+pub mod providers;
 
 /// The number of peers we target per subnet for discovery queries.
 pub const TARGET_SUBNET_PEERS: usize = 6;
 
 const MAX_IDENTIFY_ADDRESSES: usize = 10;
 
+/// The protocol name of Kademlia DHT, which differs from the IPFS one to avoid mixing networks.
+const KADEMLIA_PROTOCOL_NAME: &[u8] = b"/zgs/kad/1.0.0";
+
 /// Identifier of requests sent by a peer.
 pub type PeerRequestId = (ConnectionId, SubstreamId);
 
@@ -111,6 +124,15 @@ pub enum BehaviourEvent<AppReqId: ReqId> {
     },
     /// Inform the network to send a Status to this peer.
     StatusPeer(PeerId),
+    /// Providers of a file are found in the DHT.
+    ProvidersFound {
+        /// The file to find providers for.
+        tx_id: TxID,
+        /// The shard config of provider records that the providers published.
+        shard_config: ShardConfig,
+        /// The providers along with their known addresses.
+        providers: Vec<(PeerId, Vec<Multiaddr>)>,
+    },
 }
 
 /// Internal type to pass messages from sub-behaviours to the poll of the global behaviour to be
@@ -145,6 +167,9 @@ pub struct Behaviour<AppReqId: ReqId> {
     identify: Identify,
     /// The peer manager that keeps track of peer's reputation and status.
     peer_manager: PeerManager,
+    /// Kademlia DHT to publish and find provider records of files, which is disabled unless
+    /// `dht_enabled` is configured.
+    kademlia: Toggle<Kademlia<MemoryStore>>,
 
     /* Auxiliary Fields */
     /// The output events generated by this behaviour to be consumed in the swarm poll.
@@ -166,6 +191,12 @@ pub struct Behaviour<AppReqId: ReqId> {
     update_gossipsub_scores: tokio::time::Interval,
     #[behaviour(ignore)]
     gossip_cache: GossipCache,
+    /// Provider records that have been published, along with the shard config of records.
+    #[behaviour(ignore)]
+    provided_keys: HashMap<Key, ShardConfig>,
+    /// Ongoing queries to find providers of files.
+    #[behaviour(ignore)]
+    provider_queries: HashMap<QueryId, (TxID, ShardConfig)>,
 }
 
 /// Implements the combined behaviour for the libp2p service.
@@ -249,6 +280,10 @@ impl<AppReqId: ReqId> Behaviour<AppReqId> {
             get_hash(GossipKind::AnnounceFile),
             TopicScoreParams::default(),
         );
+        params.topics.insert(
+            get_hash(GossipKind::AnnounceFileRanges),
+            TopicScoreParams::default(),
+        );
         params.topics.insert(
             get_hash(GossipKind::AnnounceShardConfig),
             TopicScoreParams::default(),
@@ -269,6 +304,7 @@ impl<AppReqId: ReqId> Behaviour<AppReqId> {
             discovery_enabled: !config.disable_discovery,
             metrics_enabled: config.metrics_enabled,
             target_peer_count: config.target_peers,
+            peer_store_dir: Some(config.network_dir.clone()),
             ..config.peer_manager
         };
 
@@ -277,12 +313,31 @@ impl<AppReqId: ReqId> Behaviour<AppReqId> {
 
         let gossip_cache = GossipCache::default();
 
+        let local_peer_id = local_key.public().to_peer_id();
+        let mut kademlia_config = KademliaConfig::default();
+        kademlia_config.set_protocol_name(KADEMLIA_PROTOCOL_NAME);
+        let kademlia = Toggle::from(config.dht_enabled.then(|| {
+            Kademlia::with_config(
+                local_peer_id,
+                MemoryStore::with_config(
+                    local_peer_id,
+                    MemoryStoreConfig {
+                        max_records: config.dht_max_records,
+                        max_provided_keys: config.dht_max_records,
+                        ..Default::default()
+                    },
+                ),
+                kademlia_config,
+            )
+        }));
+
         Ok(Behaviour {
             // Sub-behaviours
             gossipsub,
             eth2_rpc: RPC::new(),
             discovery,
             identify: Identify::new(identify_config),
+            kademlia,
             // Auxiliary fields
             peer_manager: PeerManager::new(peer_manager_cfg, network_globals.clone()).await?,
             events: VecDeque::new(),
@@ -291,6 +346,8 @@ impl<AppReqId: ReqId> Behaviour<AppReqId> {
             waker: None,
             gossip_cache,
             update_gossipsub_scores,
+            provided_keys: HashMap::new(),
+            provider_queries: HashMap::new(),
         })
     }
 
@@ -464,7 +521,15 @@ impl<AppReqId: ReqId> Behaviour<AppReqId> {
         id: PeerRequestId,
         response: Response,
     ) {
-        self.eth2_rpc.send_response(peer_id, id, response.into())
+        let response: RPCCodedResponse = response.into();
+        if let RPCCodedResponse::Success(resp) = &response {
+            self.network_globals.bandwidth.write().record_outbound(
+                &peer_id,
+                resp.protocol(),
+                resp.ssz_bytes_len(),
+            );
+        }
+        self.eth2_rpc.send_response(peer_id, id, response)
     }
 
     /// Inform the peer that their request produced an error.
@@ -499,6 +564,67 @@ impl<AppReqId: ReqId> Behaviour<AppReqId> {
         self.discovery.add_enr(enr);
     }
 
+    /* DHT functions */
+
+    /// Returns whether provider records are published and found in the Kademlia DHT.
+    pub fn dht_enabled(&self) -> bool {
+        self.kademlia.is_enabled()
+    }
+
+    /// Publishes the provider record of the file, if not published yet.
+    pub fn provide_file(&mut self, tx_seq: u64, shard_config: ShardConfig) {
+        let network_id = self.network_globals.network_id();
+        let kademlia = match self.kademlia.as_mut() {
+            Some(kademlia) => kademlia,
+            None => return,
+        };
+
+        let key = providers::provider_key(&network_id, tx_seq, &shard_config);
+        if self.provided_keys.contains_key(&key) {
+            return;
+        }
+
+        match kademlia.start_providing(key.clone()) {
+            Ok(_) => {
+                self.provided_keys.insert(key, shard_config);
+            }
+            Err(e) => warn!(%tx_seq, error = ?e, "Failed to provide file in DHT"),
+        }
+    }
+
+    /// Stops providing records of other shard configs, which no longer hold once the local
+    /// shard config changed.
+    pub fn update_provider_shard_config(&mut self, shard_config: ShardConfig) {
+        let kademlia = match self.kademlia.as_mut() {
+            Some(kademlia) => kademlia,
+            None => return,
+        };
+
+        self.provided_keys.retain(|key, config| {
+            if *config == shard_config {
+                return true;
+            }
+            kademlia.stop_providing(key);
+            false
+        });
+    }
+
+    /// Queries the DHT for peers that provide the file, of any shard config that stores all data
+    /// of the given shard.
+    pub fn find_providers(&mut self, tx_id: TxID, shard_config: ShardConfig) {
+        let network_id = self.network_globals.network_id();
+        let kademlia = match self.kademlia.as_mut() {
+            Some(kademlia) => kademlia,
+            None => return,
+        };
+
+        for config in providers::covering_shard_configs(&shard_config) {
+            let key = providers::provider_key(&network_id, tx_id.seq, &config);
+            let query_id = kademlia.get_providers(key);
+            self.provider_queries.insert(query_id, (tx_id, config));
+        }
+    }
+
     /* Private internal functions */
 
     /// Sends a Ping request to the peer.
@@ -553,6 +679,15 @@ impl<AppReqId: ReqId> Behaviour<AppReqId> {
             Request::GetChunks { .. } => {
                 metrics::inc_counter_vec(&metrics::TOTAL_RPC_REQUESTS, &["get_chunks"])
             }
+            Request::GetErasurePiece { .. } => {
+                metrics::inc_counter_vec(&metrics::TOTAL_RPC_REQUESTS, &["get_erasure_piece"])
+            }
+            Request::StorageChallenge { .. } => {
+                metrics::inc_counter_vec(&metrics::TOTAL_RPC_REQUESTS, &["storage_challenge"])
+            }
+            Request::GetTransactions { .. } => {
+                metrics::inc_counter_vec(&metrics::TOTAL_RPC_REQUESTS, &["get_transactions"])
+            }
         }
         self.add_event(BehaviourEvent::RequestReceived {
             peer_id,
@@ -749,6 +884,24 @@ where
             }
             Ok(RPCReceived::Request(id, request)) => {
                 let peer_request_id = (handler_id, id);
+
+                // reject data requests if egress quotas used up
+                let check = self
+                    .network_globals
+                    .bandwidth
+                    .write()
+                    .check_egress(&peer_id, request.protocol());
+                if let Err(quota) = check {
+                    debug!(%peer_id, %request, ?quota, "Egress quota exceeded");
+                    self.send_error_reponse(
+                        peer_id,
+                        peer_request_id,
+                        RPCResponseErrorCode::RateLimited,
+                        format!("{:?} egress quota exceeded", quota),
+                    );
+                    return;
+                }
+
                 match request {
                     /* Behaviour managed protocols: Ping and Metadata */
                     InboundRequest::Ping(ping) => {
@@ -786,9 +939,30 @@ where
                     InboundRequest::GetChunks(req) => {
                         self.propagate_request(peer_request_id, peer_id, Request::GetChunks(req))
                     }
+                    InboundRequest::GetErasurePiece(req) => self.propagate_request(
+                        peer_request_id,
+                        peer_id,
+                        Request::GetErasurePiece(req),
+                    ),
+                    InboundRequest::StorageChallenge(req) => self.propagate_request(
+                        peer_request_id,
+                        peer_id,
+                        Request::StorageChallenge(req),
+                    ),
+                    InboundRequest::GetTransactions(req) => self.propagate_request(
+                        peer_request_id,
+                        peer_id,
+                        Request::GetTransactions(req),
+                    ),
                 }
             }
             Ok(RPCReceived::Response(id, resp)) => {
+                self.network_globals.bandwidth.write().record_inbound(
+                    &peer_id,
+                    resp.protocol(),
+                    resp.ssz_bytes_len(),
+                );
+
                 match resp {
                     /* Behaviour managed protocols */
                     RPCResponse::Pong(ping) => self.peer_manager.pong_response(&peer_id, ping.data),
@@ -805,6 +979,15 @@ where
                     RPCResponse::Chunks(resp) => {
                         self.propagate_response(id, peer_id, Response::Chunks(resp))
                     }
+                    RPCResponse::ErasurePiece(resp) => {
+                        self.propagate_response(id, peer_id, Response::ErasurePiece(resp))
+                    }
+                    RPCResponse::StorageChallenge(resp) => {
+                        self.propagate_response(id, peer_id, Response::StorageChallenge(resp))
+                    }
+                    RPCResponse::Transactions(resp) => {
+                        self.propagate_response(id, peer_id, Response::Transactions(resp))
+                    }
                 }
             }
             Ok(RPCReceived::EndOfStream(id, termination)) => {
@@ -863,6 +1046,12 @@ where
                 }
                 // send peer info to the peer manager.
                 self.peer_manager.identify(&peer_id, &info);
+
+                if let Some(kademlia) = self.kademlia.as_mut() {
+                    for addr in info.listen_addrs.iter() {
+                        kademlia.add_address(&peer_id, addr.clone());
+                    }
+                }
             }
             IdentifyEvent::Sent { .. } => {}
             IdentifyEvent::Error { .. } => {}
@@ -871,6 +1060,64 @@ where
     }
 }
 
+// Kademlia
+impl<AppReqId> NetworkBehaviourEventProcess<KademliaEvent> for Behaviour<AppReqId>
+where
+    AppReqId: ReqId,
+{
+    fn inject_event(&mut self, event: KademliaEvent) {
+        if let KademliaEvent::OutboundQueryCompleted { id, result, .. } = event {
+            match result {
+                QueryResult::GetProviders(Ok(GetProvidersOk { providers, .. })) => {
+                    let (tx_id, shard_config) = match self.provider_queries.remove(&id) {
+                        Some(v) => v,
+                        None => return,
+                    };
+
+                    let local_peer_id = self.network_globals.local_peer_id();
+                    let providers: Vec<_> = providers
+                        .into_iter()
+                        .filter(|peer_id| peer_id != &local_peer_id)
+                        .map(|peer_id| {
+                            let mut addrs = self.kademlia.addresses_of_peer(&peer_id);
+                            if let Some(info) =
+                                self.network_globals.peers.read().peer_info(&peer_id)
+                            {
+                                addrs.extend(info.listening_addresses().iter().cloned());
+                            }
+                            addrs.dedup();
+                            (peer_id, addrs)
+                        })
+                        .collect();
+
+                    debug!(
+                        tx_seq = tx_id.seq,
+                        ?shard_config,
+                        num_providers = providers.len(),
+                        "Found providers in DHT"
+                    );
+
+                    if !providers.is_empty() {
+                        self.add_event(BehaviourEvent::ProvidersFound {
+                            tx_id,
+                            shard_config,
+                            providers,
+                        });
+                    }
+                }
+                QueryResult::GetProviders(Err(e)) => {
+                    self.provider_queries.remove(&id);
+                    debug!(error = ?e, "Failed to find providers in DHT");
+                }
+                QueryResult::StartProviding(Err(e)) => {
+                    debug!(error = ?e, "Failed to publish provider record in DHT");
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
 type BehaviourHandler<AppReqId> = <Behaviour<AppReqId> as NetworkBehaviour>::ConnectionHandler;
 
 impl<AppReqId> Behaviour<AppReqId>
@@ -1001,6 +1248,12 @@ pub enum Request {
     AnswerFile(ShardedFile),
     /// A GetChunks request.
     GetChunks(GetChunksRequest),
+    /// A GetErasurePiece request.
+    GetErasurePiece(GetErasurePieceRequest),
+    /// A StorageChallenge request.
+    StorageChallenge(StorageChallengeRequest),
+    /// A GetTransactions request.
+    GetTransactions(GetTransactionsRequest),
 }
 
 impl std::convert::From<Request> for OutboundRequest {
@@ -1010,6 +1263,9 @@ impl std::convert::From<Request> for OutboundRequest {
             Request::DataByHash(r) => OutboundRequest::DataByHash(r),
             Request::AnswerFile(r) => OutboundRequest::AnswerFile(r),
             Request::GetChunks(r) => OutboundRequest::GetChunks(r),
+            Request::GetErasurePiece(r) => OutboundRequest::GetErasurePiece(r),
+            Request::StorageChallenge(r) => OutboundRequest::StorageChallenge(r),
+            Request::GetTransactions(r) => OutboundRequest::GetTransactions(r),
         }
     }
 }
@@ -1028,6 +1284,12 @@ pub enum Response {
     DataByHash(Option<Box<ZgsData>>),
     /// A response to a GET_CHUNKS request.
     Chunks(ChunkArrayWithProof),
+    /// A response to a GET_ERASURE_PIECE request.
+    ErasurePiece(ErasurePiece),
+    /// A response to a STORAGE_CHALLENGE request.
+    StorageChallenge(StorageChallengeResponse),
+    /// A response to a GET_TRANSACTIONS request.
+    Transactions(TransactionsResponse),
 }
 
 impl std::convert::From<Response> for RPCCodedResponse {
@@ -1039,6 +1301,11 @@ impl std::convert::From<Response> for RPCCodedResponse {
                 None => RPCCodedResponse::StreamTermination(ResponseTermination::DataByHash),
             },
             Response::Chunks(c) => RPCCodedResponse::Success(RPCResponse::Chunks(c)),
+            Response::ErasurePiece(p) => RPCCodedResponse::Success(RPCResponse::ErasurePiece(p)),
+            Response::StorageChallenge(r) => {
+                RPCCodedResponse::Success(RPCResponse::StorageChallenge(r))
+            }
+            Response::Transactions(r) => RPCCodedResponse::Success(RPCResponse::Transactions(r)),
         }
     }
 }
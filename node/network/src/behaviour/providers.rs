@@ -0,0 +1,90 @@
+//! Provider records in the Kademlia DHT to locate files without flooding `FindFile` messages.
+//!
+//! A node provides a record for every finalized file that it stores, keyed by the tx_seq and the
+//! shard config of the node. To find peers of a file, the records of the file and all shard
+//! configs that cover the local shard are queried.
+use libp2p::kad::record::Key;
+use shared_types::{NetworkIdentity, ShardConfig};
+use ssz::Encode;
+use tiny_keccak::{Hasher, Keccak};
+
+/// Returns the key of provider record for the file of `tx_seq` and the shard config.
+pub fn provider_key(network_id: &NetworkIdentity, tx_seq: u64, shard_config: &ShardConfig) -> Key {
+    let mut hasher = Keccak::v256();
+    hasher.update(&network_id.as_ssz_bytes());
+    hasher.update(&tx_seq.to_be_bytes());
+    hasher.update(&(shard_config.num_shard as u64).to_be_bytes());
+    hasher.update(&(shard_config.shard_id as u64).to_be_bytes());
+
+    let mut hash = [0u8; 32];
+    hasher.finalize(&mut hash);
+    Key::new(&hash)
+}
+
+/// Returns the shard configs that store all data of the given shard, i.e. the shard config
+/// itself and those with fewer shards.
+pub fn covering_shard_configs(shard_config: &ShardConfig) -> Vec<ShardConfig> {
+    let mut configs = vec![];
+    let mut num_shard = shard_config.num_shard;
+    let mut shard_id = shard_config.shard_id;
+
+    while num_shard > 0 {
+        configs.push(ShardConfig {
+            num_shard,
+            shard_id,
+        });
+        num_shard /= 2;
+        shard_id /= 2;
+    }
+
+    configs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_covering_shard_configs() {
+        let configs = covering_shard_configs(&ShardConfig {
+            num_shard: 4,
+            shard_id: 3,
+        });
+        assert_eq!(configs.len(), 3);
+        assert_eq!(configs[1].num_shard, 2);
+        assert_eq!(configs[1].shard_id, 1);
+        assert_eq!(configs[2], ShardConfig::default());
+
+        let shard_config = ShardConfig {
+            num_shard: 4,
+            shard_id: 3,
+        };
+        assert!(configs.iter().all(|v| v.intersect(&shard_config)));
+    }
+
+    #[test]
+    fn test_provider_key() {
+        let network_id = NetworkIdentity::default();
+        let shard_config = ShardConfig::default();
+
+        assert_eq!(
+            provider_key(&network_id, 1, &shard_config),
+            provider_key(&network_id, 1, &shard_config)
+        );
+        assert_ne!(
+            provider_key(&network_id, 0, &shard_config),
+            provider_key(&network_id, 1, &shard_config)
+        );
+        assert_ne!(
+            provider_key(&network_id, 0, &shard_config),
+            provider_key(
+                &network_id,
+                0,
+                &ShardConfig {
+                    num_shard: 2,
+                    shard_id: 0
+                }
+            )
+        );
+    }
+}
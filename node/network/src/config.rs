@@ -1,3 +1,4 @@
+use crate::rpc::bandwidth::BandwidthConfig;
 use crate::types::GossipKind;
 use crate::{peer_manager, Enr, PeerIdSerialized};
 use directory::{
@@ -11,7 +12,7 @@ use libp2p::gossipsub::{
 use libp2p::Multiaddr;
 use serde_derive::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use shared_types::NetworkIdentity;
+use shared_types::{NetworkIdentity, ShardConfig};
 use std::path::PathBuf;
 use std::time::Duration;
 
@@ -68,6 +69,14 @@ pub struct Config {
     /// The tcp port to broadcast to peers in order to reach back for libp2p services.
     pub enr_tcp_port: Option<u16>,
 
+    /// Addresses that libp2p listens on over QUIC, e.g. `/ip4/0.0.0.0/udp/9001/quic`. QUIC
+    /// listener is disabled if empty, but peers could still be dialed over QUIC.
+    pub quic_listen_addresses: Vec<Multiaddr>,
+
+    /// The udp port to broadcast to peers in order to reach back for libp2p services over QUIC.
+    /// If not specified, the port of the first QUIC listen address is used.
+    pub enr_quic_port: Option<u16>,
+
     /// Target number of connected peers.
     pub target_peers: usize,
 
@@ -100,6 +109,14 @@ pub struct Config {
     /// Attempt to construct external port mappings with UPnP.
     pub upnp_enabled: bool,
 
+    /// Publishes provider records of stored files in the Kademlia DHT, and allows to find file
+    /// locations by querying the DHT instead of flooding gossip messages.
+    pub dht_enabled: bool,
+
+    /// Maximum number of provider records stored in the DHT, for both records provided by the
+    /// local node and those received from peers. Each finalized file takes one record.
+    pub dht_max_records: usize,
+
     /// Subscribe to all subnets for the duration of the runtime.
     pub subscribe_all_subnets: bool,
 
@@ -127,9 +144,15 @@ pub struct Config {
     /// The id of the storage network.
     pub network_id: NetworkIdentity,
 
+    /// The shard config of the local node, which is published in ENR.
+    pub shard_config: ShardConfig,
+
     pub peer_db: peer_manager::peerdb::PeerDBConfig,
     pub peer_manager: peer_manager::config::Config,
 
+    /// Egress quotas of RPC responses.
+    pub bandwidth: BandwidthConfig,
+
     /// Whether to disable network identity in ENR.
     /// This is for test purpose only.
     pub disable_enr_network_id: bool,
@@ -196,6 +219,8 @@ impl Default for Config {
             enr_address: None,
             enr_udp_port: None,
             enr_tcp_port: None,
+            quic_listen_addresses: vec![],
+            enr_quic_port: None,
             target_peers: 50,
             gs_config,
             discv5_config,
@@ -206,6 +231,8 @@ impl Default for Config {
             client_version: zgs_version::version_with_platform(),
             disable_discovery: false,
             upnp_enabled: true,
+            dht_enabled: false,
+            dht_max_records: 1 << 20,
             network_load: 3,
             private: false,
             subscribe_all_subnets: false,
@@ -214,8 +241,10 @@ impl Default for Config {
             topics: Vec::new(),
             metrics_enabled: false,
             network_id: Default::default(),
+            shard_config: Default::default(),
             peer_db: Default::default(),
             peer_manager: Default::default(),
+            bandwidth: Default::default(),
             disable_enr_network_id: false,
             find_chunks_enabled: false,
         }
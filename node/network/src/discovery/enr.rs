@@ -3,7 +3,9 @@
 pub use discv5::enr::{CombinedKey, EnrBuilder};
 use ssz::Encode;
 
-use super::enr_ext::{CombinedKeyExt, ENR_CONTENT_KEY_NETWORK_ID};
+use super::enr_ext::{
+    CombinedKeyExt, ENR_CONTENT_KEY_NETWORK_ID, ENR_CONTENT_KEY_QUIC, ENR_CONTENT_KEY_SHARD_CONFIG,
+};
 use super::{EnrExt, ENR_FILENAME};
 use crate::types::Enr;
 use crate::NetworkConfig;
@@ -35,6 +37,7 @@ pub fn use_or_load_enr(
                         if local_enr.node_id() == disk_enr.node_id() {
                             if compare_enr(local_enr, &disk_enr)
                                 && is_disk_enr_network_id_unchanged(&disk_enr, config)
+                                && is_disk_enr_shard_config_unchanged(&disk_enr, config)
                             {
                                 debug!(file = ?enr_f, "ENR loaded from disk");
                                 // the stored ENR has the same configuration, use it
@@ -97,6 +100,10 @@ pub fn create_enr_builder_from_config<T: EnrKey>(
         let tcp_port = config.enr_tcp_port.unwrap_or(config.libp2p_port);
         builder.tcp(tcp_port);
     }
+    // advertise the QUIC port if QUIC listener enabled
+    if let Some(quic_port) = enr_quic_port(config) {
+        builder.add_value(ENR_CONTENT_KEY_QUIC, &quic_port.to_be_bytes());
+    }
     // add network identity info in ENR if not disabled
     if !config.disable_enr_network_id {
         builder.add_value(
@@ -104,9 +111,23 @@ pub fn create_enr_builder_from_config<T: EnrKey>(
             &config.network_id.as_ssz_bytes(),
         );
     }
+    // add shard config in ENR, so that peers could discover nodes that store specific shards
+    builder.add_value(
+        ENR_CONTENT_KEY_SHARD_CONFIG,
+        &config.shard_config.as_ssz_bytes(),
+    );
     builder
 }
 
+fn enr_quic_port(config: &NetworkConfig) -> Option<u16> {
+    let listen_port = config
+        .quic_listen_addresses
+        .iter()
+        .find_map(crate::quic::multiaddr_to_socketaddr)
+        .map(|(addr, _)| addr.port())?;
+    Some(config.enr_quic_port.unwrap_or(listen_port))
+}
+
 /// Builds a lighthouse ENR given a `NetworkConfig`.
 pub fn build_enr(enr_key: &CombinedKey, config: &NetworkConfig) -> Result<Enr, String> {
     let mut builder = create_enr_builder_from_config(config, true);
@@ -123,6 +144,8 @@ fn compare_enr(local_enr: &Enr, disk_enr: &Enr) -> bool {
     (local_enr.ip().is_none() || local_enr.ip() == disk_enr.ip())
         // tcp ports must match
         && local_enr.tcp() == disk_enr.tcp()
+        // quic ports must match
+        && local_enr.quic() == disk_enr.quic()
         // take preference over disk udp port if one is not specified
         && (local_enr.udp().is_none() || local_enr.udp() == disk_enr.udp())
 }
@@ -135,6 +158,10 @@ fn is_disk_enr_network_id_unchanged(disk_enr: &Enr, config: &NetworkConfig) -> b
     }
 }
 
+fn is_disk_enr_shard_config_unchanged(disk_enr: &Enr, config: &NetworkConfig) -> bool {
+    matches!(disk_enr.shard_config(), Some(Ok(v)) if v == config.shard_config)
+}
+
 /// Loads enr from the given directory
 pub fn load_enr_from_disk(dir: &Path) -> Result<Enr, String> {
     let enr_f = dir.join(ENR_FILENAME);
@@ -2,11 +2,13 @@
 use crate::{Enr, Multiaddr, PeerId};
 use discv5::enr::{CombinedKey, CombinedPublicKey};
 use libp2p::core::{identity::Keypair, identity::PublicKey, multiaddr::Protocol};
-use shared_types::NetworkIdentity;
+use shared_types::{NetworkIdentity, ShardConfig};
 use ssz::Decode;
 use tiny_keccak::{Hasher, Keccak};
 
 pub(crate) const ENR_CONTENT_KEY_NETWORK_ID: &'static str = "network_identity";
+pub(crate) const ENR_CONTENT_KEY_SHARD_CONFIG: &'static str = "shard_config";
+pub(crate) const ENR_CONTENT_KEY_QUIC: &'static str = "quic";
 
 /// Extend ENR for libp2p types.
 pub trait EnrExt {
@@ -29,8 +31,17 @@ pub trait EnrExt {
     /// Returns any multiaddrs that contain the TCP protocol.
     fn multiaddr_tcp(&self) -> Vec<Multiaddr>;
 
+    /// The QUIC port of libp2p if advertised.
+    fn quic(&self) -> Option<u16>;
+
+    /// Returns any multiaddrs that contain the QUIC protocol.
+    fn multiaddr_quic(&self) -> Vec<Multiaddr>;
+
     /// Returns network identity in content.
     fn network_identity(&self) -> Option<Result<NetworkIdentity, ssz::DecodeError>>;
+
+    /// Returns shard config in content.
+    fn shard_config(&self) -> Option<Result<ShardConfig, ssz::DecodeError>>;
 }
 
 /// Extend ENR CombinedPublicKey for libp2p types.
@@ -197,11 +208,53 @@ impl EnrExt for Enr {
         multiaddrs
     }
 
+    /// The QUIC port of libp2p if advertised.
+    fn quic(&self) -> Option<u16> {
+        let value = self.get(ENR_CONTENT_KEY_QUIC)?;
+        if value.len() > 2 {
+            return None;
+        }
+
+        let mut port = [0u8; 2];
+        port[2 - value.len()..].copy_from_slice(value);
+        Some(u16::from_be_bytes(port))
+    }
+
+    /// Returns a list of multiaddrs if the ENR has an `ip` or `ip6` and a `quic` key.
+    /// The vector remains empty if these fields are not defined.
+    fn multiaddr_quic(&self) -> Vec<Multiaddr> {
+        let quic = match self.quic() {
+            Some(port) => port,
+            None => return vec![],
+        };
+
+        let mut multiaddrs: Vec<Multiaddr> = Vec::new();
+        if let Some(ip) = self.ip() {
+            let mut multiaddr: Multiaddr = ip.into();
+            multiaddr.push(Protocol::Udp(quic));
+            multiaddr.push(Protocol::Quic);
+            multiaddrs.push(multiaddr);
+        }
+        if let Some(ip6) = self.ip6() {
+            let mut multiaddr: Multiaddr = ip6.into();
+            multiaddr.push(Protocol::Udp(quic));
+            multiaddr.push(Protocol::Quic);
+            multiaddrs.push(multiaddr);
+        }
+        multiaddrs
+    }
+
     /// Returns network identity in content.
     fn network_identity(&self) -> Option<Result<NetworkIdentity, ssz::DecodeError>> {
         let value = self.get(ENR_CONTENT_KEY_NETWORK_ID)?;
         Some(NetworkIdentity::from_ssz_bytes(value))
     }
+
+    /// Returns shard config in content.
+    fn shard_config(&self) -> Option<Result<ShardConfig, ssz::DecodeError>> {
+        let value = self.get(ENR_CONTENT_KEY_SHARD_CONFIG)?;
+        Some(ShardConfig::from_ssz_bytes(value))
+    }
 }
 
 impl CombinedKeyPublicExt for CombinedPublicKey {
@@ -286,6 +339,7 @@ pub fn peer_id_to_node_id(peer_id: &PeerId) -> Result<discv5::enr::NodeId, Strin
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ssz::Encode;
 
     #[test]
     fn test_secp256k1_peer_id_conversion() {
@@ -324,4 +378,47 @@ mod tests {
 
         assert_eq!(enr.node_id(), node_id);
     }
+
+    #[test]
+    fn test_shard_config() {
+        let keypair = libp2p::identity::Keypair::generate_secp256k1();
+        let enr_key = CombinedKey::from_libp2p(&keypair).unwrap();
+        let enr = discv5::enr::EnrBuilder::new("v4").build(&enr_key).unwrap();
+        assert!(enr.shard_config().is_none());
+
+        let shard_config = ShardConfig {
+            num_shard: 4,
+            shard_id: 3,
+        };
+        let enr = discv5::enr::EnrBuilder::new("v4")
+            .add_value(ENR_CONTENT_KEY_SHARD_CONFIG, &shard_config.as_ssz_bytes())
+            .build(&enr_key)
+            .unwrap();
+        assert_eq!(enr.shard_config(), Some(Ok(shard_config)));
+    }
+
+    #[test]
+    fn test_quic() {
+        let keypair = libp2p::identity::Keypair::generate_secp256k1();
+        let enr_key = CombinedKey::from_libp2p(&keypair).unwrap();
+        let enr = discv5::enr::EnrBuilder::new("v4")
+            .ip("1.2.3.4".parse().unwrap())
+            .tcp(1234)
+            .build(&enr_key)
+            .unwrap();
+        assert_eq!(enr.quic(), None);
+        assert!(enr.multiaddr_quic().is_empty());
+
+        let enr = discv5::enr::EnrBuilder::new("v4")
+            .ip("1.2.3.4".parse().unwrap())
+            .tcp(1234)
+            .add_value(ENR_CONTENT_KEY_QUIC, &1235u16.to_be_bytes())
+            .build(&enr_key)
+            .unwrap();
+        assert_eq!(enr.quic(), Some(1235));
+        assert_eq!(
+            enr.multiaddr_quic(),
+            vec!["/ip4/1.2.3.4/udp/1235/quic".parse::<Multiaddr>().unwrap()]
+        );
+    }
 }
@@ -25,8 +25,10 @@ pub use libp2p::{
     },
 };
 use lru::LruCache;
+use shared_types::ShardConfig;
+use ssz::Encode;
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     net::{IpAddr, SocketAddr},
     path::Path,
     pin::Pin,
@@ -58,6 +60,8 @@ pub enum DiscoveryEvent {
 enum QueryType {
     /// We are searching for more peers without ENR or time constraints.
     FindPeers,
+    /// We are searching for peers that store data of the given shard.
+    FindShardPeers(ShardConfig),
 }
 
 /// The result of a query.
@@ -106,6 +110,10 @@ pub struct Discovery {
     /// a time, regardless of the query concurrency.
     find_peer_active: bool,
 
+    /// Shards that we are actively searching peers for. We only allow a single query at a time
+    /// for each shard.
+    active_shard_queries: HashSet<ShardConfig>,
+
     /// Active discovery queries.
     active_queries: FuturesUnordered<std::pin::Pin<Box<dyn Future<Output = QueryResult> + Send>>>,
 
@@ -260,6 +268,7 @@ impl Discovery {
             cached_enrs: LruCache::new(50),
             network_globals,
             find_peer_active: false,
+            active_shard_queries: HashSet::new(),
             active_queries: FuturesUnordered::new(),
             discv5,
             event_stream,
@@ -298,6 +307,19 @@ impl Discovery {
         self.start_query(QueryType::FindPeers, target_peers);
     }
 
+    /// This adds a new `FindShardPeers` query to the queue if one doesn't already exist for the
+    /// given shard. Only peers that publish a shard config intersecting with `shard_config` in
+    /// their ENR are returned. The maximum of `target_peers` is 16.
+    pub fn discover_shard_peers(&mut self, shard_config: ShardConfig, target_peers: usize) {
+        // If the discv5 service isn't running or we are in the process of a query for the shard, don't bother queuing a new one.
+        if !self.started || !self.active_shard_queries.insert(shard_config) {
+            return;
+        }
+        let target_peers = std::cmp::min(FIND_NODE_QUERY_CLOSEST_PEERS, target_peers);
+        debug!(%target_peers, ?shard_config, "Starting a shard peer discovery request");
+        self.start_query(QueryType::FindShardPeers(shard_config), target_peers);
+    }
+
     /// Add an ENR to the routing table of the discovery mechanism.
     pub fn add_enr(&mut self, enr: Enr) {
         // add the enr to seen caches
@@ -378,6 +400,22 @@ impl Discovery {
         Ok(())
     }
 
+    /// Updates the local ENR shard config, which is changed by the pruner.
+    pub fn update_enr_shard_config(&mut self, shard_config: ShardConfig) -> Result<(), String> {
+        self.discv5
+            .enr_insert(
+                enr_ext::ENR_CONTENT_KEY_SHARD_CONFIG,
+                &shard_config.as_ssz_bytes(),
+            )
+            .map_err(|e| format!("{:?}", e))?;
+
+        // replace the global version
+        *self.network_globals.local_enr.write() = self.discv5.local_enr();
+        // persist modified enr to disk
+        enr::save_enr_to_disk(Path::new(&self.enr_dir), &self.local_enr());
+        Ok(())
+    }
+
     // Bans a peer and it's associated seen IP addresses.
     pub fn ban_peer(&mut self, peer_id: &PeerId, ip_addresses: Vec<IpAddr>) {
         // first try and convert the peer_id to a node_id.
@@ -429,8 +467,15 @@ impl Discovery {
 
         // only discover nodes with same network identity
         let local_network_id = self.network_globals.network_id();
+        let shard_config = match &query {
+            QueryType::FindPeers => None,
+            QueryType::FindShardPeers(shard_config) => Some(*shard_config),
+        };
         let predicate = move |enr: &Enr| -> bool {
             matches!(enr.network_identity(), Some(Ok(id)) if id == local_network_id)
+                && shard_config.map_or(true, |shard_config| {
+                    matches!(enr.shard_config(), Some(Ok(v)) if v.intersect(&shard_config))
+                })
         };
 
         // Build the future
@@ -452,26 +497,28 @@ impl Discovery {
         query: QueryResult,
     ) -> Option<HashMap<PeerId, Option<Instant>>> {
         match query.query_type {
-            QueryType::FindPeers => {
-                self.find_peer_active = false;
-                match query.result {
-                    Ok(r) if r.is_empty() => {
-                        debug!("Discovery query yielded no results.");
-                    }
-                    Ok(r) => {
-                        debug!(peers_found = r.len(), "Discovery query completed");
-                        let mut results: HashMap<_, Option<Instant>> = HashMap::new();
-                        r.iter().for_each(|enr| {
-                            // cache the found ENR's
-                            self.cached_enrs.put(enr.peer_id(), enr.clone());
-                            results.insert(enr.peer_id(), None);
-                        });
-                        return Some(results);
-                    }
-                    Err(e) => {
-                        warn!(error = %e, "Discovery query failed");
-                    }
-                }
+            QueryType::FindPeers => self.find_peer_active = false,
+            QueryType::FindShardPeers(shard_config) => {
+                self.active_shard_queries.remove(&shard_config);
+            }
+        }
+
+        match query.result {
+            Ok(r) if r.is_empty() => {
+                debug!(query = ?query.query_type, "Discovery query yielded no results.");
+            }
+            Ok(r) => {
+                debug!(query = ?query.query_type, peers_found = r.len(), "Discovery query completed");
+                let mut results: HashMap<_, Option<Instant>> = HashMap::new();
+                r.iter().for_each(|enr| {
+                    // cache the found ENR's
+                    self.cached_enrs.put(enr.peer_id(), enr.clone());
+                    results.insert(enr.peer_id(), None);
+                });
+                return Some(results);
+            }
+            Err(e) => {
+                warn!(query = ?query.query_type, error = %e, "Discovery query failed");
             }
         }
 
@@ -506,8 +553,11 @@ impl NetworkBehaviour for Discovery {
         if let Some(enr) = self.enr_of_peer(peer_id) {
             // ENR's may have multiple Multiaddrs. The multi-addr associated with the UDP
             // port is removed, which is assumed to be associated with the discv5 protocol (and
-            // therefore irrelevant for other libp2p components).
-            enr.multiaddr_tcp()
+            // therefore irrelevant for other libp2p components). QUIC addresses are preferred
+            // if advertised, and TCP addresses are used as fallback.
+            let mut addresses = enr.multiaddr_quic();
+            addresses.extend(enr.multiaddr_tcp());
+            addresses
         } else {
             // PeerId is not known
             Vec::new()
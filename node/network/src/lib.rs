@@ -19,6 +19,7 @@ pub mod discovery;
 pub mod metrics;
 pub mod nat;
 pub mod peer_manager;
+mod quic;
 pub mod rpc;
 mod service;
 pub mod types;
@@ -28,7 +29,7 @@ use std::net::SocketAddr;
 use std::time::Instant;
 
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
-use shared_types::TxID;
+use shared_types::{ShardConfig, TxID};
 use std::str::FromStr;
 
 /// Wrapper over a libp2p `PeerId` which implements `Serialize` and `Deserialize`
@@ -41,6 +42,12 @@ impl From<PeerIdSerialized> for PeerId {
     }
 }
 
+impl From<PeerId> for PeerIdSerialized {
+    fn from(peer_id: PeerId) -> Self {
+        Self(peer_id)
+    }
+}
+
 impl FromStr for PeerIdSerialized {
     type Err = String;
 
@@ -87,6 +94,7 @@ pub use libp2p::{multiaddr, Multiaddr};
 pub use metrics::scrape_discovery_metrics;
 pub use peer_manager::{
     peerdb::client::Client,
+    peerdb::download_stats::{DownloadEvent, DownloadStats},
     peerdb::score::{PeerAction, ReportSource},
     peerdb::PeerDB,
     ConnectionDirection, PeerConnectionStatus, PeerInfo, PeerManager, SyncInfo, SyncStatus,
@@ -113,6 +121,9 @@ pub enum RequestId {
 #[derive(Debug, Clone, Copy)]
 pub enum SyncId {
     SerialSync { tx_id: TxID },
+    ErasurePiece { batch_index: u64 },
+    StorageChallenge { tx_id: TxID },
+    Transactions { from_seq: u64 },
 }
 
 /// Types of messages that the network service can receive.
@@ -154,6 +165,13 @@ pub enum NetworkMessage {
     },
     /// Start dialing a new peer.
     DialPeer { address: Multiaddr, peer_id: PeerId },
+    /// Discover and connect to peers that store data of the given shard.
+    DiscoverShardPeers {
+        shard_config: ShardConfig,
+        num_peers: usize,
+    },
+    /// Find peers that provide the file in the DHT.
+    FindProviders { tx_id: TxID },
     /// Disconnect a peer.
     DisconnectPeer { peer_id: PeerId },
     /// Notify that new file stored in db.
@@ -1,4 +1,4 @@
-use std::{fmt::Debug, sync::Arc, time::Duration};
+use std::{fmt::Debug, path::PathBuf, sync::Arc, time::Duration};
 
 use duration_str::deserialize_duration;
 use libp2p::PeerId;
@@ -42,6 +42,10 @@ pub struct Config {
     /// Interval between PING events for peers dialed by us.
     pub ping_interval_outbound: u64,
 
+    /// Directory to persist known peers periodically, which are dialed first after restart.
+    #[serde(skip)]
+    pub peer_store_dir: Option<PathBuf>,
+
     #[serde(skip)]
     pub filters: Filters,
 }
@@ -56,6 +60,7 @@ impl Default for Config {
             status_interval: DEFAULT_STATUS_INTERVAL,
             ping_interval_inbound: DEFAULT_PING_INTERVAL_INBOUND,
             ping_interval_outbound: DEFAULT_PING_INTERVAL_OUTBOUND,
+            peer_store_dir: None,
             filters: Default::default(),
         }
     }
@@ -9,6 +9,7 @@ use libp2p::identify::IdentifyInfo;
 use peerdb::{client::ClientKind, BanOperation, BanResult, ScoreUpdateResult};
 use smallvec::SmallVec;
 use std::{
+    path::PathBuf,
     sync::Arc,
     time::{Duration, Instant},
 };
@@ -69,6 +70,8 @@ pub struct PeerManager {
     discovery_enabled: bool,
     /// Keeps track if the current instance is reporting metrics or not.
     metrics_enabled: bool,
+    /// Directory to persist known peers, if enabled.
+    peer_store_dir: Option<PathBuf>,
 
     filters: config::Filters,
 }
@@ -110,15 +113,32 @@ impl PeerManager {
             status_interval,
             ping_interval_inbound,
             ping_interval_outbound,
+            peer_store_dir,
             filters,
         } = cfg;
 
         // Set up the peer manager heartbeat interval
         let heartbeat = tokio::time::interval(heartbeat_interval);
 
+        // Restore peers persisted before restart, and ban the banned ones in libp2p
+        let mut events = SmallVec::new();
+        if let Some(dir) = &peer_store_dir {
+            match peerdb::peer_store::load_peers(dir) {
+                Ok(peers) => {
+                    let num_peers = peers.len();
+                    let banned_peers = network_globals.peers.write().restore_peers(peers);
+                    info!(%num_peers, num_banned = %banned_peers.len(), "Restored known peers");
+                    events.extend(banned_peers.into_iter().map(|(peer_id, banned_ips)| {
+                        PeerManagerEvent::Banned(peer_id, banned_ips)
+                    }));
+                }
+                Err(e) => warn!(error = %e, "Failed to load known peers"),
+            }
+        }
+
         Ok(PeerManager {
             network_globals,
-            events: SmallVec::new(),
+            events,
             inbound_ping_peers: HashSetDelay::new(Duration::from_secs(ping_interval_inbound)),
             outbound_ping_peers: HashSetDelay::new(Duration::from_secs(ping_interval_outbound)),
             status_peers: HashSetDelay::new(Duration::from_secs(status_interval)),
@@ -126,6 +146,7 @@ impl PeerManager {
             heartbeat,
             discovery_enabled,
             metrics_enabled,
+            peer_store_dir,
             filters,
         })
     }
@@ -467,6 +488,9 @@ impl PeerManager {
                     Protocol::DataByHash => PeerAction::MidToleranceError,
                     Protocol::AnswerFile => PeerAction::MidToleranceError,
                     Protocol::GetChunks => PeerAction::MidToleranceError,
+                    Protocol::GetErasurePiece => PeerAction::MidToleranceError,
+                    Protocol::StorageChallenge => PeerAction::MidToleranceError,
+                    Protocol::GetTransactions => PeerAction::MidToleranceError,
                 },
             },
             RPCError::SSZDecodeError(_) => PeerAction::Fatal,
@@ -482,6 +506,9 @@ impl PeerManager {
                     Protocol::DataByHash => return,
                     Protocol::AnswerFile => return,
                     Protocol::GetChunks => return,
+                    Protocol::GetErasurePiece => return,
+                    Protocol::StorageChallenge => return,
+                    Protocol::GetTransactions => return,
                 }
             }
             RPCError::StreamTimeout => match direction {
@@ -497,6 +524,9 @@ impl PeerManager {
                     Protocol::DataByHash => PeerAction::MidToleranceError,
                     Protocol::AnswerFile => PeerAction::MidToleranceError,
                     Protocol::GetChunks => PeerAction::MidToleranceError,
+                    Protocol::GetErasurePiece => PeerAction::MidToleranceError,
+                    Protocol::StorageChallenge => PeerAction::MidToleranceError,
+                    Protocol::GetTransactions => PeerAction::MidToleranceError,
                 },
             },
             RPCError::NegotiationTimeout => PeerAction::LowToleranceError,
@@ -1025,6 +1055,18 @@ impl PeerManager {
         // Prune any excess peers back to our target in such a way that incentivises good scores and
         // a uniform distribution of subnets.
         self.prune_excess_peers();
+
+        // Persist known peers to reconnect after restart.
+        self.save_peers();
+    }
+
+    fn save_peers(&self) {
+        if let Some(dir) = &self.peer_store_dir {
+            let peers = self.network_globals.peers.read().stored_peers();
+            if let Err(e) = peerdb::peer_store::save_peers(dir, &peers) {
+                warn!(error = %e, "Failed to persist known peers");
+            }
+        }
     }
 
     // Update metrics related to peer scoring.
@@ -3,11 +3,14 @@ use crate::{
     multiaddr::{Multiaddr, Protocol},
     Enr, Gossipsub, PeerId,
 };
+use download_stats::DownloadEvent;
 use duration_str::deserialize_duration;
 use peer_info::{ConnectionDirection, PeerConnectionStatus, PeerInfo};
+use peer_store::StoredPeer;
 use rand::seq::SliceRandom;
 use score::{PeerAction, ReportSource, Score, ScoreState};
 use serde::{Deserialize, Serialize};
+use shared_types::ShardConfig;
 use std::collections::{HashMap, HashSet};
 use std::net::{IpAddr, SocketAddr};
 use std::time::Instant;
@@ -15,7 +18,9 @@ use std::{cmp::Ordering, time::Duration};
 use sync_status::SyncStatus;
 
 pub mod client;
+pub mod download_stats;
 pub mod peer_info;
+pub mod peer_store;
 pub mod score;
 pub mod sync_status;
 
@@ -579,6 +584,65 @@ impl PeerDB {
         }
     }
 
+    /// Records the result of a data download request to a known peer.
+    pub fn record_download(&mut self, peer_id: &PeerId, event: DownloadEvent) {
+        if let Some(info) = self.peers.get_mut(peer_id) {
+            info.record_download(event);
+        }
+    }
+
+    /// Returns the download score of a peer, or `None` if unknown or never requested.
+    pub fn download_score(&self, peer_id: &PeerId) -> Option<f64> {
+        self.peers.get(peer_id)?.download_stats().score()
+    }
+
+    /// Updates the shard config announced by a known peer.
+    pub fn update_shard_config(&mut self, peer_id: &PeerId, shard_config: ShardConfig) {
+        if let Some(info) = self.peers.get_mut(peer_id) {
+            info.set_shard_config(shard_config);
+        }
+    }
+
+    /// Returns the known peers to persist across restarts.
+    pub fn stored_peers(&self) -> Vec<StoredPeer> {
+        self.peers
+            .iter()
+            .filter_map(|(peer_id, info)| StoredPeer::new(peer_id, info))
+            .collect()
+    }
+
+    /// Restores peers persisted before restart as disconnected, or banned if their score is still
+    /// too low. Peers already in the db are ignored.
+    ///
+    /// Returns the restored banned peers along with the ip addresses to ban.
+    // VISIBILITY: Only the peer manager can restore peers, since the bans must be handled in
+    // libp2p.
+    pub(super) fn restore_peers(&mut self, peers: Vec<StoredPeer>) -> Vec<(PeerId, Vec<IpAddr>)> {
+        let mut banned_peers = Vec::new();
+
+        for stored in peers {
+            let peer_id = PeerId::from(stored.peer_id.clone());
+            if self.peers.contains_key(&peer_id) {
+                continue;
+            }
+
+            let info = PeerInfo::from_stored(stored);
+            let score_is_banned = info.score_is_banned();
+            self.peers.insert(peer_id, info);
+            self.disconnected_peers += 1;
+
+            if score_is_banned {
+                if let Some(BanOperation::ReadyToBan(banned_ips)) =
+                    self.update_connection_state(&peer_id, NewConnectionState::Banned)
+                {
+                    banned_peers.push((peer_id, banned_ips));
+                }
+            }
+        }
+
+        banned_peers
+    }
+
     /// Update min ttl of a peer.
     // VISIBILITY: Only the peer manager can update the min_ttl
     pub(super) fn update_min_ttl(&mut self, peer_id: &PeerId, min_ttl: Instant) {
@@ -727,12 +791,14 @@ impl PeerDB {
                         Protocol::Ip4(ip) => (Some(ip.into()), found_port),
                         Protocol::Ip6(ip) => (Some(ip.into()), found_port),
                         Protocol::Tcp(port) => (found_ip, Some(port)),
+                        // UDP port of QUIC connections
+                        Protocol::Udp(port) => (found_ip, Some(port)),
                         _ => (found_ip, found_port),
                     },
                 ) {
                     (Some(ip), Some(port)) => Some(SocketAddr::new(ip, port)),
                     (Some(_ip), None) => {
-                        error!(peer_id = %peer_id, "Connected peer has an IP but no TCP port");
+                        error!(peer_id = %peer_id, "Connected peer has an IP but no TCP or UDP port");
                         None
                     }
                     _ => None,
@@ -1932,6 +1998,52 @@ mod tests {
         assert!(pdb.ban_status(&p2).is_banned());
     }
 
+    #[test]
+    fn test_restore_stored_peers() {
+        let mut pdb = get_db();
+        let shard_config = ShardConfig {
+            num_shard: 2,
+            shard_id: 1,
+        };
+
+        // peers never identified are not persisted
+        let unknown = connect_peer_with_ips(&mut pdb, vec![Ipv4Addr::new(1, 2, 3, 4).into()]);
+        let good = connect_peer_with_ips(&mut pdb, vec![Ipv4Addr::new(1, 2, 3, 5).into()]);
+        let listening_address: Multiaddr = "/ip4/1.2.3.5/tcp/1234".parse().unwrap();
+        pdb.peer_info_mut(&good)
+            .unwrap()
+            .set_listening_addresses(vec![listening_address.clone()]);
+        pdb.update_shard_config(&good, shard_config);
+
+        let bad = connect_peer_with_ips(&mut pdb, vec![Ipv4Addr::new(1, 2, 3, 6).into()]);
+        let _ = pdb.report_peer(&bad, PeerAction::Fatal, ReportSource::PeerManager, "");
+        pdb.inject_disconnect(&bad);
+
+        let stored = pdb.stored_peers();
+        assert_eq!(stored.len(), 2);
+        assert!(stored
+            .iter()
+            .all(|peer| PeerId::from(peer.peer_id.clone()) != unknown));
+
+        let dir = tempfile::tempdir().unwrap();
+        peer_store::save_peers(dir.path(), &stored).unwrap();
+        let loaded = peer_store::load_peers(dir.path()).unwrap();
+
+        let mut pdb = get_db();
+        let banned_peers = pdb.restore_peers(loaded);
+        assert_eq!(banned_peers.len(), 1);
+        assert_eq!(banned_peers[0].0, bad);
+
+        let info = pdb.peer_info(&good).unwrap();
+        assert!(info.is_disconnected());
+        assert_eq!(info.listening_addresses(), &vec![listening_address]);
+        assert_eq!(info.shard_config(), Some(&shard_config));
+        assert!(pdb.peer_info(&bad).unwrap().is_banned());
+        assert!(pdb.ban_status(&bad).is_banned());
+        assert_eq!(pdb.disconnected_peers, 1);
+        assert_eq!(pdb.banned_peers_count.banned_peers(), 1);
+    }
+
     #[test]
     #[allow(clippy::float_cmp)]
     fn test_trusted_peers_score() {
@@ -0,0 +1,159 @@
+//! Statistics of file data downloaded from a peer, which are used to prefer fast and honest
+//! peers when syncing files.
+//!
+//! Unlike the reputation `Score`, download statistics never lead to disconnection or banning
+//! of a peer.
+use serde::Serialize;
+use std::time::Duration;
+
+/// Weight of the latest measurement when updating the smoothed throughput and latency.
+const SMOOTHING_FACTOR: f64 = 0.3;
+
+/// The download score is halved for each proof verification failure.
+const PROOF_FAILURE_PENALTY: f64 = 0.5;
+
+/// Result of a data download request to a peer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DownloadEvent {
+    /// Data downloaded and verified successfully.
+    Completed { num_bytes: usize, latency: Duration },
+    /// Request failed or timed out.
+    RequestFailed,
+    /// Data downloaded but failed to verify the merkle proof.
+    ProofFailed,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct DownloadStats {
+    /// Number of successful downloads.
+    num_downloads: u64,
+    /// Total bytes of successful downloads.
+    num_bytes: u64,
+    /// Smoothed download throughput in bytes per second.
+    bytes_per_sec: f64,
+    /// Smoothed latency of successful downloads in milliseconds.
+    latency_ms: f64,
+    /// Number of failed or timed out requests.
+    request_failures: u64,
+    /// Number of responses that failed to verify the merkle proof.
+    proof_failures: u64,
+}
+
+impl DownloadStats {
+    pub fn num_downloads(&self) -> u64 {
+        self.num_downloads
+    }
+
+    pub fn num_bytes(&self) -> u64 {
+        self.num_bytes
+    }
+
+    pub fn bytes_per_sec(&self) -> f64 {
+        self.bytes_per_sec
+    }
+
+    pub fn latency_ms(&self) -> f64 {
+        self.latency_ms
+    }
+
+    pub fn request_failures(&self) -> u64 {
+        self.request_failures
+    }
+
+    pub fn proof_failures(&self) -> u64 {
+        self.proof_failures
+    }
+
+    pub fn record(&mut self, event: DownloadEvent) {
+        match event {
+            DownloadEvent::Completed { num_bytes, latency } => {
+                let secs = latency.as_secs_f64().max(0.001);
+                let bytes_per_sec = num_bytes as f64 / secs;
+                let latency_ms = secs * 1000.0;
+
+                if self.num_downloads == 0 {
+                    self.bytes_per_sec = bytes_per_sec;
+                    self.latency_ms = latency_ms;
+                } else {
+                    self.bytes_per_sec = smooth(self.bytes_per_sec, bytes_per_sec);
+                    self.latency_ms = smooth(self.latency_ms, latency_ms);
+                }
+
+                self.num_downloads += 1;
+                self.num_bytes += num_bytes as u64;
+            }
+            DownloadEvent::RequestFailed => self.request_failures += 1,
+            DownloadEvent::ProofFailed => self.proof_failures += 1,
+        }
+    }
+
+    /// Returns the throughput weighted by the success ratio of requests and penalized by
+    /// proof failures, or `None` if never requested.
+    pub fn score(&self) -> Option<f64> {
+        let num_requests = self.num_downloads + self.request_failures + self.proof_failures;
+        if num_requests == 0 {
+            return None;
+        }
+
+        let success_ratio = self.num_downloads as f64 / num_requests as f64;
+        let penalty = PROOF_FAILURE_PENALTY.powi(self.proof_failures.min(i32::MAX as u64) as i32);
+
+        Some(self.bytes_per_sec * success_ratio * penalty)
+    }
+}
+
+fn smooth(current: f64, measured: f64) -> f64 {
+    current * (1.0 - SMOOTHING_FACTOR) + measured * SMOOTHING_FACTOR
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_approx(actual: Option<f64>, expected: f64) {
+        assert!((actual.unwrap() - expected).abs() < 1e-6);
+    }
+
+    fn completed(num_bytes: usize, millis: u64) -> DownloadEvent {
+        DownloadEvent::Completed {
+            num_bytes,
+            latency: Duration::from_millis(millis),
+        }
+    }
+
+    #[test]
+    fn test_record_download() {
+        let mut stats = DownloadStats::default();
+        assert_eq!(stats.score(), None);
+
+        stats.record(completed(1000, 1000));
+        assert_eq!(stats.num_downloads(), 1);
+        assert_eq!(stats.bytes_per_sec(), 1000.0);
+        assert_eq!(stats.latency_ms(), 1000.0);
+
+        stats.record(completed(2000, 1000));
+        assert_eq!(stats.num_bytes(), 3000);
+        assert_approx(Some(stats.bytes_per_sec()), 1300.0);
+        assert_approx(stats.score(), 1300.0);
+    }
+
+    #[test]
+    fn test_score_penalized_by_failures() {
+        let mut stats = DownloadStats::default();
+        stats.record(completed(1000, 1000));
+
+        stats.record(DownloadEvent::RequestFailed);
+        assert_approx(stats.score(), 500.0);
+
+        stats.record(DownloadEvent::ProofFailed);
+        assert_eq!(stats.proof_failures(), 1);
+        assert_approx(stats.score(), 1000.0 / 3.0 * 0.5);
+    }
+
+    #[test]
+    fn test_score_without_downloads() {
+        let mut stats = DownloadStats::default();
+        stats.record(DownloadEvent::ProofFailed);
+        assert_eq!(stats.score(), Some(0.0));
+    }
+}
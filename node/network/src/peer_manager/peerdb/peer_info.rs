@@ -1,12 +1,15 @@
 use super::client::Client;
+use super::download_stats::{DownloadEvent, DownloadStats};
+use super::peer_store::StoredPeer;
 use super::score::{PeerAction, Score, ScoreState};
 use super::sync_status::SyncStatus;
-use crate::Multiaddr;
+use crate::{EnrExt, Multiaddr};
 use discv5::Enr;
 use serde::{
     ser::{SerializeStruct, Serializer},
     Serialize,
 };
+use shared_types::ShardConfig;
 use std::collections::HashSet;
 use std::net::{IpAddr, SocketAddr};
 use std::time::Instant;
@@ -42,6 +45,10 @@ pub struct PeerInfo {
     connection_direction: Option<ConnectionDirection>,
     /// The enr of the peer, if known.
     enr: Option<Enr>,
+    /// Statistics of file data downloaded from this peer.
+    download_stats: DownloadStats,
+    /// The last known shard config of the peer.
+    shard_config: Option<ShardConfig>,
 }
 
 impl Default for PeerInfo {
@@ -57,6 +64,8 @@ impl Default for PeerInfo {
             is_trusted: false,
             connection_direction: None,
             enr: None,
+            download_stats: DownloadStats::default(),
+            shard_config: None,
         }
     }
 }
@@ -71,6 +80,21 @@ impl PeerInfo {
         }
     }
 
+    /// Return a disconnected PeerInfo struct for a peer persisted before restart.
+    pub(super) fn from_stored(stored: StoredPeer) -> Self {
+        PeerInfo {
+            score: Score::restore(stored.score, stored.decay_delay()),
+            connection_status: Disconnected {
+                since: Instant::now(),
+            },
+            listening_addresses: stored.listening_addresses,
+            seen_addresses: stored.seen_addresses.into_iter().collect(),
+            enr: stored.enr,
+            shard_config: stored.shard_config,
+            ..Default::default()
+        }
+    }
+
     /// Obtains the client of the peer.
     pub fn client(&self) -> &Client {
         &self.client
@@ -129,6 +153,16 @@ impl PeerInfo {
         self.min_ttl.map_or(false, |i| i >= Instant::now())
     }
 
+    /// Returns the statistics of file data downloaded from the peer.
+    pub fn download_stats(&self) -> &DownloadStats {
+        &self.download_stats
+    }
+
+    /// Returns the last known shard config of the peer.
+    pub fn shard_config(&self) -> Option<&ShardConfig> {
+        self.shard_config.as_ref()
+    }
+
     /// Returns score of the peer.
     pub fn score(&self) -> &Score {
         &self.score
@@ -222,11 +256,20 @@ impl PeerInfo {
         self.connection_status = connection_status
     }
 
-    /// Sets the ENR of the peer if one is known.
+    /// Sets the ENR of the peer if one is known. The shard config published in ENR is used
+    /// until the peer announces one.
     pub(super) fn set_enr(&mut self, enr: Enr) {
+        if self.shard_config.is_none() {
+            self.shard_config = enr.shard_config().and_then(Result::ok);
+        }
         self.enr = Some(enr)
     }
 
+    /// Sets the shard config announced by the peer.
+    pub(super) fn set_shard_config(&mut self, shard_config: ShardConfig) {
+        self.shard_config = Some(shard_config)
+    }
+
     /// Sets the time that the peer is expected to be needed until for an attached validator duty.
     pub(super) fn set_min_ttl(&mut self, min_ttl: Instant) {
         self.min_ttl = Some(min_ttl)
@@ -247,6 +290,11 @@ impl PeerInfo {
         }
     }
 
+    /// Records the result of a data download request to the peer.
+    pub(super) fn record_download(&mut self, event: DownloadEvent) {
+        self.download_stats.record(event);
+    }
+
     /// Updates the gossipsub score with a new score. Optionally ignore the gossipsub score.
     pub(super) fn update_gossipsub_score(&mut self, new_score: f64, ignore: bool) {
         self.score.update_gossipsub_score(new_score, ignore);
@@ -0,0 +1,94 @@
+//! Persistence of known peers, so that a node reconnects to good peers and keeps bans across
+//! restarts instead of rediscovering the network from scratch.
+use super::peer_info::PeerInfo;
+use crate::{Enr, Multiaddr, PeerId, PeerIdSerialized};
+use serde::{Deserialize, Serialize};
+use shared_types::ShardConfig;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The file under the network directory to persist known peers.
+pub const PEERS_FILENAME: &str = "peers.json";
+
+/// A peer persisted on disk.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredPeer {
+    pub peer_id: PeerIdSerialized,
+    pub enr: Option<Enr>,
+    pub listening_addresses: Vec<Multiaddr>,
+    /// Addresses physically seen, which are used to ban the ip addresses of banned peers.
+    pub seen_addresses: Vec<SocketAddr>,
+    pub shard_config: Option<ShardConfig>,
+    /// The lighthouse score of the peer.
+    pub score: f64,
+    /// Unix timestamp in seconds before which the score of a banned peer will not decay.
+    pub banned_until: Option<u64>,
+}
+
+impl StoredPeer {
+    /// Returns the peer to persist, or `None` for trusted peers and peers that are neither
+    /// identified nor banned, which could not be dialed or are useless after restart.
+    pub fn new(peer_id: &PeerId, info: &PeerInfo) -> Option<Self> {
+        let (score, decay_delay) = info.score().to_persisted()?;
+        if info.listening_addresses().is_empty() && !info.score_is_banned() {
+            return None;
+        }
+
+        let banned_until = if decay_delay.is_zero() {
+            None
+        } else {
+            Some(unix_now() + decay_delay.as_secs())
+        };
+
+        Some(StoredPeer {
+            peer_id: (*peer_id).into(),
+            enr: info.enr().cloned(),
+            listening_addresses: info.listening_addresses().clone(),
+            seen_addresses: info.seen_addresses().copied().collect(),
+            shard_config: info.shard_config().copied(),
+            score,
+            banned_until,
+        })
+    }
+
+    /// Returns the remaining time before the score starts to decay.
+    pub fn decay_delay(&self) -> Duration {
+        let secs = self
+            .banned_until
+            .map_or(0, |until| until.saturating_sub(unix_now()));
+        Duration::from_secs(secs)
+    }
+}
+
+/// Loads the persisted peers from the given directory, or an empty list if not persisted yet.
+pub fn load_peers(dir: &Path) -> Result<Vec<StoredPeer>, String> {
+    let path = dir.join(PEERS_FILENAME);
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+
+    let content = fs::read(&path).map_err(|e| format!("Failed to read {:?}: {:?}", path, e))?;
+    serde_json::from_slice(&content).map_err(|e| format!("Failed to parse {:?}: {:?}", path, e))
+}
+
+/// Persists peers into the given directory. Peers are written to a temporary file first, so that
+/// the previous file is not corrupted if the node is killed while writing.
+pub fn save_peers(dir: &Path, peers: &[StoredPeer]) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create {:?}: {:?}", dir, e))?;
+
+    let content =
+        serde_json::to_vec(peers).map_err(|e| format!("Failed to serialize peers: {:?}", e))?;
+    let tmp_path = dir.join(format!("{}.tmp", PEERS_FILENAME));
+    fs::write(&tmp_path, content)
+        .map_err(|e| format!("Failed to write {:?}: {:?}", tmp_path, e))?;
+    fs::rename(&tmp_path, dir.join(PEERS_FILENAME))
+        .map_err(|e| format!("Failed to rename {:?}: {:?}", tmp_path, e))
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
@@ -315,6 +315,30 @@ impl Score {
         Self::Max
     }
 
+    /// Returns the lighthouse score and the remaining time before it starts to decay, which is
+    /// non-zero only for recently banned peers. Trusted peers have nothing to persist.
+    pub(crate) fn to_persisted(&self) -> Option<(f64, Duration)> {
+        match self {
+            Self::Max => None,
+            Self::Real(score) => Some((
+                score.lighthouse_score,
+                score.last_updated.saturating_duration_since(Instant::now()),
+            )),
+        }
+    }
+
+    /// Restores the score persisted before restart. The gossipsub score is not persisted and
+    /// will be updated once connected.
+    pub(crate) fn restore(lighthouse_score: f64, decay_delay: Duration) -> Self {
+        let mut score = RealScore {
+            lighthouse_score: lighthouse_score.clamp(MIN_SCORE, MAX_SCORE),
+            last_updated: Instant::now() + decay_delay,
+            ..Default::default()
+        };
+        score.recompute_score();
+        Self::Real(score)
+    }
+
     /// Returns the expected state of the peer given it's score.
     pub(crate) fn state(&self) -> ScoreState {
         match self.score() {
@@ -0,0 +1,104 @@
+//! QUIC transport for libp2p connections.
+//!
+//! Connections are secured with the libp2p TLS handshake, which binds the certificate to the
+//! node key, and every libp2p substream is mapped to a bidirectional QUIC stream. So unlike
+//! yamux/mplex over TCP, a large response (e.g. `GetChunks`) is not blocked behind other
+//! substreams of the same connection.
+//!
+//! Dialing QUIC addresses is always supported, while listening is enabled only when QUIC listen
+//! addresses are configured.
+mod muxer;
+mod transport;
+
+pub use transport::{QuicConfig, QuicError, QuicTransport};
+
+use libp2p::core::multiaddr::Protocol;
+use libp2p::{Multiaddr, PeerId};
+use std::net::{IpAddr, SocketAddr};
+
+/// Converts a QUIC multiaddr, i.e. `/ip4/<ip>/udp/<port>/quic` with an optional `/p2p/<id>`
+/// suffix, into the socket address and the expected peer id.
+pub fn multiaddr_to_socketaddr(addr: &Multiaddr) -> Option<(SocketAddr, Option<PeerId>)> {
+    let mut iter = addr.iter();
+
+    let ip = match iter.next()? {
+        Protocol::Ip4(ip) => IpAddr::V4(ip),
+        Protocol::Ip6(ip) => IpAddr::V6(ip),
+        _ => return None,
+    };
+
+    let port = match iter.next()? {
+        Protocol::Udp(port) => port,
+        _ => return None,
+    };
+
+    if iter.next()? != Protocol::Quic {
+        return None;
+    }
+
+    let peer_id = match iter.next() {
+        None => None,
+        Some(Protocol::P2p(hash)) => Some(PeerId::from_multihash(hash).ok()?),
+        Some(_) => return None,
+    };
+
+    if iter.next().is_some() {
+        return None;
+    }
+
+    Some((SocketAddr::new(ip, port), peer_id))
+}
+
+/// Converts a socket address into a QUIC multiaddr.
+pub fn socketaddr_to_multiaddr(addr: &SocketAddr) -> Multiaddr {
+    Multiaddr::empty()
+        .with(addr.ip().into())
+        .with(Protocol::Udp(addr.port()))
+        .with(Protocol::Quic)
+}
+
+/// Returns whether the multiaddr is a QUIC address.
+pub fn is_quic_addr(addr: &Multiaddr) -> bool {
+    multiaddr_to_socketaddr(addr).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_multiaddr_to_socketaddr() {
+        let peer_id = PeerId::random();
+
+        let addr: Multiaddr = "/ip4/127.0.0.1/udp/1234/quic".parse().unwrap();
+        assert_eq!(
+            multiaddr_to_socketaddr(&addr),
+            Some(("127.0.0.1:1234".parse().unwrap(), None))
+        );
+        assert_eq!(
+            socketaddr_to_multiaddr(&"127.0.0.1:1234".parse().unwrap()),
+            addr
+        );
+
+        let addr = addr.with(Protocol::P2p(peer_id.into()));
+        assert_eq!(
+            multiaddr_to_socketaddr(&addr),
+            Some(("127.0.0.1:1234".parse().unwrap(), Some(peer_id)))
+        );
+
+        let addr: Multiaddr = "/ip6/::1/udp/1234/quic".parse().unwrap();
+        assert_eq!(
+            multiaddr_to_socketaddr(&addr),
+            Some(("[::1]:1234".parse().unwrap(), None))
+        );
+
+        for addr in [
+            "/ip4/127.0.0.1/tcp/1234",
+            "/ip4/127.0.0.1/udp/1234",
+            "/dns4/localhost/udp/1234/quic",
+            "/ip4/127.0.0.1/udp/1234/quic/tcp/1",
+        ] {
+            assert!(!is_quic_addr(&addr.parse().unwrap()));
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use futures::future::BoxFuture;
+use futures::{AsyncRead, AsyncWrite, FutureExt, StreamExt};
+use libp2p::core::muxing::{StreamMuxer, StreamMuxerEvent};
+use parking_lot::Mutex;
+use quinn::{
+    Connection, ConnectionError, IncomingBiStreams, NewConnection, RecvStream, SendStream,
+};
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Stream muxer of a QUIC connection, where each substream is a bidirectional QUIC stream.
+pub struct QuicMuxer {
+    connection: Connection,
+    incoming: Mutex<IncomingBiStreams>,
+}
+
+impl QuicMuxer {
+    pub fn new(connection: NewConnection) -> Self {
+        Self {
+            connection: connection.connection,
+            incoming: Mutex::new(connection.bi_streams),
+        }
+    }
+}
+
+pub struct QuicSubstream {
+    send: SendStream,
+    recv: RecvStream,
+}
+
+type OutboundSubstream = BoxFuture<'static, Result<(SendStream, RecvStream), ConnectionError>>;
+
+fn connection_error(err: ConnectionError) -> io::Error {
+    io::Error::new(io::ErrorKind::ConnectionAborted, err)
+}
+
+impl StreamMuxer for QuicMuxer {
+    type Substream = QuicSubstream;
+    type OutboundSubstream = OutboundSubstream;
+    type Error = io::Error;
+
+    fn poll_event(
+        &self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<StreamMuxerEvent<Self::Substream>, Self::Error>> {
+        match futures::ready!(self.incoming.lock().poll_next_unpin(cx)) {
+            Some(Ok((send, recv))) => {
+                Poll::Ready(Ok(StreamMuxerEvent::InboundSubstream(QuicSubstream {
+                    send,
+                    recv,
+                })))
+            }
+            Some(Err(err)) => Poll::Ready(Err(connection_error(err))),
+            None => Poll::Ready(Err(io::ErrorKind::ConnectionAborted.into())),
+        }
+    }
+
+    fn open_outbound(&self) -> Self::OutboundSubstream {
+        self.connection.open_bi().boxed()
+    }
+
+    fn poll_outbound(
+        &self,
+        cx: &mut Context<'_>,
+        substream: &mut Self::OutboundSubstream,
+    ) -> Poll<Result<Self::Substream, Self::Error>> {
+        substream
+            .poll_unpin(cx)
+            .map_ok(|(send, recv)| QuicSubstream { send, recv })
+            .map_err(connection_error)
+    }
+
+    fn destroy_outbound(&self, _substream: Self::OutboundSubstream) {}
+
+    fn read_substream(
+        &self,
+        cx: &mut Context<'_>,
+        substream: &mut Self::Substream,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize, Self::Error>> {
+        AsyncRead::poll_read(Pin::new(&mut substream.recv), cx, buf)
+    }
+
+    fn write_substream(
+        &self,
+        cx: &mut Context<'_>,
+        substream: &mut Self::Substream,
+        buf: &[u8],
+    ) -> Poll<Result<usize, Self::Error>> {
+        AsyncWrite::poll_write(Pin::new(&mut substream.send), cx, buf)
+    }
+
+    fn flush_substream(
+        &self,
+        cx: &mut Context<'_>,
+        substream: &mut Self::Substream,
+    ) -> Poll<Result<(), Self::Error>> {
+        AsyncWrite::poll_flush(Pin::new(&mut substream.send), cx)
+    }
+
+    fn shutdown_substream(
+        &self,
+        cx: &mut Context<'_>,
+        substream: &mut Self::Substream,
+    ) -> Poll<Result<(), Self::Error>> {
+        // finishes the send side, and the peer reads EOF afterwards
+        AsyncWrite::poll_close(Pin::new(&mut substream.send), cx)
+    }
+
+    fn destroy_substream(&self, _substream: Self::Substream) {}
+
+    fn close(&self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.connection.close(0u32.into(), b"");
+        Poll::Ready(Ok(()))
+    }
+
+    fn flush_all(&self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // data written to QUIC streams is sent by the connection driver in background
+        Poll::Ready(Ok(()))
+    }
+}
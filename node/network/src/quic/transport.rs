@@ -0,0 +1,505 @@
+use super::muxer::QuicMuxer;
+use super::{multiaddr_to_socketaddr, socketaddr_to_multiaddr};
+use futures::future::BoxFuture;
+use futures::{FutureExt, Stream, StreamExt};
+use libp2p::core::identity::Keypair;
+use libp2p::core::muxing::StreamMuxerBox;
+use libp2p::core::transport::{ListenerEvent, Transport, TransportError};
+use libp2p::{Multiaddr, PeerId};
+use quinn::{
+    ConnectError, Connecting, ConnectionError, Endpoint, IdleTimeout, Incoming, TransportConfig,
+    VarInt,
+};
+use std::collections::VecDeque;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+/// The server name in TLS handshake, which is ignored since the certificate is verified against
+/// the peer id instead.
+const SERVER_NAME: &str = "l";
+
+#[derive(Debug)]
+pub enum QuicError {
+    /// Only secp256k1 node keys are supported.
+    UnsupportedKey,
+    Certificate(String),
+    Io(std::io::Error),
+    Connect(ConnectError),
+    Connection(ConnectionError),
+    HandshakeTimedOut,
+    /// The remote peer did not present a valid libp2p certificate.
+    InvalidPeerIdentity(String),
+}
+
+impl fmt::Display for QuicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuicError::UnsupportedKey => write!(f, "unsupported node key type for QUIC"),
+            QuicError::Certificate(e) => write!(f, "failed to generate TLS certificate: {}", e),
+            QuicError::Io(e) => write!(f, "{}", e),
+            QuicError::Connect(e) => write!(f, "{}", e),
+            QuicError::Connection(e) => write!(f, "{}", e),
+            QuicError::HandshakeTimedOut => write!(f, "QUIC handshake timed out"),
+            QuicError::InvalidPeerIdentity(e) => write!(f, "invalid peer identity: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for QuicError {}
+
+impl From<std::io::Error> for QuicError {
+    fn from(e: std::io::Error) -> Self {
+        QuicError::Io(e)
+    }
+}
+
+impl From<ConnectError> for QuicError {
+    fn from(e: ConnectError) -> Self {
+        QuicError::Connect(e)
+    }
+}
+
+impl From<ConnectionError> for QuicError {
+    fn from(e: ConnectionError) -> Self {
+        QuicError::Connection(e)
+    }
+}
+
+#[derive(Clone)]
+pub struct QuicConfig {
+    /// Timeout of the QUIC and TLS handshake.
+    pub handshake_timeout: Duration,
+    /// Connection is closed if there is no activity within this timeout.
+    pub max_idle_timeout: Duration,
+    /// Interval of keep alive packets, which should be less than `max_idle_timeout`.
+    pub keep_alive_interval: Duration,
+    /// Maximum number of concurrent substreams opened by the remote peer.
+    pub max_concurrent_streams: u32,
+    /// Maximum bytes that could be buffered in a substream, which should be large enough for a
+    /// full RPC response, e.g. `GetChunks`.
+    pub max_stream_data: u32,
+    /// Maximum bytes that could be buffered in a connection.
+    pub max_connection_data: u32,
+}
+
+impl Default for QuicConfig {
+    fn default() -> Self {
+        Self {
+            handshake_timeout: Duration::from_secs(10),
+            max_idle_timeout: Duration::from_secs(30),
+            keep_alive_interval: Duration::from_secs(15),
+            max_concurrent_streams: 256,
+            max_stream_data: 12 * 1024 * 1024,     // 12 MB
+            max_connection_data: 24 * 1024 * 1024, // 24 MB
+        }
+    }
+}
+
+impl QuicConfig {
+    fn transport_config(&self) -> Arc<TransportConfig> {
+        let mut transport = TransportConfig::default();
+        transport
+            .max_concurrent_bidi_streams(self.max_concurrent_streams.into())
+            .max_concurrent_uni_streams(0u32.into())
+            .max_idle_timeout(Some(IdleTimeout::from(VarInt::from_u32(
+                self.max_idle_timeout.as_millis() as u32,
+            ))))
+            .keep_alive_interval(Some(self.keep_alive_interval))
+            .stream_receive_window(self.max_stream_data.into())
+            .receive_window(self.max_connection_data.into())
+            .datagram_receive_buffer_size(None);
+        Arc::new(transport)
+    }
+}
+
+/// libp2p transport over QUIC, whose output is already authenticated and multiplexed.
+pub struct QuicTransport {
+    config: QuicConfig,
+    keypair: libp2p_identity::Keypair,
+    server_config: quinn::ServerConfig,
+    /// Endpoints of listeners, which are also used to dial so that remote peers observe the
+    /// listening port.
+    listen_endpoints: Vec<Endpoint>,
+    /// Endpoints to dial when there is no listener of the same IP version.
+    dial_endpoint_v4: Option<Endpoint>,
+    dial_endpoint_v6: Option<Endpoint>,
+}
+
+impl QuicTransport {
+    pub fn new(keypair: &Keypair, config: QuicConfig) -> Result<Self, QuicError> {
+        let keypair = to_identity_keypair(keypair)?;
+
+        let crypto = libp2p_tls::make_server_config(&keypair)
+            .map_err(|e| QuicError::Certificate(e.to_string()))?;
+        let mut server_config = quinn::ServerConfig::with_crypto(Arc::new(crypto));
+        server_config.transport = config.transport_config();
+
+        Ok(Self {
+            config,
+            keypair,
+            server_config,
+            listen_endpoints: vec![],
+            dial_endpoint_v4: None,
+            dial_endpoint_v6: None,
+        })
+    }
+
+    fn client_config(&self, peer_id: Option<PeerId>) -> Result<quinn::ClientConfig, QuicError> {
+        let peer_id = match peer_id {
+            Some(peer_id) => Some(
+                libp2p_identity::PeerId::from_bytes(&peer_id.to_bytes())
+                    .map_err(|e| QuicError::InvalidPeerIdentity(e.to_string()))?,
+            ),
+            None => None,
+        };
+
+        let crypto = libp2p_tls::make_client_config(&self.keypair, peer_id)
+            .map_err(|e| QuicError::Certificate(e.to_string()))?;
+        let mut client_config = quinn::ClientConfig::new(Arc::new(crypto));
+        client_config.transport = self.config.transport_config();
+
+        Ok(client_config)
+    }
+
+    fn dial_endpoint(&mut self, remote: &SocketAddr) -> Result<Endpoint, QuicError> {
+        if let Some(endpoint) = self.listen_endpoints.iter().find(|endpoint| {
+            matches!(endpoint.local_addr(), Ok(local) if local.is_ipv4() == remote.is_ipv4())
+        }) {
+            return Ok(endpoint.clone());
+        }
+
+        let (endpoint, bind_ip) = if remote.is_ipv4() {
+            (
+                &mut self.dial_endpoint_v4,
+                IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            )
+        } else {
+            (
+                &mut self.dial_endpoint_v6,
+                IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+            )
+        };
+
+        if endpoint.is_none() {
+            *endpoint = Some(Endpoint::client(SocketAddr::new(bind_ip, 0))?);
+        }
+
+        Ok(endpoint.clone().expect("endpoint initialized above"))
+    }
+
+    fn do_dial(
+        &mut self,
+        addr: Multiaddr,
+    ) -> Result<<Self as Transport>::Dial, TransportError<QuicError>> {
+        let (socket_addr, peer_id) = match multiaddr_to_socketaddr(&addr) {
+            Some(v) if !socket_addr_unspecified(&v.0) => v,
+            _ => return Err(TransportError::MultiaddrNotSupported(addr)),
+        };
+
+        let client_config = self.client_config(peer_id).map_err(TransportError::Other)?;
+        let endpoint = self
+            .dial_endpoint(&socket_addr)
+            .map_err(TransportError::Other)?;
+        let connecting = endpoint
+            .connect_with(client_config, socket_addr, SERVER_NAME)
+            .map_err(|e| TransportError::Other(e.into()))?;
+
+        Ok(upgrade(connecting, self.config.handshake_timeout).boxed())
+    }
+}
+
+impl Transport for QuicTransport {
+    type Output = (PeerId, StreamMuxerBox);
+    type Error = QuicError;
+    type Listener = QuicListener;
+    type ListenerUpgrade = BoxFuture<'static, Result<Self::Output, Self::Error>>;
+    type Dial = BoxFuture<'static, Result<Self::Output, Self::Error>>;
+
+    fn listen_on(
+        &mut self,
+        addr: Multiaddr,
+    ) -> Result<Self::Listener, TransportError<Self::Error>> {
+        let socket_addr = match multiaddr_to_socketaddr(&addr) {
+            Some((socket_addr, None)) => socket_addr,
+            _ => return Err(TransportError::MultiaddrNotSupported(addr)),
+        };
+
+        let (endpoint, incoming) = Endpoint::server(self.server_config.clone(), socket_addr)
+            .map_err(|e| TransportError::Other(e.into()))?;
+        let local_addr = endpoint
+            .local_addr()
+            .map_err(|e| TransportError::Other(e.into()))?;
+
+        self.listen_endpoints.push(endpoint);
+
+        Ok(QuicListener {
+            incoming,
+            local_addr: socketaddr_to_multiaddr(&local_addr),
+            new_addresses: listen_addresses(&local_addr).into(),
+            handshake_timeout: self.config.handshake_timeout,
+        })
+    }
+
+    fn dial(&mut self, addr: Multiaddr) -> Result<Self::Dial, TransportError<Self::Error>> {
+        self.do_dial(addr)
+    }
+
+    fn dial_as_listener(
+        &mut self,
+        addr: Multiaddr,
+    ) -> Result<Self::Dial, TransportError<Self::Error>> {
+        // QUIC has no simultaneous open, and the TLS roles are not relevant for libp2p
+        self.do_dial(addr)
+    }
+
+    fn address_translation(&self, listen: &Multiaddr, observed: &Multiaddr) -> Option<Multiaddr> {
+        // dialing reuses the listening socket, so the observed address is the external address
+        if super::is_quic_addr(listen) && super::is_quic_addr(observed) {
+            Some(observed.clone())
+        } else {
+            None
+        }
+    }
+}
+
+/// Listener of a QUIC endpoint, which yields incoming connections.
+pub struct QuicListener {
+    incoming: Incoming,
+    local_addr: Multiaddr,
+    new_addresses: VecDeque<Multiaddr>,
+    handshake_timeout: Duration,
+}
+
+impl Stream for QuicListener {
+    type Item = Result<
+        ListenerEvent<BoxFuture<'static, Result<(PeerId, StreamMuxerBox), QuicError>>, QuicError>,
+        QuicError,
+    >;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if let Some(addr) = self.new_addresses.pop_front() {
+            return Poll::Ready(Some(Ok(ListenerEvent::NewAddress(addr))));
+        }
+
+        match futures::ready!(self.incoming.poll_next_unpin(cx)) {
+            Some(connecting) => {
+                let remote_addr = socketaddr_to_multiaddr(&connecting.remote_address());
+                Poll::Ready(Some(Ok(ListenerEvent::Upgrade {
+                    upgrade: upgrade(connecting, self.handshake_timeout).boxed(),
+                    local_addr: self.local_addr.clone(),
+                    remote_addr,
+                })))
+            }
+            None => Poll::Ready(None),
+        }
+    }
+}
+
+async fn upgrade(
+    connecting: Connecting,
+    handshake_timeout: Duration,
+) -> Result<(PeerId, StreamMuxerBox), QuicError> {
+    let connection = tokio::time::timeout(handshake_timeout, connecting)
+        .await
+        .map_err(|_| QuicError::HandshakeTimedOut)??;
+
+    // the certificate has been verified against its public key during handshake
+    let peer_id = match remote_peer_id(&connection.connection) {
+        Ok(peer_id) => peer_id,
+        Err(e) => {
+            connection.connection.close(0u32.into(), b"");
+            return Err(e);
+        }
+    };
+
+    Ok((peer_id, StreamMuxerBox::new(QuicMuxer::new(connection))))
+}
+
+fn remote_peer_id(connection: &quinn::Connection) -> Result<PeerId, QuicError> {
+    let certificates = connection
+        .peer_identity()
+        .and_then(|identity| identity.downcast::<Vec<rustls::Certificate>>().ok())
+        .ok_or_else(|| QuicError::InvalidPeerIdentity("no certificate".into()))?;
+    let certificate = certificates
+        .first()
+        .ok_or_else(|| QuicError::InvalidPeerIdentity("no certificate".into()))?;
+    let certificate = libp2p_tls::certificate::parse(certificate)
+        .map_err(|e| QuicError::InvalidPeerIdentity(e.to_string()))?;
+
+    PeerId::from_bytes(&certificate.peer_id().to_bytes())
+        .map_err(|e| QuicError::InvalidPeerIdentity(e.to_string()))
+}
+
+/// Converts the node key to the key type of the TLS certificate generator.
+fn to_identity_keypair(keypair: &Keypair) -> Result<libp2p_identity::Keypair, QuicError> {
+    match keypair {
+        Keypair::Secp256k1(keypair) => {
+            let secret =
+                libp2p_identity::secp256k1::SecretKey::from_bytes(keypair.secret().to_bytes())
+                    .map_err(|e| QuicError::Certificate(e.to_string()))?;
+            let keypair = libp2p_identity::secp256k1::Keypair::from(secret);
+            Ok(keypair.into())
+        }
+        _ => Err(QuicError::UnsupportedKey),
+    }
+}
+
+fn socket_addr_unspecified(addr: &SocketAddr) -> bool {
+    addr.ip().is_unspecified() || addr.port() == 0
+}
+
+/// Returns the addresses to report for a listening socket, which are the addresses of all
+/// network interfaces of the same IP version if listening on an unspecified IP.
+fn listen_addresses(local_addr: &SocketAddr) -> Vec<Multiaddr> {
+    if !local_addr.ip().is_unspecified() {
+        return vec![socketaddr_to_multiaddr(local_addr)];
+    }
+
+    match if_addrs::get_if_addrs() {
+        Ok(interfaces) => interfaces
+            .into_iter()
+            .map(|interface| interface.ip())
+            .filter(|ip| ip.is_ipv4() == local_addr.is_ipv4())
+            .map(|ip| socketaddr_to_multiaddr(&SocketAddr::new(ip, local_addr.port())))
+            .collect(),
+        Err(e) => {
+            warn!(error = %e, "Failed to get network interfaces for QUIC listener");
+            vec![]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future::poll_fn;
+    use libp2p::core::multiaddr::Protocol;
+    use libp2p::core::muxing::{StreamMuxer, StreamMuxerEvent};
+
+    async fn open_outbound(muxer: &StreamMuxerBox) -> usize {
+        let mut outbound = muxer.open_outbound();
+        poll_fn(|cx| muxer.poll_outbound(cx, &mut outbound))
+            .await
+            .unwrap()
+    }
+
+    async fn next_inbound(muxer: &StreamMuxerBox) -> usize {
+        loop {
+            if let StreamMuxerEvent::InboundSubstream(substream) =
+                poll_fn(|cx| muxer.poll_event(cx)).await.unwrap()
+            {
+                return substream;
+            }
+        }
+    }
+
+    async fn write_and_close(muxer: &StreamMuxerBox, substream: &mut usize, mut data: &[u8]) {
+        while !data.is_empty() {
+            let n = poll_fn(|cx| muxer.write_substream(cx, substream, data))
+                .await
+                .unwrap();
+            data = &data[n..];
+        }
+        poll_fn(|cx| muxer.shutdown_substream(cx, substream))
+            .await
+            .unwrap();
+    }
+
+    async fn read_to_end(muxer: &StreamMuxerBox, substream: &mut usize) -> Vec<u8> {
+        let mut data = vec![];
+        let mut buf = [0u8; 64 * 1024];
+        loop {
+            let n = poll_fn(|cx| muxer.read_substream(cx, substream, &mut buf))
+                .await
+                .unwrap();
+            if n == 0 {
+                return data;
+            }
+            data.extend_from_slice(&buf[..n]);
+        }
+    }
+
+    #[test]
+    fn test_to_identity_keypair() {
+        let keypair = Keypair::generate_secp256k1();
+        let converted = to_identity_keypair(&keypair).unwrap();
+
+        assert_eq!(
+            converted.public().to_peer_id().to_bytes(),
+            keypair.public().to_peer_id().to_bytes()
+        );
+        assert!(matches!(
+            to_identity_keypair(&Keypair::generate_ed25519()),
+            Err(QuicError::UnsupportedKey)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_dial_and_substream() {
+        let server_key = Keypair::generate_secp256k1();
+        let server_peer_id = server_key.public().to_peer_id();
+        let mut server = QuicTransport::new(&server_key, QuicConfig::default()).unwrap();
+        let mut listener = server
+            .listen_on("/ip4/127.0.0.1/udp/0/quic".parse().unwrap())
+            .unwrap();
+
+        let listen_addr = match listener.next().await {
+            Some(Ok(ListenerEvent::NewAddress(addr))) => addr,
+            _ => panic!("listen address not reported"),
+        };
+
+        let client_key = Keypair::generate_secp256k1();
+        let client_peer_id = client_key.public().to_peer_id();
+        let mut client = QuicTransport::new(&client_key, QuicConfig::default()).unwrap();
+        assert!(client
+            .dial("/ip4/127.0.0.1/tcp/1234".parse().unwrap())
+            .is_err());
+
+        // dialing with a wrong peer id fails in handshake
+        let dial_wrong = client
+            .dial(
+                listen_addr
+                    .clone()
+                    .with(Protocol::P2p(PeerId::random().into())),
+            )
+            .unwrap();
+        assert!(dial_wrong.await.is_err());
+
+        let dial = client
+            .dial(listen_addr.with(Protocol::P2p(server_peer_id.into())))
+            .unwrap();
+        let accept = async {
+            loop {
+                if let Some(Ok(ListenerEvent::Upgrade { upgrade, .. })) = listener.next().await {
+                    if let Ok(output) = upgrade.await {
+                        return output;
+                    }
+                }
+            }
+        };
+
+        let (dialed, accepted) = futures::join!(dial, accept);
+        let (peer_id, client_muxer) = dialed.unwrap();
+        assert_eq!(peer_id, server_peer_id);
+        let (peer_id, server_muxer) = accepted;
+        assert_eq!(peer_id, client_peer_id);
+
+        // a large payload, e.g. a GetChunks response, goes through a single substream
+        let payload: Vec<u8> = (0..4 * 1024 * 1024).map(|i| i as u8).collect();
+        let send = async {
+            let mut substream = open_outbound(&client_muxer).await;
+            write_and_close(&client_muxer, &mut substream, &payload).await;
+        };
+        let receive = async {
+            let mut substream = next_inbound(&server_muxer).await;
+            read_to_end(&server_muxer, &mut substream).await
+        };
+
+        let (_, received) = futures::join!(send, receive);
+        assert_eq!(received, payload);
+    }
+}
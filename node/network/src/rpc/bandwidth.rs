@@ -0,0 +1,272 @@
+//! Byte based accounting of RPC responses per peer and per protocol in both directions, along
+//! with egress quotas so that a node could cap the data uploaded to peers.
+use crate::rpc::Protocol;
+use crate::PeerId;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// The period to reset egress quotas.
+const QUOTA_PERIOD: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BandwidthConfig {
+    /// Maximum bytes of RPC responses uploaded to peers within a day, or 0 for unlimited.
+    pub daily_egress_limit: u64,
+    /// Maximum percentage of `daily_egress_limit` that could be uploaded to a single peer.
+    pub peer_egress_percent: u64,
+}
+
+impl Default for BandwidthConfig {
+    fn default() -> Self {
+        Self {
+            daily_egress_limit: 0,
+            peer_egress_percent: 10,
+        }
+    }
+}
+
+impl BandwidthConfig {
+    fn peer_egress_limit(&self) -> u64 {
+        (self.daily_egress_limit as u128 * self.peer_egress_percent.min(100) as u128 / 100) as u64
+    }
+}
+
+/// Bytes downloaded from and uploaded to peers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Traffic {
+    pub inbound: u64,
+    pub outbound: u64,
+}
+
+/// The egress quota that has been used up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaExceeded {
+    Daily,
+    Peer,
+}
+
+/// Bandwidth statistics exposed to admin RPC.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BandwidthInfo {
+    /// Traffic since the node started.
+    pub total: Traffic,
+    /// Traffic by protocol name since the node started.
+    pub protocols: HashMap<String, Traffic>,
+    /// Traffic by peer id in the current quota period.
+    pub peers: HashMap<String, Traffic>,
+    /// Bytes uploaded in the current quota period.
+    pub daily_egress: u64,
+    pub daily_egress_limit: u64,
+    pub peer_egress_limit: u64,
+}
+
+/// Accounts bytes of RPC responses, and checks egress quotas before serving data requests.
+///
+/// Traffic per protocol is accumulated since the node started, while traffic per peer is reset
+/// along with the egress quotas every day.
+#[derive(Debug)]
+pub struct BandwidthAccounting {
+    config: BandwidthConfig,
+    total: Traffic,
+    protocols: HashMap<Protocol, Traffic>,
+    peers: HashMap<PeerId, Traffic>,
+    period_start: Instant,
+    period_egress: u64,
+}
+
+impl BandwidthAccounting {
+    pub fn new(config: BandwidthConfig) -> Self {
+        Self {
+            config,
+            total: Default::default(),
+            protocols: Default::default(),
+            peers: Default::default(),
+            period_start: Instant::now(),
+            period_egress: 0,
+        }
+    }
+
+    /// Records the bytes of a response received from the peer.
+    pub fn record_inbound(&mut self, peer_id: &PeerId, protocol: Protocol, bytes: usize) {
+        self.try_reset_period();
+
+        let bytes = bytes as u64;
+        self.total.inbound += bytes;
+        self.protocols.entry(protocol).or_default().inbound += bytes;
+        self.peers.entry(*peer_id).or_default().inbound += bytes;
+    }
+
+    /// Records the bytes of a response sent to the peer.
+    pub fn record_outbound(&mut self, peer_id: &PeerId, protocol: Protocol, bytes: usize) {
+        self.try_reset_period();
+
+        let bytes = bytes as u64;
+        self.total.outbound += bytes;
+        self.protocols.entry(protocol).or_default().outbound += bytes;
+        self.peers.entry(*peer_id).or_default().outbound += bytes;
+        self.period_egress += bytes;
+    }
+
+    /// Checks whether a request of the protocol from the peer could be served within the egress
+    /// quotas. Only protocols to transfer data are limited.
+    pub fn check_egress(
+        &mut self,
+        peer_id: &PeerId,
+        protocol: Protocol,
+    ) -> Result<(), QuotaExceeded> {
+        if self.config.daily_egress_limit == 0 || !is_data_protocol(protocol) {
+            return Ok(());
+        }
+
+        self.try_reset_period();
+
+        if self.period_egress >= self.config.daily_egress_limit {
+            return Err(QuotaExceeded::Daily);
+        }
+
+        if self.peer_traffic(peer_id).outbound >= self.config.peer_egress_limit() {
+            return Err(QuotaExceeded::Peer);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the traffic of the peer in the current quota period.
+    pub fn peer_traffic(&self, peer_id: &PeerId) -> Traffic {
+        self.peers.get(peer_id).copied().unwrap_or_default()
+    }
+
+    pub fn info(&self) -> BandwidthInfo {
+        BandwidthInfo {
+            total: self.total,
+            protocols: self
+                .protocols
+                .iter()
+                .map(|(protocol, traffic)| (protocol.to_string(), *traffic))
+                .collect(),
+            peers: self
+                .peers
+                .iter()
+                .map(|(peer_id, traffic)| (peer_id.to_string(), *traffic))
+                .collect(),
+            daily_egress: self.period_egress,
+            daily_egress_limit: self.config.daily_egress_limit,
+            peer_egress_limit: self.config.peer_egress_limit(),
+        }
+    }
+
+    fn try_reset_period(&mut self) {
+        if self.period_start.elapsed() >= QUOTA_PERIOD {
+            self.period_start = Instant::now();
+            self.period_egress = 0;
+            self.peers.clear();
+        }
+    }
+}
+
+/// Returns `true` if the protocol is used to transfer file data, whose responses may be large,
+/// including sectors with proofs in responses of storage challenges.
+fn is_data_protocol(protocol: Protocol) -> bool {
+    matches!(
+        protocol,
+        Protocol::DataByHash
+            | Protocol::GetChunks
+            | Protocol::GetErasurePiece
+            | Protocol::StorageChallenge
+            | Protocol::GetTransactions
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_accounting(daily_egress_limit: u64, peer_egress_percent: u64) -> BandwidthAccounting {
+        BandwidthAccounting::new(BandwidthConfig {
+            daily_egress_limit,
+            peer_egress_percent,
+        })
+    }
+
+    #[test]
+    fn test_record_traffic() {
+        let mut accounting = new_accounting(0, 10);
+        let peer = PeerId::random();
+
+        accounting.record_inbound(&peer, Protocol::GetChunks, 100);
+        accounting.record_outbound(&peer, Protocol::GetChunks, 200);
+        accounting.record_outbound(&peer, Protocol::Status, 10);
+
+        assert_eq!(
+            accounting.peer_traffic(&peer),
+            Traffic {
+                inbound: 100,
+                outbound: 210
+            }
+        );
+
+        let info = accounting.info();
+        assert_eq!(info.total.outbound, 210);
+        assert_eq!(info.daily_egress, 210);
+        assert_eq!(
+            info.protocols["get_chunks"],
+            Traffic {
+                inbound: 100,
+                outbound: 200
+            }
+        );
+        assert_eq!(info.peers.len(), 1);
+        assert_eq!(
+            info.peers[&peer.to_string()],
+            accounting.peer_traffic(&peer)
+        );
+
+        // unlimited
+        assert!(accounting.check_egress(&peer, Protocol::GetChunks).is_ok());
+    }
+
+    #[test]
+    fn test_peer_egress_quota() {
+        let mut accounting = new_accounting(1000, 10);
+        let (peer1, peer2) = (PeerId::random(), PeerId::random());
+
+        accounting.record_outbound(&peer1, Protocol::GetChunks, 100);
+        assert_eq!(
+            accounting.check_egress(&peer1, Protocol::GetChunks),
+            Err(QuotaExceeded::Peer)
+        );
+        assert!(accounting.check_egress(&peer2, Protocol::GetChunks).is_ok());
+
+        // storage challenges respond with sectors
+        assert_eq!(
+            accounting.check_egress(&peer1, Protocol::StorageChallenge),
+            Err(QuotaExceeded::Peer)
+        );
+
+        // requests not to transfer data are not limited
+        assert!(accounting.check_egress(&peer1, Protocol::Status).is_ok());
+    }
+
+    #[test]
+    fn test_daily_egress_quota() {
+        let mut accounting = new_accounting(1000, 100);
+        let peer = PeerId::random();
+
+        accounting.record_outbound(&PeerId::random(), Protocol::GetChunks, 1000);
+        assert_eq!(
+            accounting.check_egress(&peer, Protocol::GetChunks),
+            Err(QuotaExceeded::Daily)
+        );
+
+        // quotas reset in the next period
+        if let Some(start) = Instant::now().checked_sub(QUOTA_PERIOD) {
+            accounting.period_start = start;
+            assert!(accounting.check_egress(&peer, Protocol::GetChunks).is_ok());
+            assert_eq!(accounting.info().total.outbound, 1000);
+        }
+    }
+}
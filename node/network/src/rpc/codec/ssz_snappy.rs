@@ -5,7 +5,7 @@ use crate::rpc::{
 };
 use crate::rpc::{InboundRequest, OutboundRequest, RPCCodedResponse, RPCResponse};
 use libp2p::bytes::BytesMut;
-use shared_types::{ChunkArrayWithProof, ShardedFile};
+use shared_types::{ChunkArrayWithProof, ErasurePiece, ShardedFile};
 use snap::read::FrameDecoder;
 use snap::write::FrameEncoder;
 use ssz::{Decode, Encode};
@@ -52,6 +52,9 @@ impl Encoder<RPCCodedResponse> for SSZSnappyInboundCodec {
                 RPCResponse::Pong(res) => res.data.as_ssz_bytes(),
                 RPCResponse::DataByHash(res) => res.as_ssz_bytes(),
                 RPCResponse::Chunks(res) => res.as_ssz_bytes(),
+                RPCResponse::ErasurePiece(res) => res.as_ssz_bytes(),
+                RPCResponse::StorageChallenge(res) => res.as_ssz_bytes(),
+                RPCResponse::Transactions(res) => res.as_ssz_bytes(),
             },
             RPCCodedResponse::Error(_, err) => err.as_ssz_bytes(),
             RPCCodedResponse::StreamTermination(_) => {
@@ -161,6 +164,9 @@ impl Encoder<OutboundRequest> for SSZSnappyOutboundCodec {
             OutboundRequest::DataByHash(req) => req.hashes.as_ssz_bytes(),
             OutboundRequest::AnswerFile(req) => req.as_ssz_bytes(),
             OutboundRequest::GetChunks(req) => req.as_ssz_bytes(),
+            OutboundRequest::GetErasurePiece(req) => req.as_ssz_bytes(),
+            OutboundRequest::StorageChallenge(req) => req.as_ssz_bytes(),
+            OutboundRequest::GetTransactions(req) => req.as_ssz_bytes(),
         };
         // SSZ encoded bytes should be within `max_packet_size`
         if bytes.len() > self.max_packet_size {
@@ -353,6 +359,15 @@ fn handle_v1_request(
         Protocol::GetChunks => Ok(Some(InboundRequest::GetChunks(
             GetChunksRequest::from_ssz_bytes(decoded_buffer)?,
         ))),
+        Protocol::GetErasurePiece => Ok(Some(InboundRequest::GetErasurePiece(
+            GetErasurePieceRequest::from_ssz_bytes(decoded_buffer)?,
+        ))),
+        Protocol::StorageChallenge => Ok(Some(InboundRequest::StorageChallenge(
+            StorageChallengeRequest::from_ssz_bytes(decoded_buffer)?,
+        ))),
+        Protocol::GetTransactions => Ok(Some(InboundRequest::GetTransactions(
+            GetTransactionsRequest::from_ssz_bytes(decoded_buffer)?,
+        ))),
     }
 }
 
@@ -384,6 +399,15 @@ fn handle_v1_response(
         Protocol::GetChunks => Ok(Some(RPCResponse::Chunks(
             ChunkArrayWithProof::from_ssz_bytes(decoded_buffer)?,
         ))),
+        Protocol::GetErasurePiece => Ok(Some(RPCResponse::ErasurePiece(
+            ErasurePiece::from_ssz_bytes(decoded_buffer)?,
+        ))),
+        Protocol::StorageChallenge => Ok(Some(RPCResponse::StorageChallenge(
+            StorageChallengeResponse::from_ssz_bytes(decoded_buffer)?,
+        ))),
+        Protocol::GetTransactions => Ok(Some(RPCResponse::Transactions(
+            TransactionsResponse::from_ssz_bytes(decoded_buffer)?,
+        ))),
     }
 }
 
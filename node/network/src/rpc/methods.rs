@@ -1,15 +1,18 @@
 //! Available RPC methods types and ids.
 
+use crate::rpc::Protocol;
 use regex::bytes::Regex;
 use ssz_derive::{Decode, Encode};
 use ssz_types::{
-    typenum::{U1024, U256},
+    typenum::{U1024, U256, U64},
     VariableList,
 };
 use std::ops::Deref;
 use strum::IntoStaticStr;
 pub type Hash256 = ethereum_types::H256;
-use shared_types::{ChunkArrayWithProof, NetworkIdentity, TxID};
+use shared_types::{
+    ChunkArrayWithProof, ErasurePiece, NetworkIdentity, TransactionWithProof, TxID,
+};
 
 pub use ssz_types::{typenum, typenum::Unsigned, BitList, BitVector, FixedVector};
 
@@ -17,6 +20,13 @@ pub use ssz_types::{typenum, typenum::Unsigned, BitList, BitVector, FixedVector}
 pub type MaxRequestBlocks = U1024;
 pub const MAX_REQUEST_BLOCKS: u64 = 1024;
 
+/// Maximum number of sectors in a single storage challenge.
+pub type MaxChallengeSectors = U64;
+pub const MAX_CHALLENGE_SECTORS: u64 = 64;
+
+/// Maximum number of transactions in a single GetTransactions request.
+pub const MAX_REQUEST_TRANSACTIONS: u64 = 64;
+
 /// Maximum length of error message.
 pub type MaxErrorLen = U256;
 pub const MAX_ERROR_LEN: u64 = 256;
@@ -191,6 +201,42 @@ pub struct GetChunksRequest {
     pub merkle_tx_seq: u64,
 }
 
+/// Request an erasure coded piece of an entry batch from a peer.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct GetErasurePieceRequest {
+    pub batch_index: u64,
+    pub piece_index: u64,
+}
+
+/// Challenge a peer to prove that it stores a file by returning the given sectors with proofs.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct StorageChallengeRequest {
+    pub tx_id: TxID,
+    /// Sector indexes relative to the start of the file.
+    pub sectors: VariableList<u64, MaxChallengeSectors>,
+}
+
+/// Sectors with proofs to answer a storage challenge, in the same order as requested.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct StorageChallengeResponse {
+    pub tx_id: TxID,
+    pub proofs: Vec<ChunkArrayWithProof>,
+}
+
+/// Request transactions of `count` continuous sequence numbers starting from `from_seq`.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct GetTransactionsRequest {
+    pub from_seq: u64,
+    pub count: u64,
+}
+
+/// Transactions with proofs in ascending order of sequence number, starting from the requested
+/// `from_seq`. It may contain fewer transactions than requested if the peer has not synced them.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct TransactionsResponse {
+    pub transactions: Vec<TransactionWithProof>,
+}
+
 /* RPC Handling and Grouping */
 // Collection of enums and structs used by the Codecs to encode/decode RPC messages
 
@@ -207,6 +253,43 @@ pub enum RPCResponse {
 
     /// A response to a GET_CHUNKS request.
     Chunks(ChunkArrayWithProof),
+
+    /// A response to a GET_ERASURE_PIECE request.
+    ErasurePiece(ErasurePiece),
+
+    /// A response to a STORAGE_CHALLENGE request.
+    StorageChallenge(StorageChallengeResponse),
+
+    /// A response to a GET_TRANSACTIONS request.
+    Transactions(TransactionsResponse),
+}
+
+impl RPCResponse {
+    /// Returns the protocol that the response belongs to.
+    pub fn protocol(&self) -> Protocol {
+        match self {
+            RPCResponse::Status(_) => Protocol::Status,
+            RPCResponse::Pong(_) => Protocol::Ping,
+            RPCResponse::DataByHash(_) => Protocol::DataByHash,
+            RPCResponse::Chunks(_) => Protocol::GetChunks,
+            RPCResponse::ErasurePiece(_) => Protocol::GetErasurePiece,
+            RPCResponse::StorageChallenge(_) => Protocol::StorageChallenge,
+            RPCResponse::Transactions(_) => Protocol::GetTransactions,
+        }
+    }
+
+    /// Returns the length of SSZ encoded response, i.e. the payload size before compression.
+    pub fn ssz_bytes_len(&self) -> usize {
+        match self {
+            RPCResponse::Status(res) => ssz::Encode::ssz_bytes_len(res),
+            RPCResponse::Pong(res) => ssz::Encode::ssz_bytes_len(res),
+            RPCResponse::DataByHash(res) => ssz::Encode::ssz_bytes_len(res.as_ref()),
+            RPCResponse::Chunks(res) => ssz::Encode::ssz_bytes_len(res),
+            RPCResponse::ErasurePiece(res) => ssz::Encode::ssz_bytes_len(res),
+            RPCResponse::StorageChallenge(res) => ssz::Encode::ssz_bytes_len(res),
+            RPCResponse::Transactions(res) => ssz::Encode::ssz_bytes_len(res),
+        }
+    }
 }
 
 /// Indicates which response is being terminated by a stream termination response.
@@ -276,6 +359,9 @@ impl RPCCodedResponse {
                 RPCResponse::Pong(_) => false,
                 RPCResponse::DataByHash(_) => true,
                 RPCResponse::Chunks(_) => false,
+                RPCResponse::ErasurePiece(_) => false,
+                RPCResponse::StorageChallenge(_) => false,
+                RPCResponse::Transactions(_) => false,
             },
             RPCCodedResponse::Error(_, _) => true,
             // Stream terminations are part of responses that have chunks
@@ -335,6 +421,24 @@ impl std::fmt::Display for RPCResponse {
                     data.chunks.data.len()
                 )
             }
+            RPCResponse::ErasurePiece(piece) => {
+                write!(f, "Erasure Piece Response, {:?}", piece)
+            }
+            RPCResponse::StorageChallenge(res) => {
+                write!(
+                    f,
+                    "Storage Challenge Response, tx_seq: {}, sectors: {}",
+                    res.tx_id.seq,
+                    res.proofs.len()
+                )
+            }
+            RPCResponse::Transactions(res) => {
+                write!(
+                    f,
+                    "Transactions Response, count: {}",
+                    res.transactions.len()
+                )
+            }
         }
     }
 }
@@ -22,12 +22,16 @@ pub(crate) use protocol::{InboundRequest, RPCProtocol};
 
 pub use handler::SubstreamId;
 pub use methods::{
-    DataByHashRequest, GetChunksRequest, GoodbyeReason, MaxRequestBlocks, RPCResponseErrorCode,
-    ResponseTermination, StatusMessage, ZgsData, MAX_REQUEST_BLOCKS,
+    DataByHashRequest, GetChunksRequest, GetErasurePieceRequest, GetTransactionsRequest,
+    GoodbyeReason, MaxChallengeSectors, MaxRequestBlocks, RPCResponseErrorCode,
+    ResponseTermination, StatusMessage, StorageChallengeRequest, StorageChallengeResponse,
+    TransactionsResponse, ZgsData, MAX_CHALLENGE_SECTORS, MAX_REQUEST_BLOCKS,
+    MAX_REQUEST_TRANSACTIONS,
 };
 pub(crate) use outbound::OutboundRequest;
 pub use protocol::{max_rpc_size, Protocol, RPCError};
 
+pub mod bandwidth;
 pub(crate) mod codec;
 mod handler;
 pub mod methods;
@@ -120,6 +124,9 @@ impl<Id: ReqId> RPC<Id> {
             .n_every(Protocol::DataByHash, 128, Duration::from_secs(10))
             .n_every(Protocol::AnswerFile, 256, Duration::from_secs(10))
             .n_every(Protocol::GetChunks, 4096, Duration::from_secs(10))
+            .n_every(Protocol::GetErasurePiece, 1024, Duration::from_secs(10))
+            .n_every(Protocol::StorageChallenge, 16, Duration::from_secs(10))
+            .n_every(Protocol::GetTransactions, 64, Duration::from_secs(10))
             .build()
             .expect("Configuration parameters are valid");
         RPC {
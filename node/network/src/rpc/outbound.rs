@@ -37,6 +37,9 @@ pub enum OutboundRequest {
     DataByHash(DataByHashRequest),
     AnswerFile(ShardedFile),
     GetChunks(GetChunksRequest),
+    GetErasurePiece(GetErasurePieceRequest),
+    StorageChallenge(StorageChallengeRequest),
+    GetTransactions(GetTransactionsRequest),
 }
 
 impl UpgradeInfo for OutboundRequestContainer {
@@ -84,6 +87,21 @@ impl OutboundRequest {
                 Version::V1,
                 Encoding::SSZSnappy,
             )],
+            OutboundRequest::GetErasurePiece(_) => vec![ProtocolId::new(
+                Protocol::GetErasurePiece,
+                Version::V1,
+                Encoding::SSZSnappy,
+            )],
+            OutboundRequest::StorageChallenge(_) => vec![ProtocolId::new(
+                Protocol::StorageChallenge,
+                Version::V1,
+                Encoding::SSZSnappy,
+            )],
+            OutboundRequest::GetTransactions(_) => vec![ProtocolId::new(
+                Protocol::GetTransactions,
+                Version::V1,
+                Encoding::SSZSnappy,
+            )],
         }
     }
 
@@ -98,6 +116,9 @@ impl OutboundRequest {
             OutboundRequest::DataByHash(req) => req.hashes.len() as u64,
             OutboundRequest::AnswerFile(_) => 0,
             OutboundRequest::GetChunks(_) => 1,
+            OutboundRequest::GetErasurePiece(_) => 1,
+            OutboundRequest::StorageChallenge(_) => 1,
+            OutboundRequest::GetTransactions(_) => 1,
         }
     }
 
@@ -110,6 +131,9 @@ impl OutboundRequest {
             OutboundRequest::DataByHash(_) => Protocol::DataByHash,
             OutboundRequest::AnswerFile(_) => Protocol::AnswerFile,
             OutboundRequest::GetChunks(_) => Protocol::GetChunks,
+            OutboundRequest::GetErasurePiece(_) => Protocol::GetErasurePiece,
+            OutboundRequest::StorageChallenge(_) => Protocol::StorageChallenge,
+            OutboundRequest::GetTransactions(_) => Protocol::GetTransactions,
         }
     }
 
@@ -125,6 +149,9 @@ impl OutboundRequest {
             OutboundRequest::Ping(_) => unreachable!(),
             OutboundRequest::AnswerFile(_) => unreachable!(),
             OutboundRequest::GetChunks(_) => unreachable!(),
+            OutboundRequest::GetErasurePiece(_) => unreachable!(),
+            OutboundRequest::StorageChallenge(_) => unreachable!(),
+            OutboundRequest::GetTransactions(_) => unreachable!(),
         }
     }
 }
@@ -186,6 +213,15 @@ impl std::fmt::Display for OutboundRequest {
             OutboundRequest::GetChunks(req) => {
                 write!(f, "GetChunks: {:?}", req)
             }
+            OutboundRequest::GetErasurePiece(req) => {
+                write!(f, "GetErasurePiece: {:?}", req)
+            }
+            OutboundRequest::StorageChallenge(req) => {
+                write!(f, "StorageChallenge: {:?}", req)
+            }
+            OutboundRequest::GetTransactions(req) => {
+                write!(f, "GetTransactions: {:?}", req)
+            }
         }
     }
 }
@@ -2,13 +2,15 @@ use super::methods::*;
 use crate::rpc::{
     codec::{base::BaseInboundCodec, ssz_snappy::SSZSnappyInboundCodec, InboundCodec},
     methods::{MaxErrorLen, ResponseTermination, MAX_CHUNKS_LENGTH, MAX_ERROR_LEN},
-    MaxRequestBlocks, MAX_REQUEST_BLOCKS,
+    MaxChallengeSectors, MaxRequestBlocks, MAX_CHALLENGE_SECTORS, MAX_REQUEST_BLOCKS,
 };
 use futures::future::BoxFuture;
 use futures::prelude::{AsyncRead, AsyncWrite};
 use futures::{FutureExt, StreamExt};
 use libp2p::core::{InboundUpgrade, ProtocolName, UpgradeInfo};
-use shared_types::{ChunkArray, ChunkArrayWithProof, FlowRangeProof, ShardedFile};
+use shared_types::{
+    ChunkArray, ChunkArrayWithProof, ErasurePiece, FlowProof, FlowRangeProof, ShardedFile, TxID,
+};
 use ssz::Encode;
 use ssz_types::VariableList;
 use std::io;
@@ -60,6 +62,40 @@ lazy_static! {
     }
     .as_ssz_bytes()
     .len();
+    pub static ref ERASURE_PIECE_RESPONSE_MIN: usize = ErasurePiece {
+        batch_index: 0,
+        piece_index: 0,
+        data: vec![],
+        proof: FlowProof::new_empty(),
+    }
+    .as_ssz_bytes()
+    .len();
+    pub static ref STORAGE_CHALLENGE_REQUEST_MIN: usize = StorageChallengeRequest {
+        tx_id: TxID::default(),
+        sectors: VariableList::<u64, MaxChallengeSectors>::from(vec![0u64]),
+    }
+    .as_ssz_bytes()
+    .len();
+    pub static ref STORAGE_CHALLENGE_REQUEST_MAX: usize = StorageChallengeRequest {
+        tx_id: TxID::default(),
+        sectors: VariableList::<u64, MaxChallengeSectors>::from(vec![
+            0u64;
+            MAX_CHALLENGE_SECTORS as usize
+        ]),
+    }
+    .as_ssz_bytes()
+    .len();
+    pub static ref STORAGE_CHALLENGE_RESPONSE_MIN: usize = StorageChallengeResponse {
+        tx_id: TxID::default(),
+        proofs: vec![],
+    }
+    .as_ssz_bytes()
+    .len();
+    pub static ref TRANSACTIONS_RESPONSE_MIN: usize = TransactionsResponse {
+        transactions: vec![],
+    }
+    .as_ssz_bytes()
+    .len();
 }
 
 // /// The maximum bytes that can be sent across the RPC pre-merge.
@@ -80,7 +116,7 @@ pub fn max_rpc_size() -> usize {
 }
 
 /// Protocol names to be used.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Protocol {
     /// The Status protocol name.
     Status,
@@ -95,6 +131,12 @@ pub enum Protocol {
     AnswerFile,
     /// The Chunk sync protocol.
     GetChunks,
+    /// The erasure coded piece sync protocol.
+    GetErasurePiece,
+    /// The protocol to challenge a peer to prove that it stores a file.
+    StorageChallenge,
+    /// The protocol to fetch transactions with proofs against the flow root.
+    GetTransactions,
 }
 
 /// RPC Versions
@@ -119,6 +161,9 @@ impl std::fmt::Display for Protocol {
             Protocol::DataByHash => "data_by_hash",
             Protocol::AnswerFile => "answer_file",
             Protocol::GetChunks => "get_chunks",
+            Protocol::GetErasurePiece => "get_erasure_piece",
+            Protocol::StorageChallenge => "storage_challenge",
+            Protocol::GetTransactions => "get_transactions",
         };
         f.write_str(repr)
     }
@@ -160,6 +205,9 @@ impl UpgradeInfo for RPCProtocol {
             ProtocolId::new(Protocol::DataByHash, Version::V1, Encoding::SSZSnappy),
             ProtocolId::new(Protocol::AnswerFile, Version::V1, Encoding::SSZSnappy),
             ProtocolId::new(Protocol::GetChunks, Version::V1, Encoding::SSZSnappy),
+            ProtocolId::new(Protocol::GetErasurePiece, Version::V1, Encoding::SSZSnappy),
+            ProtocolId::new(Protocol::StorageChallenge, Version::V1, Encoding::SSZSnappy),
+            ProtocolId::new(Protocol::GetTransactions, Version::V1, Encoding::SSZSnappy),
         ]
     }
 }
@@ -228,6 +276,18 @@ impl ProtocolId {
                 <GetChunksRequest as Encode>::ssz_fixed_len(),
                 <GetChunksRequest as Encode>::ssz_fixed_len(),
             ),
+            Protocol::GetErasurePiece => RpcLimits::new(
+                <GetErasurePieceRequest as Encode>::ssz_fixed_len(),
+                <GetErasurePieceRequest as Encode>::ssz_fixed_len(),
+            ),
+            Protocol::StorageChallenge => RpcLimits::new(
+                *STORAGE_CHALLENGE_REQUEST_MIN,
+                *STORAGE_CHALLENGE_REQUEST_MAX,
+            ),
+            Protocol::GetTransactions => RpcLimits::new(
+                <GetTransactionsRequest as Encode>::ssz_fixed_len(),
+                <GetTransactionsRequest as Encode>::ssz_fixed_len(),
+            ),
         }
     }
 
@@ -253,6 +313,11 @@ impl ProtocolId {
 
             Protocol::AnswerFile => RpcLimits::new(0, 0), // AnswerFile request has no response
             Protocol::GetChunks => RpcLimits::new(*CHUNKS_RESPONSE_MIN, *CHUNKS_RESPONSE_MAX),
+            Protocol::GetErasurePiece => RpcLimits::new(*ERASURE_PIECE_RESPONSE_MIN, MAX_RPC_SIZE),
+            Protocol::StorageChallenge => {
+                RpcLimits::new(*STORAGE_CHALLENGE_RESPONSE_MIN, MAX_RPC_SIZE)
+            }
+            Protocol::GetTransactions => RpcLimits::new(*TRANSACTIONS_RESPONSE_MIN, MAX_RPC_SIZE),
         }
     }
 }
@@ -336,6 +401,9 @@ pub enum InboundRequest {
     DataByHash(DataByHashRequest),
     AnswerFile(ShardedFile),
     GetChunks(GetChunksRequest),
+    GetErasurePiece(GetErasurePieceRequest),
+    StorageChallenge(StorageChallengeRequest),
+    GetTransactions(GetTransactionsRequest),
 }
 
 impl UpgradeInfo for InboundRequest {
@@ -383,6 +451,21 @@ impl InboundRequest {
                 Version::V1,
                 Encoding::SSZSnappy,
             )],
+            InboundRequest::GetErasurePiece(_) => vec![ProtocolId::new(
+                Protocol::GetErasurePiece,
+                Version::V1,
+                Encoding::SSZSnappy,
+            )],
+            InboundRequest::StorageChallenge(_) => vec![ProtocolId::new(
+                Protocol::StorageChallenge,
+                Version::V1,
+                Encoding::SSZSnappy,
+            )],
+            InboundRequest::GetTransactions(_) => vec![ProtocolId::new(
+                Protocol::GetTransactions,
+                Version::V1,
+                Encoding::SSZSnappy,
+            )],
         }
     }
 
@@ -397,6 +480,9 @@ impl InboundRequest {
             InboundRequest::Ping(_) => 1,
             InboundRequest::AnswerFile(_) => 0,
             InboundRequest::GetChunks(_) => 1,
+            InboundRequest::GetErasurePiece(_) => 1,
+            InboundRequest::StorageChallenge(_) => 1,
+            InboundRequest::GetTransactions(_) => 1,
         }
     }
 
@@ -409,6 +495,9 @@ impl InboundRequest {
             InboundRequest::DataByHash(_) => Protocol::DataByHash,
             InboundRequest::AnswerFile(_) => Protocol::AnswerFile,
             InboundRequest::GetChunks(_) => Protocol::GetChunks,
+            InboundRequest::GetErasurePiece(_) => Protocol::GetErasurePiece,
+            InboundRequest::StorageChallenge(_) => Protocol::StorageChallenge,
+            InboundRequest::GetTransactions(_) => Protocol::GetTransactions,
         }
     }
 
@@ -424,6 +513,9 @@ impl InboundRequest {
             InboundRequest::Ping(_) => unreachable!(),
             InboundRequest::AnswerFile(_) => unreachable!(),
             InboundRequest::GetChunks(_) => unreachable!(),
+            InboundRequest::GetErasurePiece(_) => unreachable!(),
+            InboundRequest::StorageChallenge(_) => unreachable!(),
+            InboundRequest::GetTransactions(_) => unreachable!(),
         }
     }
 }
@@ -547,6 +639,15 @@ impl std::fmt::Display for InboundRequest {
             InboundRequest::GetChunks(req) => {
                 write!(f, "Get Chunks: {:?}", req)
             }
+            InboundRequest::GetErasurePiece(req) => {
+                write!(f, "Get Erasure Piece: {:?}", req)
+            }
+            InboundRequest::StorageChallenge(req) => {
+                write!(f, "Storage Challenge: {:?}", req)
+            }
+            InboundRequest::GetTransactions(req) => {
+                write!(f, "Get Transactions: {:?}", req)
+            }
         }
     }
 }
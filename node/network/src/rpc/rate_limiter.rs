@@ -88,6 +88,12 @@ pub struct RPCRateLimiter {
     answer_file_rl: Limiter<PeerId>,
     /// GetChunks rate limiter.
     get_chunks_rl: Limiter<PeerId>,
+    /// GetErasurePiece rate limiter.
+    get_erasure_piece_rl: Limiter<PeerId>,
+    /// StorageChallenge rate limiter.
+    storage_challenge_rl: Limiter<PeerId>,
+    /// GetTransactions rate limiter.
+    get_transactions_rl: Limiter<PeerId>,
 }
 
 /// Error type for non conformant requests
@@ -113,6 +119,12 @@ pub struct RPCRateLimiterBuilder {
     answer_file_quota: Option<Quota>,
     /// Quota for the GetChunks protocol.
     get_chunks_quota: Option<Quota>,
+    /// Quota for the GetErasurePiece protocol.
+    get_erasure_piece_quota: Option<Quota>,
+    /// Quota for the StorageChallenge protocol.
+    storage_challenge_quota: Option<Quota>,
+    /// Quota for the GetTransactions protocol.
+    get_transactions_quota: Option<Quota>,
 }
 
 impl RPCRateLimiterBuilder {
@@ -131,6 +143,9 @@ impl RPCRateLimiterBuilder {
             Protocol::DataByHash => self.data_by_hash_quota = q,
             Protocol::AnswerFile => self.answer_file_quota = q,
             Protocol::GetChunks => self.get_chunks_quota = q,
+            Protocol::GetErasurePiece => self.get_erasure_piece_quota = q,
+            Protocol::StorageChallenge => self.storage_challenge_quota = q,
+            Protocol::GetTransactions => self.get_transactions_quota = q,
         }
         self
     }
@@ -160,6 +175,15 @@ impl RPCRateLimiterBuilder {
         let get_chunks_quota = self
             .get_chunks_quota
             .ok_or("GetChunks quota not specified")?;
+        let get_erasure_piece_quota = self
+            .get_erasure_piece_quota
+            .ok_or("GetErasurePiece quota not specified")?;
+        let storage_challenge_quota = self
+            .storage_challenge_quota
+            .ok_or("StorageChallenge quota not specified")?;
+        let get_transactions_quota = self
+            .get_transactions_quota
+            .ok_or("GetTransactions quota not specified")?;
 
         // create the rate limiters
         let ping_rl = Limiter::from_quota(ping_quota)?;
@@ -168,6 +192,9 @@ impl RPCRateLimiterBuilder {
         let data_by_hash_rl = Limiter::from_quota(data_by_hash_quota)?;
         let answer_file_rl = Limiter::from_quota(answer_file_quota)?;
         let get_chunks_rl = Limiter::from_quota(get_chunks_quota)?;
+        let get_erasure_piece_rl = Limiter::from_quota(get_erasure_piece_quota)?;
+        let storage_challenge_rl = Limiter::from_quota(storage_challenge_quota)?;
+        let get_transactions_rl = Limiter::from_quota(get_transactions_quota)?;
 
         // check for peers to prune every 30 seconds, starting in 30 seconds
         let prune_every = tokio::time::Duration::from_secs(30);
@@ -181,6 +208,9 @@ impl RPCRateLimiterBuilder {
             data_by_hash_rl,
             answer_file_rl,
             get_chunks_rl,
+            get_erasure_piece_rl,
+            storage_challenge_rl,
+            get_transactions_rl,
             init_time: Instant::now(),
         })
     }
@@ -226,6 +256,9 @@ impl RPCRateLimiter {
             Protocol::DataByHash => &mut self.data_by_hash_rl,
             Protocol::AnswerFile => &mut self.answer_file_rl,
             Protocol::GetChunks => &mut self.get_chunks_rl,
+            Protocol::GetErasurePiece => &mut self.get_erasure_piece_rl,
+            Protocol::StorageChallenge => &mut self.storage_challenge_rl,
+            Protocol::GetTransactions => &mut self.get_transactions_rl,
         };
         check(limiter)
     }
@@ -237,6 +270,9 @@ impl RPCRateLimiter {
         self.goodbye_rl.prune(time_since_start);
         self.data_by_hash_rl.prune(time_since_start);
         self.get_chunks_rl.prune(time_since_start);
+        self.get_erasure_piece_rl.prune(time_since_start);
+        self.storage_challenge_rl.prune(time_since_start);
+        self.get_transactions_rl.prune(time_since_start);
     }
 }
 
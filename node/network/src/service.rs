@@ -2,18 +2,26 @@ use crate::behaviour::{Behaviour, BehaviourEvent, PeerRequestId, Request, Respon
 use crate::config::NetworkLoad;
 use crate::discovery::enr;
 use crate::multiaddr::Protocol;
+use crate::quic::{QuicConfig, QuicTransport};
 use crate::rpc::{GoodbyeReason, RPCResponseErrorCode, ReqId};
 use crate::types::{error, GossipKind};
 use crate::{EnrExt, NetworkSender};
 use crate::{NetworkConfig, NetworkGlobals, PeerAction, ReportSource};
 use futures::prelude::*;
 use libp2p::core::{
-    identity::Keypair, multiaddr::Multiaddr, muxing::StreamMuxerBox, transport::Boxed,
+    either::EitherOutput,
+    identity::Keypair,
+    multiaddr::Multiaddr,
+    muxing::StreamMuxerBox,
+    transport::{Boxed, OptionalTransport},
 };
 use libp2p::{
     bandwidth::{BandwidthLogging, BandwidthSinks},
     core, noise,
-    swarm::{ConnectionLimits, SwarmBuilder, SwarmEvent},
+    swarm::{
+        dial_opts::{DialOpts, PeerCondition},
+        ConnectionLimits, SwarmBuilder, SwarmEvent,
+    },
     PeerId, Swarm, Transport,
 };
 use std::fs::File;
@@ -22,7 +30,9 @@ use std::pin::Pin;
 use std::sync::Arc;
 use std::time::Duration;
 
-use crate::peer_manager::{MIN_OUTBOUND_ONLY_FACTOR, PEER_EXCESS_FACTOR, PRIORITY_PEER_EXCESS};
+use crate::peer_manager::{
+    PeerInfo, MIN_OUTBOUND_ONLY_FACTOR, PEER_EXCESS_FACTOR, PRIORITY_PEER_EXCESS,
+};
 
 pub const NETWORK_KEY_FILENAME: &str = "key";
 /// The maximum simultaneous libp2p connections per peer.
@@ -84,6 +94,7 @@ impl<AppReqId: ReqId> Service<AppReqId> {
                 .map(|x| PeerId::from(x.clone()))
                 .collect(),
             config.peer_db,
+            config.bandwidth,
             config.network_id.clone(),
         ));
 
@@ -185,6 +196,47 @@ impl<AppReqId: ReqId> Service<AppReqId> {
             }
         };
 
+        // listen on the QUIC addresses if configured
+        for quic_multiaddr in &config.quic_listen_addresses {
+            match Swarm::listen_on(&mut swarm, quic_multiaddr.clone()) {
+                Ok(_) => {
+                    let mut log_address = quic_multiaddr.clone();
+                    log_address.push(Protocol::P2p(local_peer_id.into()));
+                    info!(address = %log_address, "Listening established over QUIC");
+                }
+                Err(err) => {
+                    error!(
+                        error = ?err,
+                        listen_multiaddr = %quic_multiaddr,
+                        "Unable to listen on QUIC address",
+                    );
+                    return Err("Libp2p was unable to listen on the given QUIC address.".into());
+                }
+            };
+        }
+
+        // attempt to connect to the best known peers persisted before restart
+        let known_peers = network_globals
+            .peers
+            .read()
+            .best_peers_by_status(PeerInfo::is_disconnected)
+            .into_iter()
+            .filter(|(_, info)| !info.listening_addresses().is_empty())
+            .take(config.target_peers)
+            .map(|(peer_id, info)| (*peer_id, info.listening_addresses().clone()))
+            .collect::<Vec<_>>();
+
+        for (peer_id, addresses) in known_peers {
+            let opts = DialOpts::peer_id(peer_id)
+                .condition(PeerCondition::Disconnected)
+                .addresses(addresses)
+                .build();
+            match Swarm::dial(&mut swarm, opts) {
+                Ok(()) => debug!(%peer_id, "Dialing known peer"),
+                Err(err) => debug!(%peer_id, error = ?err, "Could not connect to known peer"),
+            }
+        }
+
         // helper closure for dialing peers
         let mut dial = |multiaddr: Multiaddr| {
             // strip the p2p protocol if it exists
@@ -206,10 +258,12 @@ impl<AppReqId: ReqId> Service<AppReqId> {
         boot_nodes.dedup();
 
         for bootnode_enr in boot_nodes {
-            for multiaddr in &bootnode_enr.multiaddr() {
-                // ignore udp multiaddr if it exists
+            let mut multiaddrs = bootnode_enr.multiaddr_quic();
+            multiaddrs.extend(bootnode_enr.multiaddr());
+            for multiaddr in &multiaddrs {
+                // ignore udp multiaddr of discovery if it exists
                 let components = multiaddr.iter().collect::<Vec<_>>();
-                if let Protocol::Udp(_) = components[1] {
+                if let (Protocol::Udp(_), None) = (&components[1], components.get(2)) {
                     continue;
                 }
 
@@ -224,10 +278,10 @@ impl<AppReqId: ReqId> Service<AppReqId> {
         }
 
         for multiaddr in &config.boot_nodes_multiaddr {
-            // check TCP support for dialing
+            // check TCP or QUIC support for dialing
             if multiaddr
                 .iter()
-                .any(|proto| matches!(proto, Protocol::Tcp(_)))
+                .any(|proto| matches!(proto, Protocol::Tcp(_) | Protocol::Quic))
             {
                 dial(multiaddr.clone());
             }
@@ -248,6 +302,7 @@ impl<AppReqId: ReqId> Service<AppReqId> {
             GossipKind::AskFile,
             GossipKind::FindFile,
             GossipKind::AnnounceFile,
+            GossipKind::AnnounceFileRanges,
             GossipKind::AnnounceShardConfig,
         ];
         if config.find_chunks_enabled {
@@ -401,7 +456,7 @@ impl<AppReqId: ReqId> Service<AppReqId> {
 type BoxedTransport = Boxed<(PeerId, StreamMuxerBox)>;
 
 /// The implementation supports TCP/IP, WebSockets over TCP/IP, noise as the encryption layer, and
-/// mplex as the multiplexing layer.
+/// mplex as the multiplexing layer. Besides, QUIC is supported with TLS as the encryption layer.
 fn build_transport(
     local_private_key: Keypair,
 ) -> std::io::Result<(BoxedTransport, Arc<BandwidthSinks>)> {
@@ -425,15 +480,32 @@ fn build_transport(
     yamux_config.set_window_update_mode(libp2p::yamux::WindowUpdateMode::on_read());
 
     // Authentication
+    let transport = transport
+        .upgrade(core::upgrade::Version::V1)
+        .authenticate(generate_noise_config(&local_private_key))
+        .multiplex(core::upgrade::SelectUpgrade::new(
+            yamux_config,
+            mplex_config,
+        ))
+        .timeout(Duration::from_secs(10))
+        .map(|(peer_id, muxer), _| (peer_id, StreamMuxerBox::new(muxer)));
+
+    // QUIC is always available to dial, and listens only on configured addresses
+    let quic = match QuicTransport::new(&local_private_key, QuicConfig::default()) {
+        Ok(quic) => OptionalTransport::some(quic),
+        Err(err) => {
+            warn!(error = %err, "Failed to build QUIC transport, only TCP is supported");
+            OptionalTransport::none()
+        }
+    };
+
     Ok((
         transport
-            .upgrade(core::upgrade::Version::V1)
-            .authenticate(generate_noise_config(&local_private_key))
-            .multiplex(core::upgrade::SelectUpgrade::new(
-                yamux_config,
-                mplex_config,
-            ))
-            .timeout(Duration::from_secs(10))
+            .or_transport(quic)
+            .map(|output, _| match output {
+                EitherOutput::First(output) => output,
+                EitherOutput::Second(output) => output,
+            })
             .boxed(),
         bandwidth,
     ))
@@ -1,6 +1,7 @@
 //! A collection of variables that are accessible outside of the network thread itself.
 use crate::peer_manager::peerdb::PeerDB;
 use crate::peer_manager::peerdb::PeerDBConfig;
+use crate::rpc::bandwidth::{BandwidthAccounting, BandwidthConfig};
 use crate::Client;
 use crate::EnrExt;
 use crate::{Enr, GossipTopic, Multiaddr, PeerId};
@@ -22,6 +23,8 @@ pub struct NetworkGlobals {
     pub listen_port_udp: AtomicU16,
     /// The collection of known peers.
     pub peers: RwLock<PeerDB>,
+    /// Bytes transferred via RPC and the egress quotas.
+    pub bandwidth: RwLock<BandwidthAccounting>,
     /// The current gossipsub topic subscriptions.
     pub gossipsub_subscriptions: RwLock<HashSet<GossipTopic>>,
 
@@ -36,6 +39,7 @@ impl NetworkGlobals {
         udp_port: u16,
         trusted_peers: Vec<PeerId>,
         peer_db_config: PeerDBConfig,
+        bandwidth_config: BandwidthConfig,
         network_id: NetworkIdentity,
     ) -> Self {
         NetworkGlobals {
@@ -45,6 +49,7 @@ impl NetworkGlobals {
             listen_port_tcp: AtomicU16::new(tcp_port),
             listen_port_udp: AtomicU16::new(udp_port),
             peers: RwLock::new(PeerDB::new(peer_db_config, trusted_peers)),
+            bandwidth: RwLock::new(BandwidthAccounting::new(bandwidth_config)),
             gossipsub_subscriptions: RwLock::new(HashSet::new()),
             network_id: RwLock::new(network_id),
         }
@@ -119,6 +124,7 @@ impl NetworkGlobals {
             vec![],
             Default::default(),
             Default::default(),
+            Default::default(),
         )
     }
 }
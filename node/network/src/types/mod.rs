@@ -7,7 +7,8 @@ pub type Enr = discv5::enr::Enr<discv5::enr::CombinedKey>;
 
 pub use globals::NetworkGlobals;
 pub use pubsub::{
-    AnnounceChunks, AnnounceFile, FindChunks, FindFile, HasSignature, PubsubMessage,
-    SignedAnnounceFile, SignedMessage, SnappyTransform, TimedMessage,
+    AnnounceChunks, AnnounceFile, AnnounceFileRanges, FindChunks, FindFile, HasSignature,
+    PubsubMessage, SignedAnnounceFile, SignedAnnounceFileRanges, SignedMessage, SnappyTransform,
+    TimedMessage, TxSeqRange,
 };
 pub use topics::{GossipEncoding, GossipKind, GossipTopic};
@@ -135,6 +135,40 @@ pub struct AnnounceFile {
     pub at: WrappedMultiaddr,
 }
 
+/// A range of tx_seq `[start, end)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Encode, Decode)]
+pub struct TxSeqRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+/// Announces files in tx_seq ranges, which is much more compact than `AnnounceFile` for nodes
+/// that store lots of files.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Encode, Decode)]
+pub struct AnnounceFileRanges {
+    /// Sorted and non-overlapping ranges of finalized files.
+    pub ranges: Vec<TxSeqRange>,
+    pub shard_config: ShardConfig,
+    pub peer_id: WrappedPeerId,
+    pub at: WrappedMultiaddr,
+}
+
+impl AnnounceFileRanges {
+    /// Returns whether the file of `tx_seq` is covered by the announced ranges.
+    pub fn contains(&self, tx_seq: u64) -> bool {
+        let index = self.ranges.partition_point(|range| range.end <= tx_seq);
+        self.ranges
+            .get(index)
+            .map_or(false, |range| range.start <= tx_seq)
+    }
+
+    /// Returns whether the ranges are sorted, non-empty and non-overlapping.
+    pub fn is_valid(&self) -> bool {
+        self.ranges.iter().all(|range| range.start < range.end)
+            && self.ranges.windows(2).all(|w| w[0].end <= w[1].start)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Encode, Decode)]
 pub struct AnnounceChunks {
     pub tx_id: TxID,
@@ -207,6 +241,7 @@ impl<T: Encode + Decode> HasSignature for SignedMessage<T> {
 }
 
 pub type SignedAnnounceFile = SignedMessage<TimedMessage<AnnounceFile>>;
+pub type SignedAnnounceFileRanges = SignedMessage<TimedMessage<AnnounceFileRanges>>;
 type SignedAnnounceFiles = Vec<SignedAnnounceFile>;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -222,6 +257,8 @@ pub enum PubsubMessage {
     FindChunks(TimedMessage<FindChunks>),
     /// Published to network to announce file.
     AnnounceFile(Vec<SignedAnnounceFile>),
+    /// Published to network periodically to announce all stored files in ranges.
+    AnnounceFileRanges(SignedAnnounceFileRanges),
     /// Published to network to announce shard config.
     AnnounceShardConfig(TimedMessage<ShardConfig>),
     /// Published to network to announce chunks.
@@ -304,6 +341,7 @@ impl PubsubMessage {
             PubsubMessage::FindFile(_) => GossipKind::FindFile,
             PubsubMessage::FindChunks(_) => GossipKind::FindChunks,
             PubsubMessage::AnnounceFile(_) => GossipKind::AnnounceFile,
+            PubsubMessage::AnnounceFileRanges(_) => GossipKind::AnnounceFileRanges,
             PubsubMessage::AnnounceChunks(_) => GossipKind::AnnounceChunks,
             PubsubMessage::AnnounceShardConfig(_) => GossipKind::AnnounceShardConfig,
         }
@@ -347,6 +385,10 @@ impl PubsubMessage {
                         SignedAnnounceFiles::from_ssz_bytes(data)
                             .map_err(|e| format!("{:?}", e))?,
                     )),
+                    GossipKind::AnnounceFileRanges => Ok(PubsubMessage::AnnounceFileRanges(
+                        SignedAnnounceFileRanges::from_ssz_bytes(data)
+                            .map_err(|e| format!("{:?}", e))?,
+                    )),
                     GossipKind::AnnounceChunks => Ok(PubsubMessage::AnnounceChunks(
                         TimedMessage::<AnnounceChunks>::from_ssz_bytes(data)
                             .map_err(|e| format!("{:?}", e))?,
@@ -374,6 +416,7 @@ impl PubsubMessage {
             PubsubMessage::FindFile(data) => data.as_ssz_bytes(),
             PubsubMessage::FindChunks(data) => data.as_ssz_bytes(),
             PubsubMessage::AnnounceFile(data) => data.as_ssz_bytes(),
+            PubsubMessage::AnnounceFileRanges(data) => data.as_ssz_bytes(),
             PubsubMessage::AnnounceChunks(data) => data.as_ssz_bytes(),
             PubsubMessage::AnnounceShardConfig(data) => data.as_ssz_bytes(),
         }
@@ -401,6 +444,9 @@ impl std::fmt::Display for PubsubMessage {
             PubsubMessage::AnnounceFile(msg) => {
                 write!(f, "AnnounceFile message: {:?}", msg)
             }
+            PubsubMessage::AnnounceFileRanges(msg) => {
+                write!(f, "AnnounceFileRanges message: {:?}", msg)
+            }
             PubsubMessage::AnnounceChunks(msg) => {
                 write!(f, "AnnounceChunks message: {:?}", msg)
             }
@@ -13,6 +13,7 @@ pub const ASK_FILE_TOPIC: &str = "ask_file";
 pub const FIND_FILE_TOPIC: &str = "find_file_v2";
 pub const FIND_CHUNKS_TOPIC: &str = "find_chunks_v2";
 pub const ANNOUNCE_FILE_TOPIC: &str = "announce_file_v2";
+pub const ANNOUNCE_FILE_RANGES_TOPIC: &str = "announce_file_ranges";
 pub const ANNOUNCE_CHUNKS_TOPIC: &str = "announce_chunks_v2";
 pub const ANNOUNCE_SHARD_CONFIG_TOPIC: &str = "announce_shard_config_v2";
 
@@ -37,6 +38,7 @@ pub enum GossipKind {
     FindFile,
     FindChunks,
     AnnounceFile,
+    AnnounceFileRanges,
     AnnounceShardConfig,
     AnnounceChunks,
 }
@@ -79,6 +81,7 @@ impl GossipTopic {
                 FIND_FILE_TOPIC => GossipKind::FindFile,
                 FIND_CHUNKS_TOPIC => GossipKind::FindChunks,
                 ANNOUNCE_FILE_TOPIC => GossipKind::AnnounceFile,
+                ANNOUNCE_FILE_RANGES_TOPIC => GossipKind::AnnounceFileRanges,
                 ANNOUNCE_CHUNKS_TOPIC => GossipKind::AnnounceChunks,
                 ANNOUNCE_SHARD_CONFIG_TOPIC => GossipKind::AnnounceShardConfig,
                 _ => return Err(format!("Unknown topic: {}", topic)),
@@ -110,6 +113,7 @@ impl From<GossipTopic> for String {
             GossipKind::FindFile => FIND_FILE_TOPIC,
             GossipKind::FindChunks => FIND_CHUNKS_TOPIC,
             GossipKind::AnnounceFile => ANNOUNCE_FILE_TOPIC,
+            GossipKind::AnnounceFileRanges => ANNOUNCE_FILE_RANGES_TOPIC,
             GossipKind::AnnounceChunks => ANNOUNCE_CHUNKS_TOPIC,
             GossipKind::AnnounceShardConfig => ANNOUNCE_SHARD_CONFIG_TOPIC,
         };
@@ -131,6 +135,7 @@ impl std::fmt::Display for GossipTopic {
             GossipKind::FindFile => FIND_FILE_TOPIC,
             GossipKind::FindChunks => FIND_CHUNKS_TOPIC,
             GossipKind::AnnounceFile => ANNOUNCE_FILE_TOPIC,
+            GossipKind::AnnounceFileRanges => ANNOUNCE_FILE_RANGES_TOPIC,
             GossipKind::AnnounceChunks => ANNOUNCE_CHUNKS_TOPIC,
             GossipKind::AnnounceShardConfig => ANNOUNCE_SHARD_CONFIG_TOPIC,
         };
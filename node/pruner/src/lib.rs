@@ -1,3 +1,5 @@
+mod policy;
+
 use anyhow::{bail, Result};
 use contract_interface::ChunkLinearReward;
 use ethereum_types::Address;
@@ -18,8 +20,12 @@ use tokio::sync::{broadcast, mpsc};
 use tracing::{debug, error, info};
 use zgs_spec::SECTORS_PER_PRICING;
 
+pub use policy::{PrunePolicy, StreamRetention};
+
 // Start pruning when the db directory size exceeds 0.9 * limit.
 const PRUNE_THRESHOLD: f32 = 0.9;
+// Local prune policies stop pruning when the db size falls below 0.8 * limit.
+const PRUNE_TARGET: f32 = 0.8;
 
 const FIRST_REWARDABLE_CHUNK_KEY: &str = "first_rewardable_chunk";
 
@@ -33,6 +39,7 @@ pub struct PrunerConfig {
     pub check_time: Duration,
     pub batch_size: usize,
     pub batch_wait_time: Duration,
+    pub policy: PrunePolicy,
 
     pub rpc_endpoint_url: String,
     pub reward_address: Address,
@@ -46,6 +53,10 @@ impl PrunerConfig {
     fn start_prune_size(&self) -> u64 {
         (self.max_num_sectors as f32 * PRUNE_THRESHOLD) as u64
     }
+
+    fn prune_target_size(&self) -> u64 {
+        (self.max_num_sectors as f32 * PRUNE_TARGET) as u64
+    }
 }
 
 pub struct Pruner {
@@ -58,7 +69,8 @@ pub struct Pruner {
     sender: mpsc::UnboundedSender<PrunerMessage>,
     miner_sender: Option<broadcast::Sender<MinerMessage>>,
 
-    reward_contract: ChunkLinearReward<Arc<Provider<RetryClient<Http>>>>,
+    /// Only available for the reward policy.
+    reward_contract: Option<ChunkLinearReward<Arc<Provider<RetryClient<Http>>>>>,
 }
 
 impl Pruner {
@@ -75,17 +87,24 @@ impl Pruner {
             .await?
             .unwrap_or((0, 0));
 
-        let provider = Arc::new(Provider::new(
-            RetryClientBuilder::default()
-                .rate_limit_retries(config.rate_limit_retries)
-                .timeout_retries(config.timeout_retries)
-                .initial_backoff(Duration::from_millis(config.initial_backoff))
-                .build(
-                    Http::from_str(&config.rpc_endpoint_url)?,
-                    Box::new(HttpRateLimitRetryPolicy),
-                ),
-        ));
-        let reward_contract = ChunkLinearReward::new(config.reward_address, Arc::new(provider));
+        let reward_contract = if config.policy == PrunePolicy::Reward {
+            let provider = Arc::new(Provider::new(
+                RetryClientBuilder::default()
+                    .rate_limit_retries(config.rate_limit_retries)
+                    .timeout_retries(config.timeout_retries)
+                    .initial_backoff(Duration::from_millis(config.initial_backoff))
+                    .build(
+                        Http::from_str(&config.rpc_endpoint_url)?,
+                        Box::new(HttpRateLimitRetryPolicy),
+                    ),
+            ));
+            Some(ChunkLinearReward::new(
+                config.reward_address,
+                Arc::new(provider),
+            ))
+        } else {
+            None
+        };
         let (tx, rx) = mpsc::unbounded_channel();
         let pruner = Pruner {
             config,
@@ -107,6 +126,11 @@ impl Pruner {
     }
 
     pub async fn start(mut self) -> Result<()> {
+        let reward_contract = match self.reward_contract.take() {
+            Some(contract) => contract,
+            None => return self.start_local().await,
+        };
+
         loop {
             // Check shard config update and prune unneeded data.
             if let Some(delete_list) = self.maybe_update().await? {
@@ -116,7 +140,7 @@ impl Pruner {
             }
 
             // Check no reward chunks and prune.
-            match self.reward_contract.first_rewardable_chunk().call().await {
+            match reward_contract.first_rewardable_chunk().call().await {
                 Ok(new_first_rewardable) => {
                     if let Some(no_reward_list) = self
                         .maybe_forward_first_rewardable(new_first_rewardable)
@@ -149,6 +173,142 @@ impl Pruner {
         }
     }
 
+    /// Prunes by the local policy without any chain dependency, and the shard config is
+    /// never changed.
+    async fn start_local(self) -> Result<()> {
+        info!(policy = ?self.config.policy, "start pruner with local policy");
+        loop {
+            self.store.persist_tx_access().await?;
+            let current_size = self.store.get_num_entries().await?;
+            if current_size >= self.config.start_prune_size() {
+                self.prune_by_local_policy(current_size - self.config.prune_target_size())
+                    .await?;
+            }
+            tokio::time::sleep(self.config.check_time).await;
+        }
+    }
+
+    /// Prunes finalized files in the order of policy until `to_free` entries are deleted.
+    async fn prune_by_local_policy(&self, mut to_free: u64) -> Result<()> {
+        info!(%to_free, "db size exceeds the limit, start pruning");
+        let next_tx_seq = self.store.get_store().next_tx_seq();
+        let candidates = self
+            .config
+            .policy
+            .candidates(&self.store, next_tx_seq)
+            .await?;
+        for tx_seq in candidates {
+            if to_free == 0 {
+                break;
+            }
+            if !self.store.check_tx_completed(tx_seq).await? {
+                continue;
+            }
+            let tx = match self.store.get_tx_by_seq_number(tx_seq).await? {
+                Some(tx) => tx,
+                None => continue,
+            };
+            if self
+                .config
+                .policy
+                .is_retained(&self.store, tx_seq, &tx.stream_ids)
+                .await?
+            {
+                continue;
+            }
+
+            // Batches shared with other files, e.g. files smaller than a batch, are deleted only
+            // after all of these files are pruned.
+            self.store.prune_tx(tx_seq).await?;
+            let batch_list = self
+                .batches_to_prune(tx_seq, tx.start_entry_index, tx.num_entries() as u64)
+                .await?;
+            debug!(%tx_seq, num_batches = batch_list.len(), "prune file");
+            if batch_list.is_empty() {
+                continue;
+            }
+
+            to_free = to_free.saturating_sub((batch_list.len() * PORA_CHUNK_SIZE) as u64);
+            self.prune_in_batch(Box::new(batch_list.into_iter()))
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Returns the batches in shard to delete for the pruned file, including the boundary
+    /// batches shared with other files that are all pruned.
+    async fn batches_to_prune(
+        &self,
+        tx_seq: u64,
+        start_entry_index: u64,
+        num_entries: u64,
+    ) -> Result<Vec<u64>> {
+        let shard_config = &self.config.shard_config;
+        let mut batch_list = policy::batches_of_file(start_entry_index, num_entries, shard_config);
+
+        let end_entry_index = start_entry_index + num_entries;
+        let mut boundaries = vec![];
+        if start_entry_index % PORA_CHUNK_SIZE as u64 != 0 {
+            boundaries.push(start_entry_index / PORA_CHUNK_SIZE as u64);
+        }
+        if end_entry_index % PORA_CHUNK_SIZE as u64 != 0 {
+            boundaries.push(end_entry_index / PORA_CHUNK_SIZE as u64);
+        }
+        boundaries.dedup();
+
+        for batch_index in boundaries {
+            if shard_config.in_range(batch_index)
+                && self.is_batch_pruned(tx_seq, batch_index).await?
+            {
+                batch_list.push(batch_index);
+            }
+        }
+        batch_list.sort_unstable();
+
+        Ok(batch_list)
+    }
+
+    /// Returns `true` if the batch is complete, and files other than `tx_seq` within the batch
+    /// are all pruned.
+    async fn is_batch_pruned(&self, tx_seq: u64, batch_index: u64) -> Result<bool> {
+        let batch_start = batch_index * PORA_CHUNK_SIZE as u64;
+        let batch_end = batch_start + PORA_CHUNK_SIZE as u64;
+        // Later files may be appended into the last batch.
+        if self.store.get_context().await?.1 < batch_end {
+            return Ok(false);
+        }
+
+        // Files are appended into the flow in the order of tx seq.
+        for seq in (0..tx_seq).rev() {
+            let tx = match self.store.get_tx_by_seq_number(seq).await? {
+                Some(tx) => tx,
+                None => break,
+            };
+            if tx.start_entry_index + tx.num_entries() as u64 <= batch_start {
+                break;
+            }
+            if !self.store.check_tx_pruned(seq).await? {
+                return Ok(false);
+            }
+        }
+
+        let next_tx_seq = self.store.get_store().next_tx_seq();
+        for seq in tx_seq + 1..next_tx_seq {
+            let tx = match self.store.get_tx_by_seq_number(seq).await? {
+                Some(tx) => tx,
+                None => break,
+            };
+            if tx.start_entry_index >= batch_end {
+                break;
+            }
+            if !self.store.check_tx_pruned(seq).await? {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
     async fn maybe_update(&mut self) -> Result<Option<Box<dyn Send + Iterator<Item = u64>>>> {
         let current_size = self.store.get_num_entries().await?;
         debug!(
@@ -0,0 +1,256 @@
+use anyhow::Result;
+use ethereum_types::U256;
+use std::collections::HashSet;
+use std::str::FromStr;
+use storage::config::ShardConfig;
+use storage::log_store::log_manager::PORA_CHUNK_SIZE;
+use storage_async::Store;
+
+/// Decides which data to prune when the db size exceeds `max_num_sectors`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PrunePolicy {
+    /// Halves the shard range and prunes data that is no longer rewarded, which requires the
+    /// reward contract.
+    Reward,
+    /// Prunes finalized files in the order of tx seq.
+    OldestFirst,
+    /// Prunes finalized files that are least recently read, and files never read are pruned
+    /// first in the order of tx seq.
+    Lru,
+    /// Prunes finalized files in the order of tx seq, except those retained by stream rules.
+    StreamRetention(Vec<StreamRetention>),
+}
+
+impl PrunePolicy {
+    pub fn from_config(policy: &str, stream_retention: &[String]) -> Result<Self, String> {
+        let policy = match policy {
+            "reward" => PrunePolicy::Reward,
+            "oldest" => PrunePolicy::OldestFirst,
+            "lru" => PrunePolicy::Lru,
+            "stream" => PrunePolicy::StreamRetention(
+                stream_retention
+                    .iter()
+                    .map(|s| s.parse())
+                    .collect::<Result<_, _>>()?,
+            ),
+            _ => return Err(format!("Unknown prune policy: {}", policy)),
+        };
+        Ok(policy)
+    }
+
+    /// Returns the tx seq list of files to prune in order.
+    pub async fn candidates(
+        &self,
+        store: &Store,
+        next_tx_seq: u64,
+    ) -> Result<Box<dyn Send + Iterator<Item = u64>>> {
+        match self {
+            PrunePolicy::Lru => Ok(lru_candidates(
+                next_tx_seq,
+                store.get_tx_access_list().await?,
+            )),
+            _ => Ok(Box::new(0..next_tx_seq)),
+        }
+    }
+
+    /// Returns `true` if the file should not be pruned by stream retention rules.
+    pub async fn is_retained(
+        &self,
+        store: &Store,
+        tx_seq: u64,
+        stream_ids: &[U256],
+    ) -> Result<bool> {
+        let rules = match self {
+            PrunePolicy::StreamRetention(rules) => rules,
+            _ => return Ok(false),
+        };
+
+        for rule in rules.iter().filter(|r| stream_ids.contains(&r.stream_id)) {
+            let keep_latest = match rule.keep_latest {
+                Some(n) => n,
+                None => return Ok(true),
+            };
+            let later_txs = store
+                .get_tx_seq_list_by_stream_id(&rule.stream_id, tx_seq + 1, keep_latest)
+                .await?;
+            if later_txs.len() < keep_latest {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+}
+
+/// Returns files never read in the order of tx seq, and then files in the order of last access
+/// time, where `accessed` is the list of tx seq and last access time in the order of access
+/// time.
+fn lru_candidates(
+    next_tx_seq: u64,
+    mut accessed: Vec<(u64, u64)>,
+) -> Box<dyn Send + Iterator<Item = u64>> {
+    accessed.retain(|(tx_seq, _)| *tx_seq < next_tx_seq);
+    let accessed_set: HashSet<u64> = accessed.iter().map(|(tx_seq, _)| *tx_seq).collect();
+    Box::new(
+        (0..next_tx_seq)
+            .filter(move |tx_seq| !accessed_set.contains(tx_seq))
+            .chain(accessed.into_iter().map(|(tx_seq, _)| tx_seq)),
+    )
+}
+
+/// Retains files tagged with the stream id.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StreamRetention {
+    pub stream_id: U256,
+    /// Number of the latest files of the stream to retain, or all files if `None`.
+    pub keep_latest: Option<usize>,
+}
+
+impl FromStr for StreamRetention {
+    type Err = String;
+
+    /// Parses from `<stream_id>` or `<stream_id>:<keep_latest>`, e.g. `0x1f:100`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (stream_id, keep_latest) = match s.trim().split_once(':') {
+            Some((stream_id, keep_latest)) => (
+                stream_id,
+                Some(
+                    keep_latest
+                        .trim()
+                        .parse::<usize>()
+                        .map_err(|e| format!("Invalid stream retention {}: {:?}", s, e))?,
+                ),
+            ),
+            None => (s.trim(), None),
+        };
+        let stream_id = U256::from_str_radix(stream_id.trim().trim_start_matches("0x"), 16)
+            .map_err(|e| format!("Invalid stream id {}: {:?}", stream_id, e))?;
+        Ok(Self {
+            stream_id,
+            keep_latest,
+        })
+    }
+}
+
+/// Returns the batches within the flow range of a file and the local shard, so that data of
+/// other files that share the boundary batches is not affected.
+pub fn batches_of_file(
+    start_entry_index: u64,
+    num_entries: u64,
+    shard_config: &ShardConfig,
+) -> Vec<u64> {
+    let start_batch = start_entry_index.div_ceil(PORA_CHUNK_SIZE as u64);
+    let end_batch = (start_entry_index + num_entries) / PORA_CHUNK_SIZE as u64;
+    (start_batch..end_batch)
+        .filter(|batch_index| shard_config.in_range(*batch_index))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared_types::{Transaction, CHUNK_SIZE};
+    use std::sync::Arc;
+    use storage::log_store::log_manager::{
+        sub_merkle_tree, tx_subtree_root_list_padded, LogConfig, LogManager,
+    };
+    use storage::log_store::{LogStoreRead, LogStoreWrite};
+    use task_executor::test_utils::TestRuntime;
+
+    fn put_tx(store: &LogManager, seq: u64, stream_ids: Vec<U256>) {
+        let data = vec![seq as u8 + 1; CHUNK_SIZE];
+        let merkle_nodes = tx_subtree_root_list_padded(&data);
+        let flow_len = store.get_context().unwrap().1;
+        let first_subtree_size = 1 << (merkle_nodes.first().unwrap().0 - 1);
+        let tx = Transaction {
+            stream_ids,
+            size: data.len() as u64,
+            data_merkle_root: sub_merkle_tree(&data).unwrap().root().into(),
+            seq,
+            data: vec![],
+            start_entry_index: ((flow_len - 1) / first_subtree_size + 1) * first_subtree_size,
+            merkle_nodes,
+        };
+        store.put_tx(tx).unwrap();
+    }
+
+    #[test]
+    fn test_from_config() {
+        assert_eq!(
+            PrunePolicy::from_config("lru", &[]).unwrap(),
+            PrunePolicy::Lru
+        );
+        assert_eq!(
+            PrunePolicy::from_config("stream", &["0x1f:100".into(), " 2 ".into()]).unwrap(),
+            PrunePolicy::StreamRetention(vec![
+                StreamRetention {
+                    stream_id: U256::from(0x1f),
+                    keep_latest: Some(100),
+                },
+                StreamRetention {
+                    stream_id: U256::from(2),
+                    keep_latest: None,
+                },
+            ])
+        );
+        assert!(PrunePolicy::from_config("stream", &["0x1:x".into()]).is_err());
+        assert!(PrunePolicy::from_config("stream", &["xyz".into()]).is_err());
+        assert!(PrunePolicy::from_config("newest", &[]).is_err());
+    }
+
+    #[test]
+    fn test_lru_candidates() {
+        let accessed = vec![(7, 10), (1, 50), (4, 50), (3, 100)];
+        let candidates: Vec<u64> = lru_candidates(6, accessed).collect();
+        assert_eq!(candidates, vec![0, 2, 5, 1, 4, 3]);
+    }
+
+    #[test]
+    fn test_batches_of_file() {
+        let shard_config = ShardConfig::default();
+        let batch = PORA_CHUNK_SIZE as u64;
+        assert_eq!(batches_of_file(1000, 3000, &shard_config), vec![1, 2]);
+        assert_eq!(batches_of_file(batch, 2 * batch, &shard_config), vec![1, 2]);
+        assert!(batches_of_file(1, batch, &shard_config).is_empty());
+
+        let shard_config = ShardConfig::new(1, 2).unwrap();
+        assert_eq!(batches_of_file(0, 4 * batch, &shard_config), vec![1, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_stream_retention() {
+        let runtime = TestRuntime::default();
+        let (stream_a, stream_b) = (U256::from(1), U256::from(2));
+        let store = LogManager::memorydb(LogConfig::default()).unwrap();
+        put_tx(&store, 0, vec![stream_a]);
+        put_tx(&store, 1, vec![stream_a, stream_b]);
+        put_tx(&store, 2, vec![stream_b]);
+        put_tx(&store, 3, vec![stream_a]);
+        let store = Store::new(Arc::new(store), runtime.task_executor.clone());
+
+        let policy = PrunePolicy::from_config("stream", &["0x1:2".into(), "0x2".into()]).unwrap();
+        let is_retained = |tx_seq, stream_ids: Vec<U256>| {
+            let (policy, store) = (&policy, &store);
+            async move {
+                policy
+                    .is_retained(store, tx_seq, &stream_ids)
+                    .await
+                    .unwrap()
+            }
+        };
+
+        // only the latest 2 files of stream a are retained
+        assert!(!is_retained(0, vec![stream_a]).await);
+        assert!(is_retained(1, vec![stream_a]).await);
+        assert!(is_retained(3, vec![stream_a]).await);
+        // all files of stream b are retained
+        assert!(is_retained(1, vec![stream_a, stream_b]).await);
+        assert!(is_retained(2, vec![stream_b]).await);
+        assert!(!is_retained(4, vec![U256::from(3)]).await);
+
+        assert!(!PrunePolicy::OldestFirst
+            .is_retained(&store, 3, &[stream_a])
+            .await
+            .unwrap());
+    }
+}
@@ -37,6 +37,13 @@ pub struct Config {
     pub batcher_file_capacity: usize,
     /// Number of announcements in a pubsub message
     pub batcher_announcement_capacity: usize,
+
+    // file ranges announcement
+    /// Whether to announce all stored files in tx_seq ranges periodically
+    pub announce_file_ranges_enabled: bool,
+    /// Interval to announce stored files in tx_seq ranges
+    #[serde(deserialize_with = "deserialize_duration")]
+    pub announce_file_ranges_interval: Duration,
 }
 
 impl Default for Config {
@@ -54,6 +61,9 @@ impl Default for Config {
             batcher_timeout: Duration::from_secs(1),
             batcher_file_capacity: 1,
             batcher_announcement_capacity: 1,
+
+            announce_file_ranges_enabled: false,
+            announce_file_ranges_interval: Duration::from_secs(600),
         }
     }
 }
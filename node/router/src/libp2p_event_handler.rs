@@ -9,8 +9,8 @@ use network::types::TimedMessage;
 use network::{
     rpc::StatusMessage,
     types::{
-        AnnounceChunks, AnnounceFile, FindChunks, FindFile, HasSignature, SignedAnnounceFile,
-        SignedMessage,
+        AnnounceChunks, AnnounceFile, AnnounceFileRanges, FindChunks, FindFile, HasSignature,
+        SignedAnnounceFile, SignedAnnounceFileRanges, SignedMessage, TxSeqRange,
     },
     Keypair, MessageAcceptance, MessageId, NetworkGlobals, NetworkMessage, PeerId, PeerRequestId,
     PublicKey, PubsubMessage, Request, RequestId, Response,
@@ -36,6 +36,10 @@ lazy_static::lazy_static! {
     pub static ref TOLERABLE_DRIFT: chrono::Duration = chrono::Duration::seconds(10);
 }
 
+/// Maximum number of tx_seq ranges in an `AnnounceFileRanges` message, so that the message
+/// size is bounded if files are stored sparsely.
+const MAX_ANNOUNCED_FILE_RANGES: usize = 4096;
+
 fn duration_since(timestamp: u32, latency_ms: Arc<dyn ::metrics::Histogram>) -> chrono::Duration {
     let timestamp = i64::from(timestamp);
     let timestamp = chrono::DateTime::from_timestamp(timestamp, 0).expect("should fit");
@@ -159,6 +163,15 @@ impl Libp2pEventHandler {
             "announcement",
         ));
 
+        // shard configs of known peers restored after restart
+        for (peer_id, info) in network_globals.peers.read().peers() {
+            if let Some(shard_config) = info.shard_config() {
+                if let Ok(v) = ShardConfig::try_from(*shard_config) {
+                    file_location_cache.insert_peer_config(*peer_id, v);
+                }
+            }
+        }
+
         Self {
             config,
             network_globals,
@@ -219,6 +232,19 @@ impl Libp2pEventHandler {
     pub async fn on_peer_connected(&self, peer_id: PeerId, outgoing: bool) {
         self.peers.write().await.add(peer_id, outgoing);
 
+        // shard config published in ENR is available before status exchanged
+        if self.file_location_cache.get_peer_config(&peer_id).is_none() {
+            let shard_config = self
+                .network_globals
+                .peers
+                .read()
+                .peer_info(&peer_id)
+                .and_then(|info| info.shard_config().copied());
+            if let Some(v) = shard_config.and_then(|v| ShardConfig::try_from(v).ok()) {
+                self.file_location_cache.insert_peer_config(peer_id, v);
+            }
+        }
+
         if outgoing {
             self.send_status(peer_id);
             metrics::LIBP2P_HANDLE_PEER_CONNECTED_OUTGOING.mark(1);
@@ -227,6 +253,45 @@ impl Libp2pEventHandler {
         }
     }
 
+    /// Notifies the sync layer of providers found in the DHT, which store the file of the
+    /// given shard config.
+    pub fn on_providers_found(
+        &self,
+        tx_id: TxID,
+        shard_config: shared_types::ShardConfig,
+        providers: Vec<(PeerId, Vec<Multiaddr>)>,
+    ) {
+        let shard_config = match ShardConfig::try_from(shard_config) {
+            Ok(v) => v,
+            Err(_) => return,
+        };
+
+        for (peer_id, addrs) in providers {
+            // make sure peer_id is part of the address
+            let mut addr = match addrs.into_iter().next() {
+                Some(v) => v,
+                None => continue,
+            };
+            if !matches!(addr.iter().last(), Some(Protocol::P2p(_))) {
+                addr.push(Protocol::P2p(peer_id.into()));
+            }
+
+            // peers that provide the file store data of at least the shard in provider record
+            if self.file_location_cache.get_peer_config(&peer_id).is_none() {
+                self.file_location_cache
+                    .insert_peer_config(peer_id, shard_config);
+            }
+
+            self.send_to_sync(SyncMessage::AnnounceFileGossip {
+                tx_id,
+                peer_id,
+                addr,
+            });
+        }
+
+        metrics::LIBP2P_HANDLE_PROVIDERS_FOUND.mark(1);
+    }
+
     pub async fn on_peer_disconnected(&self, peer_id: PeerId) {
         self.peers.write().await.remove(&peer_id);
         self.send_to_sync(SyncMessage::PeerDisconnected { peer_id });
@@ -254,9 +319,34 @@ impl Libp2pEventHandler {
                 });
                 metrics::LIBP2P_HANDLE_GET_CHUNKS_REQUEST.mark(1);
             }
+            Request::GetErasurePiece(request) => {
+                self.send_to_sync(SyncMessage::RequestErasurePiece {
+                    peer_id,
+                    request_id,
+                    request,
+                });
+            }
+            Request::StorageChallenge(request) => {
+                self.send_to_sync(SyncMessage::RequestStorageChallenge {
+                    peer_id,
+                    request_id,
+                    request,
+                });
+            }
+            Request::GetTransactions(request) => {
+                self.send_to_sync(SyncMessage::RequestTransactions {
+                    peer_id,
+                    request_id,
+                    request,
+                });
+            }
             Request::AnswerFile(file) => match ShardConfig::try_from(file.shard_config) {
                 Ok(v) => {
                     self.file_location_cache.insert_peer_config(peer_id, v);
+                    self.network_globals
+                        .peers
+                        .write()
+                        .update_shard_config(&peer_id, v.into());
 
                     self.send_to_sync(SyncMessage::AnswerFile { peer_id, file });
                 }
@@ -340,6 +430,42 @@ impl Libp2pEventHandler {
                     response,
                 });
             }
+            Response::ErasurePiece(response) => {
+                let request_id = match request_id {
+                    RequestId::Sync(_, sync_id) => sync_id,
+                    _ => unreachable!("All ErasurePiece responses belong to sync"),
+                };
+
+                self.send_to_sync(SyncMessage::ErasurePieceResponse {
+                    peer_id,
+                    request_id,
+                    response,
+                });
+            }
+            Response::StorageChallenge(response) => {
+                let request_id = match request_id {
+                    RequestId::Sync(_, sync_id) => sync_id,
+                    _ => unreachable!("All StorageChallenge responses belong to sync"),
+                };
+
+                self.send_to_sync(SyncMessage::StorageChallengeResponse {
+                    peer_id,
+                    request_id,
+                    response,
+                });
+            }
+            Response::Transactions(response) => {
+                let request_id = match request_id {
+                    RequestId::Sync(_, sync_id) => sync_id,
+                    _ => unreachable!("All Transactions responses belong to sync"),
+                };
+
+                self.send_to_sync(SyncMessage::TransactionsResponse {
+                    peer_id,
+                    request_id,
+                    response,
+                });
+            }
             Response::DataByHash(_) => {
                 // ignore
             }
@@ -390,6 +516,9 @@ impl Libp2pEventHandler {
 
                 MessageAcceptance::Accept
             }
+            PubsubMessage::AnnounceFileRanges(msg) => {
+                self.on_announce_file_ranges(propagation_source, msg)
+            }
             PubsubMessage::AnnounceChunks(msg) => self.on_announce_chunks(propagation_source, msg),
             PubsubMessage::AnnounceShardConfig(msg) => {
                 self.on_announce_shard_config(propagation_source, source, msg)
@@ -593,6 +722,66 @@ impl Libp2pEventHandler {
         Some(signed)
     }
 
+    pub async fn construct_announce_file_ranges_message(
+        &self,
+        mut ranges: Vec<TxSeqRange>,
+    ) -> Option<SignedAnnounceFileRanges> {
+        if ranges.is_empty() {
+            return None;
+        }
+
+        // announce the latest files if too many ranges
+        if ranges.len() > MAX_ANNOUNCED_FILE_RANGES {
+            ranges.drain(..ranges.len() - MAX_ANNOUNCED_FILE_RANGES);
+        }
+
+        let peer_id = *self.network_globals.peer_id.read();
+        let addr = self.construct_announced_ip().await?;
+        let shard_config = self.store.get_store().get_shard_config();
+
+        let msg = TimedMessage::from(AnnounceFileRanges {
+            ranges,
+            shard_config: shard_config.into(),
+            peer_id: peer_id.into(),
+            at: addr.into(),
+        });
+
+        match SignedMessage::sign_message(msg, &self.local_keypair) {
+            Ok(signed) => Some(signed),
+            Err(e) => {
+                error!(%e, "Failed to sign AnnounceFileRanges message");
+                None
+            }
+        }
+    }
+
+    /// Announces all finalized files in tx_seq ranges.
+    pub async fn publish_file_ranges(&self) {
+        let ranges = match self.store.get_finalized_tx_ranges().await {
+            Ok(v) => v,
+            Err(e) => {
+                warn!(%e, "Failed to get finalized tx ranges");
+                return;
+            }
+        };
+
+        let ranges: Vec<TxSeqRange> = ranges
+            .into_iter()
+            .map(|range| TxSeqRange {
+                start: range.start,
+                end: range.end,
+            })
+            .collect();
+        debug!(
+            num_ranges = ranges.len(),
+            "Publish AnnounceFileRanges message"
+        );
+
+        if let Some(msg) = self.construct_announce_file_ranges_message(ranges).await {
+            self.publish(PubsubMessage::AnnounceFileRanges(msg));
+        }
+    }
+
     async fn on_find_file(&self, from: PeerId, msg: TimedMessage<FindFile>) -> MessageAcceptance {
         // verify timestamp
         if !metrics::LIBP2P_HANDLE_PUBSUB_FIND_FILE.verify_timestamp(
@@ -835,6 +1024,56 @@ impl Libp2pEventHandler {
         MessageAcceptance::Accept
     }
 
+    fn on_announce_file_ranges(
+        &self,
+        propagation_source: PeerId,
+        msg: SignedAnnounceFileRanges,
+    ) -> MessageAcceptance {
+        // verify timestamp
+        if !metrics::LIBP2P_HANDLE_PUBSUB_ANNOUNCE_FILE_RANGES.verify_timestamp(
+            propagation_source,
+            msg.timestamp,
+            *PUBSUB_TIMEOUT_NETWORK,
+            None,
+        ) {
+            return MessageAcceptance::Ignore;
+        }
+
+        // verify message signature
+        if !verify_signature(&msg, &msg.peer_id, propagation_source) {
+            return MessageAcceptance::Reject;
+        }
+
+        if msg.ranges.len() > MAX_ANNOUNCED_FILE_RANGES || !msg.is_valid() {
+            return MessageAcceptance::Reject;
+        }
+
+        // verify public ip address if required
+        let addr = msg.at.clone().into();
+        if !self.config.private_ip_enabled && !Self::contains_public_ip(&addr) {
+            return MessageAcceptance::Reject;
+        }
+
+        // verify announced ip address if required
+        if !self.config.private_ip_enabled
+            && self.config.check_announced_ip
+            && !self.verify_announced_address(&msg.peer_id, &addr)
+        {
+            return MessageAcceptance::Reject;
+        }
+
+        // verify announced shard config
+        if ShardConfig::try_from(msg.shard_config).is_err() {
+            return MessageAcceptance::Reject;
+        }
+
+        // the sync layer verifies the announcement by a storage challenge before inserting it
+        // into the file location cache
+        self.send_to_sync(SyncMessage::AnnounceFileRangesGossip { msg });
+
+        MessageAcceptance::Accept
+    }
+
     fn on_announce_shard_config(
         &self,
         propagation_source: PeerId,
@@ -859,6 +1098,10 @@ impl Libp2pEventHandler {
         // insert message to cache
         self.file_location_cache
             .insert_peer_config(source, shard_config);
+        self.network_globals
+            .peers
+            .write()
+            .update_shard_config(&source, shard_config.into());
 
         // notify sync layer
         self.send_to_sync(SyncMessage::AnnounceShardConfig {
@@ -938,8 +1181,14 @@ impl Libp2pEventHandler {
 
         self.file_location_cache
             .insert_peer_config(peer_id, peer_shard_config);
+        self.network_globals
+            .peers
+            .write()
+            .update_shard_config(&peer_id, peer_shard_config.into());
 
-        if !peer_shard_config.intersect(shard_config) {
+        // In erasure coding mode, peers of other shard groups hold the erasure pieces.
+        let erasure_enabled = self.store.get_store().get_erasure_config().is_some();
+        if !erasure_enabled && !peer_shard_config.intersect(shard_config) {
             info!(%peer_id, ?shard_config, ?status, "Report peer with mismatched shard config");
             self.send_to_network(NetworkMessage::ReportPeer {
                 peer_id,
@@ -1081,6 +1330,7 @@ mod tests {
                 vec![],
                 Default::default(),
                 Default::default(),
+                Default::default(),
             );
 
             let listen_addr: Multiaddr = "/ip4/127.0.0.1/tcp/30000".parse().unwrap();
@@ -1462,4 +1712,53 @@ mod tests {
         // ensure cache updated
         assert_eq!(ctx.file_location_cache.get_all(tx).len(), 1);
     }
+
+    #[tokio::test]
+    async fn test_on_pubsub_announce_file_ranges() {
+        let mut ctx = Context::default();
+        let handler = ctx.new_handler();
+
+        let (alice, bob) = (PeerId::random(), PeerId::random());
+        let id = MessageId::new(b"dummy message");
+        let ranges = vec![
+            TxSeqRange { start: 0, end: 10 },
+            TxSeqRange { start: 20, end: 30 },
+        ];
+        let message = handler
+            .construct_announce_file_ranges_message(ranges.clone())
+            .await
+            .unwrap();
+
+        // overlapped ranges rejected
+        let mut invalid_ranges = ranges;
+        invalid_ranges.push(TxSeqRange { start: 25, end: 40 });
+        let invalid = handler
+            .construct_announce_file_ranges_message(invalid_ranges)
+            .await
+            .unwrap();
+        let result = handler
+            .on_pubsub_message(alice, bob, &id, PubsubMessage::AnnounceFileRanges(invalid))
+            .await;
+        assert!(matches!(result, MessageAcceptance::Reject));
+
+        // succeeded to handle
+        let result = handler
+            .on_pubsub_message(alice, bob, &id, PubsubMessage::AnnounceFileRanges(message))
+            .await;
+        assert!(matches!(result, MessageAcceptance::Accept));
+
+        // ensure notify to sync layer to verify before caching
+        match ctx.sync_recv.try_recv() {
+            Ok(Notification(SyncMessage::AnnounceFileRangesGossip { msg })) => {
+                assert_eq!(msg.ranges.len(), 2);
+                assert_eq!(*msg.peer_id, *ctx.network_globals.peer_id.read());
+            }
+            Ok(_) => panic!("Unexpected sync message type received"),
+            Err(e) => panic!("No sync message received: {:?}", e),
+        }
+        assert!(ctx
+            .file_location_cache
+            .get_all(TxID::random_hash(5))
+            .is_empty());
+    }
 }
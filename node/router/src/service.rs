@@ -15,6 +15,8 @@ use network::{
 use network::{MessageAcceptance, PeerAction, PeerId, ReportSource};
 use pruner::PrunerMessage;
 use shared_types::ShardedFile;
+use std::collections::VecDeque;
+use std::ops::Range;
 use std::sync::Arc;
 use std::time::Duration;
 use storage::log_store::Store as LogStore;
@@ -25,6 +27,9 @@ use tokio::sync::mpsc::UnboundedSender;
 use tokio::sync::{broadcast, mpsc, RwLock};
 use tokio::time::interval;
 
+/// Maximum number of provider records of stored files to publish in the DHT per second.
+const PROVIDE_FILES_BATCH_SIZE: u64 = 1000;
+
 /// Service that handles communication between internal services and the libp2p service.
 pub struct RouterService {
     config: Config,
@@ -53,6 +58,12 @@ pub struct RouterService {
 
     store: Arc<dyn LogStore>,
 
+    async_store: Store,
+
+    /// Finalized files in store that provider records are not published for yet, which are
+    /// published in batches to not flood the DHT.
+    unprovided_files: (shared_types::ShardConfig, VecDeque<Range<u64>>),
+
     pubsub_rate_limiter: PubsubRateLimiter,
 }
 
@@ -81,10 +92,13 @@ impl RouterService {
             .limit_by_topic(GossipKind::AskFile, 50, Duration::from_secs(10))?
             .limit_by_topic(GossipKind::FindFile, 10, Duration::from_secs(10))?
             .limit_by_topic(GossipKind::AnnounceFile, 10, Duration::from_secs(10))?
+            .limit_by_topic(GossipKind::AnnounceFileRanges, 10, Duration::from_secs(10))?
             .limit_by_topic(GossipKind::FindChunks, 10, Duration::from_secs(10))?
             .limit_by_topic(GossipKind::AnnounceChunks, 10, Duration::from_secs(10))?
             .limit_by_topic(GossipKind::AnnounceShardConfig, 50, Duration::from_secs(10))?;
 
+        let async_store = Store::new(store.clone(), executor.clone());
+
         // create the network service and spawn the task
         let router = RouterService {
             config: config.clone(),
@@ -100,12 +114,14 @@ impl RouterService {
                 sync_send,
                 chunk_pool_send,
                 local_keypair,
-                Store::new(store.clone(), executor.clone()),
+                async_store.clone(),
                 file_location_cache,
                 peers,
             ),
             upnp_mappings: (None, None),
             store,
+            async_store,
+            unprovided_files: Default::default(),
             pubsub_rate_limiter,
         };
 
@@ -121,6 +137,12 @@ impl RouterService {
         let mut heartbeat_service = interval(self.config.heartbeat_interval);
         let mut heartbeat_batcher = interval(self.config.batcher_timeout);
         let mut heartbeat_rate_limiter = interval(Duration::from_secs(30));
+        let mut heartbeat_file_ranges = interval(self.config.announce_file_ranges_interval);
+        let mut heartbeat_provide_files = interval(Duration::from_secs(1));
+
+        // provider records are not persisted, so publish them again for stored files
+        self.provide_stored_files(self.store.get_shard_config().into())
+            .await;
 
         loop {
             tokio::select! {
@@ -139,6 +161,16 @@ impl RouterService {
                 _ = heartbeat_batcher.tick() => self.libp2p_event_handler.expire_batcher().await,
 
                 _ = heartbeat_rate_limiter.tick() => self.pubsub_rate_limiter.prune(),
+
+                // heartbeat for publishing provider records of stored files
+                _ = heartbeat_provide_files.tick(), if !self.unprovided_files.1.is_empty() => {
+                    self.provide_next_files()
+                }
+
+                // heartbeat for announcing stored files in ranges
+                _ = heartbeat_file_ranges.tick(), if self.config.announce_file_ranges_enabled => {
+                    self.libp2p_event_handler.publish_file_ranges().await
+                }
             }
         }
     }
@@ -206,6 +238,14 @@ impl RouterService {
                 BehaviourEvent::StatusPeer(peer_id) => {
                     self.libp2p_event_handler.send_status(peer_id);
                 }
+                BehaviourEvent::ProvidersFound {
+                    tx_id,
+                    shard_config,
+                    providers,
+                } => {
+                    self.libp2p_event_handler
+                        .on_providers_found(tx_id, shard_config, providers);
+                }
                 BehaviourEvent::PubsubMessage {
                     id,
                     propagation_source,
@@ -367,6 +407,25 @@ impl RouterService {
                     };
                 }
             }
+            NetworkMessage::DiscoverShardPeers {
+                shard_config,
+                num_peers,
+            } => {
+                metrics::SERVICE_ROUTE_NETWORK_MESSAGE_DISCOVER_SHARD_PEERS.mark(1);
+                self.libp2p
+                    .swarm
+                    .behaviour_mut()
+                    .discovery_mut()
+                    .discover_shard_peers(shard_config, num_peers);
+            }
+            NetworkMessage::FindProviders { tx_id } => {
+                metrics::SERVICE_ROUTE_NETWORK_MESSAGE_FIND_PROVIDERS.mark(1);
+                let shard_config = self.store.get_shard_config().into();
+                self.libp2p
+                    .swarm
+                    .behaviour_mut()
+                    .find_providers(tx_id, shard_config);
+            }
             NetworkMessage::DisconnectPeer { peer_id } => {
                 self.disconnect_peer(peer_id);
             }
@@ -376,7 +435,9 @@ impl RouterService {
                     shard_config: self.store.get_shard_config().into(),
                 };
                 let msg = PubsubMessage::NewFile(new_file.into());
-                self.libp2p.swarm.behaviour_mut().publish(vec![msg]);
+                let behaviour = self.libp2p.swarm.behaviour_mut();
+                behaviour.publish(vec![msg]);
+                behaviour.provide_file(tx_id.seq, new_file.shard_config);
                 metrics::SERVICE_ROUTE_NETWORK_MESSAGE_ANNOUNCE_LOCAL_FILE.mark(1);
                 debug!(?new_file, "Publish NewFile message");
             }
@@ -420,8 +481,59 @@ impl RouterService {
                     .send_to_chunk_pool(ChunkPoolMessage::ChangeShardConfig(shard_config));
 
                 let shard_config = shared_types::ShardConfig::from(shard_config);
+                if let Err(e) = self
+                    .libp2p
+                    .swarm
+                    .behaviour_mut()
+                    .discovery_mut()
+                    .update_enr_shard_config(shard_config)
+                {
+                    warn!(error = %e, "Failed to update ENR");
+                }
                 self.libp2p_event_handler
                     .publish(PubsubMessage::AnnounceShardConfig(shard_config.into()));
+
+                self.libp2p
+                    .swarm
+                    .behaviour_mut()
+                    .update_provider_shard_config(shard_config);
+                self.provide_stored_files(shard_config).await;
+            }
+        }
+    }
+
+    /// Schedules to publish provider records of all finalized files in store to the DHT.
+    async fn provide_stored_files(&mut self, shard_config: shared_types::ShardConfig) {
+        if !self.libp2p.swarm.behaviour().dht_enabled() {
+            return;
+        }
+
+        match self.async_store.get_finalized_tx_ranges().await {
+            Ok(ranges) => self.unprovided_files = (shard_config, ranges.into()),
+            Err(e) => warn!(error = %e, "Failed to get finalized files to provide in DHT"),
+        }
+    }
+
+    /// Publishes provider records of the next batch of stored files.
+    fn provide_next_files(&mut self) {
+        let (shard_config, ranges) = &mut self.unprovided_files;
+        let behaviour = self.libp2p.swarm.behaviour_mut();
+        let mut budget = PROVIDE_FILES_BATCH_SIZE;
+
+        while let Some(range) = ranges.front_mut() {
+            if budget == 0 {
+                break;
+            }
+
+            let end = range.end.min(range.start + budget);
+            for tx_seq in range.start..end {
+                behaviour.provide_file(tx_seq, *shard_config);
+            }
+            budget -= end - range.start;
+            range.start = end;
+
+            if range.is_empty() {
+                ranges.pop_front();
             }
         }
     }
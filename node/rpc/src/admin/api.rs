@@ -27,6 +27,10 @@ pub trait Rpc {
     #[method(name = "terminateSync")]
     async fn terminate_sync(&self, tx_seq: u64) -> RpcResult<bool>;
 
+    /// Rebuild the specified batch from erasure coded pieces of peers.
+    #[method(name = "rebuildBatch")]
+    async fn rebuild_batch(&self, batch_index: u64) -> RpcResult<()>;
+
     #[method(name = "getSyncServiceState")]
     async fn get_sync_service_state(&self) -> RpcResult<SyncServiceState>;
 
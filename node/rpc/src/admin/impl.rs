@@ -5,7 +5,7 @@ use futures::prelude::*;
 use jsonrpsee::core::async_trait;
 use jsonrpsee::core::RpcResult;
 use metrics::{DEFAULT_GROUPING_REGISTRY, DEFAULT_REGISTRY};
-use network::{multiaddr::Protocol, Multiaddr};
+use network::multiaddr::Protocol;
 use std::collections::{BTreeMap, HashMap};
 use std::net::IpAddr;
 use storage::config::all_shards_available;
@@ -115,6 +115,26 @@ impl RpcServer for RpcServerImpl {
         }
     }
 
+    async fn rebuild_batch(&self, batch_index: u64) -> RpcResult<()> {
+        info!("admin_rebuildBatch({batch_index})");
+
+        let response = self
+            .ctx
+            .request_sync(SyncRequest::RebuildBatch { batch_index })
+            .await?;
+
+        match response {
+            SyncResponse::RebuildBatch { err } => {
+                if err.is_empty() {
+                    Ok(())
+                } else {
+                    Err(error::internal_error(err))
+                }
+            }
+            _ => Err(error::internal_error("unexpected response type")),
+        }
+    }
+
     async fn get_sync_service_state(&self) -> RpcResult<SyncServiceState> {
         info!("admin_getSyncServiceState()");
 
@@ -173,6 +193,7 @@ impl RpcServer for RpcServerImpl {
             connected_peers,
             connected_outgoing_peers,
             connected_incoming_peers: connected_peers - connected_outgoing_peers,
+            bandwidth: self.ctx.network_globals.bandwidth.read().info(),
         })
     }
 
@@ -207,10 +228,10 @@ impl RpcServer for RpcServerImpl {
             .file_location_cache
             .get_all(tx.id())
             .iter()
-            .map(|announcement| {
-                let multiaddr: Multiaddr = announcement.at.clone().into();
+            .map(|location| {
                 let found_ip: Option<IpAddr> =
-                    multiaddr
+                    location
+                        .at
                         .iter()
                         .fold(None, |found_ip, protocol| match protocol {
                             Protocol::Ip4(ip) => Some(ip.into()),
@@ -222,7 +243,7 @@ impl RpcServer for RpcServerImpl {
                     found_ip,
                     self.ctx
                         .file_location_cache
-                        .get_peer_config(&announcement.peer_id.clone().into()),
+                        .get_peer_config(&location.peer_id),
                 )
             })
             .filter(|(found_ip, shard_config)| shard_config.is_some() && found_ip.is_some())
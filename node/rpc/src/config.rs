@@ -8,6 +8,10 @@ pub struct Config {
     pub enabled: bool,
     pub listen_address: SocketAddr,
     pub listen_address_admin: SocketAddr,
+    /// WebSocket server address to bind for public RPC and subscriptions, disabled if `None`.
+    pub listen_address_ws: Option<SocketAddr>,
+    /// HTTP server address to bind for whole file download, disabled if `None`.
+    pub listen_address_file: Option<SocketAddr>,
     pub chunks_per_segment: usize,
     pub max_request_body_size: u32,
     pub max_cache_file_size: usize,
@@ -19,6 +23,8 @@ impl Default for Config {
             enabled: true,
             listen_address: SocketAddr::from_str("0.0.0.0:5678").unwrap(),
             listen_address_admin: SocketAddr::from_str("127.0.0.1:5679").unwrap(),
+            listen_address_ws: None,
+            listen_address_file: None,
             chunks_per_segment: 1024,
             max_request_body_size: 100 * 1024 * 1024, // 100MB
             max_cache_file_size: 10 * 1024 * 1024,    // 10MB
@@ -0,0 +1,275 @@
+//! HTTP server to download a whole file, which is reassembled from segments and verified
+//! against the file merkle root on server side.
+//!
+//! Route: `GET /file/{tx_seq_or_root}`, supports a single `Range: bytes=...` header.
+
+use crate::types::SegmentWithProof;
+use crate::{zgs, Context};
+use futures::future::BoxFuture;
+use futures::stream::{self, Stream};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{header, Body, Method, Request, Response, Server, StatusCode};
+use shared_types::{Transaction, CHUNK_SIZE};
+use std::convert::Infallible;
+use std::error::Error;
+use std::io;
+use std::str::FromStr;
+use std::sync::Arc;
+use storage::error::Result;
+use storage::log_store::tx_store::TxStatus;
+use storage::H256;
+
+const FILE_PATH_PREFIX: &str = "/file/";
+
+/// Run a HTTP server to download files if configured.
+pub fn run_file_server(
+    ctx: Context,
+) -> std::result::Result<Option<BoxFuture<'static, ()>>, Box<dyn Error>> {
+    let listen_address = match ctx.config.listen_address_file {
+        Some(addr) => addr,
+        None => return Ok(None),
+    };
+
+    let make_service = make_service_fn(move |_conn| {
+        let ctx = ctx.clone();
+        async move { Ok::<_, Infallible>(service_fn(move |req| handle_request(ctx.clone(), req))) }
+    });
+
+    let server = Server::try_bind(&listen_address)?.serve(make_service);
+
+    info!(%listen_address, "File server started");
+
+    Ok(Some(Box::pin(async move {
+        if let Err(e) = server.await {
+            error!(%e, "File server terminated");
+        }
+    })))
+}
+
+async fn handle_request(
+    ctx: Context,
+    req: Request<Body>,
+) -> std::result::Result<Response<Body>, Infallible> {
+    if req.method() != Method::GET {
+        return Ok(text_response(
+            StatusCode::METHOD_NOT_ALLOWED,
+            "only GET is supported",
+        ));
+    }
+
+    let id = match req.uri().path().strip_prefix(FILE_PATH_PREFIX) {
+        Some(id) if !id.is_empty() => id.to_string(),
+        _ => return Ok(text_response(StatusCode::NOT_FOUND, "route not found")),
+    };
+
+    let range = req
+        .headers()
+        .get(header::RANGE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string());
+
+    debug!(%id, ?range, "Download file");
+
+    match download_file(ctx, &id, range.as_deref()).await {
+        Ok(resp) => Ok(resp),
+        Err(e) => {
+            warn!(%id, %e, "Failed to download file");
+            Ok(text_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to read file",
+            ))
+        }
+    }
+}
+
+async fn download_file(ctx: Context, id: &str, range: Option<&str>) -> Result<Response<Body>> {
+    let maybe_tx = if id.starts_with("0x") {
+        match H256::from_str(id) {
+            Ok(root) => ctx.log_store.get_tx_by_data_root(&root).await?,
+            Err(_) => return Ok(text_response(StatusCode::BAD_REQUEST, "invalid file root")),
+        }
+    } else {
+        match id.parse::<u64>() {
+            Ok(tx_seq) => ctx.log_store.get_tx_by_seq_number(tx_seq).await?,
+            Err(_) => return Ok(text_response(StatusCode::BAD_REQUEST, "invalid tx seq")),
+        }
+    };
+
+    let tx = match maybe_tx {
+        Some(tx) => tx,
+        None => return Ok(text_response(StatusCode::NOT_FOUND, "file not found")),
+    };
+
+    match ctx.log_store.get_store().get_tx_status(tx.seq)? {
+        Some(TxStatus::Finalized) => {}
+        Some(TxStatus::Pruned) => return Ok(text_response(StatusCode::GONE, "file pruned")),
+        None => return Ok(text_response(StatusCode::NOT_FOUND, "file not finalized")),
+    }
+
+    let file_size = tx.size;
+    if file_size == 0 {
+        return Ok(Response::builder()
+            .header(header::ACCEPT_RANGES, "bytes")
+            .header(header::CONTENT_LENGTH, 0)
+            .body(Body::empty())?);
+    }
+
+    let builder = Response::builder()
+        .header(header::CONTENT_TYPE, "application/octet-stream")
+        .header(header::ACCEPT_RANGES, "bytes");
+
+    let (start, end, builder) = match range.map(|v| parse_range(v, file_size)) {
+        None | Some(ByteRange::Ignored) => (0, file_size - 1, builder.status(StatusCode::OK)),
+        Some(ByteRange::Satisfiable(start, end)) => (
+            start,
+            end,
+            builder.status(StatusCode::PARTIAL_CONTENT).header(
+                header::CONTENT_RANGE,
+                format!("bytes {}-{}/{}", start, end, file_size),
+            ),
+        ),
+        Some(ByteRange::Unsatisfiable) => {
+            return Ok(Response::builder()
+                .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header(header::CONTENT_RANGE, format!("bytes */{}", file_size))
+                .body(Body::empty())?)
+        }
+    };
+
+    let body = Body::wrap_stream(file_stream(ctx, tx, start, end));
+
+    Ok(builder
+        .header(header::CONTENT_LENGTH, end - start + 1)
+        .body(body)?)
+}
+
+/// Streams the file bytes within `[start, end]` segment by segment, with padding stripped.
+fn file_stream(
+    ctx: Context,
+    tx: Transaction,
+    start: u64,
+    end: u64,
+) -> impl Stream<Item = io::Result<Vec<u8>>> {
+    let chunks_per_segment = ctx.config.chunks_per_segment;
+    let segment_size = (chunks_per_segment * CHUNK_SIZE) as u64;
+    let server = Arc::new(zgs::RpcServerImpl { ctx });
+    let first_segment = (start / segment_size) as usize;
+    let last_segment = (end / segment_size) as usize;
+
+    stream::try_unfold(first_segment, move |index| {
+        let server = server.clone();
+        let tx = tx.clone();
+        async move {
+            if index > last_segment {
+                return Ok(None);
+            }
+
+            let segment = server
+                .get_segment_with_proof_by_tx(tx, index)
+                .await
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "segment missing"))?;
+
+            verify_segment(&segment, chunks_per_segment)?;
+
+            // Strip the padding and bytes out of the requested range.
+            let segment_start = index as u64 * segment_size;
+            let from = start.saturating_sub(segment_start) as usize;
+            let to = (end + 1 - segment_start).min(segment.data.len() as u64) as usize;
+            let mut data = segment.data;
+            data.truncate(to);
+            data.drain(..from);
+
+            Ok(Some((data, index + 1)))
+        }
+    })
+}
+
+fn verify_segment(segment: &SegmentWithProof, chunks_per_segment: usize) -> io::Result<()> {
+    segment.validate(chunks_per_segment).map_err(|e| {
+        error!(root = %segment.root, index = %segment.index, %e, "Invalid segment in store");
+        io::Error::new(io::ErrorKind::InvalidData, "segment verification failed")
+    })
+}
+
+fn text_response(status: StatusCode, msg: &'static str) -> Response<Body> {
+    let mut resp = Response::new(Body::from(msg));
+    *resp.status_mut() = status;
+    resp
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum ByteRange {
+    /// Inclusive byte range within the file.
+    Satisfiable(u64, u64),
+    Unsatisfiable,
+    /// Malformed or multiple ranges, which should be ignored to respond the whole file.
+    Ignored,
+}
+
+/// Parses the `Range` header value, e.g. `bytes=0-499`, `bytes=500-` or `bytes=-500`.
+fn parse_range(value: &str, file_size: u64) -> ByteRange {
+    let spec = match value.trim().strip_prefix("bytes=") {
+        Some(spec) if !spec.contains(',') => spec.trim(),
+        _ => return ByteRange::Ignored,
+    };
+
+    let (first, last) = match spec.split_once('-') {
+        Some(v) => v,
+        None => return ByteRange::Ignored,
+    };
+
+    let (start, end) = match (first.parse::<u64>(), last.parse::<u64>()) {
+        (Ok(start), Ok(end)) if start <= end => (start, end.min(file_size - 1)),
+        (Ok(start), Err(_)) if last.is_empty() => (start, file_size - 1),
+        (Err(_), Ok(suffix)) if first.is_empty() => {
+            if suffix == 0 {
+                return ByteRange::Unsatisfiable;
+            }
+            (file_size.saturating_sub(suffix), file_size - 1)
+        }
+        _ => return ByteRange::Ignored,
+    };
+
+    if start >= file_size {
+        return ByteRange::Unsatisfiable;
+    }
+
+    ByteRange::Satisfiable(start, end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_range, ByteRange};
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(
+            parse_range("bytes=0-499", 1000),
+            ByteRange::Satisfiable(0, 499)
+        );
+        assert_eq!(
+            parse_range("bytes=500-", 1000),
+            ByteRange::Satisfiable(500, 999)
+        );
+        assert_eq!(
+            parse_range("bytes=-100", 1000),
+            ByteRange::Satisfiable(900, 999)
+        );
+        assert_eq!(
+            parse_range("bytes=-2000", 1000),
+            ByteRange::Satisfiable(0, 999)
+        );
+        assert_eq!(
+            parse_range("bytes=900-2000", 1000),
+            ByteRange::Satisfiable(900, 999)
+        );
+
+        assert_eq!(parse_range("bytes=1000-", 1000), ByteRange::Unsatisfiable);
+        assert_eq!(parse_range("bytes=-0", 1000), ByteRange::Unsatisfiable);
+
+        assert_eq!(parse_range("bytes=500-100", 1000), ByteRange::Ignored);
+        assert_eq!(parse_range("bytes=0-1,5-9", 1000), ByteRange::Ignored);
+        assert_eq!(parse_range("items=0-1", 1000), ByteRange::Ignored);
+    }
+}
@@ -0,0 +1,35 @@
+use crate::types::{KeyValue, KeyValuePage, KvKey};
+use jsonrpsee::core::RpcResult;
+use jsonrpsee::proc_macros::rpc;
+use storage::U256;
+
+#[rpc(server, client, namespace = "kv")]
+pub trait Rpc {
+    /// Returns the latest value of key.
+    #[method(name = "get")]
+    async fn get(&self, stream_id: U256, key: KvKey) -> RpcResult<Option<KeyValue>>;
+
+    /// Returns the value of key at the specified version, i.e. tx seq.
+    #[method(name = "getAt")]
+    async fn get_at(
+        &self,
+        stream_id: U256,
+        key: KvKey,
+        version: u64,
+    ) -> RpcResult<Option<KeyValue>>;
+
+    /// Returns values of keys within `[start_key, end_key)` in ascending order, at the
+    /// specified version or the latest one.
+    ///
+    /// The scan is bounded, so less than `limit` values may be returned. Use `nextKey` of the
+    /// page as the start key to continue until it is `null`.
+    #[method(name = "getRange")]
+    async fn get_range(
+        &self,
+        stream_id: U256,
+        start_key: KvKey,
+        end_key: Option<KvKey>,
+        limit: usize,
+        version: Option<u64>,
+    ) -> RpcResult<KeyValuePage>;
+}
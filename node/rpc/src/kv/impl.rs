@@ -0,0 +1,91 @@
+use super::api::RpcServer;
+use crate::error;
+use crate::types::{KeyValue, KeyValuePage, KvKey};
+use crate::Context;
+use jsonrpsee::core::async_trait;
+use jsonrpsee::core::RpcResult;
+use storage::U256;
+use zgs_kv::KvStore;
+
+/// Maximum number of key-values returned by `kv_getRange` at a time.
+const MAX_RANGE_LIMIT: usize = 1000;
+
+pub struct RpcServerImpl {
+    pub ctx: Context,
+}
+
+impl RpcServerImpl {
+    fn kv_store(&self) -> &KvStore {
+        self.ctx.kv_store.as_ref().unwrap()
+    }
+
+    /// Returns the specified version if already replayed, or the latest version.
+    fn version(&self, version: Option<u64>) -> RpcResult<u64> {
+        let next_tx_seq = self.kv_store().next_tx_seq()?;
+        match version {
+            Some(v) if v >= next_tx_seq => Err(error::invalid_params(
+                "version",
+                format!("not replayed yet, next tx seq = {}", next_tx_seq),
+            )),
+            Some(v) => Ok(v),
+            None => Ok(u64::MAX),
+        }
+    }
+}
+
+#[async_trait]
+impl RpcServer for RpcServerImpl {
+    async fn get(&self, stream_id: U256, key: KvKey) -> RpcResult<Option<KeyValue>> {
+        debug!(%stream_id, "kv_get");
+
+        Ok(self
+            .kv_store()
+            .get(&stream_id, &key.0, u64::MAX)?
+            .map(Into::into))
+    }
+
+    async fn get_at(
+        &self,
+        stream_id: U256,
+        key: KvKey,
+        version: u64,
+    ) -> RpcResult<Option<KeyValue>> {
+        debug!(%stream_id, %version, "kv_getAt");
+
+        let version = self.version(Some(version))?;
+
+        Ok(self
+            .kv_store()
+            .get(&stream_id, &key.0, version)?
+            .map(Into::into))
+    }
+
+    async fn get_range(
+        &self,
+        stream_id: U256,
+        start_key: KvKey,
+        end_key: Option<KvKey>,
+        limit: usize,
+        version: Option<u64>,
+    ) -> RpcResult<KeyValuePage> {
+        debug!(%stream_id, %limit, ?version, "kv_getRange");
+
+        if limit > MAX_RANGE_LIMIT {
+            return Err(error::invalid_params(
+                "limit",
+                format!("exceeds maximum key-values {}", MAX_RANGE_LIMIT),
+            ));
+        }
+
+        let version = self.version(version)?;
+        let page = self.kv_store().get_range(
+            &stream_id,
+            &start_key.0,
+            end_key.as_ref().map(|v| v.0.as_slice()),
+            version,
+            limit,
+        )?;
+
+        Ok(page.into())
+    }
+}
@@ -1,16 +1,20 @@
 #[macro_use]
 extern crate tracing;
 
+extern crate kv as zgs_kv;
 extern crate miner as zgs_miner;
 
 mod admin;
 mod config;
 mod error;
+mod file_server;
+mod kv;
 mod middleware;
 mod miner;
 pub mod types;
 mod zgs;
 
+use crate::kv::RpcServer as KvRpcServer;
 use crate::miner::RpcServer as MinerRpcServer;
 use admin::RpcServer as AdminRpcServer;
 use chunk_pool::MemoryChunkPool;
@@ -18,6 +22,8 @@ use file_location_cache::FileLocationCache;
 use futures::channel::mpsc::Sender;
 use jsonrpsee::core::RpcResult;
 use jsonrpsee::http_server::{HttpServerBuilder, HttpServerHandle};
+use jsonrpsee::ws_server::{WsServerBuilder, WsServerHandle};
+use log_entry_sync::LogSyncEvent;
 use network::{NetworkGlobals, NetworkMessage, NetworkSender};
 use std::error::Error;
 use std::sync::Arc;
@@ -26,10 +32,15 @@ use sync::{SyncRequest, SyncResponse, SyncSender};
 use task_executor::ShutdownReason;
 use tokio::sync::broadcast;
 use zgs::RpcServer as ZgsRpcServer;
+use zgs_kv::KvStore;
+use zgs_miner::remote::RemoteMineServer;
 use zgs_miner::MinerMessage;
 
 pub use admin::RpcClient as ZgsAdminRpcClient;
 pub use config::Config as RPCConfig;
+pub use file_server::run_file_server;
+pub use kv::RpcClient as ZgsKvRpcClient;
+pub use miner::RemoteMinerClient;
 pub use miner::RpcClient as ZgsMinerRpcClient;
 pub use zgs::RpcClient as ZgsRPCClient;
 
@@ -47,6 +58,9 @@ pub struct Context {
     pub log_store: Arc<Store>,
     pub shutdown_sender: Sender<ShutdownReason>,
     pub mine_service_sender: Option<broadcast::Sender<MinerMessage>>,
+    pub remote_mine_server: Option<Arc<RemoteMineServer>>,
+    pub log_sync_event_sender: broadcast::Sender<LogSyncEvent>,
+    pub kv_store: Option<Arc<KvStore>>,
 }
 
 impl Context {
@@ -78,6 +92,27 @@ pub async fn run_server(
     Ok(handles)
 }
 
+/// Run a WebSocket server for public RPCs and subscriptions if configured.
+pub async fn run_ws_server(ctx: Context) -> Result<Option<WsServerHandle>, Box<dyn Error>> {
+    let listen_address = match ctx.config.listen_address_ws {
+        Some(addr) => addr,
+        None => return Ok(None),
+    };
+
+    let zgs = (zgs::RpcServerImpl { ctx: ctx.clone() }).into_rpc();
+
+    let handle = WsServerBuilder::default()
+        .max_request_body_size(ctx.config.max_request_body_size)
+        .set_middleware(middleware::Metrics::default())
+        .build(listen_address)
+        .await?
+        .start(zgs)?;
+
+    info!(%listen_address, "WebSocket server started");
+
+    Ok(Some(handle))
+}
+
 fn server_builder(ctx: Context) -> HttpServerBuilder<middleware::Metrics> {
     HttpServerBuilder::default()
         .max_request_body_size(ctx.config.max_request_body_size)
@@ -99,6 +134,12 @@ async fn run_server_all(ctx: Context) -> Result<HttpServerHandle, Box<dyn Error>
         zgs.merge(mine)?;
     }
 
+    // kv rpc if configured
+    if ctx.kv_store.is_some() {
+        let kv = (kv::RpcServerImpl { ctx: ctx.clone() }).into_rpc();
+        zgs.merge(kv)?;
+    }
+
     Ok(server_builder(ctx.clone())
         .build(ctx.config.listen_address)
         .await?
@@ -110,7 +151,13 @@ async fn run_server_public_private(
     ctx: Context,
 ) -> Result<(HttpServerHandle, Option<HttpServerHandle>), Box<dyn Error>> {
     // public rpc
-    let zgs = (zgs::RpcServerImpl { ctx: ctx.clone() }).into_rpc();
+    let mut zgs = (zgs::RpcServerImpl { ctx: ctx.clone() }).into_rpc();
+
+    // kv rpc if configured
+    if ctx.kv_store.is_some() {
+        let kv = (kv::RpcServerImpl { ctx: ctx.clone() }).into_rpc();
+        zgs.merge(kv)?;
+    }
 
     // admin rpc
     let mut admin = (admin::RpcServerImpl { ctx: ctx.clone() }).into_rpc();
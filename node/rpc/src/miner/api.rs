@@ -1,5 +1,8 @@
 use jsonrpsee::core::RpcResult;
 use jsonrpsee::proc_macros::rpc;
+use miner::remote::{RemoteAnswer, RemoteMineJob, SealedLoad};
+use miner::MiningStats;
+use storage::log_store::SealStatus;
 
 #[rpc(server, client, namespace = "miner")]
 pub trait Rpc {
@@ -11,4 +14,19 @@ pub trait Rpc {
 
     #[method(name = "setStartPosition")]
     async fn set_start_position(&self, index: u64) -> RpcResult<bool>;
+
+    #[method(name = "getMiningStats")]
+    async fn get_mining_stats(&self) -> RpcResult<MiningStats>;
+
+    #[method(name = "getSealStatus")]
+    async fn get_seal_status(&self, pricing_index: u64) -> RpcResult<SealStatus>;
+
+    #[method(name = "getMineJob")]
+    async fn get_mine_job(&self) -> RpcResult<Option<RemoteMineJob>>;
+
+    #[method(name = "loadSealedData")]
+    async fn load_sealed_data(&self, load_index: u64) -> RpcResult<Option<SealedLoad>>;
+
+    #[method(name = "submitAnswer")]
+    async fn submit_answer(&self, answer: RemoteAnswer) -> RpcResult<()>;
 }
@@ -0,0 +1,39 @@
+use super::api::RpcClient;
+use jsonrpsee::core::async_trait;
+use jsonrpsee::http_client::{HttpClient, HttpClientBuilder};
+use miner::remote::{RemoteAnswer, RemoteMineClient, RemoteMineJob, SealedLoad};
+
+/// Client of the `miner` RPC namespace of a storage node, which is used by remote workers.
+pub struct RemoteMinerClient {
+    client: HttpClient,
+}
+
+impl RemoteMinerClient {
+    pub fn new(url: &str) -> Result<Self, String> {
+        let client = HttpClientBuilder::default()
+            .build(url)
+            .map_err(|e| format!("Failed to create RPC client for {}: {:?}", url, e))?;
+        Ok(Self { client })
+    }
+}
+
+#[async_trait]
+impl RemoteMineClient for RemoteMinerClient {
+    async fn get_mine_job(&self) -> Result<Option<RemoteMineJob>, String> {
+        RpcClient::get_mine_job(&self.client)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn load_sealed_data(&self, load_index: u64) -> Result<Option<SealedLoad>, String> {
+        RpcClient::load_sealed_data(&self.client, load_index)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn submit_answer(&self, answer: RemoteAnswer) -> Result<(), String> {
+        RpcClient::submit_answer(&self.client, answer)
+            .await
+            .map_err(|e| e.to_string())
+    }
+}
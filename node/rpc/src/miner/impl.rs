@@ -1,9 +1,12 @@
 use super::api::RpcServer;
-use crate::Context;
+use crate::{error, Context};
 use futures::prelude::*;
 use jsonrpsee::core::async_trait;
 use jsonrpsee::core::{Error, RpcResult};
-use miner::MinerMessage;
+use miner::remote::{RemoteAnswer, RemoteMineJob, RemoteMineServer, SealedLoad};
+use miner::{MinerMessage, MiningStats};
+use std::sync::Arc;
+use storage::log_store::SealStatus;
 use tokio::sync::broadcast;
 
 pub struct RpcServerImpl {
@@ -14,12 +17,19 @@ impl RpcServerImpl {
     fn mine_service_sender(&self) -> &broadcast::Sender<MinerMessage> {
         self.ctx.mine_service_sender.as_ref().unwrap()
     }
+
+    fn remote_mine_server(&self) -> RpcResult<&Arc<RemoteMineServer>> {
+        self.ctx
+            .remote_mine_server
+            .as_ref()
+            .ok_or_else(|| error::internal_error("Remote mining is not available"))
+    }
 }
 
 #[async_trait]
 impl RpcServer for RpcServerImpl {
     async fn start(&self) -> RpcResult<bool> {
-        info!("mine_start()");
+        info!("miner_start()");
         let success = self
             .mine_service_sender()
             .send(MinerMessage::ToggleMining(true))
@@ -28,7 +38,7 @@ impl RpcServer for RpcServerImpl {
     }
 
     async fn stop(&self) -> RpcResult<bool> {
-        info!("mine_stop()");
+        info!("miner_stop()");
 
         let success = self
             .mine_service_sender()
@@ -38,7 +48,7 @@ impl RpcServer for RpcServerImpl {
     }
 
     async fn set_start_position(&self, index: u64) -> RpcResult<bool> {
-        info!("mine_setStartPosition({})", index);
+        info!("miner_setStartPosition({})", index);
 
         let success = self
             .mine_service_sender()
@@ -46,4 +56,35 @@ impl RpcServer for RpcServerImpl {
             .is_ok();
         Ok(success)
     }
+
+    async fn get_mining_stats(&self) -> RpcResult<MiningStats> {
+        debug!("miner_getMiningStats()");
+        Ok(miner::mining_stats())
+    }
+
+    async fn get_seal_status(&self, pricing_index: u64) -> RpcResult<SealStatus> {
+        debug!("miner_getSealStatus({})", pricing_index);
+        Ok(self.ctx.log_store.get_seal_status(pricing_index).await?)
+    }
+
+    async fn get_mine_job(&self) -> RpcResult<Option<RemoteMineJob>> {
+        debug!("miner_getMineJob()");
+        Ok(self.remote_mine_server()?.mine_job())
+    }
+
+    async fn load_sealed_data(&self, load_index: u64) -> RpcResult<Option<SealedLoad>> {
+        debug!("miner_loadSealedData({})", load_index);
+        Ok(self
+            .remote_mine_server()?
+            .load_sealed_data(load_index)
+            .await)
+    }
+
+    async fn submit_answer(&self, answer: RemoteAnswer) -> RpcResult<()> {
+        info!(nonce = ?answer.nonce, "miner_submitAnswer()");
+        self.remote_mine_server()?
+            .submit_answer(answer)
+            .await
+            .map_err(|e| error::invalid_params("answer", e))
+    }
 }
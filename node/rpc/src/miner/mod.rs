@@ -1,7 +1,9 @@
 #![allow(unused)]
 mod api;
+mod client;
 mod r#impl;
 
 pub use api::RpcClient;
 pub use api::RpcServer;
+pub use client::RemoteMinerClient;
 pub use r#impl::RpcServerImpl;
@@ -4,6 +4,7 @@ use jsonrpsee::core::RpcResult;
 use merkle_light::hash::Algorithm;
 use merkle_light::merkle::{log2_pow2, next_pow2, MerkleTree};
 use merkle_tree::RawLeafSha3Algorithm;
+use network::rpc::bandwidth::BandwidthInfo;
 use network::Multiaddr;
 use serde::{Deserialize, Serialize};
 use shared_types::{
@@ -16,6 +17,7 @@ use std::net::IpAddr;
 use std::time::Instant;
 use storage::config::ShardConfig;
 use storage::log_store::log_manager::bytes_to_entries;
+use storage::log_store::tx_store::{TxStatus, TxStatusUpdate};
 use storage::H256;
 
 const ZERO_HASH: [u8; 32] = [
@@ -44,6 +46,7 @@ pub struct NetworkInfo {
     pub connected_peers: usize,
     pub connected_outgoing_peers: usize,
     pub connected_incoming_peers: usize,
+    pub bandwidth: BandwidthInfo,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -57,6 +60,63 @@ pub struct FileInfo {
     pub pruned: bool,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadSession {
+    pub root: DataRoot,
+    /// Available once the file submission is synced from blockchain.
+    pub tx_seq: Option<u64>,
+    pub file_size: u64,
+    pub total_seg_num: usize,
+    pub uploaded_seg_num: usize,
+    /// Indexes of segments that not uploaded yet, excluding segments out of shard.
+    pub missing_segments: Vec<usize>,
+    /// Unix timestamp in seconds when the last segment uploaded.
+    pub updated_at: u64,
+}
+
+/// Key of key-value store in base64.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KvKey(#[serde(with = "base64")] pub Vec<u8>);
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyValue {
+    #[serde(with = "base64")]
+    pub key: Vec<u8>,
+    #[serde(with = "base64")]
+    pub value: Vec<u8>,
+    /// Tx seq that writes the value.
+    pub version: u64,
+}
+
+/// A page of key-values, which is continued from `next_key` if any.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyValuePage {
+    pub values: Vec<KeyValue>,
+    pub next_key: Option<KvKey>,
+}
+
+impl From<zgs_kv::KeyValuePage> for KeyValuePage {
+    fn from(value: zgs_kv::KeyValuePage) -> Self {
+        KeyValuePage {
+            values: value.values.into_iter().map(Into::into).collect(),
+            next_key: value.next_key.map(KvKey),
+        }
+    }
+}
+
+impl From<zgs_kv::KeyValue> for KeyValue {
+    fn from(value: zgs_kv::KeyValue) -> Self {
+        KeyValue {
+            key: value.key,
+            value: value.value,
+            version: value.version,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Segment(#[serde(with = "base64")] pub Vec<u8>);
 
@@ -274,6 +334,7 @@ pub struct PeerInfo {
     pub is_trusted: bool,
     pub connection_direction: Option<String>, // Incoming/Outgoing
     pub enr: Option<String>,
+    pub download_stats: DownloadStats,
 }
 
 impl From<&network::PeerInfo> for PeerInfo {
@@ -289,6 +350,33 @@ impl From<&network::PeerInfo> for PeerInfo {
                 network::ConnectionDirection::Outgoing => "Outgoing".into(),
             }),
             enr: value.enr().map(|x| x.to_base64()),
+            download_stats: value.download_stats().into(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadStats {
+    pub num_downloads: u64,
+    pub num_bytes: u64,
+    pub bytes_per_sec: f64,
+    pub latency_ms: f64,
+    pub request_failures: u64,
+    pub proof_failures: u64,
+    pub score: Option<f64>,
+}
+
+impl From<&network::DownloadStats> for DownloadStats {
+    fn from(value: &network::DownloadStats) -> Self {
+        Self {
+            num_downloads: value.num_downloads(),
+            num_bytes: value.num_bytes(),
+            bytes_per_sec: value.bytes_per_sec(),
+            latency_ms: value.latency_ms(),
+            request_failures: value.request_failures(),
+            proof_failures: value.proof_failures(),
+            score: value.score(),
         }
     }
 }
@@ -363,6 +451,62 @@ impl From<network::PeerConnectionStatus> for PeerConnectionStatus {
     }
 }
 
+/// Kind of events to subscribe via `zgs_subscribe`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SubscriptionKind {
+    /// New transactions synced from blockchain.
+    NewTxs,
+    /// Files finalized or pruned in the log store.
+    FileStatus,
+    /// Segments uploaded into the chunk pool.
+    UploadProgress,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum FileEvent {
+    #[serde(rename_all = "camelCase")]
+    NewTx { tx: Transaction },
+    #[serde(rename_all = "camelCase")]
+    Finalized { tx_seq: u64 },
+    #[serde(rename_all = "camelCase")]
+    Pruned { tx_seq: u64 },
+    #[serde(rename_all = "camelCase")]
+    UploadProgress {
+        root: DataRoot,
+        tx_seq: Option<u64>,
+        seg_index: usize,
+        uploaded_seg_num: usize,
+        total_seg_num: Option<usize>,
+    },
+}
+
+impl From<TxStatusUpdate> for FileEvent {
+    fn from(value: TxStatusUpdate) -> Self {
+        match value.status {
+            TxStatus::Finalized => FileEvent::Finalized {
+                tx_seq: value.tx_seq,
+            },
+            TxStatus::Pruned => FileEvent::Pruned {
+                tx_seq: value.tx_seq,
+            },
+        }
+    }
+}
+
+impl From<chunk_pool::UploadProgress> for FileEvent {
+    fn from(value: chunk_pool::UploadProgress) -> Self {
+        FileEvent::UploadProgress {
+            root: value.root,
+            tx_seq: value.tx_seq,
+            seg_index: value.seg_index,
+            uploaded_seg_num: value.uploaded_seg_num,
+            total_seg_num: value.total_seg_num,
+        }
+    }
+}
+
 mod base64 {
     use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
@@ -379,7 +523,7 @@ mod base64 {
 
 #[cfg(test)]
 mod tests {
-    use super::Segment;
+    use super::{FileEvent, Segment, SubscriptionKind};
 
     #[test]
     fn test_segment_serde() {
@@ -390,4 +534,14 @@ mod tests {
         let seg2: Segment = serde_json::from_str("\"aGVsbG8sIHdvcmxk\"").unwrap();
         assert_eq!(String::from_utf8(seg2.0).unwrap().as_str(), "hello, world");
     }
+
+    #[test]
+    fn test_file_event_serde() {
+        let kind: SubscriptionKind = serde_json::from_str("\"fileStatus\"").unwrap();
+        assert_eq!(kind, SubscriptionKind::FileStatus);
+
+        let event = FileEvent::Finalized { tx_seq: 3 };
+        let result = serde_json::to_string(&event).unwrap();
+        assert_eq!(result.as_str(), r#"{"type":"finalized","txSeq":3}"#);
+    }
 }
@@ -1,8 +1,10 @@
-use crate::types::{FileInfo, Segment, SegmentWithProof, Status};
+use crate::types::{
+    FileEvent, FileInfo, Segment, SegmentWithProof, Status, SubscriptionKind, UploadSession,
+};
 use jsonrpsee::core::RpcResult;
 use jsonrpsee::proc_macros::rpc;
-use shared_types::{DataRoot, FlowProof, TxSeqOrRoot};
-use storage::{config::ShardConfig, H256};
+use shared_types::{DataRoot, FlowProof, Transaction, TxSeqOrRoot};
+use storage::{config::ShardConfig, H256, U256};
 
 #[rpc(server, client, namespace = "zgs")]
 pub trait Rpc {
@@ -68,6 +70,21 @@ pub trait Rpc {
     #[method(name = "getFileInfoByTxSeq")]
     async fn get_file_info_by_tx_seq(&self, tx_seq: u64) -> RpcResult<Option<FileInfo>>;
 
+    /// Returns the resumable upload session of specified file, including the indexes of
+    /// segments that not uploaded yet.
+    #[method(name = "getUploadSession")]
+    async fn get_upload_session(&self, root: DataRoot) -> RpcResult<Option<UploadSession>>;
+
+    /// Returns txs tagged with the stream id in ascending order of tx seq, which starts
+    /// from `from_seq` inclusive.
+    #[method(name = "getTxsByStreamId")]
+    async fn get_txs_by_stream_id(
+        &self,
+        stream_id: U256,
+        from_seq: u64,
+        limit: usize,
+    ) -> RpcResult<Vec<Transaction>>;
+
     #[method(name = "getShardConfig")]
     async fn get_shard_config(&self) -> RpcResult<ShardConfig>;
 
@@ -80,4 +97,8 @@ pub trait Rpc {
 
     #[method(name = "getFlowContext")]
     async fn get_flow_context(&self) -> RpcResult<(H256, u64)>;
+
+    /// Subscribes file lifecycle events, which is only available over WebSocket.
+    #[subscription(name = "subscribe" => "subscription", unsubscribe = "unsubscribe", item = FileEvent)]
+    fn subscribe(&self, kind: SubscriptionKind);
 }
@@ -1,15 +1,27 @@
 use super::api::RpcServer;
 use crate::error;
-use crate::types::{FileInfo, Segment, SegmentWithProof, Status};
+use crate::types::{
+    FileEvent, FileInfo, Segment, SegmentWithProof, Status, SubscriptionKind, UploadSession,
+};
 use crate::Context;
 use chunk_pool::{FileID, SegmentInfo};
+use futures::future;
+use futures::stream::{BoxStream, StreamExt};
 use jsonrpsee::core::async_trait;
 use jsonrpsee::core::RpcResult;
+use jsonrpsee::ws_server::PendingSubscription;
+use log_entry_sync::LogSyncEvent;
 use shared_types::{DataRoot, FlowProof, Transaction, TxSeqOrRoot, CHUNK_SIZE};
 use std::fmt::{Debug, Formatter, Result};
 use storage::config::ShardConfig;
+use storage::log_store::log_manager::sector_to_segment;
 use storage::log_store::tx_store::TxStatus;
-use storage::{try_option, H256};
+use storage::{try_option, H256, U256};
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::{errors::BroadcastStreamRecvError, BroadcastStream};
+
+/// Maximum number of txs returned by `zgs_getTxsByStreamId` at a time.
+const MAX_TXS_BY_STREAM_ID: usize = 1000;
 
 pub struct RpcServerImpl {
     pub ctx: Context,
@@ -179,6 +191,75 @@ impl RpcServer for RpcServerImpl {
         Ok(Some(self.get_file_info_by_tx(tx).await?))
     }
 
+    async fn get_upload_session(&self, root: DataRoot) -> RpcResult<Option<UploadSession>> {
+        debug!(%root, "zgs_getUploadSession");
+
+        let record = try_option!(self.ctx.chunk_pool.get_upload_session(&root).await?);
+        let maybe_tx = self.ctx.log_store.get_tx_by_data_root(&root).await?;
+
+        // Only segments in shard are required once the file is known in log entry.
+        let missing_segments = match &maybe_tx {
+            Some(tx) => {
+                let shard_config = self.ctx.log_store.get_store().get_shard_config();
+                let start_segment = sector_to_segment(tx.start_entry_index());
+                self.ctx
+                    .chunk_pool
+                    .get_missing_segments(&root, &record, |index| {
+                        shard_config.in_range((start_segment + index) as u64)
+                    })
+                    .await?
+            }
+            None => {
+                self.ctx
+                    .chunk_pool
+                    .get_missing_segments(&root, &record, |_| true)
+                    .await?
+            }
+        };
+
+        Ok(Some(UploadSession {
+            root,
+            tx_seq: maybe_tx.map(|tx| tx.seq),
+            file_size: record.file_size,
+            total_seg_num: record.total_segments(),
+            uploaded_seg_num: record.cached_segments.len() + record.written_seg_num as usize,
+            missing_segments,
+            updated_at: record.updated_at,
+        }))
+    }
+
+    async fn get_txs_by_stream_id(
+        &self,
+        stream_id: U256,
+        from_seq: u64,
+        limit: usize,
+    ) -> RpcResult<Vec<Transaction>> {
+        debug!(%stream_id, %from_seq, %limit, "zgs_getTxsByStreamId");
+
+        if limit > MAX_TXS_BY_STREAM_ID {
+            return Err(error::invalid_params(
+                "limit",
+                format!("exceeds maximum txs {}", MAX_TXS_BY_STREAM_ID),
+            ));
+        }
+
+        let tx_seq_list = self
+            .ctx
+            .log_store
+            .get_tx_seq_list_by_stream_id(&stream_id, from_seq, limit)
+            .await?;
+
+        let mut txs = Vec::with_capacity(tx_seq_list.len());
+        for tx_seq in tx_seq_list {
+            match self.ctx.log_store.get_tx_by_seq_number(tx_seq).await? {
+                Some(tx) => txs.push(tx),
+                None => return Err(error::internal_error("tx missing in stream id index")),
+            }
+        }
+
+        Ok(txs)
+    }
+
     async fn get_shard_config(&self) -> RpcResult<ShardConfig> {
         debug!("zgs_getShardConfig");
         let shard_config = self.ctx.log_store.get_store().get_shard_config();
@@ -202,6 +283,57 @@ impl RpcServer for RpcServerImpl {
     async fn get_flow_context(&self) -> RpcResult<(H256, u64)> {
         Ok(self.ctx.log_store.get_context().await?)
     }
+
+    fn subscribe(&self, pending: PendingSubscription, kind: SubscriptionKind) {
+        info!(?kind, "zgs_subscribe");
+
+        let events = match kind {
+            SubscriptionKind::NewTxs => {
+                event_stream(self.ctx.log_sync_event_sender.subscribe(), |e| match e {
+                    LogSyncEvent::TxSynced { tx } => Some(FileEvent::NewTx { tx }),
+                    _ => None,
+                })
+            }
+            SubscriptionKind::FileStatus => event_stream(
+                self.ctx.log_store.get_store().subscribe_tx_status(),
+                |update| Some(update.into()),
+            ),
+            SubscriptionKind::UploadProgress => event_stream(
+                self.ctx.chunk_pool.subscribe_upload_progress(),
+                |progress| Some(progress.into()),
+            ),
+        };
+
+        let sink = match pending.accept() {
+            Some(sink) => sink,
+            None => return,
+        };
+
+        tokio::spawn(async move {
+            let closed = sink.pipe_from_stream(events).await;
+            debug!(?kind, ?closed, "zgs_subscribe closed");
+        });
+    }
+}
+
+/// Converts the broadcast `receiver` into a stream of file events, where messages that
+/// `f` maps to `None` are skipped.
+fn event_stream<T, F>(receiver: broadcast::Receiver<T>, mut f: F) -> BoxStream<'static, FileEvent>
+where
+    T: Clone + Send + 'static,
+    F: FnMut(T) -> Option<FileEvent> + Send + 'static,
+{
+    BroadcastStream::new(receiver)
+        .filter_map(move |item| {
+            future::ready(match item {
+                Ok(msg) => f(msg),
+                Err(BroadcastStreamRecvError::Lagged(lagged)) => {
+                    warn!(%lagged, "Subscriber lagged behind file events");
+                    None
+                }
+            })
+        })
+        .boxed()
 }
 
 impl RpcServerImpl {
@@ -332,7 +464,10 @@ impl RpcServerImpl {
         };
 
         if need_cache {
-            self.ctx.chunk_pool.cache_chunks(seg_info).await?;
+            self.ctx
+                .chunk_pool
+                .cache_chunks(seg_info, segment.file_size)
+                .await?;
         } else {
             let file_id = FileID {
                 root: seg_info.root,
@@ -376,7 +511,7 @@ impl RpcServerImpl {
         Ok(Some(Segment(segment.data)))
     }
 
-    async fn get_segment_with_proof_by_tx(
+    pub(crate) async fn get_segment_with_proof_by_tx(
         &self,
         tx: Transaction,
         index: usize,
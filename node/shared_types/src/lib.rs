@@ -16,6 +16,7 @@ use std::fmt;
 use std::hash::Hasher;
 use tiny_keccak::{Hasher as KeccakHasher, Keccak};
 use tracing::debug;
+use zgs_spec::SECTORS_PER_LOAD;
 
 const ZERO_HASH: [u8; 32] = [
     0xd3, 0x97, 0xb3, 0xb0, 0x43, 0xd8, 0x7f, 0xcd, 0x6f, 0xad, 0x12, 0x91, 0xff, 0xb, 0xfd, 0x16,
@@ -65,6 +66,25 @@ pub fn compute_padded_chunk_size(size_bytes: usize) -> (usize, usize) {
     (padded_chunks, chunks_next_pow2)
 }
 
+/// Returns the sizes in chunks of subtrees that a file of `data_size` bytes is split into in
+/// the flow, from the largest to the smallest.
+pub fn split_nodes(data_size: usize) -> Vec<usize> {
+    let (mut padded_chunks, chunks_next_pow2) = compute_padded_chunk_size(data_size);
+    let mut next_chunk_size = chunks_next_pow2;
+
+    let mut nodes = vec![];
+    while padded_chunks > 0 {
+        if padded_chunks >= next_chunk_size {
+            padded_chunks -= next_chunk_size;
+            nodes.push(next_chunk_size);
+        }
+
+        next_chunk_size >>= 1;
+    }
+
+    nodes
+}
+
 pub fn compute_segment_size(chunks: usize, chunks_per_segment: usize) -> (usize, usize) {
     if chunks % chunks_per_segment == 0 {
         (chunks / chunks_per_segment, chunks_per_segment)
@@ -121,6 +141,15 @@ impl Transaction {
         Self::num_entries_of_list(&self.merkle_nodes)
     }
 
+    /// Returns the data root of merkle nodes, which is computed in the same way as the flow
+    /// contract does for submissions.
+    pub fn data_root_of_nodes(merkle_nodes: &[(usize, DataRoot)]) -> Option<DataRoot> {
+        let ((_, last), rest) = merkle_nodes.split_last()?;
+        Some(rest.iter().rev().fold(*last, |root, (_, node)| {
+            <Sha3Algorithm as append_merkle::Algorithm<H256>>::parent(node, &root)
+        }))
+    }
+
     pub fn hash(&self) -> H256 {
         let bytes = self.as_ssz_bytes();
         let mut h = Keccak::v256();
@@ -154,6 +183,100 @@ pub struct ChunkArrayWithProof {
     pub proof: FlowRangeProof,
 }
 
+/// A transaction along with the proofs of its merkle nodes in the flow, so that the
+/// transaction could be verified against the flow root before confirmed on chain.
+#[derive(Debug, Clone, PartialEq, Eq, DeriveEncode, DeriveDecode)]
+pub struct TransactionWithProof {
+    pub transaction: Transaction,
+    /// Proofs of `transaction.merkle_nodes` in order. The proof of a node smaller than a
+    /// segment is generated for the first entry of the node, otherwise for the first segment
+    /// of the node in the segment level tree.
+    pub proofs: Vec<FlowProof>,
+}
+
+impl TransactionWithProof {
+    /// Validates the merkle nodes against other fields of the transaction and the proofs of
+    /// all merkle nodes, and returns the flow root they are proved against.
+    ///
+    /// Note, `seq` is not covered by proofs, which should be checked by the caller.
+    pub fn validate(&self) -> anyhow::Result<DataRoot> {
+        let tx = &self.transaction;
+        if tx.merkle_nodes.is_empty() || tx.merkle_nodes.len() != self.proofs.len() {
+            bail!(
+                "proofs mismatch, nodes={} proofs={}",
+                tx.merkle_nodes.len(),
+                self.proofs.len()
+            );
+        }
+
+        // the proofs only cover merkle nodes, which are derived from size and data root
+        let depths = split_nodes(tx.size as usize)
+            .into_iter()
+            .map(|num_entries| num_entries.trailing_zeros() as usize + 1);
+        if !tx.merkle_nodes.iter().map(|&(depth, _)| depth).eq(depths) {
+            bail!("merkle nodes mismatch with size {}", tx.size);
+        }
+        if Transaction::data_root_of_nodes(&tx.merkle_nodes) != Some(tx.data_merkle_root) {
+            bail!(
+                "merkle nodes mismatch with data root {:?}",
+                tx.data_merkle_root
+            );
+        }
+
+        let mut position = tx.start_entry_index;
+        let mut root = None;
+        for (&(depth, node), proof) in tx.merkle_nodes.iter().zip(self.proofs.iter()) {
+            if !(1..=64).contains(&depth) {
+                bail!("invalid subtree depth {}", depth);
+            }
+            let height = depth - 1;
+            if (1u64 << height) < SECTORS_PER_LOAD as u64 {
+                proof.validate_node::<Sha3Algorithm>(&node, height, position as usize)?;
+            } else {
+                if position % SECTORS_PER_LOAD as u64 != 0 {
+                    bail!("subtree not aligned with segment, position={}", position);
+                }
+                let segment_height = height - SECTORS_PER_LOAD.trailing_zeros() as usize;
+                proof.validate_node::<Sha3Algorithm>(
+                    &node,
+                    segment_height,
+                    (position / SECTORS_PER_LOAD as u64) as usize,
+                )?;
+            }
+
+            match root {
+                None => root = Some(proof.root()),
+                Some(r) if r != proof.root() => bail!("proofs against different roots"),
+                _ => {}
+            }
+            position += 1u64 << height;
+        }
+
+        Ok(root.expect("merkle nodes not empty"))
+    }
+}
+
+/// An erasure coded piece of an entry batch, along with the proof of batch root in flow.
+#[derive(Clone, PartialEq, Eq, DeriveEncode, DeriveDecode)]
+pub struct ErasurePiece {
+    pub batch_index: u64,
+    pub piece_index: u64,
+    pub data: Vec<u8>,
+    pub proof: FlowProof,
+}
+
+impl fmt::Debug for ErasurePiece {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ErasurePiece: batch_index={} piece_index={} data_len={}",
+            self.batch_index,
+            self.piece_index,
+            self.data.len()
+        )
+    }
+}
+
 #[derive(Clone, Eq, PartialEq, DeriveEncode, DeriveDecode)]
 pub struct ChunkArray {
     // The length is exactly a multiple of `CHUNK_SIZE`
@@ -403,7 +526,9 @@ pub enum TxSeqOrRoot {
     Root(DataRoot),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, DeriveEncode, DeriveDecode)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, DeriveEncode, DeriveDecode, Serialize, Deserialize,
+)]
 pub struct ShardConfig {
     pub num_shard: usize,
     pub shard_id: usize,
@@ -418,6 +543,32 @@ impl Default for ShardConfig {
     }
 }
 
+impl ShardConfig {
+    /// Whether `self` intersect with the `other` shard config.
+    pub fn intersect(&self, other: &ShardConfig) -> bool {
+        let ShardConfig {
+            num_shard: mut left_num_shard,
+            shard_id: mut left_shard_id,
+        } = self;
+        let ShardConfig {
+            num_shard: mut right_num_shard,
+            shard_id: mut right_shard_id,
+        } = other;
+
+        while left_num_shard != right_num_shard {
+            if left_num_shard < right_num_shard {
+                right_num_shard /= 2;
+                right_shard_id /= 2;
+            } else {
+                left_num_shard /= 2;
+                left_shard_id /= 2;
+            }
+        }
+
+        left_shard_id == right_shard_id
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, DeriveEncode, DeriveDecode)]
 pub struct ShardedFile {
     pub tx_id: TxID,
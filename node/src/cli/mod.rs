@@ -9,6 +9,50 @@ pub fn cli_app() -> Command {
             arg!(--"blockchain-rpc-endpoint" [URL] "Sets blockchain RPC endpoint (Default: http://127.0.0.1:8545)")
         )
         .arg(arg!(--"db-max-num-chunks" [NUM] "Sets the max number of chunks to store in db (Default: None)"))
+        .subcommand(
+            Command::new("db")
+                .about("Checks or repairs the database offline when node stopped")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("check")
+                        .about("Checks entry batches and finalized files against flow merkle tree"),
+                )
+                .subcommand(
+                    Command::new("repair")
+                        .about("Resets inconsistent files to unfinalized to sync them again"),
+                ),
+        )
+        .subcommand(
+            Command::new("snapshot")
+                .about("Exports the log store offline when node stopped")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("export")
+                        .about("Exports transactions, flow merkle nodes and sync progress")
+                        .arg(arg!(<FILE> "Sets the snapshot file to export"))
+                        .arg(arg!(--"with-data" "Exports entry batches and file status as well")),
+                ),
+        )
+        .subcommand(
+            Command::new("mine")
+                .about("Runs mining tools, which do not start the storage node")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("simulate")
+                        .about("Replays recorded mine contexts against the local log store without submitting answers")
+                        .arg(arg!(<FILE> "Sets the JSON file of recorded mine contexts"))
+                        .arg(arg!(--"miner-id" [ID] "Sets the miner id (Default: miner_id in config)"))
+                        .arg(arg!(--seconds [NUM] "Sets the mining time of each mine context (Default: 10)"))
+                        .arg(arg!(--"start-position" [NUM] "Sets the start position of mine range (Default: 0)"))
+                        .arg(arg!(--"end-position" [NUM] "Sets the end position of mine range (Default: unlimited)")),
+                )
+                .subcommand(
+                    Command::new("worker")
+                        .about("Runs a remote PoRA hashing worker for the mine jobs of a storage node")
+                        .arg(arg!(--node <URL> "Sets the admin RPC endpoint of the storage node"))
+                        .arg(arg!(--"poll-seconds" [NUM] "Sets the interval to poll mine jobs (Default: 1)")),
+                ),
+        )
         .allow_external_subcommands(true)
         .version(zgs_version::VERSION)
 }
@@ -1,7 +1,9 @@
 use super::{Client, RuntimeContext};
 use chunk_pool::{Config as ChunkPoolConfig, MemoryChunkPool};
 use file_location_cache::FileLocationCache;
-use log_entry_sync::{LogSyncConfig, LogSyncEvent, LogSyncManager};
+use kv::{KvRuntime, KvStore};
+use log_entry_sync::{FlowReader, LogSyncConfig, LogSyncEvent, LogSyncManager};
+use miner::remote::RemoteMineServer;
 use miner::{MineService, MinerConfig, MinerMessage, ShardConfig};
 use network::{
     self, new_network_channel, Keypair, NetworkConfig, NetworkGlobals, NetworkReceiver,
@@ -41,11 +43,13 @@ struct SyncComponents {
 
 struct MinerComponents {
     send: broadcast::Sender<MinerMessage>,
+    remote_mine_server: Arc<RemoteMineServer>,
 }
 
 struct LogSyncComponents {
     send: broadcast::Sender<LogSyncEvent>,
     catch_up_end_recv: Option<oneshot::Receiver<()>>,
+    flow_reader: FlowReader,
 }
 
 struct PrunerComponents {
@@ -75,6 +79,7 @@ pub struct ClientBuilder {
     log_sync: Option<LogSyncComponents>,
     pruner: Option<PrunerComponents>,
     chunk_pool: Option<ChunkPoolComponents>,
+    kv_store: Option<Arc<KvStore>>,
 }
 
 impl ClientBuilder {
@@ -174,6 +179,7 @@ impl ClientBuilder {
         let store = require!("sync", self, store).clone();
         let file_location_cache = require!("sync", self, file_location_cache).clone();
         let network_send = require!("sync", self, network).send.clone();
+        let network_globals = require!("sync", self, network).globals.clone();
         let event_recv = require!("sync", self, log_sync).send.subscribe();
         let catch_up_end_recv = self
             .log_sync
@@ -182,11 +188,14 @@ impl ClientBuilder {
             .catch_up_end_recv
             .take()
             .ok_or("sync requires a catch_up_end_recv")?;
+        let flow_reader = require!("sync", self, log_sync).flow_reader.clone();
 
         let send = SyncService::spawn_with_config(
             config,
             executor,
             network_send,
+            Some(network_globals),
+            Some(flow_reader),
             store,
             file_location_cache,
             event_recv,
@@ -205,8 +214,12 @@ impl ClientBuilder {
             let network_send = require!("miner", self, network).send.clone();
             let store = self.async_store.as_ref().unwrap().clone();
 
-            let send = MineService::spawn(executor, network_send, config, store).await?;
-            self.miner = Some(MinerComponents { send });
+            let (send, remote_mine_server) =
+                MineService::spawn(executor, network_send, config, store).await?;
+            self.miner = Some(MinerComponents {
+                send,
+                remote_mine_server,
+            });
         }
 
         Ok(self)
@@ -225,6 +238,26 @@ impl ClientBuilder {
         Ok(self)
     }
 
+    /// Starts the key-value runtime if enabled.
+    pub fn with_kv(mut self, config: kv::Config, storage: &StorageConfig) -> Result<Self, String> {
+        if !config.enabled {
+            return Ok(self);
+        }
+
+        let executor = require!("kv", self, runtime_context).clone().executor;
+        let async_store = require!("kv", self, async_store).clone();
+        let kv_store = Arc::new(
+            KvStore::rocksdb(storage.db_dir.join("kv_db"))
+                .map_err(|e| format!("Unable to start key-value store: {:?}", e))?,
+        );
+
+        KvRuntime::spawn(executor, config, kv_store.clone(), async_store)
+            .map_err(|e| e.to_string())?;
+        self.kv_store = Some(kv_store);
+
+        Ok(self)
+    }
+
     pub async fn with_shard(self, config: ShardConfig) -> Result<Self, String> {
         self.async_store
             .as_ref()
@@ -279,8 +312,10 @@ impl ClientBuilder {
         let async_store = require!("rpc", self, async_store).clone();
         let network_send = require!("rpc", self, network).send.clone();
         let mine_send = self.miner.as_ref().map(|x| x.send.clone());
+        let remote_mine_server = self.miner.as_ref().map(|x| x.remote_mine_server.clone());
         let file_location_cache = require!("rpc", self, file_location_cache).clone();
         let chunk_pool = require!("rpc", self, chunk_pool).chunk_pool.clone();
+        let log_sync_event_sender = require!("rpc", self, log_sync).send.clone();
 
         let ctx = rpc::Context {
             config: rpc_config,
@@ -292,9 +327,12 @@ impl ClientBuilder {
             chunk_pool,
             shutdown_sender: executor.shutdown_sender(),
             mine_service_sender: mine_send,
+            remote_mine_server,
+            log_sync_event_sender,
+            kv_store: self.kv_store.clone(),
         };
 
-        let (rpc_handle, maybe_admin_rpc_handle) = rpc::run_server(ctx)
+        let (rpc_handle, maybe_admin_rpc_handle) = rpc::run_server(ctx.clone())
             .await
             .map_err(|e| format!("Unable to start HTTP RPC server: {:?}", e))?;
 
@@ -303,6 +341,19 @@ impl ClientBuilder {
             executor.spawn(admin_rpc_handle, "rpc_admin");
         }
 
+        if let Some(ws_rpc_handle) = rpc::run_ws_server(ctx.clone())
+            .await
+            .map_err(|e| format!("Unable to start WebSocket RPC server: {:?}", e))?
+        {
+            executor.spawn(ws_rpc_handle, "rpc_ws");
+        }
+
+        if let Some(file_server) = rpc::run_file_server(ctx)
+            .map_err(|e| format!("Unable to start file server: {:?}", e))?
+        {
+            executor.spawn(file_server, "rpc_file");
+        }
+
         Ok(self)
     }
 
@@ -324,6 +375,15 @@ impl ClientBuilder {
             "chunk_pool_log_monitor",
         );
 
+        chunk_pool
+            .restore_upload_sessions()
+            .await
+            .map_err(|e| format!("Failed to restore upload sessions: {:?}", e))?;
+        executor.spawn(
+            MemoryChunkPool::monitor_upload_sessions(chunk_pool.clone()),
+            "chunk_pool_session_monitor",
+        );
+
         self.chunk_pool = Some(ChunkPoolComponents { chunk_pool });
 
         Ok(self)
@@ -332,6 +392,7 @@ impl ClientBuilder {
     pub async fn with_log_sync(mut self, config: LogSyncConfig) -> Result<Self, String> {
         let executor = require!("log_sync", self, runtime_context).clone().executor;
         let store = require!("log_sync", self, store).clone();
+        let flow_reader = FlowReader::new(&config).await.map_err(|e| e.to_string())?;
         let (send, catch_up_end_recv) = LogSyncManager::spawn(config, executor, store)
             .await
             .map_err(|e| e.to_string())?;
@@ -339,6 +400,7 @@ impl ClientBuilder {
         self.log_sync = Some(LogSyncComponents {
             send,
             catch_up_end_recv: Some(catch_up_end_recv),
+            flow_reader,
         });
         Ok(self)
     }
@@ -4,17 +4,25 @@ use crate::ZgsConfig;
 use ethereum_types::H256;
 use ethers::prelude::{Http, Middleware, Provider};
 use log_entry_sync::{CacheConfig, ContractAddress, LogSyncConfig};
-use miner::MinerConfig;
+use miner::{MineWorkerConfig, MinerConfig};
 use network::{EnrExt, NetworkConfig};
-use pruner::PrunerConfig;
+use pruner::{PrunePolicy, PrunerConfig};
 use shared_types::{NetworkIdentity, ProtocolVersion};
 use std::net::IpAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
-use storage::config::ShardConfig;
+use storage::config::{ErasureConfig, ShardConfig};
 use storage::log_store::log_manager::LogConfig;
+use storage::segment_db::SegmentConfig;
 use storage::StorageConfig;
 
+// Compact a segment file once half of its entry batches are overwritten or deleted.
+const SEGMENT_COMPACT_GARBAGE_RATIO: f64 = 0.5;
+// Sync segment files every second or 64MB appended, whichever comes first.
+const SEGMENT_SYNC_INTERVAL: Duration = Duration::from_secs(1);
+const SEGMENT_SYNC_BYTES: u64 = 64 * 1024 * 1024;
+
 impl ZgsConfig {
     pub async fn network_config(&self) -> Result<NetworkConfig, String> {
         let mut network_config = NetworkConfig::default();
@@ -27,6 +35,8 @@ impl ZgsConfig {
         network_config.network_dir = self.network_dir.clone().into();
         network_config.libp2p_port = self.network_libp2p_port;
         network_config.disable_discovery = self.network_disable_discovery;
+        network_config.dht_enabled = self.network_dht_enabled;
+        network_config.dht_max_records = self.network_dht_max_records;
         network_config.discovery_port = self.network_discovery_port;
         let flow_address = self
             .log_contract_address
@@ -49,10 +59,12 @@ impl ZgsConfig {
             },
         };
         network_config.network_id = local_network_id.clone();
+        network_config.shard_config = self.shard_config()?.into();
 
         if !self.network_disable_discovery {
             network_config.enr_tcp_port = Some(self.network_enr_tcp_port);
             network_config.enr_udp_port = Some(self.network_enr_udp_port);
+            network_config.enr_quic_port = self.network_enr_quic_port;
             network_config.enr_address = match &self.network_enr_address {
                 Some(addr) => Some(addr.parse().unwrap()),
                 None => match public_ip::addr_v4().await {
@@ -84,6 +96,13 @@ impl ZgsConfig {
             .collect::<Result<_, _>>()
             .map_err(|e| format!("Unable to parse network_libp2p_nodes: {:?}", e))?;
 
+        network_config.quic_listen_addresses = self
+            .network_quic_listen_addresses
+            .iter()
+            .map(|addr| addr.parse::<libp2p::Multiaddr>())
+            .collect::<Result<_, _>>()
+            .map_err(|e| format!("Unable to parse network_quic_listen_addresses: {:?}", e))?;
+
         network_config.discv5_config.table_filter = if self.discv5_disable_enr_network_id {
             Arc::new(|_| true)
         } else {
@@ -106,6 +125,7 @@ impl ZgsConfig {
 
         network_config.peer_db = self.network_peer_db;
         network_config.peer_manager = self.network_peer_manager.clone();
+        network_config.bandwidth = self.network_bandwidth;
         network_config.disable_enr_network_id = self.discv5_disable_enr_network_id;
         network_config.find_chunks_enabled = self.network_find_chunks_enabled;
 
@@ -115,6 +135,25 @@ impl ZgsConfig {
     pub fn storage_config(&self) -> Result<StorageConfig, String> {
         let mut log_config = LogConfig::default();
         log_config.flow.merkle_node_cache_capacity = self.merkle_node_cache_capacity;
+        if self.erasure_data_shards > 0 || self.erasure_parity_shards > 0 {
+            let erasure_config =
+                ErasureConfig::new(self.erasure_data_shards, self.erasure_parity_shards)?;
+            erasure_config.validate(&self.shard_config()?)?;
+            log_config.flow.erasure_config = Some(erasure_config);
+        }
+        log_config.entry_batch_segments = match self.db_entry_batch_backend.as_str() {
+            "rocksdb" => None,
+            "segment" => Some(SegmentConfig {
+                dir: PathBuf::from(&self.db_dir).join("data_segments"),
+                segment_size: self.db_segment_size_mb * 1024 * 1024,
+                compact_garbage_ratio: SEGMENT_COMPACT_GARBAGE_RATIO,
+                sync_interval: SEGMENT_SYNC_INTERVAL,
+                sync_bytes: SEGMENT_SYNC_BYTES,
+            }),
+            backend => return Err(format!("Unknown entry batch backend: {}", backend)),
+        };
+        log_config.track_tx_access =
+            self.db_max_num_sectors.is_some() && self.prune_policy == "lru";
         Ok(StorageConfig {
             db_dir: self.db_dir.clone().into(),
             log_config,
@@ -179,8 +218,6 @@ impl ZgsConfig {
         } else {
             None
         };
-        let cpu_percentage = self.miner_cpu_percentage;
-        let iter_batch = self.mine_iter_batch_size;
         let context_query_seconds = self.mine_context_query_seconds;
 
         let shard_config = self.shard_config()?;
@@ -191,8 +228,8 @@ impl ZgsConfig {
             self.blockchain_rpc_endpoint.clone(),
             mine_address,
             flow_address,
-            cpu_percentage,
-            iter_batch,
+            self.mine_worker_config(),
+            self.miner_seal_threads,
             context_query_seconds,
             shard_config,
             self.rate_limit_retries,
@@ -202,12 +239,24 @@ impl ZgsConfig {
         ))
     }
 
+    pub fn mine_worker_config(&self) -> MineWorkerConfig {
+        MineWorkerConfig {
+            cpu_percentage: self.miner_cpu_percentage,
+            iter_batch: self.mine_iter_batch_size,
+            threads: self.miner_threads,
+            cpu_affinity: self.miner_cpu_affinity.clone(),
+            pora_hasher: self.miner_pora_hasher.clone(),
+        }
+    }
+
     pub fn chunk_pool_config(&self) -> Result<chunk_pool::Config, String> {
         Ok(chunk_pool::Config {
             write_window_size: self.chunk_pool_write_window_size,
             max_cached_chunks_all: self.chunk_pool_max_cached_chunks_all,
             max_writings: self.chunk_pool_max_writings,
             expiration_time_secs: self.chunk_pool_expiration_time_secs,
+            session_expiration_time_secs: self.chunk_pool_session_expiration_time_secs,
+            max_upload_sessions: self.chunk_pool_max_upload_sessions,
             shard_config: self.shard_config()?,
         })
     }
@@ -228,10 +277,16 @@ impl ZgsConfig {
     pub fn pruner_config(&self) -> Result<Option<PrunerConfig>, String> {
         if let Some(max_num_sectors) = self.db_max_num_sectors {
             let shard_config = self.shard_config()?;
-            let reward_address = self
-                .reward_contract_address
-                .parse::<ContractAddress>()
-                .map_err(|e| format!("Unable to parse reward_contract_address: {:?}", e))?;
+            let policy =
+                PrunePolicy::from_config(&self.prune_policy, &self.prune_stream_retention)?;
+            // The reward contract is not required by local prune policies.
+            let reward_address = if policy == PrunePolicy::Reward {
+                self.reward_contract_address
+                    .parse::<ContractAddress>()
+                    .map_err(|e| format!("Unable to parse reward_contract_address: {:?}", e))?
+            } else {
+                ContractAddress::zero()
+            };
             Ok(Some(PrunerConfig {
                 shard_config,
                 db_path: self.db_dir.clone().into(),
@@ -239,6 +294,7 @@ impl ZgsConfig {
                 check_time: Duration::from_secs(self.prune_check_time_s),
                 batch_size: self.prune_batch_size,
                 batch_wait_time: Duration::from_millis(self.prune_batch_wait_time_ms),
+                policy,
                 rpc_endpoint_url: self.blockchain_rpc_endpoint.clone(),
                 reward_address,
                 rate_limit_retries: self.rate_limit_retries,
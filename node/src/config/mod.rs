@@ -13,12 +13,16 @@ build_config! {
     (network_enr_tcp_port, (u16), 1234)
     (network_enr_udp_port, (u16), 1234)
     (network_libp2p_port, (u16), 1234)
+    (network_quic_listen_addresses, (Vec<String>), vec![])
+    (network_enr_quic_port, (Option<u16>), None)
     (network_discovery_port, (u16), 1234)
     (network_target_peers, (usize), 50)
     (network_boot_nodes, (Vec<String>), vec![])
     (network_libp2p_nodes, (Vec<String>), vec![])
     (network_private, (bool), false)
     (network_disable_discovery, (bool), false)
+    (network_dht_enabled, (bool), false)
+    (network_dht_max_records, (usize), 1 << 20)
     (network_find_chunks_enabled, (bool), false)
 
     // discv5
@@ -57,6 +61,8 @@ build_config! {
     (chunk_pool_max_cached_chunks_all, (usize), 4*1024*1024)    // 1G
     (chunk_pool_max_writings, (usize), 16)
     (chunk_pool_expiration_time_secs, (u64), 300)   // 5 minutes
+    (chunk_pool_session_expiration_time_secs, (u64), 86400)   // 1 day
+    (chunk_pool_max_upload_sessions, (usize), 1024)
 
     // db
     (db_dir, (String), "db".to_string())
@@ -64,7 +70,14 @@ build_config! {
     (prune_check_time_s, (u64), 60)
     (prune_batch_size, (usize), 16 * 1024)
     (prune_batch_wait_time_ms, (u64), 1000)
+    (prune_policy, (String), "reward".to_string())
+    (prune_stream_retention, (Vec<String>), vec![])
     (merkle_node_cache_capacity, (usize), 32 * 1024 * 1024)
+    (erasure_data_shards, (usize), 0)
+    (erasure_parity_shards, (usize), 0)
+    (snapshot_import_file, (Option<String>), None)
+    (db_entry_batch_backend, (String), "rocksdb".to_string())
+    (db_segment_size_mb, (u64), 1024)
 
     // misc
     (log_config_file, (String), "log_config".to_string())
@@ -76,6 +89,10 @@ build_config! {
     (miner_key, (Option<String>), None)
     (miner_cpu_percentage, (u64), 100)
     (mine_iter_batch_size, (usize), 100)
+    (miner_threads, (usize), 1)
+    (miner_cpu_affinity, (Vec<usize>), vec![])
+    (miner_pora_hasher, (String), "reference".to_string())
+    (miner_seal_threads, (usize), 4)
     (reward_contract_address, (String), "".to_string())
     (shard_position, (Option<String>), None)
 
@@ -93,6 +110,9 @@ pub struct ZgsConfig {
     /// Network peer manager config, configured by [network_peer_manager] section by `config` crate.
     pub network_peer_manager: network::peer_manager::config::Config,
 
+    /// Network bandwidth config, configured by [network_bandwidth] section by `config` crate.
+    pub network_bandwidth: network::rpc::bandwidth::BandwidthConfig,
+
     // router config, configured by [router] section by `config` crate.
     pub router: router::Config,
 
@@ -105,6 +125,9 @@ pub struct ZgsConfig {
     // rpc config, configured by [rpc] section by `config` crate.
     pub rpc: rpc::RPCConfig,
 
+    // key-value runtime config, configured by [kv] section by `config` crate.
+    pub kv: kv::Config,
+
     // submission config, configured by [submission_config] section by `config` crate.
     pub submission_config: contract_wrapper::SubmitConfig,
 
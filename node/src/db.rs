@@ -0,0 +1,78 @@
+//! Offline database maintenance, which should be executed when the node is stopped.
+
+use crate::config::ZgsConfig;
+use clap::ArgMatches;
+use storage::config::{ShardConfig, SHARD_CONFIG_KEY};
+use storage::log_store::config::ConfigurableExt;
+use storage::log_store::log_manager::DATA_DB_KEY;
+use storage::log_store::LogStoreWrite;
+use storage::LogManager;
+use sync::auto_sync::sync_store::SyncStore;
+
+pub fn run(matches: &ArgMatches, config: &ZgsConfig) -> Result<(), String> {
+    let storage_config = config.storage_config()?;
+    let store = LogManager::rocksdb(
+        storage_config.log_config,
+        storage_config.db_dir.join("flow_db"),
+        storage_config.db_dir.join("data_db"),
+    )
+    .map_err(|e| format!("Unable to open RocksDB store: {:?}", e))?;
+
+    // The shard config may be updated by pruner, so the stored one takes precedence.
+    let shard_config = match store
+        .get_config_decoded::<_, ShardConfig>(&SHARD_CONFIG_KEY, DATA_DB_KEY)
+        .map_err(|e| format!("Unable to load shard config: {:?}", e))?
+    {
+        Some(shard_config) => shard_config,
+        None => config.shard_config()?,
+    };
+    store.update_shard_config(shard_config);
+
+    let repair = match matches.subcommand_name() {
+        Some("check") => false,
+        Some("repair") => true,
+        _ => return Err("unknown db subcommand".into()),
+    };
+
+    let report = store
+        .check_integrity()
+        .map_err(|e| format!("Failed to check database: {:?}", e))?;
+
+    println!(
+        "Checked {} batches and {} transactions",
+        report.checked_batches, report.checked_txs
+    );
+    if report.is_consistent() {
+        println!("Database is consistent");
+        return Ok(());
+    }
+    println!("Corrupted batches: {:?}", report.corrupted_batches);
+    println!("Inconsistent transactions: {:?}", report.inconsistent_txs);
+
+    if !repair {
+        return Err("database is inconsistent".into());
+    }
+
+    store
+        .repair(&report)
+        .map_err(|e| format!("Failed to repair database: {:?}", e))?;
+    println!(
+        "Reset {} transactions to unfinalized: {:?}",
+        report.inconsistent_txs.len(),
+        report.inconsistent_txs
+    );
+
+    // Reset transactions are behind the auto sync progress, so queue them to sync again.
+    let queued = SyncStore::insert_pending_offline(
+        &store,
+        config.sync.neighbors_only,
+        &report.inconsistent_txs,
+    )
+    .map_err(|e| format!("Failed to queue transactions to sync: {:?}", e))?;
+    println!("Queued {} transactions to sync from peers again", queued);
+    if !config.sync.auto_sync_enabled {
+        println!("Auto sync disabled, so sync the transactions above by `admin_startSyncFile` RPC");
+    }
+
+    Ok(())
+}
@@ -4,7 +4,10 @@ extern crate tracing;
 mod cli;
 mod client;
 mod config;
+mod db;
 mod log;
+mod mine;
+mod snapshot;
 
 use crate::config::ZgsConfig;
 use client::{Client, ClientBuilder, RuntimeContext};
@@ -20,6 +23,8 @@ async fn start_node(context: RuntimeContext, config: ZgsConfig) -> Result<Client
     let pruner_config = config.pruner_config()?;
     let shard_config = config.shard_config()?;
 
+    snapshot::import(&config, &storage_config).await?;
+
     ClientBuilder::default()
         .with_runtime_context(context)
         .with_rocksdb_store(&storage_config)?
@@ -38,6 +43,7 @@ async fn start_node(context: RuntimeContext, config: ZgsConfig) -> Result<Client
         .await?
         .with_pruner(pruner_config)
         .await?
+        .with_kv(config.kv.clone(), &storage_config)?
         .with_rpc(config.rpc)
         .await?
         .with_router(router_config)?
@@ -65,6 +71,32 @@ fn main() -> Result<(), Box<dyn Error>> {
     // CLI, config, and logs
     let matches = cli::cli_app().get_matches();
     let config = ZgsConfig::parse(&matches)?;
+
+    // remote mining worker, which runs without the storage node
+    let worker_matches = matches
+        .subcommand_matches("mine")
+        .and_then(|mine_matches| mine_matches.subcommand_matches("worker"));
+
+    // offline database maintenance
+    match matches.subcommand() {
+        Some(("db", db_matches)) => return Ok(db::run(db_matches, &config)?),
+        Some(("snapshot", snapshot_matches)) => {
+            return Ok(snapshot::run(snapshot_matches, &config)?)
+        }
+        Some(("mine", mine_matches)) if worker_matches.is_none() => {
+            let result = executor
+                .block_on_dangerous(
+                    mine::run(mine_matches, &config, executor.clone()),
+                    "mine_simulate",
+                )
+                .unwrap_or_else(|| Err("mine simulation cancelled".into()));
+            environment.fire_signal();
+            environment.shutdown_on_idle();
+            return Ok(result?);
+        }
+        _ => {}
+    }
+
     metrics::initialize(config.metrics.clone());
     log::configure(
         &config.log_config_file,
@@ -73,25 +105,26 @@ fn main() -> Result<(), Box<dyn Error>> {
     );
 
     // start services
-    executor.clone().spawn(
-        async move {
-            info!("Starting services...");
-            if let Err(e) = start_node(context.clone(), config).await {
-                error!(reason = %e, "Failed to start zgs node");
-                // Ignore the error since it always occurs during normal operation when
-                // shutting down.
-                let _ =
-                    executor
-                        .shutdown_sender()
-                        .try_send(task_executor::ShutdownReason::Failure(
-                            "Failed to start zgs node",
-                        ));
-            } else {
-                info!("Services started");
-            }
-        },
-        "zgs_node",
-    );
+    if let Some(worker_matches) = worker_matches {
+        mine::spawn_worker(worker_matches, &config, executor.clone())?;
+    } else {
+        executor.clone().spawn(
+            async move {
+                info!("Starting services...");
+                if let Err(e) = start_node(context.clone(), config).await {
+                    error!(reason = %e, "Failed to start zgs node");
+                    // Ignore the error since it always occurs during normal operation when
+                    // shutting down.
+                    let _ = executor.shutdown_sender().try_send(
+                        task_executor::ShutdownReason::Failure("Failed to start zgs node"),
+                    );
+                } else {
+                    info!("Services started");
+                }
+            },
+            "zgs_node",
+        );
+    }
 
     // Block this thread until we get a ctrl-c or a task sends a shutdown signal.
     let shutdown_reason = environment.block_until_shutdown_requested()?;
@@ -0,0 +1,135 @@
+//! Mining tools, which run without starting the storage node. The simulation should be
+//! executed when the node is stopped.
+
+use crate::config::ZgsConfig;
+use clap::ArgMatches;
+use ethereum_types::H256;
+use miner::remote::RemoteMineWorker;
+use miner::simulate::{load_recorded_puzzles, simulate, SimulateConfig};
+use rpc::RemoteMinerClient;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use storage::config::{ShardConfig, SHARD_CONFIG_KEY};
+use storage::log_store::config::ConfigurableExt;
+use storage::log_store::log_manager::DATA_DB_KEY;
+use storage::log_store::LogStoreWrite;
+use storage::LogManager;
+use storage_async::Store;
+use task_executor::TaskExecutor;
+
+pub async fn run(
+    matches: &ArgMatches,
+    config: &ZgsConfig,
+    executor: TaskExecutor,
+) -> Result<(), String> {
+    let simulate_matches = match matches.subcommand() {
+        Some(("simulate", simulate_matches)) => simulate_matches,
+        _ => return Err("unknown mine subcommand".into()),
+    };
+    let file = simulate_matches
+        .get_one::<String>("FILE")
+        .ok_or("mine context file not specified")?;
+    let parse_u64 = |name: &str| -> Result<Option<u64>, String> {
+        simulate_matches
+            .get_one::<String>(name)
+            .map(|v| v.parse().map_err(|_| format!("Invalid {}", name)))
+            .transpose()
+    };
+
+    let miner_config = config
+        .mine_config()?
+        .ok_or("miner_key is required to simulate mining with the node config")?;
+    let miner_id = match simulate_matches
+        .get_one::<String>("miner-id")
+        .or(config.miner_id.as_ref())
+    {
+        Some(miner_id) => miner_id
+            .parse::<H256>()
+            .map_err(|e| format!("Unable to parse miner_id: {:?}", e))?,
+        None => return Err("miner_id is required to simulate mining".into()),
+    };
+    let simulate_config = SimulateConfig {
+        miner_id,
+        start_position: parse_u64("start-position")?,
+        end_position: parse_u64("end-position")?,
+        duration: Duration::from_secs(parse_u64("seconds")?.unwrap_or(10)),
+    };
+    let puzzles = load_recorded_puzzles(Path::new(file))?;
+
+    let storage_config = config.storage_config()?;
+    let store = LogManager::rocksdb(
+        storage_config.log_config,
+        storage_config.db_dir.join("flow_db"),
+        storage_config.db_dir.join("data_db"),
+    )
+    .map_err(|e| format!("Unable to open RocksDB store: {:?}", e))?;
+
+    // The shard config may be updated by pruner, so the stored one takes precedence.
+    let shard_config = match store
+        .get_config_decoded::<_, ShardConfig>(&SHARD_CONFIG_KEY, DATA_DB_KEY)
+        .map_err(|e| format!("Unable to load shard config: {:?}", e))?
+    {
+        Some(shard_config) => shard_config,
+        None => config.shard_config()?,
+    };
+    store.update_shard_config(shard_config);
+    let miner_config = miner_config.with_shard_config(shard_config);
+
+    let store = Arc::new(Store::new(Arc::new(store), executor.clone()));
+    println!(
+        "Simulating {} mine contexts for {:?} each, miner_id = {:?}, shard = {}/{}",
+        puzzles.len(),
+        simulate_config.duration,
+        miner_id,
+        shard_config.shard_id,
+        shard_config.num_shard
+    );
+
+    let reports = simulate(executor, store, &miner_config, simulate_config, puzzles).await?;
+    for report in reports {
+        if let Some(reason) = report.skipped {
+            println!("epoch {}: skipped, {}", report.epoch, reason);
+            continue;
+        }
+
+        println!(
+            "epoch {}: hashes = {}, coverage = {:.4} (expected {:.4}), loads missing = {}, seals unsealed = {}, hit rate = {:.3e} (expected {:.3e}), answers = {}",
+            report.epoch,
+            report.hashes,
+            report.actual_coverage,
+            report.expected_coverage,
+            report.loads_missing,
+            report.seals_unsealed,
+            report.actual_hit_rate,
+            report.expected_hit_rate,
+            report.answers
+        );
+    }
+
+    Ok(())
+}
+
+/// Spawns a remote hashing worker, which mines the jobs of a storage node until shutdown.
+pub fn spawn_worker(
+    matches: &ArgMatches,
+    config: &ZgsConfig,
+    executor: TaskExecutor,
+) -> Result<(), String> {
+    let url = matches
+        .get_one::<String>("node")
+        .ok_or("storage node RPC endpoint not specified")?;
+    let poll_seconds = match matches.get_one::<String>("poll-seconds") {
+        Some(v) => v.parse().map_err(|_| "Invalid poll-seconds")?,
+        None => 1,
+    };
+
+    let client = Arc::new(RemoteMinerClient::new(url)?);
+    info!(%url, "Starting remote mining worker");
+    RemoteMineWorker::spawn(
+        executor,
+        client,
+        config.mine_worker_config(),
+        Duration::from_secs(poll_seconds),
+    )
+}
@@ -0,0 +1,190 @@
+//! Snapshot of the log store, which is used to bootstrap a new node quickly.
+
+use crate::config::ZgsConfig;
+use clap::ArgMatches;
+use contract_interface::ZgsFlow;
+use ethereum_types::H256;
+use ethers::prelude::{Http, Provider};
+use log_entry_sync::ContractAddress;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::Path;
+use std::sync::Arc;
+use storage::log_store::LogStoreRead;
+use storage::{LogManager, StorageConfig};
+
+/// Exports the log store offline, which should be executed when the node is stopped.
+pub fn run(matches: &ArgMatches, config: &ZgsConfig) -> Result<(), String> {
+    let export_matches = match matches.subcommand() {
+        Some(("export", export_matches)) => export_matches,
+        _ => return Err("unknown snapshot subcommand".into()),
+    };
+    let file = export_matches
+        .get_one::<String>("FILE")
+        .ok_or("snapshot file not specified")?;
+    let with_data = export_matches.get_flag("with-data");
+
+    let storage_config = config.storage_config()?;
+    let store = LogManager::rocksdb(
+        storage_config.log_config,
+        storage_config.db_dir.join("flow_db"),
+        storage_config.db_dir.join("data_db"),
+    )
+    .map_err(|e| format!("Unable to open RocksDB store: {:?}", e))?;
+
+    let writer = BufWriter::new(
+        File::create(file).map_err(|e| format!("Unable to create snapshot file: {:?}", e))?,
+    );
+    let summary = store
+        .export_snapshot(writer, with_data)
+        .map_err(|e| format!("Failed to export snapshot: {:?}", e))?;
+
+    let (flow_root, flow_length) = store
+        .get_context()
+        .map_err(|e| format!("Failed to get flow context: {:?}", e))?;
+    println!(
+        "Exported {} records to {}, with_data = {}, next_tx_seq = {}, flow_root = {:?}, flow_length = {}",
+        summary.num_records,
+        file,
+        with_data,
+        store.next_tx_seq(),
+        flow_root,
+        flow_length
+    );
+
+    Ok(())
+}
+
+/// Marker file in the db directory, which exists until the imported snapshot is verified, so
+/// that the snapshot is verified again at startup if failed to verify last time.
+const PENDING_VERIFICATION_FILE: &str = "snapshot_pending_verification";
+
+/// Imports the configured snapshot if the log store is empty, and verifies the imported flow
+/// root against the on-chain flow root of the last imported tx.
+///
+/// The marker file exists until verified, so that it is handled again at next startup if
+/// interrupted. The imported db is removed if incomplete or the flow root mismatches, and kept
+/// along with the marker if the on-chain flow root is unavailable, so that the node fails to
+/// start until verified.
+pub async fn import(config: &ZgsConfig, storage_config: &StorageConfig) -> Result<(), String> {
+    let marker = storage_config.db_dir.join(PENDING_VERIFICATION_FILE);
+    let mut pending = marker.exists();
+
+    if pending && local_flow_root(storage_config)?.0 == 0 {
+        warn!("Snapshot import interrupted, remove the imported db");
+        remove_imported_db(storage_config)?;
+        remove_marker(&marker)?;
+        pending = false;
+    }
+
+    if !pending {
+        let file = match &config.snapshot_import_file {
+            Some(file) => file,
+            None => return Ok(()),
+        };
+
+        fs::create_dir_all(&storage_config.db_dir)
+            .map_err(|e| format!("Unable to create db directory: {:?}", e))?;
+        File::create(&marker)
+            .map_err(|e| format!("Unable to create snapshot verification marker: {:?}", e))?;
+
+        match LogManager::rocksdb_import_snapshot(
+            &storage_config.log_config,
+            storage_config.db_dir.join("flow_db"),
+            storage_config.db_dir.join("data_db"),
+            file,
+        )
+        .map_err(|e| format!("Failed to import snapshot: {:?}", e))?
+        {
+            Some(summary) => {
+                info!(%file, num_records = %summary.num_records, with_data = %summary.with_data, "Snapshot imported")
+            }
+            None => {
+                info!(%file, "Log store is not empty, skip to import snapshot");
+                return remove_marker(&marker);
+            }
+        }
+    }
+
+    let (next_tx_seq, local_root) = local_flow_root(storage_config)?;
+    if next_tx_seq == 0 {
+        info!("Snapshot has no transaction, remove the imported db");
+        remove_imported_db(storage_config)?;
+        return remove_marker(&marker);
+    }
+
+    // contract_root is zero for tx submitted before upgrading.
+    let contract_root = contract_flow_root(config, next_tx_seq - 1).await?;
+    if contract_root.is_zero() {
+        return Err(format!(
+            "On-chain flow root unavailable to verify snapshot, remove {:?} to skip the verification",
+            marker
+        ));
+    }
+
+    if contract_root != local_root {
+        remove_imported_db(storage_config)?;
+        remove_marker(&marker)?;
+        return Err(format!(
+            "Snapshot flow root mismatch, the imported db is removed: local = {:?}, on-chain = {:?}",
+            local_root, contract_root
+        ));
+    }
+
+    info!(?local_root, "Snapshot flow root verified");
+    remove_marker(&marker)
+}
+
+/// Returns the next tx seq and flow root of the local log store.
+fn local_flow_root(storage_config: &StorageConfig) -> Result<(u64, H256), String> {
+    let store = LogManager::rocksdb(
+        storage_config.log_config.clone(),
+        storage_config.db_dir.join("flow_db"),
+        storage_config.db_dir.join("data_db"),
+    )
+    .map_err(|e| format!("Unable to open RocksDB store: {:?}", e))?;
+    let (local_root, _) = store
+        .get_context()
+        .map_err(|e| format!("Failed to get flow context: {:?}", e))?;
+    Ok((store.next_tx_seq(), local_root))
+}
+
+/// Returns the flow root after the specified tx in the flow contract.
+async fn contract_flow_root(config: &ZgsConfig, tx_seq: u64) -> Result<H256, String> {
+    let flow_address = config
+        .log_contract_address
+        .parse::<ContractAddress>()
+        .map_err(|e| format!("Unable to parse log_contract_address: {:?}", e))?;
+    let provider = Provider::<Http>::try_from(&config.blockchain_rpc_endpoint)
+        .map_err(|e| format!("Can not parse blockchain endpoint: {:?}", e))?;
+    let flow_contract = ZgsFlow::new(flow_address, Arc::new(provider));
+    let contract_root = flow_contract
+        .get_flow_root_by_tx_seq(tx_seq.into())
+        .call()
+        .await
+        .map_err(|e| format!("Unable to get on-chain flow root: {:?}", e))?;
+
+    Ok(H256::from_slice(&contract_root))
+}
+
+fn remove_imported_db(storage_config: &StorageConfig) -> Result<(), String> {
+    let mut dirs = vec![
+        storage_config.db_dir.join("flow_db"),
+        storage_config.db_dir.join("data_db"),
+    ];
+    if let Some(segment_config) = &storage_config.log_config.entry_batch_segments {
+        dirs.push(segment_config.dir.clone());
+    }
+
+    for dir in dirs.into_iter().filter(|dir| dir.exists()) {
+        fs::remove_dir_all(&dir)
+            .map_err(|e| format!("Unable to remove imported db {:?}: {:?}", dir, e))?;
+    }
+
+    Ok(())
+}
+
+fn remove_marker(marker: &Path) -> Result<(), String> {
+    fs::remove_file(marker)
+        .map_err(|e| format!("Unable to remove snapshot verification marker: {:?}", e))
+}
@@ -3,17 +3,18 @@ extern crate tracing;
 
 use anyhow::bail;
 use shared_types::{
-    Chunk, ChunkArray, ChunkArrayWithProof, DataRoot, FlowProof, FlowRangeProof, Transaction,
+    Chunk, ChunkArray, ChunkArrayWithProof, DataRoot, ErasurePiece, FlowProof, FlowRangeProof,
+    Transaction, TransactionWithProof,
 };
 use ssz::{Decode, Encode};
 use std::sync::Arc;
-use storage::{error, error::Result, log_store::Store as LogStore, H256};
+use storage::{error, error::Result, log_store::Store as LogStore, H256, U256};
 use task_executor::TaskExecutor;
 use tokio::sync::oneshot;
 
 pub use storage::config::ShardConfig;
-use storage::log_store::config::ConfigurableExt;
-use storage::log_store::{MineLoadChunk, SealAnswer, SealTask};
+use storage::log_store::config::{ConfigTx, ConfigurableExt};
+use storage::log_store::{MineLoadChunk, SealAnswer, SealStatus, SealTask};
 
 /// The name of the worker tokio tasks.
 const WORKER_TASK_NAME: &str = "async_storage_worker";
@@ -46,18 +47,24 @@ impl Store {
 
     delegate!(fn check_tx_completed(tx_seq: u64) -> Result<bool>);
     delegate!(fn check_tx_pruned(tx_seq: u64) -> Result<bool>);
+    delegate!(fn get_finalized_tx_ranges() -> Result<Vec<std::ops::Range<u64>>>);
     delegate!(fn get_chunk_by_tx_and_index(tx_seq: u64, index: usize) -> Result<Option<Chunk>>);
     delegate!(fn get_chunks_by_tx_and_index_range(tx_seq: u64, index_start: usize, index_end: usize) -> Result<Option<ChunkArray>>);
     delegate!(fn get_chunks_with_proof_by_tx_and_index_range(tx_seq: u64, index_start: usize, index_end: usize, merkle_tx_seq: Option<u64>) -> Result<Option<ChunkArrayWithProof>>);
     delegate!(fn get_tx_by_seq_number(seq: u64) -> Result<Option<Transaction>>);
+    delegate!(fn get_tx_with_proof_by_seq_number(seq: u64, merkle_tx_seq: Option<u64>) -> Result<Option<TransactionWithProof>>);
     delegate!(fn put_chunks(tx_seq: u64, chunks: ChunkArray) -> Result<()>);
     delegate!(fn put_chunks_with_tx_hash(tx_seq: u64, tx_hash: H256, chunks: ChunkArray, maybe_file_proof: Option<FlowProof>) -> Result<bool>);
     delegate!(fn get_chunk_by_flow_index(index: u64, length: u64) -> Result<Option<ChunkArray>>);
     delegate!(fn finalize_tx(tx_seq: u64) -> Result<()>);
     delegate!(fn prune_tx(tx_seq: u64) -> Result<()>);
+    delegate!(fn persist_tx_access() -> Result<()>);
     delegate!(fn finalize_tx_with_hash(tx_seq: u64, tx_hash: H256) -> Result<bool>);
     delegate!(fn get_proof_at_root(root: Option<DataRoot>, index: u64, length: u64) -> Result<FlowRangeProof>);
     delegate!(fn get_context() -> Result<(DataRoot, u64)>);
+    delegate!(fn get_erasure_piece(batch_index: u64, piece_index: usize) -> Result<Option<ErasurePiece>>);
+    delegate!(fn put_erasure_piece(batch_index: u64, piece_index: usize, pieces: Vec<ErasurePiece>) -> Result<Option<Vec<u64>>>);
+    delegate!(fn rebuild_batch(batch_index: u64, pieces: Vec<ErasurePiece>) -> Result<Option<Vec<u64>>>);
 
     pub async fn get_tx_seq_by_data_root(&self, data_root: &DataRoot) -> Result<Option<u64>> {
         let root = *data_root;
@@ -71,6 +78,17 @@ impl Store {
             .await
     }
 
+    pub async fn get_tx_seq_list_by_stream_id(
+        &self,
+        stream_id: &U256,
+        from_seq: u64,
+        limit: usize,
+    ) -> Result<Vec<u64>> {
+        let stream_id = *stream_id;
+        self.spawn(move |store| store.get_tx_seq_list_by_stream_id(&stream_id, from_seq, limit))
+            .await
+    }
+
     pub async fn get_config_decoded<K: AsRef<[u8]> + Send + Sync, T: Decode + Send + 'static>(
         &self,
         key: &K,
@@ -95,11 +113,17 @@ impl Store {
             .await
     }
 
+    pub async fn exec_configs(&self, tx: ConfigTx, dest: &str) -> anyhow::Result<()> {
+        let dest = dest.to_string();
+        self.spawn(move |store| store.exec_configs(tx, &dest)).await
+    }
+
     pub async fn pull_seal_chunk(
         &self,
         seal_index_max: usize,
+        max_loads: usize,
     ) -> anyhow::Result<Option<Vec<SealTask>>> {
-        self.spawn(move |store| store.pull_seal_chunk(seal_index_max))
+        self.spawn(move |store| store.pull_seal_chunk(seal_index_max, max_loads))
             .await
     }
 
@@ -108,6 +132,11 @@ impl Store {
             .await
     }
 
+    pub async fn get_seal_status(&self, pricing_index: u64) -> anyhow::Result<SealStatus> {
+        self.spawn(move |store| store.get_seal_status(pricing_index))
+            .await
+    }
+
     pub async fn load_sealed_data(&self, chunk_index: u64) -> Result<Option<MineLoadChunk>> {
         self.spawn(move |store| store.load_sealed_data(chunk_index))
             .await
@@ -117,6 +146,10 @@ impl Store {
         self.spawn(move |store| store.get_num_entries()).await
     }
 
+    pub async fn get_tx_access_list(&self) -> Result<Vec<(u64, u64)>> {
+        self.spawn(move |store| store.get_tx_access_list()).await
+    }
+
     pub async fn remove_chunks_batch(&self, batch_list: &[u64]) -> Result<()> {
         let batch_list = batch_list.to_vec();
         self.spawn(move |store| store.remove_chunks_batch(&batch_list))
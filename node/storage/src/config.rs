@@ -1,3 +1,4 @@
+use crate::log_store::erasure::MAX_TOTAL_SHARDS;
 use crate::log_store::log_manager::LogConfig;
 use serde::{Deserialize, Serialize};
 use ssz_derive::{Decode, Encode};
@@ -131,26 +132,71 @@ impl ShardConfig {
 
     /// Whether `self` intersect with the `other` shard config.
     pub fn intersect(&self, other: &ShardConfig) -> bool {
-        let ShardConfig {
-            num_shard: mut left_num_shard,
-            shard_id: mut left_shard_id,
-        } = self;
-        let ShardConfig {
-            num_shard: mut right_num_shard,
-            shard_id: mut right_shard_id,
-        } = other;
-
-        while left_num_shard != right_num_shard {
-            if left_num_shard < right_num_shard {
-                right_num_shard /= 2;
-                right_shard_id /= 2;
-            } else {
-                left_num_shard /= 2;
-                left_shard_id /= 2;
-            }
+        shared_types::ShardConfig::from(*self).intersect(&(*other).into())
+    }
+}
+
+/// Erasure coding of entry batches across shard groups.
+///
+/// Each batch is encoded into `data_shards + parity_shards` pieces, and the piece `i` of
+/// batch `b` is held by the shard group `(b + i) % num_shard`. So the shard group of piece `0`
+/// is the one that stores the whole batch, and the batch could be rebuilt from any
+/// `data_shards` pieces held by other shard groups.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ErasureConfig {
+    pub data_shards: usize,
+    pub parity_shards: usize,
+}
+
+impl ErasureConfig {
+    pub fn new(data_shards: usize, parity_shards: usize) -> Result<Self, String> {
+        if data_shards == 0 || parity_shards == 0 {
+            return Err("Number of data shards and parity shards should be positive".into());
         }
 
-        left_shard_id == right_shard_id
+        if data_shards + parity_shards > MAX_TOTAL_SHARDS {
+            return Err(format!(
+                "Too many erasure shards: {}, should be at most {}",
+                data_shards + parity_shards,
+                MAX_TOTAL_SHARDS
+            ));
+        }
+
+        Ok(Self {
+            data_shards,
+            parity_shards,
+        })
+    }
+
+    pub fn total_shards(&self) -> usize {
+        self.data_shards + self.parity_shards
+    }
+
+    /// Checks that all pieces of a batch are held by different shard groups.
+    pub fn validate(&self, shard_config: &ShardConfig) -> Result<(), String> {
+        if self.total_shards() > shard_config.num_shard {
+            return Err(format!(
+                "Erasure shards {} exceed the number of shard groups {}",
+                self.total_shards(),
+                shard_config.num_shard
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the index of piece held by `shard_config` for the batch, or `None` if the
+    /// shard group holds no piece. `Some(0)` means that the whole batch is held.
+    pub fn piece_index(&self, shard_config: &ShardConfig, batch_index: u64) -> Option<usize> {
+        let num_shard = shard_config.num_shard;
+        let index =
+            (shard_config.shard_id + num_shard - batch_index as usize % num_shard) % num_shard;
+        if index < self.total_shards() {
+            Some(index)
+        } else {
+            None
+        }
     }
 }
 
@@ -223,7 +269,7 @@ pub fn all_shards_available(shard_configs: Vec<ShardConfig>) -> bool {
 mod tests {
     use crate::config::all_shards_available;
 
-    use super::ShardConfig;
+    use super::{ErasureConfig, ShardConfig};
 
     fn new_config(id: usize, num: usize) -> ShardConfig {
         ShardConfig::new(id, num).unwrap()
@@ -306,4 +352,22 @@ mod tests {
         assert!(!new_config(2, 4).intersect(&new_config(0, 2)));
         assert!(new_config(2, 4).intersect(&new_config(1, 2)));
     }
+
+    #[test]
+    fn test_erasure_piece_index() {
+        let config = ErasureConfig::new(2, 1).unwrap();
+        assert!(config.validate(&new_config(0, 2)).is_err());
+        assert!(config.validate(&new_config(0, 4)).is_ok());
+
+        // batch 5 is stored by shard 1, and pieces are held by shard 1, 2 and 3.
+        assert_eq!(config.piece_index(&new_config(0, 4), 5), None);
+        assert_eq!(config.piece_index(&new_config(1, 4), 5), Some(0));
+        assert_eq!(config.piece_index(&new_config(2, 4), 5), Some(1));
+        assert_eq!(config.piece_index(&new_config(3, 4), 5), Some(2));
+
+        // pieces wrap around shard groups
+        assert_eq!(config.piece_index(&new_config(0, 4), 7), Some(1));
+        assert_eq!(config.piece_index(&new_config(1, 4), 7), Some(2));
+        assert_eq!(config.piece_index(&new_config(2, 4), 7), None);
+    }
 }
@@ -3,11 +3,12 @@ use kvdb::KeyValueDB;
 pub mod config;
 pub mod error;
 pub mod log_store;
+pub mod segment_db;
 
 pub use config::Config as StorageConfig;
 pub use log_store::log_manager::LogManager;
 
-pub use ethereum_types::H256;
+pub use ethereum_types::{H256, U256};
 use kvdb_memorydb::InMemory;
 use kvdb_rocksdb::Database;
 
@@ -48,7 +49,7 @@ impl ZgsKeyValueDB for Database {
 }
 
 impl ZgsKeyValueDB for InMemory {
-    fn num_keys(&self, _col: u32) -> std::io::Result<u64> {
-        todo!("not used")
+    fn num_keys(&self, col: u32) -> std::io::Result<u64> {
+        Ok(self.iter(col).count() as u64)
     }
 }
@@ -0,0 +1,396 @@
+//! Systematic Reed-Solomon erasure coding over GF(2^8).
+//!
+//! Data is split into `data_shards` pieces, and `parity_shards` parity pieces are computed
+//! from a Cauchy matrix. Since every square sub-matrix of a Cauchy matrix is invertible, the
+//! original data could be recovered from any `data_shards` pieces.
+
+use anyhow::{bail, Result};
+use itertools::Itertools;
+
+/// Max number of pieces, which is limited by the field size.
+pub const MAX_TOTAL_SHARDS: usize = 256;
+
+/// Max number of piece combinations to try in `reconstruct_verified`.
+const MAX_RECONSTRUCT_ATTEMPTS: usize = 64;
+
+/// Primitive polynomial x^8 + x^4 + x^3 + x^2 + 1.
+const GF_POLY: u16 = 0x11d;
+
+const GF_EXP: [u8; 512] = build_exp_table();
+const GF_LOG: [u8; 256] = build_log_table();
+
+const fn build_exp_table() -> [u8; 512] {
+    let mut table = [0u8; 512];
+    let mut x: u16 = 1;
+    let mut i = 0;
+    while i < 255 {
+        table[i] = x as u8;
+        table[i + 255] = x as u8;
+        x <<= 1;
+        if x & 0x100 != 0 {
+            x ^= GF_POLY;
+        }
+        i += 1;
+    }
+    table
+}
+
+const fn build_log_table() -> [u8; 256] {
+    let exp = build_exp_table();
+    let mut table = [0u8; 256];
+    let mut i = 0;
+    while i < 255 {
+        table[exp[i] as usize] = i as u8;
+        i += 1;
+    }
+    table
+}
+
+fn gf_mul(a: u8, b: u8) -> u8 {
+    if a == 0 || b == 0 {
+        return 0;
+    }
+    GF_EXP[GF_LOG[a as usize] as usize + GF_LOG[b as usize] as usize]
+}
+
+fn gf_inv(a: u8) -> u8 {
+    assert_ne!(a, 0, "zero has no inverse");
+    GF_EXP[255 - GF_LOG[a as usize] as usize]
+}
+
+/// `dst += coef * src` in GF(2^8).
+fn gf_mul_add(dst: &mut [u8], src: &[u8], coef: u8) {
+    if coef == 0 {
+        return;
+    }
+    let log_coef = GF_LOG[coef as usize] as usize;
+    for (d, s) in dst.iter_mut().zip(src.iter()) {
+        if *s != 0 {
+            *d ^= GF_EXP[log_coef + GF_LOG[*s as usize] as usize];
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ReedSolomon {
+    data_shards: usize,
+    parity_shards: usize,
+    /// Rows of the encoding matrix for parity pieces, `parity_shards * data_shards`.
+    parity_matrix: Vec<Vec<u8>>,
+}
+
+impl ReedSolomon {
+    pub fn new(data_shards: usize, parity_shards: usize) -> Result<Self> {
+        if data_shards == 0 || parity_shards == 0 {
+            bail!("number of data and parity shards should be positive");
+        }
+        if data_shards + parity_shards > MAX_TOTAL_SHARDS {
+            bail!(
+                "too many shards: data={} parity={} max={}",
+                data_shards,
+                parity_shards,
+                MAX_TOTAL_SHARDS
+            );
+        }
+
+        // Cauchy matrix with x_i = data_shards + i and y_j = j, which are all distinct.
+        let parity_matrix = (0..parity_shards)
+            .map(|i| {
+                (0..data_shards)
+                    .map(|j| gf_inv(((data_shards + i) ^ j) as u8))
+                    .collect()
+            })
+            .collect();
+
+        Ok(Self {
+            data_shards,
+            parity_shards,
+            parity_matrix,
+        })
+    }
+
+    pub fn data_shards(&self) -> usize {
+        self.data_shards
+    }
+
+    pub fn total_shards(&self) -> usize {
+        self.data_shards + self.parity_shards
+    }
+
+    /// Returns the size of each piece to encode `data_len` bytes.
+    pub fn piece_size(&self, data_len: usize) -> usize {
+        (data_len + self.data_shards - 1) / self.data_shards
+    }
+
+    /// Encodes data into `data_shards + parity_shards` pieces of the same size.
+    /// The data is padded with zeros if not aligned with `data_shards`.
+    pub fn encode(&self, data: &[u8]) -> Vec<Vec<u8>> {
+        let piece_size = self.piece_size(data.len());
+        let mut pieces: Vec<Vec<u8>> = (0..self.data_shards)
+            .map(|i| {
+                let start = (i * piece_size).min(data.len());
+                let end = ((i + 1) * piece_size).min(data.len());
+                let mut piece = data[start..end].to_vec();
+                piece.resize(piece_size, 0);
+                piece
+            })
+            .collect();
+
+        for row in self.parity_matrix.iter() {
+            let mut parity = vec![0u8; piece_size];
+            for (coef, piece) in row.iter().zip(pieces.iter()) {
+                gf_mul_add(&mut parity, piece, *coef);
+            }
+            pieces.push(parity);
+        }
+
+        pieces
+    }
+
+    /// Encodes data and returns the piece at `piece_index` only.
+    pub fn encode_piece(&self, data: &[u8], piece_index: usize) -> Result<Vec<u8>> {
+        if piece_index >= self.total_shards() {
+            bail!("piece index out of bound: {}", piece_index);
+        }
+        Ok(self.encode(data).swap_remove(piece_index))
+    }
+
+    /// Recovers the original `data_len` bytes from any `data_shards` pieces, which are given
+    /// along with their piece indexes.
+    pub fn reconstruct(&self, pieces: &[(usize, Vec<u8>)], data_len: usize) -> Result<Vec<u8>> {
+        let piece_size = self.piece_size(data_len);
+
+        let mut selected: Vec<&(usize, Vec<u8>)> = Vec::with_capacity(self.data_shards);
+        for piece in pieces.iter() {
+            if piece.0 >= self.total_shards() {
+                bail!("piece index out of bound: {}", piece.0);
+            }
+            if piece.1.len() != piece_size {
+                bail!(
+                    "invalid piece size: index={} expected={} actual={}",
+                    piece.0,
+                    piece_size,
+                    piece.1.len()
+                );
+            }
+            if selected.iter().all(|p| p.0 != piece.0) {
+                selected.push(piece);
+            }
+            if selected.len() == self.data_shards {
+                break;
+            }
+        }
+        if selected.len() < self.data_shards {
+            bail!(
+                "not enough pieces to reconstruct: expected={} actual={}",
+                self.data_shards,
+                selected.len()
+            );
+        }
+
+        let mut data = Vec::with_capacity(piece_size * self.data_shards);
+        if selected.iter().all(|p| p.0 < self.data_shards) {
+            // Only data pieces, no decoding is needed.
+            selected.sort_by_key(|p| p.0);
+            for piece in selected {
+                data.extend_from_slice(&piece.1);
+            }
+        } else {
+            let rows: Vec<Vec<u8>> = selected.iter().map(|p| self.matrix_row(p.0)).collect();
+            let inverted = invert_matrix(rows)?;
+            for row in inverted.iter() {
+                let mut piece = vec![0u8; piece_size];
+                for (coef, (_, src)) in row.iter().zip(selected.iter()) {
+                    gf_mul_add(&mut piece, src, *coef);
+                }
+                data.append(&mut piece);
+            }
+        }
+
+        data.truncate(data_len);
+        Ok(data)
+    }
+
+    /// Recovers the original `data_len` bytes from pieces that may be invalid, by trying
+    /// `data_shards` pieces in turn until `verify` accepts the data, e.g. against the batch
+    /// root. Each piece is then verified by encoding from the verified data.
+    ///
+    /// Returns the data along with the indexes of invalid pieces, or `None` if no combination
+    /// is verified.
+    pub fn reconstruct_verified<F>(
+        &self,
+        pieces: &[(usize, Vec<u8>)],
+        data_len: usize,
+        mut verify: F,
+    ) -> Result<Option<(Vec<u8>, Vec<usize>)>>
+    where
+        F: FnMut(&[u8]) -> bool,
+    {
+        let piece_size = self.piece_size(data_len);
+        let mut candidates: Vec<&(usize, Vec<u8>)> = Vec::with_capacity(pieces.len());
+        let mut invalid = vec![];
+        for piece in pieces.iter() {
+            if piece.0 >= self.total_shards() || candidates.iter().any(|p| p.0 == piece.0) {
+                bail!("invalid or duplicated piece index: {}", piece.0);
+            }
+            if piece.1.len() == piece_size {
+                candidates.push(piece);
+            } else {
+                invalid.push(piece.0);
+            }
+        }
+        // data pieces first which need no decoding
+        candidates.sort_by_key(|p| p.0);
+
+        let combinations = candidates
+            .iter()
+            .combinations(self.data_shards)
+            .take(MAX_RECONSTRUCT_ATTEMPTS);
+        for selected in combinations {
+            let selected: Vec<(usize, Vec<u8>)> =
+                selected.into_iter().map(|p| (*p).clone()).collect();
+            let data = self.reconstruct(&selected, data_len)?;
+            if !verify(&data) {
+                continue;
+            }
+
+            let encoded = self.encode(&data);
+            invalid.extend(
+                candidates
+                    .iter()
+                    .filter(|(index, piece)| encoded[*index] != *piece)
+                    .map(|(index, _)| *index),
+            );
+            invalid.sort_unstable();
+            return Ok(Some((data, invalid)));
+        }
+
+        Ok(None)
+    }
+
+    /// Returns the row of the systematic encoding matrix for the piece.
+    fn matrix_row(&self, piece_index: usize) -> Vec<u8> {
+        if piece_index < self.data_shards {
+            let mut row = vec![0u8; self.data_shards];
+            row[piece_index] = 1;
+            row
+        } else {
+            self.parity_matrix[piece_index - self.data_shards].clone()
+        }
+    }
+}
+
+/// Inverts a square matrix by Gauss-Jordan elimination.
+fn invert_matrix(mut matrix: Vec<Vec<u8>>) -> Result<Vec<Vec<u8>>> {
+    let n = matrix.len();
+    let mut inverted: Vec<Vec<u8>> = (0..n)
+        .map(|i| {
+            let mut row = vec![0u8; n];
+            row[i] = 1;
+            row
+        })
+        .collect();
+
+    for col in 0..n {
+        let pivot = match (col..n).find(|&r| matrix[r][col] != 0) {
+            Some(r) => r,
+            None => bail!("singular matrix"),
+        };
+        matrix.swap(col, pivot);
+        inverted.swap(col, pivot);
+
+        let scale = gf_inv(matrix[col][col]);
+        matrix[col].iter_mut().for_each(|v| *v = gf_mul(*v, scale));
+        inverted[col]
+            .iter_mut()
+            .for_each(|v| *v = gf_mul(*v, scale));
+
+        let pivot_row = matrix[col].clone();
+        let pivot_inverted = inverted[col].clone();
+        for (r, (row, inverted_row)) in matrix.iter_mut().zip(inverted.iter_mut()).enumerate() {
+            let factor = row[col];
+            if r != col && factor != 0 {
+                gf_mul_add(row, &pivot_row, factor);
+                gf_mul_add(inverted_row, &pivot_inverted, factor);
+            }
+        }
+    }
+
+    Ok(inverted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ReedSolomon;
+    use itertools::Itertools;
+    use rand::random;
+
+    #[test]
+    fn test_reconstruct_from_any_pieces() {
+        let rs = ReedSolomon::new(4, 3).unwrap();
+        // not aligned with the number of data shards
+        let data: Vec<u8> = (0..1001).map(|_| random()).collect();
+        let pieces = rs.encode(&data);
+        assert_eq!(pieces.len(), 7);
+        assert_eq!(pieces[0], data[..251].to_vec());
+
+        for indexes in (0..7).combinations(4) {
+            let selected: Vec<(usize, Vec<u8>)> =
+                indexes.iter().map(|i| (*i, pieces[*i].clone())).collect();
+            assert_eq!(rs.reconstruct(&selected, data.len()).unwrap(), data);
+        }
+
+        assert_eq!(rs.encode_piece(&data, 5).unwrap(), pieces[5]);
+    }
+
+    #[test]
+    fn test_reconstruct_invalid_pieces() {
+        let rs = ReedSolomon::new(2, 2).unwrap();
+        let data = vec![7u8; 64];
+        let pieces = rs.encode(&data);
+
+        // not enough distinct pieces
+        let duplicated = vec![(3, pieces[3].clone()), (3, pieces[3].clone())];
+        assert!(rs.reconstruct(&duplicated, data.len()).is_err());
+
+        // invalid piece size or index
+        assert!(rs
+            .reconstruct(&[(0, vec![0; 31]), (1, pieces[1].clone())], 64)
+            .is_err());
+        assert!(rs
+            .reconstruct(&[(4, pieces[0].clone()), (1, pieces[1].clone())], 64)
+            .is_err());
+
+        assert!(ReedSolomon::new(0, 2).is_err());
+        assert!(ReedSolomon::new(200, 57).is_err());
+    }
+
+    #[test]
+    fn test_reconstruct_verified() {
+        let rs = ReedSolomon::new(3, 3).unwrap();
+        let data: Vec<u8> = (0..300).map(|_| random()).collect();
+        let mut pieces: Vec<(usize, Vec<u8>)> = rs.encode(&data).into_iter().enumerate().collect();
+        pieces[0].1[0] ^= 1;
+        pieces[4].1[9] ^= 1;
+        pieces[5].1.pop();
+
+        let (rebuilt, invalid) = rs
+            .reconstruct_verified(&pieces, data.len(), |d| d == data)
+            .unwrap()
+            .unwrap();
+        assert_eq!(rebuilt, data);
+        assert_eq!(invalid, vec![0, 4, 5]);
+
+        // not enough valid pieces
+        assert!(rs
+            .reconstruct_verified(&pieces[..3], data.len(), |d| d == data)
+            .unwrap()
+            .is_none());
+        assert!(rs
+            .reconstruct_verified(&[pieces[1].clone(), pieces[1].clone()], data.len(), |_| {
+                true
+            })
+            .is_err());
+        assert!(ReedSolomon::new(200, 57).is_err());
+    }
+}
@@ -1,13 +1,14 @@
-use crate::config::ShardConfig;
+use crate::config::{ErasureConfig, ShardConfig};
 use crate::error::Error;
 use crate::log_store::load_chunk::EntryBatch;
 use crate::log_store::log_manager::{
-    bytes_to_entries, COL_ENTRY_BATCH, COL_FLOW_MPT_NODES, COL_PAD_DATA_LIST,
-    COL_PAD_DATA_SYNC_HEIGH, PORA_CHUNK_SIZE,
+    bytes_to_entries, COL_ENTRY_BATCH, COL_ERASURE_PIECE, COL_FLOW_MPT_NODES, COL_MISC,
+    COL_PAD_DATA_LIST, COL_PAD_DATA_SYNC_HEIGH, COL_SEAL_STATUS, PORA_CHUNK_SIZE,
 };
 use crate::log_store::seal_task_manager::SealTaskManager;
 use crate::log_store::{
-    metrics, FlowRead, FlowSeal, FlowWrite, MineLoadChunk, SealAnswer, SealTask,
+    metrics, FlowRead, FlowSeal, FlowWrite, LoadSealStatus, MineLoadChunk, SealAnswer, SealStatus,
+    SealTask,
 };
 use crate::{try_option, ZgsKeyValueDB};
 use any::Any;
@@ -15,17 +16,28 @@ use anyhow::{anyhow, bail, Result};
 use append_merkle::{MerkleTreeRead, NodeDatabase, NodeTransaction};
 use itertools::Itertools;
 use kvdb::DBTransaction;
-use parking_lot::RwLock;
-use shared_types::{ChunkArray, DataRoot, FlowProof};
+use parking_lot::{Mutex, RwLock};
+use shared_types::{ChunkArray, DataRoot, ErasurePiece, FlowProof};
 use ssz::{Decode, Encode};
 use ssz_derive::{Decode as DeriveDecode, Encode as DeriveEncode};
 
 use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Instant;
 use std::{any, cmp};
-use tracing::{debug, error, trace};
-use zgs_spec::{BYTES_PER_SECTOR, SEALS_PER_LOAD, SECTORS_PER_LOAD, SECTORS_PER_SEAL};
+use tracing::{debug, error, info, trace, warn};
+use zgs_spec::{
+    BYTES_PER_SECTOR, SEALS_PER_LOAD, SECTORS_PER_LOAD, SECTORS_PER_PRICING, SECTORS_PER_SEAL,
+};
+
+/// Key of the data db marker that the seal status of all entry batches is persisted.
+pub(crate) const SEAL_STATUS_BACKFILLED_KEY: &str = "seal_status_backfilled";
+/// Key of the next batch index to backfill the seal status from, so that the backfill
+/// resumes after restart.
+const SEAL_STATUS_BACKFILL_PROGRESS_KEY: &str = "seal_status_backfill_progress";
+/// Number of entry batches scanned in a transaction to backfill the seal status.
+const SEAL_STATUS_BACKFILL_BATCH_SIZE: usize = 1024;
 
 pub struct FlowStore {
     flow_db: Arc<FlowDBStore>,
@@ -79,6 +91,33 @@ impl FlowStore {
         self.seal_manager.delete_batch_list(batch_list);
         self.data_db.delete_batch_list(batch_list)
     }
+
+    /// Return the unsealed data of a batch, or `None` if the batch is incomplete.
+    pub fn get_batch_data(&self, batch_index: u64) -> Result<Option<Vec<u8>>> {
+        let batch = try_option!(self.data_db.get_entry_batch(batch_index)?);
+        Ok(batch.get_unsealed_data(0, self.config.batch_size))
+    }
+
+    pub fn get_erasure_piece(&self, batch_index: u64) -> Result<Option<ErasurePiece>> {
+        self.data_db.get_erasure_piece(batch_index)
+    }
+
+    pub fn put_erasure_piece(&self, piece: ErasurePiece) -> Result<()> {
+        self.data_db.put_erasure_piece(piece)
+    }
+
+    pub fn get_erasure_config(&self) -> Option<ErasureConfig> {
+        self.config.erasure_config
+    }
+
+    /// Recomputes the merkle root of every stored batch, which is `None` if the batch is
+    /// incomplete, or an error if the batch is corrupted.
+    pub fn for_each_batch_root<F>(&self, f: F) -> Result<()>
+    where
+        F: FnMut(u64, Result<Option<DataRoot>>) -> Result<()>,
+    {
+        self.data_db.for_each_batch_root(f)
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -86,6 +125,8 @@ pub struct FlowConfig {
     pub batch_size: usize,
     pub merkle_node_cache_capacity: usize,
     pub shard_config: Arc<RwLock<ShardConfig>>,
+    /// Erasure coding of batches across shard groups, which is disabled if `None`.
+    pub erasure_config: Option<ErasureConfig>,
 }
 
 impl Default for FlowConfig {
@@ -95,6 +136,7 @@ impl Default for FlowConfig {
             // Each node takes (8+8+32=)48 Bytes, so the default value is 1.5 GB memory size.
             merkle_node_cache_capacity: 32 * 1024 * 1024,
             shard_config: Default::default(),
+            erasure_config: None,
         }
     }
 }
@@ -270,6 +312,8 @@ impl FlowWrite for FlowStore {
     fn truncate(&self, start_index: u64) -> crate::error::Result<()> {
         let mut to_seal_set = self.seal_manager.to_seal_set.write();
         let to_reseal = self.data_db.truncate(start_index, self.config.batch_size)?;
+        self.data_db
+            .truncate_erasure_pieces(start_index / self.config.batch_size as u64)?;
 
         to_seal_set.split_off(&(start_index as usize / SECTORS_PER_SEAL));
         let new_seal_version = self.seal_manager.inc_seal_version();
@@ -294,28 +338,32 @@ impl FlowWrite for FlowStore {
 }
 
 impl FlowSeal for FlowStore {
-    fn pull_seal_chunk(&self, seal_index_max: usize) -> Result<Option<Vec<SealTask>>> {
+    fn pull_seal_chunk(
+        &self,
+        seal_index_max: usize,
+        max_loads: usize,
+    ) -> Result<Option<Vec<SealTask>>> {
         let to_seal_set = self.seal_manager.to_seal_set.read();
         self.seal_manager.update_pull_time();
 
-        let mut to_seal_iter = to_seal_set.iter();
-        let (&first_index, &first_version) = try_option!(to_seal_iter.next());
-        if first_index >= seal_index_max {
-            return Ok(None);
-        }
-
         let mut tasks = Vec::with_capacity(SEALS_PER_LOAD);
+        let mut batch: Option<(usize, EntryBatch)> = None;
+        let mut num_loads = 0;
+        for (&seal_index, &version) in to_seal_set.range(..seal_index_max) {
+            let batch_index = seal_index / SEALS_PER_LOAD;
+            if batch.as_ref().map(|(index, _)| *index) != Some(batch_index) {
+                if num_loads == max_loads {
+                    break;
+                }
+                let batch_data = self
+                    .data_db
+                    .get_entry_batch(batch_index as u64)?
+                    .expect("Lost data chunk in to_seal_set");
+                batch = Some((batch_index, batch_data));
+                num_loads += 1;
+            }
 
-        let batch_data = self
-            .data_db
-            .get_entry_batch((first_index / SEALS_PER_LOAD) as u64)?
-            .expect("Lost data chunk in to_seal_set");
-
-        for (&seal_index, &version) in
-            std::iter::once((&first_index, &first_version)).chain(to_seal_iter.filter(|(&x, _)| {
-                first_index / SEALS_PER_LOAD == x / SEALS_PER_LOAD && x < seal_index_max
-            }))
-        {
+            let (_, batch_data) = batch.as_ref().expect("batch loaded");
             let seal_index_local = seal_index % SEALS_PER_LOAD;
             let non_sealed_data = batch_data
                 .get_non_sealed_data(seal_index_local as u16)
@@ -327,6 +375,9 @@ impl FlowSeal for FlowStore {
             })
         }
 
+        if tasks.is_empty() {
+            return Ok(None);
+        }
         Ok(Some(tasks))
     }
 
@@ -366,6 +417,56 @@ impl FlowSeal for FlowStore {
 
         Ok(())
     }
+
+    fn get_seal_status(&self, pricing_index: u64) -> Result<SealStatus> {
+        const LOADS_PER_PRICING: u64 = (SECTORS_PER_PRICING / SECTORS_PER_LOAD) as u64;
+        const SEALS_PER_PRICING: u64 = (SECTORS_PER_PRICING / SECTORS_PER_SEAL) as u64;
+
+        let out_of_range = || anyhow!("pricing index out of range: {}", pricing_index);
+        let start_load = pricing_index
+            .checked_mul(LOADS_PER_PRICING)
+            .ok_or_else(out_of_range)?;
+        let end_load = start_load
+            .checked_add(LOADS_PER_PRICING)
+            .ok_or_else(out_of_range)?;
+        let start_seal = pricing_index
+            .checked_mul(SEALS_PER_PRICING)
+            .ok_or_else(out_of_range)?;
+        let end_seal = start_seal
+            .checked_add(SEALS_PER_PRICING)
+            .ok_or_else(out_of_range)?;
+
+        let shard_config = *self.config.shard_config.read();
+        let backfill_progress = self.data_db.seal_status_backfill_progress();
+        let mut status = SealStatus {
+            pricing_index,
+            ..Default::default()
+        };
+        for load_index in start_load..end_load {
+            if !shard_config.in_range(load_index) {
+                continue;
+            }
+            match self.data_db.get_seal_status(load_index)? {
+                Some(load_status) => {
+                    status.loads += 1;
+                    status.sealed_sectors += load_status.sealed * SECTORS_PER_SEAL as u64;
+                    status.unsealed_sectors += load_status.unsealed * SECTORS_PER_SEAL as u64;
+                }
+                None if load_index >= backfill_progress => status.unknown_loads += 1,
+                None => {}
+            }
+        }
+
+        let pending_seals = self
+            .seal_manager
+            .to_seal_set
+            .read()
+            .range(start_seal as usize..end_seal as usize)
+            .count();
+        status.pending_sectors = (pending_seals * SECTORS_PER_SEAL) as u64;
+
+        Ok(status)
+    }
 }
 
 #[derive(Debug, PartialEq, DeriveEncode, DeriveDecode)]
@@ -376,11 +477,20 @@ pub struct PadPair {
 
 pub struct FlowDBStore {
     kvdb: Arc<dyn ZgsKeyValueDB>,
+    /// Entry batches from this index may have no seal status before the backfill completes.
+    seal_status_backfill_progress: AtomicU64,
+    /// Serializes the backfill of seal status with the writes of entry batches, so that the
+    /// backfill never overwrites the seal status of a newer entry batch.
+    seal_status_lock: Mutex<()>,
 }
 
 impl FlowDBStore {
     pub fn new(kvdb: Arc<dyn ZgsKeyValueDB>) -> Self {
-        Self { kvdb }
+        Self {
+            kvdb,
+            seal_status_backfill_progress: AtomicU64::new(u64::MAX),
+            seal_status_lock: Mutex::new(()),
+        }
     }
 
     fn put_entry_batch_list(
@@ -391,16 +501,13 @@ impl FlowDBStore {
         let mut completed_batches = Vec::new();
         let mut tx = self.kvdb.transaction();
         for (batch_index, batch) in batch_list {
-            tx.put(
-                COL_ENTRY_BATCH,
-                &batch_index.to_be_bytes(),
-                &batch.as_ssz_bytes(),
-            );
+            put_entry_batch(&mut tx, batch_index, &batch);
             if let Some(root) = batch.build_root(batch_index == 0)? {
                 trace!("complete batch: index={}", batch_index);
                 completed_batches.push((batch_index, root));
             }
         }
+        let _guard = self.seal_status_lock.lock();
         self.kvdb.write(tx)?;
         metrics::PUT_ENTRY_BATCH_LIST.update_since(start_time);
         Ok(completed_batches)
@@ -409,12 +516,9 @@ impl FlowDBStore {
     fn put_entry_raw(&self, batch_list: Vec<(u64, EntryBatch)>) -> Result<()> {
         let mut tx = self.kvdb.transaction();
         for (batch_index, batch) in batch_list {
-            tx.put(
-                COL_ENTRY_BATCH,
-                &batch_index.to_be_bytes(),
-                &batch.as_ssz_bytes(),
-            );
+            put_entry_batch(&mut tx, batch_index, &batch);
         }
+        let _guard = self.seal_status_lock.lock();
         self.kvdb.write(tx)?;
         Ok(())
     }
@@ -424,6 +528,146 @@ impl FlowDBStore {
         Ok(Some(EntryBatch::from_ssz_bytes(&raw).map_err(Error::from)?))
     }
 
+    fn get_seal_status(&self, batch_index: u64) -> Result<Option<LoadSealStatus>> {
+        let raw = try_option!(self.kvdb.get(COL_SEAL_STATUS, &batch_index.to_be_bytes())?);
+        Ok(Some(
+            LoadSealStatus::from_ssz_bytes(&raw).map_err(Error::from)?,
+        ))
+    }
+
+    /// Returns the batch index from which the seal status may be missing because the backfill
+    /// is in progress, or `u64::MAX` if all entry batches have the seal status.
+    pub fn seal_status_backfill_progress(&self) -> u64 {
+        self.seal_status_backfill_progress.load(Ordering::Relaxed)
+    }
+
+    /// Persist the seal status of entry batches which are written before `COL_SEAL_STATUS` is
+    /// introduced in a background thread. It only scans the entry batches once for a database.
+    pub fn start_seal_status_backfill(self: &Arc<Self>) -> Result<()> {
+        if self
+            .kvdb
+            .get(COL_MISC, SEAL_STATUS_BACKFILLED_KEY.as_bytes())?
+            .is_some()
+        {
+            return Ok(());
+        }
+        if self.kvdb.iter(COL_ENTRY_BATCH).next().is_none() {
+            let mut tx = self.kvdb.transaction();
+            tx.put(COL_MISC, SEAL_STATUS_BACKFILLED_KEY.as_bytes(), &[]);
+            self.kvdb.write(tx)?;
+            return Ok(());
+        }
+
+        let start = match self
+            .kvdb
+            .get(COL_MISC, SEAL_STATUS_BACKFILL_PROGRESS_KEY.as_bytes())?
+        {
+            Some(raw) => u64::from_be_bytes(raw.try_into().map_err(|e| anyhow!("{:?}", e))?),
+            None => 0,
+        };
+        self.seal_status_backfill_progress
+            .store(start, Ordering::Relaxed);
+
+        let store = self.clone();
+        std::thread::Builder::new()
+            .name("seal_status_backfill".to_string())
+            .spawn(move || {
+                if let Err(e) = store.backfill_seal_status(start) {
+                    error!("Failed to backfill seal status: {:?}", e);
+                }
+            })?;
+        Ok(())
+    }
+
+    fn backfill_seal_status(&self, start: u64) -> Result<()> {
+        let mut num_backfilled = 0;
+        let mut pending = Vec::new();
+        let mut scanned = 0;
+        for item in self.kvdb.iter(COL_ENTRY_BATCH) {
+            let (key, value) = item?;
+            let batch_index = decode_batch_index(key.as_ref())? as u64;
+            if batch_index < start {
+                continue;
+            }
+            scanned += 1;
+            if self.kvdb.get(COL_SEAL_STATUS, &key)?.is_none() {
+                match EntryBatch::from_ssz_bytes(&value) {
+                    Ok(batch) => pending.push((batch_index, batch.seal_status())),
+                    // Corrupted batches are left to the db check.
+                    Err(e) => {
+                        warn!(%batch_index, "Skip seal status of corrupted entry batch: {:?}", e)
+                    }
+                }
+            }
+            if scanned % SEAL_STATUS_BACKFILL_BATCH_SIZE == 0 {
+                num_backfilled +=
+                    self.put_backfilled_seal_status(&pending, Some(batch_index + 1))?;
+                pending.clear();
+            }
+        }
+        num_backfilled += self.put_backfilled_seal_status(&pending, None)?;
+
+        info!(
+            num = num_backfilled,
+            "Seal status of entry batches backfilled"
+        );
+        Ok(())
+    }
+
+    /// Writes the backfilled seal status with the progress, or the marker that the backfill
+    /// completes if `next_index` is `None`. Returns the number of seal status written.
+    fn put_backfilled_seal_status(
+        &self,
+        backfilled: &[(u64, LoadSealStatus)],
+        next_index: Option<u64>,
+    ) -> Result<usize> {
+        let _guard = self.seal_status_lock.lock();
+        let mut num_written = 0;
+        let mut tx = self.kvdb.transaction();
+        for (batch_index, status) in backfilled {
+            let key = batch_index.to_be_bytes();
+            // Skip the entry batches written or deleted since they were scanned.
+            if self.kvdb.get(COL_SEAL_STATUS, &key)?.is_some()
+                || self.kvdb.get(COL_ENTRY_BATCH, &key)?.is_none()
+            {
+                continue;
+            }
+            tx.put(COL_SEAL_STATUS, &key, &status.as_ssz_bytes());
+            num_written += 1;
+        }
+        match next_index {
+            Some(index) => tx.put(
+                COL_MISC,
+                SEAL_STATUS_BACKFILL_PROGRESS_KEY.as_bytes(),
+                &index.to_be_bytes(),
+            ),
+            None => {
+                tx.delete(COL_MISC, SEAL_STATUS_BACKFILL_PROGRESS_KEY.as_bytes());
+                tx.put(COL_MISC, SEAL_STATUS_BACKFILLED_KEY.as_bytes(), &[]);
+            }
+        }
+        self.kvdb.write(tx)?;
+        self.seal_status_backfill_progress
+            .store(next_index.unwrap_or(u64::MAX), Ordering::Relaxed);
+        Ok(num_written)
+    }
+
+    fn for_each_batch_root<F>(&self, mut f: F) -> Result<()>
+    where
+        F: FnMut(u64, Result<Option<DataRoot>>) -> Result<()>,
+    {
+        for item in self.kvdb.iter(COL_ENTRY_BATCH) {
+            let (key, value) = item?;
+            let batch_index = decode_batch_index(key.as_ref())? as u64;
+            let root = match EntryBatch::from_ssz_bytes(&value) {
+                Ok(batch) => batch.build_root(batch_index == 0),
+                Err(e) => Err(Error::from(e).into()),
+            };
+            f(batch_index, root)?;
+        }
+        Ok(())
+    }
+
     fn truncate(&self, start_index: u64, batch_size: usize) -> crate::error::Result<Vec<usize>> {
         let mut tx = self.kvdb.transaction();
         let mut start_batch_index = start_index / batch_size as u64;
@@ -437,13 +681,9 @@ impl FlowDBStore {
                     .map(|x| start_batch_index as usize * SEALS_PER_LOAD + x as usize)
                     .collect();
                 if !first_batch.is_empty() {
-                    tx.put(
-                        COL_ENTRY_BATCH,
-                        &start_batch_index.to_be_bytes(),
-                        &first_batch.as_ssz_bytes(),
-                    );
+                    put_entry_batch(&mut tx, start_batch_index, &first_batch);
                 } else {
-                    tx.delete(COL_ENTRY_BATCH, &start_batch_index.to_be_bytes());
+                    delete_entry_batch(&mut tx, start_batch_index);
                 }
             }
 
@@ -462,9 +702,10 @@ impl FlowDBStore {
                 return Ok(index_to_reseal);
             }
         };
-        for batch_index in start_batch_index as usize..=end {
-            tx.delete(COL_ENTRY_BATCH, &batch_index.to_be_bytes());
+        for batch_index in start_batch_index..=end as u64 {
+            delete_entry_batch(&mut tx, batch_index);
         }
+        let _guard = self.seal_status_lock.lock();
         self.kvdb.write(tx)?;
         Ok(index_to_reseal)
     }
@@ -472,7 +713,40 @@ impl FlowDBStore {
     fn delete_batch_list(&self, batch_list: &[u64]) -> Result<()> {
         let mut tx = self.kvdb.transaction();
         for i in batch_list {
-            tx.delete(COL_ENTRY_BATCH, &i.to_be_bytes());
+            delete_entry_batch(&mut tx, *i);
+            tx.delete(COL_ERASURE_PIECE, &i.to_be_bytes());
+        }
+        let _guard = self.seal_status_lock.lock();
+        Ok(self.kvdb.write(tx)?)
+    }
+
+    fn get_erasure_piece(&self, batch_index: u64) -> Result<Option<ErasurePiece>> {
+        let raw = try_option!(self
+            .kvdb
+            .get(COL_ERASURE_PIECE, &batch_index.to_be_bytes())?);
+        Ok(Some(
+            ErasurePiece::from_ssz_bytes(&raw).map_err(Error::from)?,
+        ))
+    }
+
+    fn put_erasure_piece(&self, piece: ErasurePiece) -> Result<()> {
+        let mut tx = self.kvdb.transaction();
+        tx.put(
+            COL_ERASURE_PIECE,
+            &piece.batch_index.to_be_bytes(),
+            &piece.as_ssz_bytes(),
+        );
+        Ok(self.kvdb.write(tx)?)
+    }
+
+    /// Delete the erasure pieces of batches from `start_batch_index`, whose data are reverted.
+    fn truncate_erasure_pieces(&self, start_batch_index: u64) -> Result<()> {
+        let mut tx = self.kvdb.transaction();
+        for item in self.kvdb.iter(COL_ERASURE_PIECE) {
+            let (key, _) = item?;
+            if decode_batch_index(key.as_ref())? as u64 >= start_batch_index {
+                tx.delete(COL_ERASURE_PIECE, key.as_ref());
+            }
         }
         Ok(self.kvdb.write(tx)?)
     }
@@ -520,6 +794,25 @@ impl FlowDBStore {
     }
 }
 
+/// Write an entry batch along with its seal status.
+fn put_entry_batch(tx: &mut DBTransaction, batch_index: u64, batch: &EntryBatch) {
+    tx.put(
+        COL_ENTRY_BATCH,
+        &batch_index.to_be_bytes(),
+        &batch.as_ssz_bytes(),
+    );
+    tx.put(
+        COL_SEAL_STATUS,
+        &batch_index.to_be_bytes(),
+        &batch.seal_status().as_ssz_bytes(),
+    );
+}
+
+fn delete_entry_batch(tx: &mut DBTransaction, batch_index: u64) {
+    tx.delete(COL_ENTRY_BATCH, &batch_index.to_be_bytes());
+    tx.delete(COL_SEAL_STATUS, &batch_index.to_be_bytes());
+}
+
 #[derive(DeriveEncode, DeriveDecode, Clone, Debug)]
 #[ssz(enum_behaviour = "union")]
 pub enum BatchRoot {
@@ -19,7 +19,7 @@ use zgs_spec::{
     SECTORS_PER_SEAL,
 };
 
-use super::SealAnswer;
+use super::{LoadSealStatus, SealAnswer};
 pub use chunk_data::EntryBatchData;
 use seal::SealInfo;
 
@@ -55,6 +55,23 @@ impl EntryBatch {
         }
     }
 
+    /// Return the number of sealed seals and complete seals which are not sealed yet.
+    pub fn seal_status(&self) -> LoadSealStatus {
+        let mut status = LoadSealStatus::default();
+        for seal_index in 0..SEALS_PER_LOAD {
+            if self.seal.is_sealed(seal_index as u16) {
+                status.sealed += 1;
+            } else if self
+                .data
+                .get(seal_index * BYTES_PER_SEAL, BYTES_PER_SEAL)
+                .is_some()
+            {
+                status.unsealed += 1;
+            }
+        }
+        status
+    }
+
     pub fn get_non_sealed_data(&self, seal_index: u16) -> Option<[u8; BYTES_PER_SEAL]> {
         if !self.seal.is_sealed(seal_index) {
             let loaded_slice = self
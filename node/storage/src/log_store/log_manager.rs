@@ -1,16 +1,22 @@
-use crate::config::ShardConfig;
+use crate::config::{ErasureConfig, ShardConfig};
+use crate::log_store::erasure::ReedSolomon;
 use crate::log_store::flow_store::{
     batch_iter_sharded, FlowConfig, FlowDBStore, FlowStore, PadPair,
 };
-use crate::log_store::tx_store::{BlockHashAndSubmissionIndex, TransactionStore, TxStatus};
+use crate::log_store::snapshot::{self, SnapshotSummary};
+use crate::log_store::tx_access::TxAccessIndex;
+use crate::log_store::tx_store::{
+    BlockHashAndSubmissionIndex, TransactionStore, TxStatus, TxStatusUpdate, NEXT_TX_KEY,
+};
 use crate::log_store::{
     FlowRead, FlowSeal, FlowWrite, LogStoreChunkRead, LogStoreChunkWrite, LogStoreRead,
-    LogStoreWrite, MineLoadChunk, SealAnswer, SealTask,
+    LogStoreWrite, MineLoadChunk, SealAnswer, SealStatus, SealTask,
 };
+use crate::segment_db::{SegmentConfig, SegmentDB};
 use crate::{try_option, ZgsKeyValueDB};
 use anyhow::{anyhow, bail, Result};
 use append_merkle::{Algorithm, MerkleTreeRead, Sha3Algorithm};
-use ethereum_types::H256;
+use ethereum_types::{H256, U256};
 use kvdb_rocksdb::{Database, DatabaseConfig};
 use merkle_light::merkle::{log2_pow2, MerkleTree};
 use merkle_tree::RawLeafSha3Algorithm;
@@ -19,16 +25,20 @@ use parking_lot::RwLock;
 use rayon::iter::ParallelIterator;
 use rayon::prelude::ParallelSlice;
 use shared_types::{
-    bytes_to_chunks, compute_padded_chunk_size, compute_segment_size, Chunk, ChunkArray,
-    ChunkArrayWithProof, ChunkWithProof, DataRoot, FlowProof, FlowRangeProof, Merkle, Transaction,
+    bytes_to_chunks, compute_padded_chunk_size, compute_segment_size, split_nodes, Chunk,
+    ChunkArray, ChunkArrayWithProof, ChunkWithProof, DataRoot, ErasurePiece, FlowProof,
+    FlowRangeProof, Merkle, Transaction, TransactionWithProof,
 };
 use std::cmp::Ordering;
+use std::fs::File;
+use std::io::{BufReader, Write};
 
 use std::path::Path;
 use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use tracing::{debug, error, info, instrument, trace, warn};
+use zgs_spec::BYTES_PER_LOAD;
 
 use crate::log_store::metrics;
 
@@ -46,7 +56,12 @@ pub const COL_FLOW_MPT_NODES: u32 = 5; // flow db
 pub const COL_BLOCK_PROGRESS: u32 = 6; // flow db
 pub const COL_PAD_DATA_LIST: u32 = 7; // flow db
 pub const COL_PAD_DATA_SYNC_HEIGH: u32 = 8; // data db
-pub const COL_NUM: u32 = 9;
+pub const COL_TX_STREAM_ID_INDEX: u32 = 9; // flow db
+pub const COL_ERASURE_PIECE: u32 = 10; // data db
+pub const COL_SEGMENT_INDEX: u32 = 11; // data db
+pub const COL_SEAL_STATUS: u32 = 12; // data db
+pub const COL_TX_LAST_ACCESS: u32 = 13; // data db
+pub const COL_NUM: u32 = 14;
 
 pub const DATA_DB_KEY: &str = "data_db";
 pub const FLOW_DB_KEY: &str = "flow_db";
@@ -74,6 +89,9 @@ pub struct LogManager {
     tx_store: TransactionStore,
     flow_store: Arc<FlowStore>,
     merkle: RwLock<MerkleManager>,
+    erasure_codec: Option<ReedSolomon>,
+    /// The last access time of transactions, which is only tracked if enabled.
+    tx_access: Option<RwLock<TxAccessIndex>>,
 }
 
 struct MerkleManager {
@@ -162,6 +180,27 @@ impl MerkleManager {
 #[derive(Clone, Default)]
 pub struct LogConfig {
     pub flow: FlowConfig,
+    /// Stores entry batches in segment files instead of RocksDB if set.
+    pub entry_batch_segments: Option<SegmentConfig>,
+    /// Tracks the last access time of transactions for the LRU prune policy.
+    pub track_tx_access: bool,
+}
+
+/// Result of the database integrity check.
+#[derive(Debug, Default)]
+pub struct IntegrityReport {
+    pub checked_batches: u64,
+    pub checked_txs: u64,
+    /// Batches that failed to decode or mismatched with the flow merkle tree.
+    pub corrupted_batches: Vec<u64>,
+    /// Finalized transactions with corrupted or missing data.
+    pub inconsistent_txs: Vec<u64>,
+}
+
+impl IntegrityReport {
+    pub fn is_consistent(&self) -> bool {
+        self.corrupted_batches.is_empty() && self.inconsistent_txs.is_empty()
+    }
 }
 
 impl LogStoreChunkWrite for LogManager {
@@ -362,7 +401,34 @@ impl LogStoreWrite for LogManager {
     }
 
     fn prune_tx(&self, tx_seq: u64) -> crate::error::Result<()> {
-        self.tx_store.prune_tx(tx_seq)
+        self.tx_store.prune_tx(tx_seq)?;
+        if let Some(tx_access) = &self.tx_access {
+            tx_access.write().remove(tx_seq);
+            self.data_db
+                .delete(COL_TX_LAST_ACCESS, &tx_seq.to_be_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn persist_tx_access(&self) -> Result<()> {
+        let dirty = match &self.tx_access {
+            Some(tx_access) => tx_access.write().take_dirty(),
+            None => return Ok(()),
+        };
+        if dirty.is_empty() {
+            return Ok(());
+        }
+
+        let mut db_tx = self.data_db.transaction();
+        for (tx_seq, time) in dirty {
+            db_tx.put(
+                COL_TX_LAST_ACCESS,
+                &tx_seq.to_be_bytes(),
+                &time.to_be_bytes(),
+            );
+        }
+        self.data_db.write(db_tx)?;
+        Ok(())
     }
 
     fn put_sync_progress(&self, progress: (u64, H256, Option<Option<u64>>)) -> Result<()> {
@@ -390,7 +456,19 @@ impl LogStoreWrite for LogManager {
             + merkle.last_chunk_merkle.leaves() as u64;
         self.flow_store.truncate(start_index)?;
         let start = if tx_seq != u64::MAX { tx_seq + 1 } else { 0 };
-        self.tx_store.remove_tx_after(start)
+        let reverted = self.tx_store.remove_tx_after(start)?;
+
+        // The access time may be persisted before tracking is disabled.
+        if let Some(tx_access) = &self.tx_access {
+            tx_access.write().remove_after(start);
+        }
+        let mut db_tx = self.data_db.transaction();
+        for tx in reverted.iter() {
+            db_tx.delete(COL_TX_LAST_ACCESS, &tx.seq.to_be_bytes());
+        }
+        self.data_db.write(db_tx)?;
+
+        Ok(reverted)
     }
 
     fn validate_and_insert_range_proof(
@@ -417,6 +495,64 @@ impl LogStoreWrite for LogManager {
         self.flow_store.update_shard_config(shard_config)
     }
 
+    fn put_erasure_piece(
+        &self,
+        batch_index: u64,
+        piece_index: usize,
+        pieces: Vec<ErasurePiece>,
+    ) -> Result<Option<Vec<u64>>> {
+        let codec = self
+            .erasure_codec
+            .as_ref()
+            .ok_or_else(|| anyhow!("erasure coding disabled"))?;
+        if piece_index >= codec.total_shards() {
+            return Ok(None);
+        }
+
+        // The piece could not be verified alone, so encode it from the verified batch.
+        let (data, proof, invalid_pieces) =
+            match self.reconstruct_batch(codec, batch_index, pieces)? {
+                Some(batch) => batch,
+                None => return Ok(None),
+            };
+        self.flow_store.put_erasure_piece(ErasurePiece {
+            batch_index,
+            piece_index: piece_index as u64,
+            data: codec.encode_piece(&data, piece_index)?,
+            proof,
+        })?;
+        Ok(Some(invalid_pieces))
+    }
+
+    fn rebuild_batch(
+        &self,
+        batch_index: u64,
+        pieces: Vec<ErasurePiece>,
+    ) -> Result<Option<Vec<u64>>> {
+        let codec = self
+            .erasure_codec
+            .as_ref()
+            .ok_or_else(|| anyhow!("erasure coding disabled"))?;
+        if batch_index == 0 || !self.flow_store.get_shard_config().in_range(batch_index) {
+            bail!("rebuild batch not in shard: batch_index={}", batch_index);
+        }
+
+        let (data, _, invalid_pieces) = match self.reconstruct_batch(codec, batch_index, pieces)? {
+            Some(batch) => batch,
+            None => return Ok(None),
+        };
+
+        let mut merkle = self.merkle.write();
+        self.append_entries(
+            ChunkArray {
+                data,
+                start_index: batch_index * PORA_CHUNK_SIZE as u64,
+            },
+            &mut merkle,
+        )?;
+        Ok(Some(invalid_pieces))
+    }
+
     fn submit_seal_result(&self, answers: Vec<SealAnswer>) -> Result<()> {
         self.flow_store.submit_seal_result(answers)
     }
@@ -494,6 +630,7 @@ impl LogStoreChunkRead for LogManager {
             .flow_store
             .get_entries(start_flow_index, end_flow_index)?);
         tx_chunk.start_index -= tx.start_entry_index;
+        self.record_tx_access(tx_seq);
         Ok(Some(tx_chunk))
     }
 
@@ -589,14 +726,50 @@ impl LogStoreRead for LogManager {
         }))
     }
 
+    fn get_tx_with_proof_by_seq_number(
+        &self,
+        seq: u64,
+        merkle_tx_seq: Option<u64>,
+    ) -> Result<Option<TransactionWithProof>> {
+        let tx = try_option!(self.tx_store.get_tx_by_seq_number(seq)?);
+        let mut proofs = Vec::with_capacity(tx.merkle_nodes.len());
+        let mut position = tx.start_entry_index;
+        for &(depth, _) in tx.merkle_nodes.iter() {
+            proofs.push(self.gen_node_proof(position, depth, merkle_tx_seq)?);
+            position += Transaction::num_entries_of_node(depth) as u64;
+        }
+        Ok(Some(TransactionWithProof {
+            transaction: tx,
+            proofs,
+        }))
+    }
+
     fn get_tx_status(&self, tx_seq: u64) -> Result<Option<TxStatus>> {
         self.tx_store.get_tx_status(tx_seq)
     }
 
+    fn subscribe_tx_status(&self) -> tokio::sync::broadcast::Receiver<TxStatusUpdate> {
+        self.tx_store.subscribe_tx_status()
+    }
+
+    fn get_tx_seq_list_by_stream_id(
+        &self,
+        stream_id: &U256,
+        from_seq: u64,
+        limit: usize,
+    ) -> Result<Vec<u64>> {
+        self.tx_store
+            .get_tx_seq_list_by_stream_id(stream_id, from_seq, limit)
+    }
+
     fn check_tx_completed(&self, tx_seq: u64) -> crate::error::Result<bool> {
         self.tx_store.check_tx_completed(tx_seq)
     }
 
+    fn get_finalized_tx_ranges(&self) -> Result<Vec<std::ops::Range<u64>>> {
+        self.tx_store.get_finalized_tx_ranges()
+    }
+
     fn validate_range_proof(&self, tx_seq: u64, data: &ChunkArrayWithProof) -> Result<bool> {
         let tx = self
             .get_tx_by_seq_number(tx_seq)?
@@ -659,14 +832,30 @@ impl LogStoreRead for LogManager {
         self.tx_store.check_tx_pruned(tx_seq)
     }
 
-    fn pull_seal_chunk(&self, seal_index_max: usize) -> Result<Option<Vec<SealTask>>> {
-        self.flow_store.pull_seal_chunk(seal_index_max)
+    fn pull_seal_chunk(
+        &self,
+        seal_index_max: usize,
+        max_loads: usize,
+    ) -> Result<Option<Vec<SealTask>>> {
+        self.flow_store.pull_seal_chunk(seal_index_max, max_loads)
+    }
+
+    fn get_seal_status(&self, pricing_index: u64) -> Result<SealStatus> {
+        self.flow_store.get_seal_status(pricing_index)
     }
 
     fn get_num_entries(&self) -> Result<u64> {
         self.flow_store.get_num_entries()
     }
 
+    fn get_tx_access_list(&self) -> Result<Vec<(u64, u64)>> {
+        Ok(self
+            .tx_access
+            .as_ref()
+            .map(|tx_access| tx_access.read().ordered_list())
+            .unwrap_or_default())
+    }
+
     fn load_sealed_data(&self, chunk_index: u64) -> Result<Option<MineLoadChunk>> {
         self.flow_store.load_sealed_data(chunk_index)
     }
@@ -674,6 +863,41 @@ impl LogStoreRead for LogManager {
     fn get_shard_config(&self) -> ShardConfig {
         self.flow_store.get_shard_config()
     }
+
+    fn get_erasure_config(&self) -> Option<ErasureConfig> {
+        self.flow_store.get_erasure_config()
+    }
+
+    fn get_erasure_piece(
+        &self,
+        batch_index: u64,
+        piece_index: usize,
+    ) -> Result<Option<ErasurePiece>> {
+        let codec = try_option!(self.erasure_codec.as_ref());
+        // The first sector of the first batch is never written, so it is not encoded.
+        if batch_index == 0 || piece_index >= codec.total_shards() {
+            return Ok(None);
+        }
+
+        if let Some(piece) = self.flow_store.get_erasure_piece(batch_index)? {
+            if piece.piece_index == piece_index as u64 {
+                return Ok(Some(piece));
+            }
+        }
+
+        let data = try_option!(self.flow_store.get_batch_data(batch_index)?);
+        let proof = self
+            .merkle
+            .read_recursive()
+            .pora_chunks_merkle
+            .gen_proof(batch_index as usize)?;
+        Ok(Some(ErasurePiece {
+            batch_index,
+            piece_index: piece_index as u64,
+            data: codec.encode_piece(&data, piece_index)?,
+            proof,
+        }))
+    }
 }
 
 impl LogManager {
@@ -682,11 +906,28 @@ impl LogManager {
         flow_path: impl AsRef<Path>,
         data_path: impl AsRef<Path>,
     ) -> Result<Self> {
+        let (flow_db_source, data_db_source) = Self::open_rocksdb(&config, flow_path, data_path)?;
+        Self::new(flow_db_source, data_db_source, config)
+    }
+
+    fn open_rocksdb(
+        config: &LogConfig,
+        flow_path: impl AsRef<Path>,
+        data_path: impl AsRef<Path>,
+    ) -> Result<(Arc<dyn ZgsKeyValueDB>, Arc<dyn ZgsKeyValueDB>)> {
         let mut db_config = DatabaseConfig::with_columns(COL_NUM);
         db_config.enable_statistics = true;
-        let flow_db_source = Arc::new(Database::open(&db_config, flow_path)?);
-        let data_db_source = Arc::new(Database::open(&db_config, data_path)?);
-        Self::new(flow_db_source, data_db_source, config)
+        let flow_db: Arc<dyn ZgsKeyValueDB> = Arc::new(Database::open(&db_config, flow_path)?);
+        let mut data_db: Arc<dyn ZgsKeyValueDB> = Arc::new(Database::open(&db_config, data_path)?);
+        if let Some(segment_config) = &config.entry_batch_segments {
+            data_db = Arc::new(SegmentDB::open(
+                data_db,
+                COL_ENTRY_BATCH,
+                COL_SEGMENT_INDEX,
+                segment_config.clone(),
+            )?);
+        }
+        Ok((flow_db, data_db))
     }
 
     pub fn memorydb(config: LogConfig) -> Result<Self> {
@@ -695,7 +936,7 @@ impl LogManager {
         Self::new(flow_db, data_db, config)
     }
 
-    fn new(
+    pub(crate) fn new(
         flow_db_source: Arc<dyn ZgsKeyValueDB>,
         data_db_source: Arc<dyn ZgsKeyValueDB>,
         config: LogConfig,
@@ -703,6 +944,7 @@ impl LogManager {
         let tx_store = TransactionStore::new(flow_db_source.clone(), data_db_source.clone())?;
         let flow_db = Arc::new(FlowDBStore::new(flow_db_source.clone()));
         let data_db = Arc::new(FlowDBStore::new(data_db_source.clone()));
+        data_db.start_seal_status_backfill()?;
         let flow_store = Arc::new(FlowStore::new(
             flow_db.clone(),
             data_db.clone(),
@@ -808,12 +1050,30 @@ impl LogManager {
             last_chunk_merkle,
         });
 
+        let erasure_codec = match config.flow.erasure_config {
+            Some(c) => Some(ReedSolomon::new(c.data_shards, c.parity_shards)?),
+            None => None,
+        };
+
+        let tx_access = if config.track_tx_access {
+            let mut persisted = Vec::new();
+            for r in data_db_source.iter(COL_TX_LAST_ACCESS) {
+                let (key, value) = r?;
+                persisted.push((decode_u64(&key)?, decode_u64(&value)?));
+            }
+            Some(RwLock::new(TxAccessIndex::new(persisted)))
+        } else {
+            None
+        };
+
         let log_manager = Self {
             flow_db: flow_db_source,
             data_db: data_db_source,
             tx_store,
             flow_store,
             merkle,
+            erasure_codec,
+            tx_access,
         };
 
         if let Some(tx) = last_tx_to_insert {
@@ -830,6 +1090,92 @@ impl LogManager {
         Ok(log_manager)
     }
 
+    /// Updates the access time of the transaction coarsely in memory, so that reading chunks
+    /// mostly takes the read lock only and never writes the database.
+    fn record_tx_access(&self, tx_seq: u64) {
+        let tx_access = match &self.tx_access {
+            Some(tx_access) => tx_access,
+            None => return,
+        };
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        if !tx_access.read().is_fresh(tx_seq, now) {
+            tx_access.write().update(tx_seq, now);
+        }
+    }
+
+    /// Reconstructs the batch data from erasure coded pieces along with the proof of batch root
+    /// in flow, and the indexes of pieces that are invalid. Each piece is verified by its proof,
+    /// and against the batch rebuilt from `data_shards` verified pieces. Returns `None` if not
+    /// enough valid pieces to rebuild the batch root.
+    fn reconstruct_batch(
+        &self,
+        codec: &ReedSolomon,
+        batch_index: u64,
+        pieces: Vec<ErasurePiece>,
+    ) -> Result<Option<(Vec<u8>, FlowProof, Vec<u64>)>> {
+        let mut batch_proof: Option<FlowProof> = None;
+        let mut coded_pieces = Vec::with_capacity(pieces.len());
+        let mut invalid_pieces = vec![];
+        for piece in pieces {
+            if piece.batch_index != batch_index || !self.validate_erasure_proof(&piece) {
+                invalid_pieces.push(piece.piece_index);
+                continue;
+            }
+            // All pieces should be encoded from the same batch data.
+            match &batch_proof {
+                Some(proof) if proof.item() != piece.proof.item() => {
+                    invalid_pieces.push(piece.piece_index);
+                    continue;
+                }
+                Some(_) => {}
+                None => batch_proof = Some(piece.proof.clone()),
+            }
+            coded_pieces.push((piece.piece_index as usize, piece.data));
+        }
+        let batch_proof = match batch_proof {
+            Some(proof) => proof,
+            None => return Ok(None),
+        };
+
+        let verified = codec.reconstruct_verified(&coded_pieces, BYTES_PER_LOAD, |data| {
+            match data_to_merkle_leaves(data) {
+                Ok(leaves) => Merkle::new(leaves, 0, None).root() == batch_proof.item(),
+                Err(_) => false,
+            }
+        });
+        let (data, mismatched) = match verified {
+            Ok(Some(verified)) => verified,
+            Ok(None) => return Ok(None),
+            Err(e) => {
+                warn!(%batch_index, %e, "Failed to reconstruct batch from erasure pieces");
+                return Ok(None);
+            }
+        };
+        invalid_pieces.extend(mismatched.into_iter().map(|index| index as u64));
+
+        Ok(Some((data, batch_proof, invalid_pieces)))
+    }
+
+    fn validate_erasure_proof(&self, piece: &ErasurePiece) -> bool {
+        // `item()` and `root()` panic on an empty proof.
+        if piece.proof.lemma().is_empty() {
+            return false;
+        }
+
+        piece
+            .proof
+            .validate::<Sha3Algorithm>(&piece.proof.item(), piece.batch_index as usize)
+            .is_ok()
+            && self
+                .merkle
+                .read_recursive()
+                .pora_chunks_merkle
+                .check_root(&piece.proof.root())
+    }
+
     fn gen_proof(&self, flow_index: u64, maybe_root: Option<DataRoot>) -> Result<FlowProof> {
         match maybe_root {
             None => self.gen_proof_at_version(flow_index, None),
@@ -882,6 +1228,30 @@ impl LogManager {
         entry_proof(&top_proof, &sub_proof)
     }
 
+    /// Generates the proof of a subtree in the flow starting at `flow_index`. Subtrees smaller
+    /// than a PoRA chunk are proved by the first entry, and others by the first PoRA chunk in
+    /// the top tree, so that the data of large subtrees is not required.
+    fn gen_node_proof(
+        &self,
+        flow_index: u64,
+        depth: usize,
+        maybe_tx_seq: Option<u64>,
+    ) -> Result<FlowProof> {
+        if Transaction::num_entries_of_node(depth) < PORA_CHUNK_SIZE {
+            return self.gen_proof_at_version(flow_index, maybe_tx_seq);
+        }
+
+        let merkle = self.merkle.read_recursive();
+        let seg_index = sector_to_segment(flow_index);
+        match maybe_tx_seq {
+            None => merkle.pora_chunks_merkle.gen_proof(seg_index),
+            Some(tx_seq) => merkle
+                .pora_chunks_merkle
+                .at_version(tx_seq)?
+                .gen_proof(seg_index),
+        }
+    }
+
     #[instrument(skip(self, merkle))]
     fn append_subtree_list(
         &self,
@@ -1100,6 +1470,127 @@ impl LogManager {
         &self.flow_store
     }
 
+    /// Recomputes the merkle roots of all stored batches and compares them with the flow
+    /// merkle tree, then checks the data of all finalized transactions.
+    pub fn check_integrity(&self) -> Result<IntegrityReport> {
+        let mut report = IntegrityReport::default();
+
+        {
+            let merkle = self.merkle.read();
+            let num_batches = merkle.pora_chunks_merkle.leaves() as u64;
+            self.flow_store.for_each_batch_root(|batch_index, root| {
+                report.checked_batches += 1;
+                let root = match root {
+                    Ok(Some(root)) => root,
+                    // Incomplete batch, whose root is not in the flow merkle tree.
+                    Ok(None) => return Ok(()),
+                    Err(e) => {
+                        warn!(%batch_index, %e, "Failed to load entry batch");
+                        report.corrupted_batches.push(batch_index);
+                        return Ok(());
+                    }
+                };
+
+                if batch_index >= num_batches {
+                    warn!(%batch_index, %num_batches, "Entry batch out of flow merkle tree");
+                    report.corrupted_batches.push(batch_index);
+                } else if let Some(expected) =
+                    merkle.pora_chunks_merkle.leaf_at(batch_index as usize)?
+                {
+                    if expected != root {
+                        warn!(%batch_index, ?expected, ?root, "Entry batch root mismatch");
+                        report.corrupted_batches.push(batch_index);
+                    }
+                }
+                Ok(())
+            })?;
+        }
+
+        for tx_seq in 0..self.tx_store.next_tx_seq() {
+            report.checked_txs += 1;
+            let tx = match self.tx_store.get_tx_by_seq_number(tx_seq) {
+                Ok(Some(tx)) => tx,
+                Ok(None) => {
+                    warn!(%tx_seq, "Transaction missing");
+                    report.inconsistent_txs.push(tx_seq);
+                    continue;
+                }
+                Err(e) => {
+                    warn!(%tx_seq, %e, "Failed to load transaction");
+                    report.inconsistent_txs.push(tx_seq);
+                    continue;
+                }
+            };
+
+            if !self.tx_store.check_tx_completed(tx_seq)? {
+                continue;
+            }
+
+            let tx_end_index = tx.start_entry_index + bytes_to_entries(tx.size);
+            let start_batch = tx.start_entry_index / PORA_CHUNK_SIZE as u64;
+            let end_batch = (tx_end_index + PORA_CHUNK_SIZE as u64 - 1) / PORA_CHUNK_SIZE as u64;
+            let corrupted = report
+                .corrupted_batches
+                .iter()
+                .any(|b| *b >= start_batch && *b < end_batch);
+            if corrupted || !self.check_data_completed(tx.start_entry_index, tx_end_index)? {
+                warn!(%tx_seq, %corrupted, "Finalized transaction with data corrupted or missing");
+                report.inconsistent_txs.push(tx_seq);
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Deletes the corrupted batches and resets the inconsistent transactions to unfinalized.
+    /// Note, the auto sync may have already passed the reset transactions, so the caller
+    /// should queue them to sync from peers again.
+    pub fn repair(&self, report: &IntegrityReport) -> Result<()> {
+        if !report.corrupted_batches.is_empty() {
+            self.flow_store
+                .delete_batch_list(&report.corrupted_batches)?;
+        }
+
+        for tx_seq in report.inconsistent_txs.iter() {
+            self.tx_store.reset_tx_status(*tx_seq)?;
+        }
+
+        Ok(())
+    }
+
+    /// Exports the log store into a snapshot. The flow merkle tree is locked during export, so
+    /// that no transaction is appended meanwhile.
+    pub fn export_snapshot<W: Write>(&self, writer: W, with_data: bool) -> Result<SnapshotSummary> {
+        let _merkle = self.merkle.read();
+        snapshot::export(
+            self.flow_db.as_ref(),
+            self.data_db.as_ref(),
+            with_data,
+            writer,
+        )
+    }
+
+    /// Imports the snapshot into RocksDB before the log store is opened. Returns `None` if
+    /// the database already has transactions, in which case the snapshot is ignored.
+    pub fn rocksdb_import_snapshot(
+        config: &LogConfig,
+        flow_path: impl AsRef<Path>,
+        data_path: impl AsRef<Path>,
+        snapshot_path: impl AsRef<Path>,
+    ) -> Result<Option<SnapshotSummary>> {
+        let (flow_db, data_db) = Self::open_rocksdb(config, flow_path, data_path)?;
+        if flow_db.get(COL_TX, NEXT_TX_KEY.as_bytes())?.is_some() {
+            return Ok(None);
+        }
+
+        let reader = BufReader::new(File::open(snapshot_path)?);
+        Ok(Some(snapshot::import(
+            flow_db.as_ref(),
+            data_db.as_ref(),
+            reader,
+        )?))
+    }
+
     fn padding_rear_data(&self, tx: &Transaction) -> Result<()> {
         let (chunks, _) = compute_padded_chunk_size(tx.size as usize);
         let (segments_for_proof, last_segment_size_for_proof) =
@@ -1294,6 +1785,12 @@ pub fn data_to_merkle_leaves(leaf_data: &[u8]) -> Result<Vec<H256>> {
     Ok(r)
 }
 
+fn decode_u64(data: &[u8]) -> Result<u64> {
+    Ok(u64::from_be_bytes(
+        data.try_into().map_err(|e| anyhow!("{:?}", e))?,
+    ))
+}
+
 pub fn bytes_to_entries(size_bytes: u64) -> u64 {
     if size_bytes % ENTRY_SIZE as u64 == 0 {
         size_bytes / ENTRY_SIZE as u64
@@ -1318,23 +1815,6 @@ fn entry_proof(top_proof: &FlowProof, sub_proof: &FlowProof) -> Result<FlowProof
     FlowProof::new(lemma, path)
 }
 
-pub fn split_nodes(data_size: usize) -> Vec<usize> {
-    let (mut padded_chunks, chunks_next_pow2) = compute_padded_chunk_size(data_size);
-    let mut next_chunk_size = chunks_next_pow2;
-
-    let mut nodes = vec![];
-    while padded_chunks > 0 {
-        if padded_chunks >= next_chunk_size {
-            padded_chunks -= next_chunk_size;
-            nodes.push(next_chunk_size);
-        }
-
-        next_chunk_size >>= 1;
-    }
-
-    nodes
-}
-
 pub fn tx_subtree_root_list_padded(data: &[u8]) -> Vec<(usize, DataRoot)> {
     let mut root_list = Vec::new();
     let mut start_index = 0;
@@ -1,25 +1,31 @@
-use crate::config::ShardConfig;
+use crate::config::{ErasureConfig, ShardConfig};
 
-use ethereum_types::H256;
+use ethereum_types::{H256, U256};
 use flow_store::PadPair;
+use serde::{Deserialize, Serialize};
 use shared_types::{
-    Chunk, ChunkArray, ChunkArrayWithProof, ChunkWithProof, DataRoot, FlowProof, FlowRangeProof,
-    Transaction,
+    Chunk, ChunkArray, ChunkArrayWithProof, ChunkWithProof, DataRoot, ErasurePiece, FlowProof,
+    FlowRangeProof, Transaction, TransactionWithProof,
 };
+use ssz_derive::{Decode as DeriveDecode, Encode as DeriveEncode};
 use zgs_spec::{BYTES_PER_SEAL, SEALS_PER_LOAD};
 
 use crate::error::Result;
 
-use self::tx_store::{BlockHashAndSubmissionIndex, TxStatus};
+use self::tx_store::{BlockHashAndSubmissionIndex, TxStatus, TxStatusUpdate};
 
 pub mod config;
+pub mod erasure;
 mod flow_store;
 pub mod load_chunk;
 pub mod log_manager;
 mod metrics;
 mod seal_task_manager;
+pub mod snapshot;
 #[cfg(test)]
 mod tests;
+mod tx_access;
+mod tx_ranges;
 pub mod tx_store;
 
 /// The trait to read the transactions already appended to the log.
@@ -58,12 +64,36 @@ pub trait LogStoreRead: LogStoreChunkRead {
         merkle_tx_seq: Option<u64>,
     ) -> Result<Option<ChunkArrayWithProof>>;
 
+    /// Get a transaction along with the proofs of its merkle nodes in the flow at the version
+    /// of `merkle_tx_seq`, or the latest version if `None`.
+    fn get_tx_with_proof_by_seq_number(
+        &self,
+        seq: u64,
+        merkle_tx_seq: Option<u64>,
+    ) -> Result<Option<TransactionWithProof>>;
+
     fn check_tx_completed(&self, tx_seq: u64) -> Result<bool>;
 
     fn check_tx_pruned(&self, tx_seq: u64) -> Result<bool>;
 
     fn get_tx_status(&self, tx_seq: u64) -> Result<Option<TxStatus>>;
 
+    /// Returns the sorted and non-overlapping ranges of finalized tx_seq, which is used to
+    /// announce stored files in a compact way.
+    fn get_finalized_tx_ranges(&self) -> Result<Vec<std::ops::Range<u64>>>;
+
+    /// Subscribe to the status updates of transactions, i.e. finalized or pruned.
+    fn subscribe_tx_status(&self) -> tokio::sync::broadcast::Receiver<TxStatusUpdate>;
+
+    /// Get the seq list of transactions tagged with the stream id in ascending order,
+    /// starting from `from_seq` and returning at most `limit` items.
+    fn get_tx_seq_list_by_stream_id(
+        &self,
+        stream_id: &U256,
+        from_seq: u64,
+        limit: usize,
+    ) -> Result<Vec<u64>>;
+
     fn next_tx_seq(&self) -> u64;
 
     fn get_sync_progress(&self) -> Result<Option<(u64, H256)>>;
@@ -86,13 +116,34 @@ pub trait LogStoreRead: LogStoreChunkRead {
     /// Return flow root and length.
     fn get_context(&self) -> Result<(DataRoot, u64)>;
 
-    fn pull_seal_chunk(&self, seal_index_max: usize) -> Result<Option<Vec<SealTask>>>;
+    fn pull_seal_chunk(
+        &self,
+        seal_index_max: usize,
+        max_loads: usize,
+    ) -> Result<Option<Vec<SealTask>>>;
+
+    /// Return the seal status of the stored loads in a pricing chunk.
+    fn get_seal_status(&self, pricing_index: u64) -> Result<SealStatus>;
 
     fn get_num_entries(&self) -> Result<u64>;
 
+    /// Return the last access time in seconds of transactions whose data are read in the order
+    /// of access time, which is updated at most once a minute, and empty if not tracked.
+    fn get_tx_access_list(&self) -> Result<Vec<(u64, u64)>>;
+
     fn load_sealed_data(&self, chunk_index: u64) -> Result<Option<MineLoadChunk>>;
 
     fn get_shard_config(&self) -> ShardConfig;
+
+    fn get_erasure_config(&self) -> Option<ErasureConfig>;
+
+    /// Return the erasure coded piece of a batch, which is either stored locally or encoded
+    /// from the whole batch data. Return `Ok(None)` if not available.
+    fn get_erasure_piece(
+        &self,
+        batch_index: u64,
+        piece_index: usize,
+    ) -> Result<Option<ErasurePiece>>;
 }
 
 pub trait LogStoreChunkRead {
@@ -142,6 +193,9 @@ pub trait LogStoreWrite: LogStoreChunkWrite {
     /// Mark the tx as pruned, meaning the data will not be stored.
     fn prune_tx(&self, tx_seq: u64) -> Result<()>;
 
+    /// Persist the access time of transactions updated in memory.
+    fn persist_tx_access(&self) -> Result<()>;
+
     /// Store the progress of synced block number and its hash.
     fn put_sync_progress(&self, progress: (u64, H256, Option<Option<u64>>)) -> Result<()>;
 
@@ -165,6 +219,26 @@ pub trait LogStoreWrite: LogStoreChunkWrite {
 
     fn update_shard_config(&self, shard_config: ShardConfig);
 
+    /// Store the erasure coded piece held by this node, which is encoded from the batch rebuilt
+    /// from `pieces`, since a single piece could not be verified against the batch root.
+    /// Return the indexes of invalid pieces once stored, or `None` if not enough valid pieces
+    /// to rebuild the batch root.
+    fn put_erasure_piece(
+        &self,
+        batch_index: u64,
+        piece_index: usize,
+        pieces: Vec<ErasurePiece>,
+    ) -> Result<Option<Vec<u64>>>;
+
+    /// Rebuild a batch in the shard of this node from erasure coded pieces.
+    /// Return the indexes of invalid pieces once rebuilt, or `None` if not enough valid pieces
+    /// to rebuild the batch root.
+    fn rebuild_batch(
+        &self,
+        batch_index: u64,
+        pieces: Vec<ErasurePiece>,
+    ) -> Result<Option<Vec<u64>>>;
+
     fn submit_seal_result(&self, answers: Vec<SealAnswer>) -> Result<()>;
 
     fn start_padding(&self, executor: &task_executor::TaskExecutor);
@@ -196,6 +270,7 @@ pub trait Store:
 }
 impl<T: LogStoreRead + LogStoreWrite + config::Configurable + Send + Sync + 'static> Store for T {}
 
+#[derive(Clone)]
 pub struct MineLoadChunk {
     // Use `Vec` instead of array to avoid thread stack overflow.
     pub loaded_chunk: Vec<[u8; BYTES_PER_SEAL]>,
@@ -275,14 +350,45 @@ pub struct SealAnswer {
     pub context_end_seal: u64,
 }
 
+/// Number of seals in a load by sealing state, which is persisted along with the entry batch.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, DeriveEncode, DeriveDecode)]
+pub struct LoadSealStatus {
+    pub sealed: u64,
+    /// Seals whose data are complete but not sealed yet.
+    pub unsealed: u64,
+}
+
+/// Seal status of a pricing chunk in sectors.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SealStatus {
+    pub pricing_index: u64,
+    /// Number of loads stored locally.
+    pub loads: u64,
+    /// Number of loads whose seal status is not backfilled yet after upgrade, which may be
+    /// stored locally or not.
+    pub unknown_loads: u64,
+    pub sealed_sectors: u64,
+    pub unsealed_sectors: u64,
+    /// Unsealed sectors that are queued for the sealer.
+    pub pending_sectors: u64,
+}
+
 pub trait FlowSeal {
-    /// Pull a seal chunk ready for sealing
+    /// Pull seal chunks ready for sealing in at most `max_loads` loads
     /// Return the global index (in sector) and the data
-    fn pull_seal_chunk(&self, seal_index_max: usize) -> Result<Option<Vec<SealTask>>>;
+    fn pull_seal_chunk(
+        &self,
+        seal_index_max: usize,
+        max_loads: usize,
+    ) -> Result<Option<Vec<SealTask>>>;
 
     /// Submit sealing result
 
     fn submit_seal_result(&self, answers: Vec<SealAnswer>) -> Result<()>;
+
+    /// Return the seal status of the stored loads in a pricing chunk.
+    fn get_seal_status(&self, pricing_index: u64) -> Result<SealStatus>;
 }
 
 pub trait Flow: FlowRead + FlowWrite + FlowSeal {}
@@ -0,0 +1,235 @@
+//! Portable snapshot of the flow and data databases, which is used to bootstrap a new node
+//! without replaying the whole log from blockchain.
+//!
+//! The archive consists of a header, a list of key-value records and a keccak checksum of
+//! all the preceding bytes:
+//!
+//! ```text
+//! header: magic(8) | version(u32) | with_data(u8)
+//! record: db(u8) | col(u32) | key_len(u32) | key | value_len(u32) | value
+//! end:    RECORD_END(u8) | checksum(32)
+//! ```
+//!
+//! All integers are encoded in big endian.
+
+use crate::log_store::log_manager::{
+    COL_BLOCK_PROGRESS, COL_ENTRY_BATCH, COL_ERASURE_PIECE, COL_FLOW_MPT_NODES, COL_MISC,
+    COL_PAD_DATA_LIST, COL_PAD_DATA_SYNC_HEIGH, COL_SEAL_STATUS, COL_TX, COL_TX_COMPLETED,
+    COL_TX_DATA_ROOT_INDEX, COL_TX_STREAM_ID_INDEX,
+};
+use crate::log_store::tx_store::NEXT_TX_KEY;
+use crate::ZgsKeyValueDB;
+use anyhow::{bail, Result};
+use std::io::{Read, Write};
+use tiny_keccak::{Hasher, Keccak};
+
+const MAGIC: &[u8; 8] = b"ZGSSNAP\0";
+const VERSION: u32 = 1;
+
+const DB_FLOW: u8 = 0;
+const DB_DATA: u8 = 1;
+const RECORD_END: u8 = 0xff;
+
+/// Max size of key or value in a record, which is used to avoid huge allocation for
+/// corrupted archives.
+const MAX_RECORD_ITEM_SIZE: usize = 1 << 30;
+
+/// Number of records to write into databases at a time when importing.
+const IMPORT_BATCH_SIZE: u64 = 10_000;
+
+/// Columns of transactions, merkle nodes and log sync progress, which are always exported.
+const FLOW_COLUMNS: [u32; 7] = [
+    COL_TX,
+    COL_TX_DATA_ROOT_INDEX,
+    COL_MISC,
+    COL_FLOW_MPT_NODES,
+    COL_BLOCK_PROGRESS,
+    COL_PAD_DATA_LIST,
+    COL_TX_STREAM_ID_INDEX,
+];
+
+/// Columns of entry batches and file status, which are exported optionally.
+const DATA_COLUMNS: [u32; 6] = [
+    COL_ENTRY_BATCH,
+    COL_TX_COMPLETED,
+    COL_MISC,
+    COL_PAD_DATA_SYNC_HEIGH,
+    COL_ERASURE_PIECE,
+    COL_SEAL_STATUS,
+];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SnapshotSummary {
+    pub with_data: bool,
+    pub num_records: u64,
+}
+
+/// Wraps the reader or writer to compute checksum of all bytes passed through.
+struct Checksummed<T> {
+    inner: T,
+    hasher: Keccak,
+}
+
+impl<T> Checksummed<T> {
+    fn new(inner: T) -> Self {
+        Self {
+            inner,
+            hasher: Keccak::v256(),
+        }
+    }
+
+    fn finalize(self) -> (T, [u8; 32]) {
+        let mut checksum = [0u8; 32];
+        self.hasher.finalize(&mut checksum);
+        (self.inner, checksum)
+    }
+}
+
+impl<W: Write> Checksummed<W> {
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<()> {
+        self.inner.write_all(bytes)?;
+        self.hasher.update(bytes);
+        Ok(())
+    }
+
+    fn write_item(&mut self, item: &[u8]) -> Result<()> {
+        self.write_bytes(&(item.len() as u32).to_be_bytes())?;
+        self.write_bytes(item)
+    }
+}
+
+impl<R: Read> Checksummed<R> {
+    fn read_bytes(&mut self, len: usize) -> Result<Vec<u8>> {
+        let mut bytes = vec![0u8; len];
+        self.inner.read_exact(&mut bytes)?;
+        self.hasher.update(&bytes);
+        Ok(bytes)
+    }
+
+    fn read_u8(&mut self) -> Result<u8> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    fn read_u32(&mut self) -> Result<u32> {
+        let bytes = self.read_bytes(4)?;
+        Ok(u32::from_be_bytes(bytes.try_into().expect("4 bytes")))
+    }
+
+    fn read_item(&mut self) -> Result<Vec<u8>> {
+        let len = self.read_u32()? as usize;
+        if len > MAX_RECORD_ITEM_SIZE {
+            bail!("record item too large: {}", len);
+        }
+        self.read_bytes(len)
+    }
+}
+
+/// Writes all the records of the flow database, and data database if `with_data` is true.
+///
+/// The caller should make sure there are no concurrent writes to the databases.
+pub fn export<W: Write>(
+    flow_db: &dyn ZgsKeyValueDB,
+    data_db: &dyn ZgsKeyValueDB,
+    with_data: bool,
+    writer: W,
+) -> Result<SnapshotSummary> {
+    let mut writer = Checksummed::new(writer);
+    writer.write_bytes(MAGIC)?;
+    writer.write_bytes(&VERSION.to_be_bytes())?;
+    writer.write_bytes(&[with_data as u8])?;
+
+    let mut dbs = vec![(DB_FLOW, flow_db, &FLOW_COLUMNS[..])];
+    if with_data {
+        dbs.push((DB_DATA, data_db, &DATA_COLUMNS[..]));
+    }
+
+    let mut num_records = 0;
+    for (db_id, db, columns) in dbs {
+        for col in columns {
+            for item in db.iter(*col) {
+                let (key, value) = item?;
+                writer.write_bytes(&[db_id])?;
+                writer.write_bytes(&col.to_be_bytes())?;
+                writer.write_item(key.as_ref())?;
+                writer.write_item(&value)?;
+                num_records += 1;
+            }
+        }
+    }
+
+    writer.write_bytes(&[RECORD_END])?;
+    let (mut writer, checksum) = writer.finalize();
+    writer.write_all(&checksum)?;
+    writer.flush()?;
+
+    Ok(SnapshotSummary {
+        with_data,
+        num_records,
+    })
+}
+
+/// Reads the snapshot into the databases, which are expected to be empty.
+///
+/// Records are written in batches, except that the next tx seq is only written after the
+/// checksum verified. So an interrupted or corrupted import leaves the log store empty, and
+/// the databases should be removed before importing again.
+pub fn import<R: Read>(
+    flow_db: &dyn ZgsKeyValueDB,
+    data_db: &dyn ZgsKeyValueDB,
+    reader: R,
+) -> Result<SnapshotSummary> {
+    let mut reader = Checksummed::new(reader);
+    if reader.read_bytes(MAGIC.len())? != MAGIC {
+        bail!("invalid snapshot magic");
+    }
+    let version = reader.read_u32()?;
+    if version != VERSION {
+        bail!("unsupported snapshot version: {}", version);
+    }
+    let with_data = reader.read_u8()? != 0;
+
+    let mut flow_tx = flow_db.transaction();
+    let mut data_tx = data_db.transaction();
+    let mut next_tx_seq = None;
+    let mut num_records = 0;
+    loop {
+        let db_id = reader.read_u8()?;
+        if db_id == RECORD_END {
+            break;
+        }
+        let col = reader.read_u32()?;
+        let key = reader.read_item()?;
+        let value = reader.read_item()?;
+
+        match db_id {
+            DB_FLOW if col == COL_TX && key == NEXT_TX_KEY.as_bytes() => next_tx_seq = Some(value),
+            DB_FLOW if FLOW_COLUMNS.contains(&col) => flow_tx.put(col, &key, &value),
+            DB_DATA if with_data && DATA_COLUMNS.contains(&col) => data_tx.put(col, &key, &value),
+            _ => bail!("unexpected snapshot record: db={} col={}", db_id, col),
+        }
+        num_records += 1;
+
+        if num_records % IMPORT_BATCH_SIZE == 0 {
+            flow_db.write(std::mem::replace(&mut flow_tx, flow_db.transaction()))?;
+            data_db.write(std::mem::replace(&mut data_tx, data_db.transaction()))?;
+        }
+    }
+
+    let (mut reader, expected) = reader.finalize();
+    let mut checksum = [0u8; 32];
+    reader.read_exact(&mut checksum)?;
+    if checksum != expected {
+        bail!("snapshot checksum mismatch");
+    }
+
+    data_db.write(data_tx)?;
+    if let Some(next_tx_seq) = next_tx_seq {
+        flow_tx.put(COL_TX, NEXT_TX_KEY.as_bytes(), &next_tx_seq);
+    }
+    flow_db.write(flow_tx)?;
+
+    Ok(SnapshotSummary {
+        with_data,
+        num_records,
+    })
+}
@@ -1,13 +1,24 @@
+use crate::config::ErasureConfig;
+use crate::log_store::flow_store::SEAL_STATUS_BACKFILLED_KEY;
 use crate::log_store::log_manager::{
     data_to_merkle_leaves, sub_merkle_tree, tx_subtree_root_list_padded, LogConfig, LogManager,
-    PORA_CHUNK_SIZE,
+    COL_ENTRY_BATCH, COL_MISC, COL_NUM, COL_SEAL_STATUS, COL_TX_LAST_ACCESS, PORA_CHUNK_SIZE,
+};
+use crate::log_store::snapshot;
+use crate::log_store::{
+    LogStoreChunkRead, LogStoreChunkWrite, LogStoreRead, LogStoreWrite, SealAnswer,
 };
-use crate::log_store::{LogStoreChunkRead, LogStoreChunkWrite, LogStoreRead, LogStoreWrite};
 use append_merkle::{Algorithm, AppendMerkleTree, MerkleTreeRead, Sha3Algorithm};
-use ethereum_types::H256;
+use ethereum_types::{H256, U256};
+use kvdb::KeyValueDB;
 use rand::random;
-use shared_types::{compute_padded_chunk_size, ChunkArray, Transaction, CHUNK_SIZE};
+use shared_types::{
+    compute_padded_chunk_size, ChunkArray, ErasurePiece, Transaction, TransactionWithProof,
+    CHUNK_SIZE,
+};
 use std::cmp;
+use std::sync::Arc;
+use zgs_spec::{SEALS_PER_LOAD, SECTORS_PER_SEAL};
 
 #[test]
 fn test_put_get() {
@@ -28,16 +39,15 @@ fn test_put_get() {
     data_padded.append(&mut vec![0u8; CHUNK_SIZE]);
     merkle.append_list(data_to_merkle_leaves(&data_padded).unwrap());
     merkle.commit(Some(0));
-    let tx_merkle = sub_merkle_tree(&data).unwrap();
+    let merkle_nodes = tx_subtree_root_list_padded(&data);
     let tx = Transaction {
         stream_ids: vec![],
         size: data_size as u64,
-        data_merkle_root: tx_merkle.root().into(),
+        data_merkle_root: Transaction::data_root_of_nodes(&merkle_nodes).unwrap(),
         seq: 0,
         data: vec![],
         start_entry_index: start_offset as u64,
-        // TODO: This can come from `tx_merkle`.
-        merkle_nodes: tx_subtree_root_list_padded(&data),
+        merkle_nodes,
     };
     store.put_tx(tx.clone()).unwrap();
     for start_index in (0..chunk_count).step_by(PORA_CHUNK_SIZE) {
@@ -107,6 +117,42 @@ fn test_put_get() {
             )
             .is_ok());
     }
+
+    let tx_with_proof = store
+        .get_tx_with_proof_by_seq_number(tx.seq, Some(tx.seq))
+        .unwrap()
+        .unwrap();
+    assert_eq!(tx_with_proof.transaction, tx);
+    assert!(merkle.check_root(&tx_with_proof.validate().unwrap()));
+
+    // tampered fields are rejected
+    let tampered = |f: &dyn Fn(&mut TransactionWithProof)| {
+        let mut tx_with_proof = tx_with_proof.clone();
+        f(&mut tx_with_proof);
+        tx_with_proof.validate()
+    };
+    assert!(tampered(&|v| v.transaction.size += CHUNK_SIZE as u64 * 1024).is_err());
+    assert!(tampered(&|v| v.transaction.size = CHUNK_SIZE as u64).is_err());
+    assert!(tampered(&|v| v.transaction.data_merkle_root = H256::repeat_byte(1)).is_err());
+    assert!(tampered(&|v| v.transaction.start_entry_index += 1).is_err());
+    assert!(tampered(&|v| v.transaction.merkle_nodes[0].1 = H256::repeat_byte(1)).is_err());
+    assert!(tampered(&|v| v.transaction.merkle_nodes[0].0 += 1).is_err());
+    assert!(tampered(&|v| {
+        v.transaction.merkle_nodes.pop();
+        v.proofs.pop();
+    })
+    .is_err());
+    // the data root is consistent with tampered nodes, but the proof fails
+    assert!(tampered(&|v| {
+        v.transaction.merkle_nodes[0].1 = H256::repeat_byte(1);
+        v.transaction.data_merkle_root =
+            Transaction::data_root_of_nodes(&v.transaction.merkle_nodes).unwrap();
+    })
+    .is_err());
+    assert!(store
+        .get_tx_with_proof_by_seq_number(tx.seq + 1, None)
+        .unwrap()
+        .is_none());
 }
 
 #[test]
@@ -167,12 +213,375 @@ fn test_put_tx() {
     }
 }
 
+#[test]
+fn test_stream_id_index() {
+    let mut store = create_store();
+    let (stream_a, stream_b) = (U256::from(1), U256::from(2));
+    put_tx_with_stream_ids(&mut store, 1, 0, vec![stream_a]);
+    put_tx_with_stream_ids(&mut store, 1, 1, vec![stream_b]);
+    put_tx_with_stream_ids(&mut store, 1, 2, vec![stream_a, stream_b]);
+    put_tx_with_stream_ids(&mut store, 1, 3, vec![stream_a]);
+
+    let get = |store: &LogManager, stream_id, from_seq, limit| {
+        store
+            .get_tx_seq_list_by_stream_id(&stream_id, from_seq, limit)
+            .unwrap()
+    };
+    assert_eq!(get(&store, stream_a, 0, 10), vec![0, 2, 3]);
+    assert_eq!(get(&store, stream_b, 0, 10), vec![1, 2]);
+    assert_eq!(get(&store, stream_a, 1, 1), vec![2]);
+    assert_eq!(get(&store, stream_a, 3, 10), vec![3]);
+    assert!(get(&store, stream_a, 4, 10).is_empty());
+    assert!(get(&store, U256::from(3), 0, 10).is_empty());
+
+    store.revert_to(1).unwrap();
+    assert_eq!(get(&store, stream_a, 0, 10), vec![0]);
+    assert_eq!(get(&store, stream_b, 0, 10), vec![1]);
+}
+
+#[test]
+fn test_stream_id_index_seek() {
+    let mut store = create_store();
+    let stream_id = U256::from(1);
+    // every third tx belongs to the stream
+    for seq in 0..30 {
+        let stream_ids = if seq % 3 == 0 {
+            vec![stream_id, stream_id]
+        } else {
+            vec![]
+        };
+        put_tx_with_stream_ids(&mut store, 1, seq, stream_ids);
+    }
+
+    for from_seq in 0..32 {
+        let expected: Vec<u64> = (from_seq..30).filter(|x| x % 3 == 0).take(4).collect();
+        assert_eq!(
+            store
+                .get_tx_seq_list_by_stream_id(&stream_id, from_seq, 4)
+                .unwrap(),
+            expected,
+            "from_seq = {}",
+            from_seq
+        );
+    }
+
+    store.revert_to(10).unwrap();
+    put_tx_with_stream_ids(&mut store, 1, 11, vec![stream_id]);
+    assert_eq!(
+        store
+            .get_tx_seq_list_by_stream_id(&stream_id, 5, 10)
+            .unwrap(),
+        vec![6, 9, 11]
+    );
+}
+
+#[test]
+fn test_finalized_tx_ranges() {
+    let mut store = create_store();
+    assert!(store.get_finalized_tx_ranges().unwrap().is_empty());
+
+    for seq in 0..4 {
+        put_tx(&mut store, 1, seq);
+    }
+    assert_eq!(store.get_finalized_tx_ranges().unwrap(), vec![0..4]);
+
+    store.prune_tx(1).unwrap();
+    assert_eq!(store.get_finalized_tx_ranges().unwrap(), vec![0..1, 2..4]);
+}
+
+#[test]
+fn test_erasure_rebuild_batch() {
+    let mut config = LogConfig::default();
+    config.flow.erasure_config = Some(ErasureConfig::new(2, 2).unwrap());
+    let mut store = LogManager::memorydb(config).unwrap();
+    put_tx(&mut store, 3 * PORA_CHUNK_SIZE, 0);
+
+    let tx = store.get_tx_by_seq_number(0).unwrap().unwrap();
+    let batch_index = tx.start_entry_index / PORA_CHUNK_SIZE as u64;
+    let batch_start = batch_index * PORA_CHUNK_SIZE as u64;
+    let get_batch = |store: &LogManager| {
+        store
+            .get_chunk_by_flow_index(batch_start, PORA_CHUNK_SIZE as u64)
+            .unwrap()
+    };
+    let data = get_batch(&store).unwrap();
+
+    // Pieces are encoded from the whole batch.
+    let pieces: Vec<ErasurePiece> = (1..4)
+        .map(|i| store.get_erasure_piece(batch_index, i).unwrap().unwrap())
+        .collect();
+    assert!(store.get_erasure_piece(batch_index, 4).unwrap().is_none());
+
+    store.remove_chunks_batch(&[batch_index]).unwrap();
+    assert!(get_batch(&store).is_none());
+    assert!(store.get_erasure_piece(batch_index, 1).unwrap().is_none());
+
+    // The piece is encoded from the batch rebuilt from pieces of peers.
+    let mut tampered = pieces[1].clone();
+    tampered.data[0] ^= 1;
+    assert!(store
+        .put_erasure_piece(batch_index, 1, vec![tampered.clone(), pieces[2].clone()])
+        .unwrap()
+        .is_none());
+    assert!(store
+        .put_erasure_piece(batch_index, 9, pieces[1..].to_vec())
+        .unwrap()
+        .is_none());
+    assert!(store.get_erasure_piece(batch_index, 1).unwrap().is_none());
+
+    // Only the stored piece is available without the batch data.
+    assert_eq!(
+        store
+            .put_erasure_piece(batch_index, 1, pieces[1..].to_vec())
+            .unwrap(),
+        Some(vec![])
+    );
+    assert_eq!(
+        store.get_erasure_piece(batch_index, 1).unwrap(),
+        Some(pieces[0].clone())
+    );
+    assert!(store.get_erasure_piece(batch_index, 2).unwrap().is_none());
+
+    let mut tampered = pieces[2].clone();
+    tampered.data[0] ^= 1;
+    assert!(store
+        .rebuild_batch(batch_index, vec![pieces[1].clone(), tampered.clone()])
+        .unwrap()
+        .is_none());
+    assert!(store
+        .rebuild_batch(batch_index, vec![pieces[1].clone()])
+        .unwrap()
+        .is_none());
+    assert!(get_batch(&store).is_none());
+
+    // The invalid piece is found out once rebuilt from other pieces.
+    assert_eq!(
+        store
+            .rebuild_batch(
+                batch_index,
+                vec![tampered, pieces[0].clone(), pieces[1].clone()]
+            )
+            .unwrap(),
+        Some(vec![3])
+    );
+    assert_eq!(get_batch(&store).unwrap(), data);
+}
+
+#[test]
+fn test_tx_access() {
+    let config = LogConfig {
+        track_tx_access: true,
+        ..Default::default()
+    };
+    let mut store = LogManager::memorydb(config).unwrap();
+    for seq in 0..3 {
+        put_tx(&mut store, 3, seq);
+    }
+
+    store
+        .get_chunks_by_tx_and_index_range(2, 0, 1)
+        .unwrap()
+        .unwrap();
+    store
+        .get_chunks_by_tx_and_index_range(1, 0, 1)
+        .unwrap()
+        .unwrap();
+    let access_list = store.get_tx_access_list().unwrap();
+    assert_eq!(
+        access_list
+            .iter()
+            .map(|(tx_seq, _)| *tx_seq)
+            .collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+    // only persisted on demand
+    assert_eq!(store.data_db.iter(COL_TX_LAST_ACCESS).count(), 0);
+    store.persist_tx_access().unwrap();
+    assert_eq!(store.data_db.iter(COL_TX_LAST_ACCESS).count(), 2);
+
+    store.revert_to(1).unwrap();
+    assert_eq!(store.get_tx_access_list().unwrap().len(), 1);
+    assert_eq!(store.data_db.iter(COL_TX_LAST_ACCESS).count(), 1);
+    store.prune_tx(1).unwrap();
+    assert!(store.get_tx_access_list().unwrap().is_empty());
+    assert_eq!(store.data_db.iter(COL_TX_LAST_ACCESS).count(), 0);
+}
+
+#[test]
+fn test_check_integrity() {
+    let mut store = create_store();
+    put_tx(&mut store, 3 * PORA_CHUNK_SIZE, 0);
+    put_tx(&mut store, 3 * PORA_CHUNK_SIZE, 1);
+
+    let report = store.check_integrity().unwrap();
+    assert!(report.is_consistent());
+    assert_eq!(report.checked_txs, 2);
+
+    // corrupt a batch of the second tx
+    let tx = store.get_tx_by_seq_number(1).unwrap().unwrap();
+    let batch_index = tx.start_entry_index / PORA_CHUNK_SIZE as u64 + 1;
+    store
+        .data_db
+        .put(COL_ENTRY_BATCH, &batch_index.to_be_bytes(), &[0u8; 10])
+        .unwrap();
+
+    let report = store.check_integrity().unwrap();
+    assert_eq!(report.corrupted_batches, vec![batch_index]);
+    assert_eq!(report.inconsistent_txs, vec![1]);
+
+    store.repair(&report).unwrap();
+    assert!(store.check_tx_completed(0).unwrap());
+    assert!(!store.check_tx_completed(1).unwrap());
+    assert!(store.check_integrity().unwrap().is_consistent());
+}
+
+#[test]
+fn test_snapshot_export_import() {
+    let mut store = create_store();
+    put_tx(&mut store, 3 * PORA_CHUNK_SIZE, 0);
+    put_tx(&mut store, 3 * PORA_CHUNK_SIZE, 1);
+
+    for with_data in [false, true] {
+        let mut archive = vec![];
+        store.export_snapshot(&mut archive, with_data).unwrap();
+
+        let flow_db = Arc::new(kvdb_memorydb::create(COL_NUM));
+        let data_db = Arc::new(kvdb_memorydb::create(COL_NUM));
+        let summary = snapshot::import(flow_db.as_ref(), data_db.as_ref(), &archive[..]).unwrap();
+        assert_eq!(summary.with_data, with_data);
+        let imported = LogManager::new(flow_db, data_db, LogConfig::default()).unwrap();
+
+        assert_eq!(
+            imported.get_context().unwrap(),
+            store.get_context().unwrap()
+        );
+        assert_eq!(imported.next_tx_seq(), 2);
+        let tx = imported.get_tx_by_seq_number(1).unwrap().unwrap();
+        assert_eq!(tx, store.get_tx_by_seq_number(1).unwrap().unwrap());
+        assert_eq!(imported.check_tx_completed(1).unwrap(), with_data);
+        assert_eq!(
+            imported
+                .get_chunk_by_flow_index(tx.start_entry_index, 1)
+                .unwrap()
+                .is_some(),
+            with_data
+        );
+    }
+
+    // corrupted archive
+    let mut archive = vec![];
+    store.export_snapshot(&mut archive, false).unwrap();
+    let len = archive.len();
+    archive[len / 2] ^= 1;
+    let flow_db = kvdb_memorydb::create(COL_NUM);
+    let data_db = kvdb_memorydb::create(COL_NUM);
+    assert!(snapshot::import(&flow_db, &data_db, &archive[..]).is_err());
+}
+
+#[test]
+fn test_seal_status() {
+    let mut store = create_store();
+    put_tx(&mut store, PORA_CHUNK_SIZE * 3, 0);
+
+    let status = store.get_seal_status(0).unwrap();
+    assert!(status.loads > 0);
+    assert_eq!(status.unknown_loads, 0);
+    assert_eq!(status.sealed_sectors, 0);
+    assert!(status.unsealed_sectors > 0);
+    assert!(status.pending_sectors > 0);
+
+    let tasks = store.pull_seal_chunk(usize::MAX, 2).unwrap().unwrap();
+    let mut loads: Vec<u64> = tasks
+        .iter()
+        .map(|task| task.seal_index / SEALS_PER_LOAD as u64)
+        .collect();
+    loads.dedup();
+    assert_eq!(loads, vec![0, 1]);
+
+    let tasks = store.pull_seal_chunk(usize::MAX, 1).unwrap().unwrap();
+    assert!(tasks
+        .iter()
+        .all(|task| task.seal_index < SEALS_PER_LOAD as u64));
+    let num_sealed = tasks.len() as u64;
+    let (miner_id, seal_context) = (H256::repeat_byte(1), H256::repeat_byte(2));
+    let answers = tasks
+        .into_iter()
+        .map(|task| SealAnswer {
+            seal_index: task.seal_index,
+            version: task.version,
+            sealed_data: task.non_sealed_data,
+            miner_id,
+            seal_context,
+            context_end_seal: (SEALS_PER_LOAD * 1024) as u64,
+        })
+        .collect();
+    store.submit_seal_result(answers).unwrap();
+
+    let sealed = store.get_seal_status(0).unwrap();
+    assert_eq!(sealed.loads, status.loads);
+    assert_eq!(sealed.sealed_sectors, num_sealed * SECTORS_PER_SEAL as u64);
+    assert_eq!(
+        sealed.sealed_sectors + sealed.unsealed_sectors,
+        status.unsealed_sectors
+    );
+    assert_eq!(
+        sealed.pending_sectors,
+        status.pending_sectors - sealed.sealed_sectors
+    );
+
+    assert_eq!(store.get_seal_status(1).unwrap().loads, 0);
+    assert!(store.get_seal_status(u64::MAX).is_err());
+}
+
+#[test]
+fn test_seal_status_backfill() {
+    let flow_db = Arc::new(kvdb_memorydb::create(COL_NUM));
+    let data_db = Arc::new(kvdb_memorydb::create(COL_NUM));
+    let mut store =
+        LogManager::new(flow_db.clone(), data_db.clone(), LogConfig::default()).unwrap();
+    put_tx(&mut store, PORA_CHUNK_SIZE * 3, 0);
+    let status = store.get_seal_status(0).unwrap();
+    assert!(status.loads > 0);
+    drop(store);
+
+    // a database written before the seal status is persisted
+    let mut tx = data_db.transaction();
+    for item in data_db.iter(COL_SEAL_STATUS) {
+        tx.delete(COL_SEAL_STATUS, &item.unwrap().0);
+    }
+    tx.delete(COL_MISC, SEAL_STATUS_BACKFILLED_KEY.as_bytes());
+    data_db.write(tx).unwrap();
+
+    let store = LogManager::new(flow_db, data_db, LogConfig::default()).unwrap();
+    // the seal status is backfilled in background
+    let mut backfilled = store.get_seal_status(0).unwrap();
+    for _ in 0..100 {
+        if backfilled.unknown_loads == 0 {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        backfilled = store.get_seal_status(0).unwrap();
+    }
+    assert_eq!(backfilled.unknown_loads, 0);
+    assert_eq!(backfilled.loads, status.loads);
+    assert_eq!(backfilled.unsealed_sectors, status.unsealed_sectors);
+}
+
 fn create_store() -> LogManager {
     let config = LogConfig::default();
     LogManager::memorydb(config).unwrap()
 }
 
 fn put_tx(store: &mut LogManager, chunk_count: usize, seq: u64) {
+    put_tx_with_stream_ids(store, chunk_count, seq, vec![]);
+}
+
+fn put_tx_with_stream_ids(
+    store: &mut LogManager,
+    chunk_count: usize,
+    seq: u64,
+    stream_ids: Vec<U256>,
+) {
     let data_size = CHUNK_SIZE * chunk_count;
     let mut data = vec![0u8; data_size];
     for i in 0..chunk_count {
@@ -184,7 +593,7 @@ fn put_tx(store: &mut LogManager, chunk_count: usize, seq: u64) {
     let first_subtree_size = 1 << (merkle_nodes.first().unwrap().0 - 1);
     let start_entry_index = ((flow_len - 1) / first_subtree_size + 1) * first_subtree_size;
     let tx = Transaction {
-        stream_ids: vec![],
+        stream_ids,
         size: data_size as u64,
         data_merkle_root: tx_merkle.root().into(),
         seq,
@@ -0,0 +1,112 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// Minimum interval to update the access time of a transaction.
+const TX_ACCESS_UPDATE_INTERVAL_SECS: u64 = 60;
+
+/// In-memory index of the last access time in seconds of transactions whose data are read,
+/// which is ordered by access time for the LRU prune policy. Updates are persisted in batch
+/// later, so that reading chunks never writes the database.
+#[derive(Default)]
+pub struct TxAccessIndex {
+    last_access: HashMap<u64, u64>,
+    /// Ordered by `(access_time, tx_seq)`.
+    ordered: BTreeSet<(u64, u64)>,
+    /// Transactions updated but not persisted yet.
+    dirty: HashSet<u64>,
+}
+
+impl TxAccessIndex {
+    /// Builds the index from persisted `(tx_seq, access_time)` pairs.
+    pub fn new(persisted: impl IntoIterator<Item = (u64, u64)>) -> Self {
+        let mut index = Self::default();
+        for (tx_seq, time) in persisted {
+            index.last_access.insert(tx_seq, time);
+            index.ordered.insert((time, tx_seq));
+        }
+        index
+    }
+
+    /// Returns `true` if the access time of the transaction is updated recently.
+    pub fn is_fresh(&self, tx_seq: u64, now: u64) -> bool {
+        self.last_access
+            .get(&tx_seq)
+            .map_or(false, |time| time + TX_ACCESS_UPDATE_INTERVAL_SECS > now)
+    }
+
+    pub fn update(&mut self, tx_seq: u64, now: u64) {
+        if let Some(time) = self.last_access.insert(tx_seq, now) {
+            self.ordered.remove(&(time, tx_seq));
+        }
+        self.ordered.insert((now, tx_seq));
+        self.dirty.insert(tx_seq);
+    }
+
+    pub fn remove(&mut self, tx_seq: u64) {
+        if let Some(time) = self.last_access.remove(&tx_seq) {
+            self.ordered.remove(&(time, tx_seq));
+        }
+        self.dirty.remove(&tx_seq);
+    }
+
+    /// Removes the transactions with `tx_seq >= start`, e.g. reverted by chain reorg, and
+    /// returns the removed ones.
+    pub fn remove_after(&mut self, start: u64) -> Vec<u64> {
+        let removed: Vec<u64> = self
+            .last_access
+            .keys()
+            .filter(|tx_seq| **tx_seq >= start)
+            .copied()
+            .collect();
+        for tx_seq in removed.iter() {
+            self.remove(*tx_seq);
+        }
+        removed
+    }
+
+    /// Takes the `(tx_seq, access_time)` pairs updated since last time to persist.
+    pub fn take_dirty(&mut self) -> Vec<(u64, u64)> {
+        let dirty = std::mem::take(&mut self.dirty);
+        dirty
+            .into_iter()
+            .filter_map(|tx_seq| Some((tx_seq, *self.last_access.get(&tx_seq)?)))
+            .collect()
+    }
+
+    /// Returns the `(tx_seq, access_time)` pairs in the order of access time.
+    pub fn ordered_list(&self) -> Vec<(u64, u64)> {
+        self.ordered
+            .iter()
+            .map(|(time, tx_seq)| (*tx_seq, *time))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TxAccessIndex;
+
+    #[test]
+    fn test_tx_access_index() {
+        let mut index = TxAccessIndex::new(vec![(3, 100), (1, 200)]);
+        assert!(index.take_dirty().is_empty());
+
+        assert!(index.is_fresh(3, 159));
+        assert!(!index.is_fresh(3, 160));
+        assert!(!index.is_fresh(2, 0));
+
+        index.update(3, 300);
+        index.update(2, 250);
+        assert_eq!(index.ordered_list(), vec![(1, 200), (2, 250), (3, 300)]);
+        let mut dirty = index.take_dirty();
+        dirty.sort();
+        assert_eq!(dirty, vec![(2, 250), (3, 300)]);
+        assert!(index.take_dirty().is_empty());
+
+        index.update(1, 400);
+        assert_eq!(index.remove_after(2).len(), 2);
+        assert_eq!(index.ordered_list(), vec![(1, 400)]);
+        index.remove(1);
+        assert!(index.ordered_list().is_empty());
+        assert!(index.take_dirty().is_empty());
+    }
+}
@@ -0,0 +1,105 @@
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+/// Sorted and non-overlapping ranges of tx_seq, e.g. finalized transactions, which are merged
+/// once adjacent.
+#[derive(Default)]
+pub struct TxSeqRanges {
+    /// Maps the start of each range to its end (exclusive).
+    ranges: BTreeMap<u64, u64>,
+}
+
+impl TxSeqRanges {
+    /// Builds the ranges from tx_seq in ascending order.
+    pub fn from_sorted(tx_seqs: impl IntoIterator<Item = u64>) -> Self {
+        let mut ranges: Vec<Range<u64>> = vec![];
+        for tx_seq in tx_seqs {
+            match ranges.last_mut() {
+                Some(last) if last.end == tx_seq => last.end += 1,
+                _ => ranges.push(tx_seq..tx_seq + 1),
+            }
+        }
+
+        Self {
+            ranges: ranges.into_iter().map(|r| (r.start, r.end)).collect(),
+        }
+    }
+
+    pub fn insert(&mut self, tx_seq: u64) {
+        let mut start = tx_seq;
+        let mut end = tx_seq + 1;
+
+        if let Some((&prev_start, &prev_end)) = self.ranges.range(..=tx_seq).next_back() {
+            if prev_end > tx_seq {
+                return;
+            }
+            if prev_end == tx_seq {
+                start = prev_start;
+            }
+        }
+
+        if let Some(next_end) = self.ranges.remove(&end) {
+            end = next_end;
+        }
+
+        self.ranges.insert(start, end);
+    }
+
+    pub fn remove(&mut self, tx_seq: u64) {
+        let (start, end) = match self.ranges.range(..=tx_seq).next_back() {
+            Some((&start, &end)) if end > tx_seq => (start, end),
+            _ => return,
+        };
+
+        self.ranges.remove(&start);
+        if start < tx_seq {
+            self.ranges.insert(start, tx_seq);
+        }
+        if tx_seq + 1 < end {
+            self.ranges.insert(tx_seq + 1, end);
+        }
+    }
+
+    /// Removes all tx_seq that are not less than `min_seq`, e.g. reverted by chain reorg.
+    pub fn remove_after(&mut self, min_seq: u64) {
+        self.ranges.split_off(&min_seq);
+        if let Some((_, end)) = self.ranges.iter_mut().next_back() {
+            if *end > min_seq {
+                *end = min_seq;
+            }
+        }
+    }
+
+    pub fn to_vec(&self) -> Vec<Range<u64>> {
+        self.ranges
+            .iter()
+            .map(|(start, end)| *start..*end)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TxSeqRanges;
+
+    #[test]
+    fn test_tx_seq_ranges() {
+        let mut ranges = TxSeqRanges::from_sorted(vec![0, 1, 2, 5, 6, 9]);
+        assert_eq!(ranges.to_vec(), vec![0..3, 5..7, 9..10]);
+
+        ranges.insert(1);
+        ranges.insert(4);
+        ranges.insert(3);
+        assert_eq!(ranges.to_vec(), vec![0..7, 9..10]);
+
+        ranges.remove(0);
+        ranges.remove(3);
+        ranges.remove(8);
+        assert_eq!(ranges.to_vec(), vec![1..3, 4..7, 9..10]);
+
+        ranges.remove_after(5);
+        assert_eq!(ranges.to_vec(), vec![1..3, 4..5]);
+        ranges.remove_after(4);
+        assert_eq!(ranges.to_vec(), vec![1..3]);
+    }
+}
@@ -1,29 +1,35 @@
 use crate::error::Error;
 use crate::log_store::log_manager::{
     data_to_merkle_leaves, sub_merkle_tree, COL_BLOCK_PROGRESS, COL_MISC, COL_TX, COL_TX_COMPLETED,
-    COL_TX_DATA_ROOT_INDEX, ENTRY_SIZE, PORA_CHUNK_SIZE,
+    COL_TX_DATA_ROOT_INDEX, COL_TX_STREAM_ID_INDEX, ENTRY_SIZE, PORA_CHUNK_SIZE,
 };
 use crate::log_store::metrics;
+use crate::log_store::tx_ranges::TxSeqRanges;
 use crate::{try_option, LogManager, ZgsKeyValueDB};
 use anyhow::{anyhow, Result};
 use append_merkle::{AppendMerkleTree, MerkleTreeRead, Sha3Algorithm};
-use ethereum_types::H256;
+use ethereum_types::{H256, U256};
 use merkle_light::merkle::log2_pow2;
+use parking_lot::RwLock;
 use shared_types::{DataRoot, Transaction};
 use ssz::{Decode, Encode};
 use std::cmp;
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
+use std::ops::Range;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Instant;
+use tokio::sync::broadcast;
 use tracing::{error, instrument};
 
 const LOG_SYNC_PROGRESS_KEY: &str = "log_sync_progress";
-const NEXT_TX_KEY: &str = "next_tx_seq";
+pub(crate) const NEXT_TX_KEY: &str = "next_tx_seq";
 const LOG_LATEST_BLOCK_NUMBER_KEY: &str = "log_latest_block_number_key";
+const TX_STATUS_CHANNEL_CAPACITY: usize = 1024;
+const STREAM_ID_SIZE: usize = 32;
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum TxStatus {
     Finalized,
     Pruned,
@@ -50,6 +56,13 @@ impl TryFrom<u8> for TxStatus {
     }
 }
 
+/// Notification of a transaction status persisted in the store.
+#[derive(Clone, Copy, Debug)]
+pub struct TxStatusUpdate {
+    pub tx_seq: u64,
+    pub status: TxStatus,
+}
+
 #[derive(Clone, Debug)]
 pub struct BlockHashAndSubmissionIndex {
     pub block_hash: H256,
@@ -61,6 +74,11 @@ pub struct TransactionStore {
     data_kvdb: Arc<dyn ZgsKeyValueDB>,
     /// This is always updated before writing the database to ensure no intermediate states.
     next_tx_seq: AtomicU64,
+    /// Notifies subscribers once a tx is finalized or pruned.
+    status_sender: broadcast::Sender<TxStatusUpdate>,
+    /// Ranges of finalized txs, which are loaded from the database on the first query and then
+    /// updated along with the tx status.
+    finalized_ranges: RwLock<Option<TxSeqRanges>>,
 }
 
 impl TransactionStore {
@@ -72,10 +90,13 @@ impl TransactionStore {
             .get(COL_TX, NEXT_TX_KEY.as_bytes())?
             .map(|a| decode_tx_seq(&a))
             .unwrap_or(Ok(0))?;
+        let (status_sender, _) = broadcast::channel(TX_STATUS_CHANNEL_CAPACITY);
         Ok(Self {
             flow_kvdb,
             data_kvdb,
             next_tx_seq: AtomicU64::new(next_tx_seq),
+            status_sender,
+            finalized_ranges: RwLock::new(None),
         })
     }
 
@@ -117,6 +138,22 @@ impl TransactionStore {
             tx.data_merkle_root.as_bytes(),
             &new_tx_seq_list.as_ssz_bytes(),
         );
+        let mut stream_ids = tx.stream_ids.clone();
+        stream_ids.sort();
+        stream_ids.dedup();
+        for stream_id in stream_ids.iter() {
+            let num_txs = self.get_num_txs_by_stream_id(stream_id)?;
+            db_tx.put(
+                COL_TX_STREAM_ID_INDEX,
+                &stream_id_index_key(stream_id, num_txs),
+                &tx.seq.to_be_bytes(),
+            );
+            db_tx.put(
+                COL_TX_STREAM_ID_INDEX,
+                &stream_id_index_prefix(stream_id),
+                &(num_txs + 1).to_be_bytes(),
+            );
+        }
         self.next_tx_seq.store(tx.seq + 1, Ordering::SeqCst);
         self.flow_kvdb.write(db_tx)?;
         metrics::TX_STORE_PUT.update_since(start_time);
@@ -140,6 +177,7 @@ impl TransactionStore {
         let mut flow_db_tx = self.flow_kvdb.transaction();
         let mut data_db_tx = self.data_kvdb.transaction();
         let mut modified_merkle_root_map = HashMap::new();
+        let mut removed_stream_txs: HashMap<U256, u64> = HashMap::new();
         for seq in min_seq..max_seq {
             let Some(tx) = self.get_tx_by_seq_number(seq)? else {
                 error!(?seq, ?max_seq, "Transaction missing before the end");
@@ -147,6 +185,12 @@ impl TransactionStore {
             };
             flow_db_tx.delete(COL_TX, &seq.to_be_bytes());
             data_db_tx.delete(COL_TX_COMPLETED, &seq.to_be_bytes());
+            let mut stream_ids = tx.stream_ids.clone();
+            stream_ids.sort();
+            stream_ids.dedup();
+            for stream_id in stream_ids {
+                *removed_stream_txs.entry(stream_id).or_default() += 1;
+            }
             // We only remove tx when the blockchain reorgs.
             // If a tx is reverted, all data after it will also be reverted, so we call remove
             // all indices after it.
@@ -159,6 +203,26 @@ impl TransactionStore {
             tx_seq_list.retain(|e| *e < seq);
             removed_txs.push(tx);
         }
+        // The reverted txs are always the last ones of each stream.
+        for (stream_id, num_removed) in removed_stream_txs {
+            let num_txs = self.get_num_txs_by_stream_id(&stream_id)?;
+            let remaining = num_txs.saturating_sub(num_removed);
+            for position in remaining..num_txs {
+                flow_db_tx.delete(
+                    COL_TX_STREAM_ID_INDEX,
+                    &stream_id_index_key(&stream_id, position),
+                );
+            }
+            if remaining == 0 {
+                flow_db_tx.delete(COL_TX_STREAM_ID_INDEX, &stream_id_index_prefix(&stream_id));
+            } else {
+                flow_db_tx.put(
+                    COL_TX_STREAM_ID_INDEX,
+                    &stream_id_index_prefix(&stream_id),
+                    &remaining.to_be_bytes(),
+                );
+            }
+        }
         for (merkle_root, tx_seq_list) in modified_merkle_root_map {
             if tx_seq_list.is_empty() {
                 flow_db_tx.delete(COL_TX_DATA_ROOT_INDEX, merkle_root.as_bytes());
@@ -174,6 +238,7 @@ impl TransactionStore {
         self.next_tx_seq.store(min_seq, Ordering::SeqCst);
         self.data_kvdb.write(data_db_tx)?;
         self.flow_kvdb.write(flow_db_tx)?;
+        self.update_finalized_ranges(|ranges| ranges.remove_after(min_seq));
         Ok(removed_txs)
     }
 
@@ -188,22 +253,84 @@ impl TransactionStore {
         Ok(Vec::<u64>::from_ssz_bytes(&value).map_err(Error::from)?)
     }
 
+    pub fn get_tx_seq_list_by_stream_id(
+        &self,
+        stream_id: &U256,
+        from_seq: u64,
+        limit: usize,
+    ) -> Result<Vec<u64>> {
+        // `kvdb` could not seek, so txs of a stream are indexed by their positions in order,
+        // and the first tx no less than `from_seq` is located by a binary search.
+        let num_txs = self.get_num_txs_by_stream_id(stream_id)?;
+        let (mut left, mut right) = (0, num_txs);
+        while left < right {
+            let mid = left + (right - left) / 2;
+            if self.get_stream_tx_seq(stream_id, mid)? < from_seq {
+                left = mid + 1;
+            } else {
+                right = mid;
+            }
+        }
+
+        let end = num_txs.min(left.saturating_add(limit as u64));
+        (left..end)
+            .map(|position| self.get_stream_tx_seq(stream_id, position))
+            .collect()
+    }
+
+    fn get_num_txs_by_stream_id(&self, stream_id: &U256) -> Result<u64> {
+        match self
+            .flow_kvdb
+            .get(COL_TX_STREAM_ID_INDEX, &stream_id_index_prefix(stream_id))?
+        {
+            Some(value) => decode_tx_seq(&value),
+            None => Ok(0),
+        }
+    }
+
+    fn get_stream_tx_seq(&self, stream_id: &U256, position: u64) -> Result<u64> {
+        let value = self
+            .flow_kvdb
+            .get(
+                COL_TX_STREAM_ID_INDEX,
+                &stream_id_index_key(stream_id, position),
+            )?
+            .ok_or_else(|| anyhow!("stream tx missing: {:?} {}", stream_id, position))?;
+        decode_tx_seq(&value)
+    }
+
     #[instrument(skip(self))]
     pub fn finalize_tx(&self, tx_seq: u64) -> Result<()> {
-        Ok(self.data_kvdb.put(
-            COL_TX_COMPLETED,
-            &tx_seq.to_be_bytes(),
-            &[TxStatus::Finalized.into()],
-        )?)
+        self.put_tx_status(tx_seq, TxStatus::Finalized)
     }
 
     #[instrument(skip(self))]
     pub fn prune_tx(&self, tx_seq: u64) -> Result<()> {
-        Ok(self.data_kvdb.put(
-            COL_TX_COMPLETED,
-            &tx_seq.to_be_bytes(),
-            &[TxStatus::Pruned.into()],
-        )?)
+        self.put_tx_status(tx_seq, TxStatus::Pruned)
+    }
+
+    /// Removes the finalized or pruned status, so that the file could be synced again.
+    pub fn reset_tx_status(&self, tx_seq: u64) -> Result<()> {
+        self.data_kvdb
+            .delete(COL_TX_COMPLETED, &tx_seq.to_be_bytes())?;
+        self.update_finalized_ranges(|ranges| ranges.remove(tx_seq));
+        Ok(())
+    }
+
+    fn put_tx_status(&self, tx_seq: u64, status: TxStatus) -> Result<()> {
+        self.data_kvdb
+            .put(COL_TX_COMPLETED, &tx_seq.to_be_bytes(), &[status.into()])?;
+        self.update_finalized_ranges(|ranges| match status {
+            TxStatus::Finalized => ranges.insert(tx_seq),
+            TxStatus::Pruned => ranges.remove(tx_seq),
+        });
+        // Sending only fails when there is no subscriber, which is fine.
+        let _ = self.status_sender.send(TxStatusUpdate { tx_seq, status });
+        Ok(())
+    }
+
+    pub fn subscribe_tx_status(&self) -> broadcast::Receiver<TxStatusUpdate> {
+        self.status_sender.subscribe()
     }
 
     pub fn get_tx_status(&self, tx_seq: u64) -> Result<Option<TxStatus>> {
@@ -229,6 +356,38 @@ impl TransactionStore {
         Ok(matches!(status, Some(TxStatus::Pruned)))
     }
 
+    /// Returns the sorted and non-overlapping ranges of finalized tx_seq.
+    pub fn get_finalized_tx_ranges(&self) -> Result<Vec<Range<u64>>> {
+        if let Some(ranges) = self.finalized_ranges.read().as_ref() {
+            return Ok(ranges.to_vec());
+        }
+
+        // Hold the lock while loading, so that status updates in the meantime are applied after.
+        let mut cache = self.finalized_ranges.write();
+        if let Some(ranges) = cache.as_ref() {
+            return Ok(ranges.to_vec());
+        }
+
+        let mut finalized = vec![];
+        for r in self.data_kvdb.iter(COL_TX_COMPLETED) {
+            let (key, val) = r?;
+            if val.first().copied() == Some(TxStatus::Finalized.into()) {
+                finalized.push(decode_tx_seq(key.as_ref())?);
+            }
+        }
+
+        let ranges = TxSeqRanges::from_sorted(finalized);
+        let result = ranges.to_vec();
+        *cache = Some(ranges);
+        Ok(result)
+    }
+
+    fn update_finalized_ranges(&self, f: impl FnOnce(&mut TxSeqRanges)) {
+        if let Some(ranges) = self.finalized_ranges.write().as_mut() {
+            f(ranges);
+        }
+    }
+
     pub fn next_tx_seq(&self) -> u64 {
         self.next_tx_seq.load(Ordering::SeqCst)
     }
@@ -414,3 +573,18 @@ fn decode_tx_seq(data: &[u8]) -> Result<u64> {
         data.try_into().map_err(|e| anyhow!("{:?}", e))?,
     ))
 }
+
+fn stream_id_index_prefix(stream_id: &U256) -> [u8; STREAM_ID_SIZE] {
+    let mut prefix = [0u8; STREAM_ID_SIZE];
+    stream_id.to_big_endian(&mut prefix);
+    prefix
+}
+
+/// The index key is `stream_id ++ position`, where `position` is the order of the tx in the
+/// stream in big endian and the value is the tx seq. Besides, the number of txs of a stream is
+/// stored with the key `stream_id`.
+fn stream_id_index_key(stream_id: &U256, position: u64) -> Vec<u8> {
+    let mut key = stream_id_index_prefix(stream_id).to_vec();
+    key.extend_from_slice(&position.to_be_bytes());
+    key
+}
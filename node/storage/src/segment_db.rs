@@ -0,0 +1,684 @@
+//! A key-value backend that stores values of a column in flat append-only segment files, while
+//! the locations of values and all other columns are kept in the inner database.
+//!
+//! It is used for entry batches, which are large and rewritten frequently when sealed, so that
+//! they do not go through the compaction of RocksDB. A segment is compacted by copying the live
+//! values into the active segment once most of its values are overwritten or deleted.
+//!
+//! Appended values are synced to disk in batch, like the WAL of the inner database which is not
+//! synced on every write. Locations of values lost on a crash are removed from the index when
+//! opened, so that they are regarded as missing instead of corrupted.
+
+use crate::ZgsKeyValueDB;
+use kvdb::{DBKeyValue, DBOp, DBTransaction, DBValue, KeyValueDB};
+use parking_lot::{Mutex, RwLock};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+const SEGMENT_FILE_EXTENSION: &str = "seg";
+
+/// Size of the value location in the index, `segment_id(u64) | offset(u64) | len(u32)`.
+const LOCATION_SIZE: usize = 20;
+
+/// Number of values to move into segments at a time when migrating an existing column.
+const MIGRATE_BATCH_SIZE: usize = 1024;
+
+#[derive(Clone, Debug)]
+pub struct SegmentConfig {
+    /// Directory to store segment files.
+    pub dir: PathBuf,
+    /// A new segment is created once the active segment exceeds this size in bytes.
+    pub segment_size: u64,
+    /// A segment is compacted once the ratio of overwritten or deleted bytes exceeds this.
+    pub compact_garbage_ratio: f64,
+    /// The active segment is synced to disk once this interval elapsed since the last sync.
+    pub sync_interval: Duration,
+    /// The active segment is synced to disk once this many bytes appended since the last sync.
+    pub sync_bytes: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Location {
+    segment_id: u64,
+    /// Offset of the value in the segment file.
+    offset: u64,
+    len: u32,
+}
+
+impl Location {
+    fn encode(&self) -> [u8; LOCATION_SIZE] {
+        let mut bytes = [0u8; LOCATION_SIZE];
+        bytes[..8].copy_from_slice(&self.segment_id.to_be_bytes());
+        bytes[8..16].copy_from_slice(&self.offset.to_be_bytes());
+        bytes[16..].copy_from_slice(&self.len.to_be_bytes());
+        bytes
+    }
+
+    fn decode(bytes: &[u8]) -> io::Result<Self> {
+        if bytes.len() != LOCATION_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid segment location size: {}", bytes.len()),
+            ));
+        }
+        Ok(Self {
+            segment_id: u64::from_be_bytes(bytes[..8].try_into().unwrap()),
+            offset: u64::from_be_bytes(bytes[8..16].try_into().unwrap()),
+            len: u32::from_be_bytes(bytes[16..].try_into().unwrap()),
+        })
+    }
+}
+
+#[derive(Default)]
+struct SegmentStats {
+    size: u64,
+    /// Bytes of values still referenced by the index.
+    live: u64,
+}
+
+struct WriterState {
+    active_id: u64,
+    active_file: File,
+    stats: BTreeMap<u64, SegmentStats>,
+    /// Bytes appended into the active segment since the last sync.
+    unsynced_bytes: u64,
+    last_sync: Instant,
+}
+
+impl WriterState {
+    fn sync(&mut self) -> io::Result<()> {
+        if self.unsynced_bytes > 0 {
+            self.active_file.sync_data()?;
+            self.unsynced_bytes = 0;
+        }
+        self.last_sync = Instant::now();
+        Ok(())
+    }
+}
+
+pub struct SegmentDB {
+    inner: Arc<dyn ZgsKeyValueDB>,
+    /// The column whose values are stored in segments.
+    col: u32,
+    /// The column of the inner database to store value locations of `col`.
+    index_col: u32,
+    config: SegmentConfig,
+    /// File handles to read segments. Segments are only removed with the write lock held, so
+    /// a location read from the index is valid while the read lock is held.
+    readers: RwLock<HashMap<u64, Mutex<File>>>,
+    /// Serializes writes and the commit of compaction.
+    writer: Mutex<WriterState>,
+    /// Serializes compaction, whose live values are read without the writer lock.
+    compaction: Mutex<()>,
+}
+
+impl SegmentDB {
+    pub fn open(
+        inner: Arc<dyn ZgsKeyValueDB>,
+        col: u32,
+        index_col: u32,
+        config: SegmentConfig,
+    ) -> io::Result<Self> {
+        fs::create_dir_all(&config.dir)?;
+
+        let mut stats = BTreeMap::new();
+        let mut readers = HashMap::new();
+        for entry in fs::read_dir(&config.dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(SEGMENT_FILE_EXTENSION) {
+                continue;
+            }
+            let segment_id = match path
+                .file_stem()
+                .and_then(|s| s.to_str()?.parse::<u64>().ok())
+            {
+                Some(id) => id,
+                None => continue,
+            };
+            let file = File::open(&path)?;
+            let size = file.metadata()?.len();
+            stats.insert(
+                segment_id,
+                SegmentStats {
+                    size,
+                    ..Default::default()
+                },
+            );
+            readers.insert(segment_id, Mutex::new(file));
+        }
+
+        // Values not synced before a crash may be lost, while their locations are committed.
+        let mut lost = inner.transaction();
+        for item in inner.iter(index_col) {
+            let (key, raw) = item?;
+            let location = Location::decode(&raw)?;
+            match stats.get_mut(&location.segment_id) {
+                Some(stats) if location.offset + location.len as u64 <= stats.size => {
+                    stats.live += location.len as u64
+                }
+                _ => lost.delete(index_col, &key),
+            }
+        }
+        if !lost.ops.is_empty() {
+            warn!(num_lost = %lost.ops.len(), "Values lost in segment files are removed");
+            inner.write(lost)?;
+        }
+
+        let active_id = stats.keys().last().copied().unwrap_or(0);
+        let active_path = segment_path(&config, active_id);
+        let active_file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&active_path)?;
+        readers
+            .entry(active_id)
+            .or_insert(Mutex::new(File::open(&active_path)?));
+        stats.entry(active_id).or_default();
+
+        let db = Self {
+            inner,
+            col,
+            index_col,
+            config,
+            readers: RwLock::new(readers),
+            writer: Mutex::new(WriterState {
+                active_id,
+                active_file,
+                stats,
+                unsynced_bytes: 0,
+                last_sync: Instant::now(),
+            }),
+            compaction: Mutex::new(()),
+        };
+        db.migrate()?;
+        Ok(db)
+    }
+
+    /// Moves values already stored in the inner database into segments, which happens when
+    /// the backend is switched for an existing database.
+    fn migrate(&self) -> io::Result<()> {
+        let mut migrated = 0;
+        loop {
+            let items = self
+                .inner
+                .iter(self.col)
+                .take(MIGRATE_BATCH_SIZE)
+                .collect::<io::Result<Vec<_>>>()?;
+            if items.is_empty() {
+                break;
+            }
+            migrated += items.len();
+
+            let mut tx = self.transaction();
+            let mut delete_tx = self.inner.transaction();
+            for (key, value) in items {
+                delete_tx.delete(self.col, &key);
+                tx.put_vec(self.col, &key, value);
+            }
+            // Values are removed from the inner database after synced and the index committed,
+            // and would be migrated again if interrupted.
+            self.write(tx)?;
+            self.writer.lock().sync()?;
+            self.inner.write(delete_tx)?;
+        }
+        if migrated > 0 {
+            info!(%migrated, "Values migrated into segment files");
+        }
+        Ok(())
+    }
+
+    fn read_location(
+        &self,
+        readers: &HashMap<u64, Mutex<File>>,
+        location: &Location,
+    ) -> io::Result<DBValue> {
+        let reader = readers.get(&location.segment_id).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("segment not found: {}", location.segment_id),
+            )
+        })?;
+        let mut file = reader.lock();
+        file.seek(SeekFrom::Start(location.offset))?;
+        let mut value = vec![0u8; location.len as usize];
+        file.read_exact(&mut value)?;
+        Ok(value)
+    }
+
+    fn get_indexed(&self, key: &[u8]) -> io::Result<Option<DBValue>> {
+        let readers = self.readers.read();
+        match self.inner.get(self.index_col, key)? {
+            Some(raw) => Ok(Some(
+                self.read_location(&readers, &Location::decode(&raw)?)?,
+            )),
+            None => Ok(None),
+        }
+    }
+
+    /// Reads the value of an index item from iterators, which may be stale if the segment
+    /// is compacted meanwhile, in which case the latest location is used.
+    fn read_item(&self, key: &[u8], raw: &[u8]) -> io::Result<Option<DBValue>> {
+        let location = Location::decode(raw)?;
+        {
+            let readers = self.readers.read();
+            if readers.contains_key(&location.segment_id) {
+                return self.read_location(&readers, &location).map(Some);
+            }
+        }
+        self.get_indexed(key)
+    }
+
+    fn indexed_iter<'a>(
+        &'a self,
+        iter: Box<dyn Iterator<Item = io::Result<DBKeyValue>> + 'a>,
+    ) -> Box<dyn Iterator<Item = io::Result<DBKeyValue>> + 'a> {
+        Box::new(iter.filter_map(move |item| {
+            let (key, raw) = match item {
+                Ok(item) => item,
+                Err(e) => return Some(Err(e)),
+            };
+            self.read_item(&key, &raw)
+                .transpose()
+                .map(|value| value.map(|value| (key, value)))
+        }))
+    }
+
+    /// Appends the value into the active segment, and creates a new one if it is full.
+    fn append(&self, state: &mut WriterState, key: &[u8], value: &[u8]) -> io::Result<Location> {
+        if state.stats[&state.active_id].size >= self.config.segment_size {
+            state.sync()?;
+            let active_id = state.active_id + 1;
+            let path = segment_path(&self.config, active_id);
+            state.active_file = OpenOptions::new().create(true).append(true).open(&path)?;
+            self.readers
+                .write()
+                .insert(active_id, Mutex::new(File::open(&path)?));
+            state.stats.insert(active_id, Default::default());
+            state.active_id = active_id;
+            debug!(%active_id, "New segment created");
+        }
+
+        let mut record = Vec::with_capacity(8 + key.len() + value.len());
+        record.extend_from_slice(&(key.len() as u32).to_be_bytes());
+        record.extend_from_slice(&(value.len() as u32).to_be_bytes());
+        record.extend_from_slice(key);
+        record.extend_from_slice(value);
+        state.active_file.write_all(&record)?;
+        state.unsynced_bytes += record.len() as u64;
+
+        let stats = state
+            .stats
+            .get_mut(&state.active_id)
+            .expect("active segment");
+        let location = Location {
+            segment_id: state.active_id,
+            offset: stats.size + 8 + key.len() as u64,
+            len: value.len() as u32,
+        };
+        stats.size += record.len() as u64;
+        stats.live += value.len() as u64;
+        Ok(location)
+    }
+
+    /// Marks the value at the location as garbage.
+    fn release(state: &mut WriterState, location: &Location) {
+        if let Some(stats) = state.stats.get_mut(&location.segment_id) {
+            stats.live = stats.live.saturating_sub(location.len as u64);
+        }
+    }
+
+    /// Returns the location of the key, either written in the current transaction or committed.
+    fn current_location(
+        &self,
+        pending: &HashMap<Vec<u8>, Option<Location>>,
+        key: &[u8],
+    ) -> io::Result<Option<Location>> {
+        match pending.get(key) {
+            Some(location) => Ok(*location),
+            None => self
+                .inner
+                .get(self.index_col, key)?
+                .map(|raw| Location::decode(&raw))
+                .transpose(),
+        }
+    }
+
+    /// Compacts segments with too much garbage. It is skipped if another compaction is running,
+    /// and the segments are checked again on the next write.
+    fn maybe_compact(&self) -> io::Result<()> {
+        let _guard = match self.compaction.try_lock() {
+            Some(guard) => guard,
+            None => return Ok(()),
+        };
+        let to_compact: Vec<u64> = {
+            let state = self.writer.lock();
+            state
+                .stats
+                .iter()
+                .filter(|(id, stats)| {
+                    **id != state.active_id
+                        && (stats.size - stats.live.min(stats.size)) as f64
+                            >= stats.size as f64 * self.config.compact_garbage_ratio
+                })
+                .map(|(id, _)| *id)
+                .collect()
+        };
+
+        for segment_id in to_compact {
+            self.compact_segment(segment_id)?;
+        }
+        Ok(())
+    }
+
+    /// Copies the live values of a segment into the active segment, and removes the segment.
+    ///
+    /// The live values are read from a snapshot of the index without the writer lock, since no
+    /// value is written into an inactive segment. Values overwritten or deleted since the
+    /// snapshot are skipped when committing under the writer lock.
+    fn compact_segment(&self, segment_id: u64) -> io::Result<()> {
+        let mut live = vec![];
+        for item in self.inner.iter(self.index_col) {
+            let (key, raw) = item?;
+            let location = Location::decode(&raw)?;
+            if location.segment_id == segment_id {
+                let value = self.read_location(&self.readers.read(), &location)?;
+                live.push((key, location, value));
+            }
+        }
+
+        let mut state = self.writer.lock();
+        let mut tx = self.inner.transaction();
+        let mut moved = 0;
+        for (key, location, value) in live {
+            let current = self
+                .inner
+                .get(self.index_col, &key)?
+                .map(|raw| Location::decode(&raw))
+                .transpose()?;
+            if current != Some(location) {
+                continue;
+            }
+            let new_location = self.append(&mut state, &key, &value)?;
+            tx.put(self.index_col, &key, &new_location.encode());
+            moved += 1;
+        }
+        // The moved values must be persisted before the segment is removed.
+        state.sync()?;
+        self.inner.write(tx)?;
+
+        self.readers.write().remove(&segment_id);
+        state.stats.remove(&segment_id);
+        fs::remove_file(segment_path(&self.config, segment_id))?;
+        info!(%segment_id, %moved, "Segment compacted");
+        Ok(())
+    }
+}
+
+impl Drop for SegmentDB {
+    fn drop(&mut self) {
+        if let Err(e) = self.writer.lock().sync() {
+            warn!(%e, "Failed to sync segment file");
+        }
+    }
+}
+
+fn segment_path(config: &SegmentConfig, segment_id: u64) -> PathBuf {
+    config
+        .dir
+        .join(format!("{:016}.{}", segment_id, SEGMENT_FILE_EXTENSION))
+}
+
+impl KeyValueDB for SegmentDB {
+    fn get(&self, col: u32, key: &[u8]) -> io::Result<Option<DBValue>> {
+        if col == self.col {
+            self.get_indexed(key)
+        } else {
+            self.inner.get(col, key)
+        }
+    }
+
+    fn get_by_prefix(&self, col: u32, prefix: &[u8]) -> io::Result<Option<DBValue>> {
+        if col != self.col {
+            return self.inner.get_by_prefix(col, prefix);
+        }
+        match self.inner.iter_with_prefix(self.index_col, prefix).next() {
+            Some(item) => {
+                let (key, raw) = item?;
+                self.read_item(&key, &raw)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn write(&self, transaction: DBTransaction) -> io::Result<()> {
+        let mut state = self.writer.lock();
+        let mut tx = self.inner.transaction();
+        let mut pending: HashMap<Vec<u8>, Option<Location>> = HashMap::new();
+        let mut appended = false;
+
+        for op in transaction.ops {
+            match op {
+                DBOp::Insert { col, key, value } if col == self.col => {
+                    if let Some(old) = self.current_location(&pending, &key)? {
+                        Self::release(&mut state, &old);
+                    }
+                    let location = self.append(&mut state, &key, &value)?;
+                    tx.put(self.index_col, &key, &location.encode());
+                    pending.insert(key.to_vec(), Some(location));
+                    appended = true;
+                }
+                DBOp::Delete { col, key } if col == self.col => {
+                    if let Some(old) = self.current_location(&pending, &key)? {
+                        Self::release(&mut state, &old);
+                    }
+                    tx.delete(self.index_col, &key);
+                    pending.insert(key.to_vec(), None);
+                }
+                DBOp::DeletePrefix { col, prefix } if col == self.col => {
+                    for item in self.inner.iter_with_prefix(self.index_col, &prefix) {
+                        let (key, raw) = item?;
+                        if !pending.contains_key(key.as_ref()) {
+                            Self::release(&mut state, &Location::decode(&raw)?);
+                        }
+                    }
+                    for (key, location) in pending.iter_mut() {
+                        if key.starts_with(&prefix) {
+                            if let Some(old) = location.take() {
+                                Self::release(&mut state, &old);
+                            }
+                        }
+                    }
+                    tx.delete_prefix(self.index_col, &prefix);
+                }
+                op => tx.ops.push(op),
+            }
+        }
+
+        if appended
+            && (state.unsynced_bytes >= self.config.sync_bytes
+                || state.last_sync.elapsed() >= self.config.sync_interval)
+        {
+            state.sync()?;
+        }
+        self.inner.write(tx)?;
+        drop(state);
+        self.maybe_compact()
+    }
+
+    fn iter<'a>(&'a self, col: u32) -> Box<dyn Iterator<Item = io::Result<DBKeyValue>> + 'a> {
+        if col == self.col {
+            self.indexed_iter(self.inner.iter(self.index_col))
+        } else {
+            self.inner.iter(col)
+        }
+    }
+
+    fn iter_with_prefix<'a>(
+        &'a self,
+        col: u32,
+        prefix: &'a [u8],
+    ) -> Box<dyn Iterator<Item = io::Result<DBKeyValue>> + 'a> {
+        if col == self.col {
+            self.indexed_iter(self.inner.iter_with_prefix(self.index_col, prefix))
+        } else {
+            self.inner.iter_with_prefix(col, prefix)
+        }
+    }
+}
+
+impl ZgsKeyValueDB for SegmentDB {
+    fn num_keys(&self, col: u32) -> io::Result<u64> {
+        if col == self.col {
+            self.inner.num_keys(self.index_col)
+        } else {
+            self.inner.num_keys(col)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SegmentConfig, SegmentDB};
+    use crate::ZgsKeyValueDB;
+    use kvdb::KeyValueDB;
+    use rand::random;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    const COL_DATA: u32 = 0;
+    const COL_INDEX: u32 = 1;
+    const COL_OTHER: u32 = 2;
+
+    fn open(inner: Arc<dyn ZgsKeyValueDB>, dir: &std::path::Path) -> SegmentDB {
+        SegmentDB::open(
+            inner,
+            COL_DATA,
+            COL_INDEX,
+            SegmentConfig {
+                dir: dir.to_path_buf(),
+                segment_size: 4096,
+                compact_garbage_ratio: 0.5,
+                sync_interval: Duration::from_secs(1),
+                sync_bytes: 2048,
+            },
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_segment_db() {
+        let dir = std::env::temp_dir().join(format!("segment_db_test_{}", random::<u64>()));
+        let inner: Arc<dyn ZgsKeyValueDB> = Arc::new(kvdb_memorydb::create(3));
+        // values in the inner database are migrated
+        inner.put(COL_DATA, &[0xff], &[1; 10]).unwrap();
+
+        let db = open(inner.clone(), &dir);
+        assert_eq!(db.get(COL_DATA, &[0xff]).unwrap(), Some(vec![1; 10]));
+        assert_eq!(inner.get(COL_DATA, &[0xff]).unwrap(), None);
+
+        // overwrite values repeatedly to create and compact segments
+        for round in 0..10u8 {
+            let mut tx = db.transaction();
+            for i in 0..8u8 {
+                tx.put(COL_DATA, &[i], &[round; 1000]);
+            }
+            tx.put(COL_OTHER, &[round], &[round]);
+            db.write(tx).unwrap();
+        }
+        db.delete(COL_DATA, &[0]).unwrap();
+
+        let check = |db: &SegmentDB| {
+            assert_eq!(db.get(COL_DATA, &[0]).unwrap(), None);
+            assert_eq!(db.get(COL_DATA, &[1]).unwrap(), Some(vec![9; 1000]));
+            assert_eq!(db.get(COL_OTHER, &[9]).unwrap(), Some(vec![9]));
+            let items: Vec<_> = db.iter(COL_DATA).map(|item| item.unwrap()).collect();
+            assert_eq!(items.len(), 8);
+            assert_eq!(items.last().unwrap().1, vec![1; 10]);
+            assert_eq!(db.num_keys(COL_DATA).unwrap(), 8);
+        };
+        check(&db);
+
+        // garbage segments are removed
+        let num_segments = std::fs::read_dir(&dir).unwrap().count();
+        assert!(num_segments <= 4, "num_segments = {}", num_segments);
+
+        // reopen
+        drop(db);
+        check(&open(inner, &dir));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_lost_values_removed() {
+        let dir = std::env::temp_dir().join(format!("segment_db_test_{}", random::<u64>()));
+        let inner: Arc<dyn ZgsKeyValueDB> = Arc::new(kvdb_memorydb::create(3));
+        let db = open(inner.clone(), &dir);
+        db.put(COL_DATA, &[1], &[1; 100]).unwrap();
+        db.put(COL_DATA, &[2], &[2; 100]).unwrap();
+        drop(db);
+
+        // the tail of the segment is lost on a crash
+        let path = std::fs::read_dir(&dir)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        let file = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+        file.set_len(file.metadata().unwrap().len() - 1).unwrap();
+
+        let db = open(inner.clone(), &dir);
+        assert_eq!(db.get(COL_DATA, &[1]).unwrap(), Some(vec![1; 100]));
+        assert_eq!(db.get(COL_DATA, &[2]).unwrap(), None);
+        assert_eq!(inner.num_keys(COL_INDEX).unwrap(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_concurrent_compaction() {
+        let dir = std::env::temp_dir().join(format!("segment_db_test_{}", random::<u64>()));
+        let inner: Arc<dyn ZgsKeyValueDB> = Arc::new(kvdb_memorydb::create(3));
+        let db = Arc::new(open(inner.clone(), &dir));
+
+        // writers overwrite values while segments are compacted by each other
+        let handles: Vec<_> = (0..4u8)
+            .map(|thread| {
+                let db = db.clone();
+                std::thread::spawn(move || {
+                    for round in 0..20u8 {
+                        let mut tx = db.transaction();
+                        for i in 0..4u8 {
+                            tx.put(COL_DATA, &[thread, i], &[round; 500]);
+                        }
+                        db.write(tx).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let check = |db: &SegmentDB| {
+            for thread in 0..4u8 {
+                for i in 0..4u8 {
+                    assert_eq!(db.get(COL_DATA, &[thread, i]).unwrap(), Some(vec![19; 500]));
+                }
+            }
+            assert_eq!(db.num_keys(COL_DATA).unwrap(), 16);
+        };
+        check(&db);
+
+        drop(db);
+        check(&open(inner, &dir));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -20,7 +20,7 @@ use super::{
     batcher_serial::SerialBatcher,
     historical_tx_writer::HistoricalTxWriter,
     metrics,
-    sync_store::{Queue, SyncStore},
+    sync_store::{queue_names, Queue, SyncStore},
 };
 
 pub struct AutoSyncManager {
@@ -43,12 +43,12 @@ impl AutoSyncManager {
         let (file_announcement_send, file_announcement_recv) = unbounded_channel();
         let (new_file_send, new_file_recv) = unbounded_channel();
         let sync_store = if config.neighbors_only {
-            // use v2 db to avoid reading v1 files that announced from the whole network instead of neighbors
+            let (pending, ready) = queue_names(true);
             Arc::new(SyncStore::new_with_name(
                 store.clone(),
                 config.ready_txs_cache_cap,
-                "pendingv2",
-                "readyv2",
+                pending,
+                ready,
             ))
         } else {
             Arc::new(SyncStore::new(store.clone(), 0))
@@ -4,6 +4,7 @@ use std::sync::Arc;
 use storage::log_store::{
     config::{ConfigTx, ConfigurableExt},
     log_manager::DATA_DB_KEY,
+    Store as LogStore,
 };
 use storage_async::Store;
 use tokio::sync::RwLock;
@@ -11,6 +12,17 @@ use tokio::sync::RwLock;
 const KEY_NEXT_TX_SEQ: &str = "sync.manager.next_tx_seq";
 const KEY_MAX_TX_SEQ: &str = "sync.manager.max_tx_seq";
 
+/// Returns the names of pending and ready queues to sync announced files.
+pub fn queue_names(neighbors_only: bool) -> (&'static str, &'static str) {
+    if neighbors_only {
+        // use v2 db to avoid reading v1 files that announced from the whole network instead
+        // of neighbors
+        ("pendingv2", "readyv2")
+    } else {
+        ("pending", "ready")
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum Queue {
     Ready,
@@ -38,7 +50,30 @@ pub struct SyncStore {
 
 impl SyncStore {
     pub fn new(store: Store, ready_txs_cache_cap: usize) -> Self {
-        Self::new_with_name(store, ready_txs_cache_cap, "pending", "ready")
+        let (pending, ready) = queue_names(false);
+        Self::new_with_name(store, ready_txs_cache_cap, pending, ready)
+    }
+
+    /// Adds transactions into the pending queue while the node is stopped, e.g. once reset by
+    /// the offline db repair, so that they are synced from peers again. Returns the number of
+    /// transactions added.
+    pub fn insert_pending_offline(
+        store: &dyn LogStore,
+        neighbors_only: bool,
+        tx_seqs: &[u64],
+    ) -> Result<usize> {
+        let (pending, ready) = queue_names(neighbors_only);
+        let pending_txs = TxStore::new(pending);
+        let ready_txs = TxStore::new(ready);
+
+        let mut added = 0;
+        for tx_seq in tx_seqs {
+            if !ready_txs.has(store, *tx_seq)? && pending_txs.add(store, None, *tx_seq)? {
+                added += 1;
+            }
+        }
+
+        Ok(added)
     }
 
     pub fn new_with_name(
@@ -289,4 +324,29 @@ mod tests {
         assert_eq!(store.remove(2).await.unwrap(), Some(Ready));
         assert_eq!(store.remove(2).await.unwrap(), None);
     }
+
+    #[tokio::test]
+    async fn test_insert_pending_offline() {
+        let runtime = TestStoreRuntime::default();
+        let store = SyncStore::new(runtime.store.clone(), 0);
+        assert_eq!(store.insert(2, Ready).await.unwrap(), NewAdded);
+
+        let log_store = runtime.store.get_store();
+        assert_eq!(
+            SyncStore::insert_pending_offline(log_store, false, &[1, 2, 3, 1]).unwrap(),
+            2
+        );
+        assert_eq!(store.contains(1).await.unwrap(), Some(Pending));
+        assert_eq!(store.contains(2).await.unwrap(), Some(Ready));
+        assert_eq!(store.contains(3).await.unwrap(), Some(Pending));
+
+        // queues of neighbors only mode are separated
+        let store = SyncStore::new_with_name(runtime.store.clone(), 0, "pendingv2", "readyv2");
+        assert_eq!(
+            SyncStore::insert_pending_offline(log_store, true, &[1]).unwrap(),
+            1
+        );
+        assert_eq!(store.contains(1).await.unwrap(), Some(Pending));
+        assert_eq!(store.contains(3).await.unwrap(), None);
+    }
 }
@@ -1,12 +1,27 @@
-use network::{NetworkMessage, NetworkSender, PeerAction, PeerId, PubsubMessage, ReportSource};
+use network::{
+    DownloadEvent, NetworkGlobals, NetworkMessage, NetworkSender, PeerAction, PeerId,
+    PubsubMessage, ReportSource,
+};
+use std::sync::Arc;
 
 pub struct SyncNetworkContext {
     network_send: NetworkSender,
+
+    /// Used to access peer download statistics, which is not available in tests.
+    network_globals: Option<Arc<NetworkGlobals>>,
 }
 
 impl SyncNetworkContext {
     pub fn new(network_send: NetworkSender) -> Self {
-        Self { network_send }
+        Self {
+            network_send,
+            network_globals: None,
+        }
+    }
+
+    pub fn with_network_globals(mut self, network_globals: Arc<NetworkGlobals>) -> Self {
+        self.network_globals = Some(network_globals);
+        self
     }
 
     /// Sends an arbitrary network message.
@@ -42,4 +57,24 @@ impl SyncNetworkContext {
             msg,
         })
     }
+
+    /// Records the result of a chunks request to the peer.
+    pub fn report_download(&self, peer_id: PeerId, event: DownloadEvent) {
+        trace!(%peer_id, ?event, "Report download");
+        if let Some(network_globals) = &self.network_globals {
+            network_globals
+                .peers
+                .write()
+                .record_download(&peer_id, event);
+        }
+    }
+
+    /// Returns the download score of the peer, or `None` if never requested.
+    pub fn download_score(&self, peer_id: &PeerId) -> Option<f64> {
+        self.network_globals
+            .as_ref()?
+            .peers
+            .read()
+            .download_score(peer_id)
+    }
 }
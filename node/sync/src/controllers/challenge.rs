@@ -0,0 +1,292 @@
+use network::types::{SignedAnnounceFileRanges, TxSeqRange};
+use network::PeerId;
+use rand::Rng;
+use shared_types::TxID;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use storage::log_store::log_manager::PORA_CHUNK_SIZE;
+use storage_async::ShardConfig;
+
+/// A storage challenge that awaits response from peer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Challenge {
+    pub tx_id: TxID,
+    /// Challenged sector indexes relative to the start of the file.
+    pub sectors: Vec<u64>,
+    pub since: Instant,
+    /// Range announcement of the peer, which is accepted only if the challenge passed.
+    pub ranges: Option<SignedAnnounceFileRanges>,
+}
+
+/// Tracks storage challenges to peers that announced files, so as to verify that peers really
+/// store the announced files.
+///
+/// A peer is challenged at most once in an interval, and at most one challenge is pending for
+/// a peer at a time.
+#[derive(Debug, Default)]
+pub struct StorageChallenges {
+    pending: HashMap<PeerId, Challenge>,
+    last_challenged: HashMap<PeerId, Instant>,
+    /// Peers that passed the last challenge.
+    last_passed: HashMap<PeerId, Instant>,
+}
+
+impl StorageChallenges {
+    /// Returns `true` if the peer is neither being challenged nor challenged within `interval`.
+    pub fn can_challenge(&self, peer_id: &PeerId, interval: Duration) -> bool {
+        !self.pending.contains_key(peer_id)
+            && self
+                .last_challenged
+                .get(peer_id)
+                .map_or(true, |since| since.elapsed() >= interval)
+    }
+
+    /// Returns `true` if the peer passed a challenge within `interval`, so that its
+    /// announcements could be trusted without another challenge.
+    pub fn is_verified(&self, peer_id: &PeerId, interval: Duration) -> bool {
+        self.last_passed
+            .get(peer_id)
+            .map_or(false, |since| since.elapsed() < interval)
+    }
+
+    pub fn start(
+        &mut self,
+        peer_id: PeerId,
+        tx_id: TxID,
+        sectors: Vec<u64>,
+        ranges: Option<SignedAnnounceFileRanges>,
+    ) {
+        let now = Instant::now();
+        self.last_challenged.insert(peer_id, now);
+        self.last_passed.remove(&peer_id);
+        self.pending.insert(
+            peer_id,
+            Challenge {
+                tx_id,
+                sectors,
+                since: now,
+                ranges,
+            },
+        );
+    }
+
+    /// Marks that the peer passed the challenge just completed.
+    pub fn pass(&mut self, peer_id: PeerId) {
+        self.last_passed.insert(peer_id, Instant::now());
+    }
+
+    /// Removes the pending challenge of the peer for the specified file.
+    pub fn complete(&mut self, peer_id: &PeerId, tx_id: &TxID) -> Option<Challenge> {
+        match self.pending.get(peer_id) {
+            Some(challenge) if challenge.tx_id == *tx_id => self.pending.remove(peer_id),
+            _ => None,
+        }
+    }
+
+    /// Removes challenges that timed out, and forgets peers not challenged within `interval`.
+    /// Returns the peers and files of timed out challenges.
+    pub fn garbage_collect(
+        &mut self,
+        timeout: Duration,
+        interval: Duration,
+    ) -> Vec<(PeerId, TxID)> {
+        let timed_out: Vec<(PeerId, TxID)> = self
+            .pending
+            .iter()
+            .filter(|(_, challenge)| challenge.since.elapsed() >= timeout)
+            .map(|(peer_id, challenge)| (*peer_id, challenge.tx_id))
+            .collect();
+
+        for (peer_id, _) in timed_out.iter() {
+            self.pending.remove(peer_id);
+        }
+
+        self.last_challenged
+            .retain(|_, since| since.elapsed() < interval);
+        self.last_passed
+            .retain(|_, since| since.elapsed() < interval);
+
+        timed_out
+    }
+}
+
+/// Randomly selects a tx_seq in the announced ranges that is less than `next_tx_seq`, i.e. a
+/// file known locally, to challenge the peer that announced the ranges.
+pub fn select_range_tx_seq(ranges: &[TxSeqRange], next_tx_seq: u64) -> Option<u64> {
+    let candidates: Vec<(u64, u64)> = ranges
+        .iter()
+        .filter(|range| range.start < next_tx_seq)
+        .map(|range| (range.start, range.end.min(next_tx_seq)))
+        .collect();
+    let total: u64 = candidates.iter().map(|(start, end)| end - start).sum();
+    if total == 0 {
+        return None;
+    }
+
+    let mut offset = rand::thread_rng().gen_range(0..total);
+    for (start, end) in candidates {
+        if offset < end - start {
+            return Some(start + offset);
+        }
+        offset -= end - start;
+    }
+
+    None
+}
+
+/// Randomly selects at most `count` distinct sectors of a file that are stored by a peer of
+/// the specified shard config. Returns sector indexes relative to the start of the file in
+/// ascending order, or an empty list if no sector of the file falls into the shard.
+pub fn select_sectors(
+    start_entry_index: u64,
+    num_sectors: u64,
+    shard_config: &ShardConfig,
+    count: usize,
+) -> Vec<u64> {
+    if num_sectors == 0 || count == 0 {
+        return vec![];
+    }
+
+    let segment_size = PORA_CHUNK_SIZE as u64;
+    let num_shard = shard_config.num_shard as u64;
+    let shard_id = shard_config.shard_id as u64;
+    let end_entry_index = start_entry_index + num_sectors;
+
+    // segments of the file within shard: `first_segment + k * num_shard`
+    let start_segment = start_entry_index / segment_size;
+    let last_segment = (end_entry_index - 1) / segment_size;
+    let first_segment =
+        start_segment + (shard_id + num_shard - start_segment % num_shard) % num_shard;
+    if first_segment > last_segment {
+        return vec![];
+    }
+    let num_segments = (last_segment - first_segment) / num_shard + 1;
+
+    let mut rng = rand::thread_rng();
+    let mut sectors: Vec<u64> = (0..count)
+        .map(|_| {
+            let segment = first_segment + rng.gen_range(0..num_segments) * num_shard;
+            let from = (segment * segment_size).max(start_entry_index);
+            let to = ((segment + 1) * segment_size).min(end_entry_index);
+            rng.gen_range(from..to) - start_entry_index
+        })
+        .collect();
+
+    sectors.sort_unstable();
+    sectors.dedup();
+    sectors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libp2p::identity;
+
+    fn random_peer() -> PeerId {
+        identity::Keypair::generate_ed25519().public().to_peer_id()
+    }
+
+    #[test]
+    fn test_select_sectors_in_shard() {
+        let shard_config = ShardConfig {
+            num_shard: 4,
+            shard_id: 1,
+        };
+        let start = PORA_CHUNK_SIZE as u64 / 2;
+        let num_sectors = 8 * PORA_CHUNK_SIZE as u64;
+
+        let sectors = select_sectors(start, num_sectors, &shard_config, 32);
+        assert!(!sectors.is_empty() && sectors.len() <= 32);
+        assert!(sectors.windows(2).all(|w| w[0] < w[1]));
+        for sector in sectors {
+            assert!(sector < num_sectors);
+            let segment = (start + sector) / PORA_CHUNK_SIZE as u64;
+            assert!(shard_config.in_range(segment));
+        }
+    }
+
+    #[test]
+    fn test_select_sectors_out_of_shard() {
+        let shard_config = ShardConfig {
+            num_shard: 2,
+            shard_id: 1,
+        };
+
+        // file within the first segment only
+        assert!(select_sectors(0, 10, &shard_config, 4).is_empty());
+        assert!(!select_sectors(PORA_CHUNK_SIZE as u64 - 1, 2, &shard_config, 4).is_empty());
+        assert!(select_sectors(0, 0, &ShardConfig::default(), 4).is_empty());
+
+        // small file in a single segment
+        assert_eq!(select_sectors(5, 1, &ShardConfig::default(), 4), vec![0]);
+    }
+
+    #[test]
+    fn test_challenge_lifecycle() {
+        let mut challenges = StorageChallenges::default();
+        let peer = random_peer();
+        let tx_id = TxID::random_hash(1);
+        let interval = Duration::from_secs(600);
+
+        assert!(challenges.can_challenge(&peer, interval));
+        challenges.start(peer, tx_id, vec![1, 2], None);
+        assert!(!challenges.can_challenge(&peer, interval));
+
+        // response of another file is not expected
+        assert!(challenges.complete(&peer, &TxID::random_hash(2)).is_none());
+        assert_eq!(
+            challenges.complete(&peer, &tx_id).unwrap().sectors,
+            vec![1, 2]
+        );
+
+        // challenged recently
+        assert!(!challenges.can_challenge(&peer, interval));
+        assert!(challenges.can_challenge(&peer, Duration::ZERO));
+
+        assert!(!challenges.is_verified(&peer, interval));
+        challenges.pass(peer);
+        assert!(challenges.is_verified(&peer, interval));
+        assert!(!challenges.is_verified(&peer, Duration::ZERO));
+
+        // verified status is reset once challenged again
+        challenges.start(peer, tx_id, vec![3], None);
+        assert!(!challenges.is_verified(&peer, interval));
+    }
+
+    #[test]
+    fn test_select_range_tx_seq() {
+        let ranges = vec![
+            TxSeqRange { start: 0, end: 2 },
+            TxSeqRange { start: 5, end: 10 },
+        ];
+
+        assert_eq!(select_range_tx_seq(&ranges, 0), None);
+        assert_eq!(select_range_tx_seq(&[], 10), None);
+        assert_eq!(select_range_tx_seq(&ranges[1..], 6), Some(5));
+        for _ in 0..100 {
+            let tx_seq = select_range_tx_seq(&ranges, 8).unwrap();
+            assert!(tx_seq < 2 || (5..8).contains(&tx_seq));
+        }
+    }
+
+    #[test]
+    fn test_challenge_garbage_collect() {
+        let mut challenges = StorageChallenges::default();
+        let peer = random_peer();
+        let tx_id = TxID::random_hash(1);
+        let interval = Duration::from_secs(600);
+
+        challenges.start(peer, tx_id, vec![0], None);
+        assert!(challenges
+            .garbage_collect(Duration::from_secs(60), interval)
+            .is_empty());
+        assert_eq!(
+            challenges.garbage_collect(Duration::ZERO, interval),
+            vec![(peer, tx_id)]
+        );
+        assert!(!challenges.can_challenge(&peer, interval));
+
+        challenges.garbage_collect(Duration::ZERO, Duration::ZERO);
+        assert!(challenges.can_challenge(&peer, interval));
+    }
+}
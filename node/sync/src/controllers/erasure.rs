@@ -0,0 +1,361 @@
+use crate::context::SyncNetworkContext;
+use crate::{Config, InstantWrapper};
+use file_location_cache::FileLocationCache;
+use network::{
+    rpc::GetErasurePieceRequest, NetworkMessage, PeerAction, PeerId, SyncId as RequestId,
+};
+use shared_types::ErasurePiece;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use storage::config::ErasureConfig;
+use storage_async::{ShardConfig, Store};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErasureSyncGoal {
+    /// Fetches the erasure coded piece that this node is responsible for, which is encoded
+    /// from the batch rebuilt from any `data_shards` pieces to verify against the batch root.
+    FetchPiece(usize),
+    /// Collects pieces from peers to rebuild a batch that this node should hold.
+    RebuildBatch,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ErasureSyncState {
+    Requesting { since: InstantWrapper },
+    Completed,
+    Failed { reason: String },
+}
+
+/// Syncs erasure coded pieces of an entry batch from peers, and rebuilds the whole batch from
+/// any `data_shards` pieces, either to store the piece of this node, or the batch itself.
+///
+/// Pieces are verified against the batch root when rebuilding, and peers of invalid pieces are
+/// banned. If not enough valid pieces, one more piece is requested in turn.
+pub struct ErasureSyncController {
+    config: Config,
+
+    batch_index: u64,
+
+    goal: ErasureSyncGoal,
+
+    erasure_config: ErasureConfig,
+
+    state: ErasureSyncState,
+
+    /// Pieces received from peers, indexed by piece index.
+    pieces: HashMap<usize, (PeerId, ErasurePiece)>,
+
+    /// Number of pieces requested in addition to `data_shards` to replace invalid pieces.
+    extra_pieces: usize,
+
+    /// Pending requests mapped from peer to the requested piece index and request time.
+    pending: HashMap<PeerId, (usize, Instant)>,
+
+    /// Peers that already requested, which will not be requested again.
+    requested: HashSet<PeerId>,
+
+    /// A network context to contact the network service.
+    ctx: Arc<SyncNetworkContext>,
+
+    /// Log and transaction storage.
+    store: Store,
+
+    /// Cache for storing and serving gossip messages.
+    file_location_cache: Arc<FileLocationCache>,
+}
+
+impl ErasureSyncController {
+    pub fn new(
+        config: Config,
+        batch_index: u64,
+        goal: ErasureSyncGoal,
+        erasure_config: ErasureConfig,
+        ctx: Arc<SyncNetworkContext>,
+        store: Store,
+        file_location_cache: Arc<FileLocationCache>,
+    ) -> Self {
+        ErasureSyncController {
+            config,
+            batch_index,
+            goal,
+            erasure_config,
+            state: ErasureSyncState::Requesting {
+                since: Instant::now().into(),
+            },
+            pieces: Default::default(),
+            extra_pieces: 0,
+            pending: Default::default(),
+            requested: Default::default(),
+            ctx,
+            store,
+            file_location_cache,
+        }
+    }
+
+    pub fn get_status(&self) -> &ErasureSyncState {
+        &self.state
+    }
+
+    pub fn is_completed_or_failed(&self) -> bool {
+        matches!(
+            self.state,
+            ErasureSyncState::Completed | ErasureSyncState::Failed { .. }
+        )
+    }
+
+    /// Returns the piece index to request from the specified peer if any.
+    fn piece_to_request(&self, peer_id: &PeerId) -> Option<usize> {
+        let peer_config = self.file_location_cache.get_peer_config(peer_id)?;
+        let is_requested = |index: &usize| {
+            self.pieces.contains_key(index)
+                || self.pending.values().any(|(pending, _)| pending == index)
+        };
+
+        // Peers that hold the whole batch could encode any piece, and data pieces are preferred
+        // which need no decoding.
+        if peer_config.in_range(self.batch_index) {
+            return (0..self.erasure_config.total_shards()).find(|index| !is_requested(index));
+        }
+
+        let piece_index = self
+            .erasure_config
+            .piece_index(&peer_config, self.batch_index)?;
+        if is_requested(&piece_index) {
+            None
+        } else {
+            Some(piece_index)
+        }
+    }
+
+    fn pieces_needed(&self) -> usize {
+        self.erasure_config.data_shards + self.extra_pieces
+    }
+
+    pub fn transition(&mut self, connected_peers: &HashSet<PeerId>) {
+        if self.is_completed_or_failed() {
+            return;
+        }
+
+        // timeout pending requests
+        let timeout = self.config.peer_erasure_piece_timeout;
+        self.pending.retain(|peer_id, (_, since)| {
+            let expired = since.elapsed() >= timeout;
+            if expired {
+                debug!(%peer_id, "Erasure piece request timeout");
+            }
+            !expired
+        });
+
+        for peer_id in connected_peers.iter() {
+            if self.pieces.len() + self.pending.len() >= self.pieces_needed() {
+                break;
+            }
+
+            if self.requested.contains(peer_id) {
+                continue;
+            }
+
+            let piece_index = match self.piece_to_request(peer_id) {
+                Some(index) => index,
+                None => continue,
+            };
+
+            self.ctx.send(NetworkMessage::SendRequest {
+                peer_id: *peer_id,
+                request_id: network::RequestId::Sync(
+                    Instant::now(),
+                    RequestId::ErasurePiece {
+                        batch_index: self.batch_index,
+                    },
+                ),
+                request: network::Request::GetErasurePiece(GetErasurePieceRequest {
+                    batch_index: self.batch_index,
+                    piece_index: piece_index as u64,
+                }),
+            });
+
+            debug!(%self.batch_index, %piece_index, %peer_id, "Sent request to get erasure piece");
+
+            self.requested.insert(*peer_id);
+            self.pending.insert(*peer_id, (piece_index, Instant::now()));
+        }
+
+        if self.pending.is_empty() {
+            self.state = ErasureSyncState::Failed {
+                reason: format!(
+                    "Not enough peers to sync erasure pieces, received = {}, needed = {}",
+                    self.pieces.len(),
+                    self.pieces_needed()
+                ),
+            };
+        }
+    }
+
+    pub async fn on_response(&mut self, peer_id: PeerId, piece: ErasurePiece) {
+        let piece_index = match self.pending.remove(&peer_id) {
+            Some((index, _)) => index,
+            None => {
+                debug!(%peer_id, %self.batch_index, "Received unexpected erasure piece");
+                return;
+            }
+        };
+
+        if piece.batch_index != self.batch_index || piece.piece_index != piece_index as u64 {
+            self.ctx.report_peer(
+                peer_id,
+                PeerAction::LowToleranceError,
+                "Mismatched erasure piece",
+            );
+            return;
+        }
+
+        self.pieces.insert(piece_index, (peer_id, piece));
+        if self.pieces.len() >= self.pieces_needed() {
+            self.rebuild().await;
+        }
+    }
+
+    async fn rebuild(&mut self) {
+        let pieces = self
+            .pieces
+            .values()
+            .map(|(_, piece)| piece.clone())
+            .collect();
+        match self.try_rebuild(pieces).await {
+            Ok(Some(invalid_pieces)) => {
+                for piece_index in invalid_pieces {
+                    if let Some((peer_id, _)) = self.pieces.remove(&(piece_index as usize)) {
+                        self.ctx
+                            .ban_peer(peer_id, "Invalid erasure piece in response");
+                    }
+                }
+                self.state = ErasureSyncState::Completed;
+                return;
+            }
+            Ok(None) => {}
+            Err(err) => {
+                warn!(%err, %self.batch_index, "Failed to rebuild batch");
+                self.state = ErasureSyncState::Failed {
+                    reason: err.to_string(),
+                };
+                return;
+            }
+        }
+
+        if self.extra_pieces < self.erasure_config.parity_shards {
+            warn!(%self.batch_index, "Not enough valid erasure pieces, request one more");
+            self.extra_pieces += 1;
+        } else {
+            self.state = ErasureSyncState::Failed {
+                reason: "Not enough valid erasure pieces".into(),
+            };
+        }
+    }
+
+    /// Returns the indexes of invalid pieces once rebuilt, or `None` if not enough valid pieces.
+    async fn try_rebuild(&self, pieces: Vec<ErasurePiece>) -> anyhow::Result<Option<Vec<u64>>> {
+        let rebuilt = match self.goal {
+            ErasureSyncGoal::FetchPiece(piece_index) => {
+                self.store
+                    .put_erasure_piece(self.batch_index, piece_index, pieces)
+                    .await?
+            }
+            ErasureSyncGoal::RebuildBatch => {
+                self.store.rebuild_batch(self.batch_index, pieces).await?
+            }
+        };
+
+        if rebuilt.is_some() {
+            match self.goal {
+                ErasureSyncGoal::FetchPiece(piece_index) => {
+                    info!(%self.batch_index, %piece_index, "Erasure piece stored")
+                }
+                ErasureSyncGoal::RebuildBatch => {
+                    info!(%self.batch_index, "Batch rebuilt from erasure pieces")
+                }
+            }
+        }
+
+        Ok(rebuilt)
+    }
+
+    pub fn on_request_failed(&mut self, peer_id: PeerId) {
+        if self.pending.remove(&peer_id).is_some() {
+            debug!(%peer_id, %self.batch_index, "Failed to request erasure piece");
+        }
+    }
+}
+
+/// Batches that failed to sync erasure pieces, which are retried with exponential backoff, so
+/// that neither later batches are blocked nor peers are flooded with requests.
+#[derive(Default)]
+pub struct ErasureRetries {
+    /// Number of failures and the time to retry by batch index.
+    batches: BTreeMap<u64, (u32, Instant)>,
+}
+
+impl ErasureRetries {
+    /// Maximum exponent of backoff, i.e. at most `64 * interval` to retry.
+    const MAX_BACKOFF_EXP: u32 = 6;
+
+    pub fn on_failed(&mut self, batch_index: u64, interval: Duration) {
+        let now = Instant::now();
+        let (failures, retry_at) = self.batches.entry(batch_index).or_insert((0, now));
+        *retry_at = now + interval * (1 << (*failures).min(Self::MAX_BACKOFF_EXP));
+        *failures += 1;
+    }
+
+    pub fn remove(&mut self, batch_index: u64) {
+        self.batches.remove(&batch_index);
+    }
+
+    /// Returns the failed batches that are ready to retry in ascending order.
+    pub fn ready(&self) -> Vec<u64> {
+        let now = Instant::now();
+        self.batches
+            .iter()
+            .filter(|(_, (_, retry_at))| *retry_at <= now)
+            .map(|(batch_index, _)| *batch_index)
+            .collect()
+    }
+}
+
+/// Returns the piece index that the local node should hold for the specified batch, or `None`
+/// if the whole batch is stored locally or no piece assigned.
+pub fn local_piece_index(
+    erasure_config: &ErasureConfig,
+    shard_config: &ShardConfig,
+    batch_index: u64,
+) -> Option<usize> {
+    match erasure_config.piece_index(shard_config, batch_index)? {
+        0 => None,
+        index => Some(index),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ErasureRetries;
+    use std::time::Duration;
+
+    #[test]
+    fn test_erasure_retries() {
+        let mut retries = ErasureRetries::default();
+        retries.on_failed(3, Duration::ZERO);
+        retries.on_failed(1, Duration::ZERO);
+        retries.on_failed(5, Duration::from_secs(60));
+        assert_eq!(retries.ready(), vec![1, 3]);
+
+        retries.remove(1);
+        assert_eq!(retries.ready(), vec![3]);
+
+        // backoff doubles on each failure
+        retries.on_failed(3, Duration::from_secs(1));
+        retries.on_failed(3, Duration::from_secs(1));
+        let (failures, retry_at) = retries.batches[&3];
+        assert_eq!(failures, 3);
+        assert!(retry_at > std::time::Instant::now() + Duration::from_secs(3));
+        assert!(retries.ready().is_empty());
+    }
+}
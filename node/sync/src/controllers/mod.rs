@@ -1,4 +1,7 @@
+mod challenge;
+mod erasure;
 mod metrics;
+mod parallel;
 mod peers;
 mod serial;
 
@@ -7,6 +10,10 @@ use std::collections::HashMap;
 use peers::PeerState;
 use serde::{Deserialize, Serialize};
 
+pub use challenge::{select_range_tx_seq, select_sectors, Challenge, StorageChallenges};
+pub use erasure::{
+    local_piece_index, ErasureRetries, ErasureSyncController, ErasureSyncGoal, ErasureSyncState,
+};
 pub use serial::{FailureReason, SerialSyncController, SyncState};
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
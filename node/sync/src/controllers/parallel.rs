@@ -0,0 +1,253 @@
+use network::PeerId;
+use std::cmp::Ordering;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+use storage_async::ShardConfig;
+
+/// Weight of the latest measurement when updating peer throughput.
+const THROUGHPUT_SMOOTHING: f64 = 0.3;
+
+/// Chunk range `[from_chunk, to_chunk)` requested from a peer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChunkRange {
+    pub from_chunk: u64,
+    pub to_chunk: u64,
+    pub since: Instant,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct PeerStats {
+    /// Smoothed download throughput in bytes per second, or `None` if not measured yet.
+    throughput: Option<f64>,
+    /// Continuous failures to download chunks from the peer.
+    failures: usize,
+}
+
+/// Tracks chunk ranges of a file that are downloaded from multiple peers simultaneously.
+///
+/// At most one range is requested from a peer at a time, so that responses could be matched
+/// by peer id. Ranges failed to download are requested again prior to new ranges.
+#[derive(Debug, Default)]
+pub struct ParallelDownloads {
+    in_flight: HashMap<PeerId, ChunkRange>,
+    failed: VecDeque<(u64, u64)>,
+    stats: HashMap<PeerId, PeerStats>,
+}
+
+impl ParallelDownloads {
+    pub fn num_in_flight(&self) -> usize {
+        self.in_flight.len()
+    }
+
+    /// Returns `true` if there are ranges in flight or to request again.
+    pub fn has_pending(&self) -> bool {
+        !self.in_flight.is_empty() || !self.failed.is_empty()
+    }
+
+    pub fn get(&self, peer_id: &PeerId) -> Option<ChunkRange> {
+        self.in_flight.get(peer_id).copied()
+    }
+
+    pub fn in_flight_peers(&self) -> Vec<PeerId> {
+        self.in_flight.keys().copied().collect()
+    }
+
+    pub fn timed_out_peers(&self, timeout: Duration) -> Vec<PeerId> {
+        self.in_flight
+            .iter()
+            .filter(|(_, range)| range.since.elapsed() >= timeout)
+            .map(|(peer_id, _)| *peer_id)
+            .collect()
+    }
+
+    pub fn failures(&self, peer_id: &PeerId) -> usize {
+        self.stats.get(peer_id).map_or(0, |stats| stats.failures)
+    }
+
+    pub fn pop_failed(&mut self) -> Option<(u64, u64)> {
+        self.failed.pop_front()
+    }
+
+    /// Puts back the range that could not be requested for now.
+    pub fn push_failed(&mut self, from_chunk: u64, to_chunk: u64) {
+        self.failed.push_front((from_chunk, to_chunk));
+    }
+
+    pub fn start(&mut self, peer_id: PeerId, from_chunk: u64, to_chunk: u64) {
+        self.in_flight.insert(
+            peer_id,
+            ChunkRange {
+                from_chunk,
+                to_chunk,
+                since: Instant::now(),
+            },
+        );
+    }
+
+    /// Marks the range of the peer as downloaded, and updates the peer throughput.
+    pub fn complete(&mut self, peer_id: &PeerId, num_bytes: usize) -> Option<ChunkRange> {
+        let range = self.in_flight.remove(peer_id)?;
+
+        let elapsed = range.since.elapsed().as_secs_f64().max(0.001);
+        let measured = num_bytes as f64 / elapsed;
+        let stats = self.stats.entry(*peer_id).or_default();
+        stats.throughput = Some(match stats.throughput {
+            Some(v) => v * (1.0 - THROUGHPUT_SMOOTHING) + measured * THROUGHPUT_SMOOTHING,
+            None => measured,
+        });
+        stats.failures = 0;
+
+        Some(range)
+    }
+
+    /// Marks the range of the peer as failed, which will be requested again later. If
+    /// `penalize` is true, the failure is counted against the peer.
+    pub fn fail(&mut self, peer_id: &PeerId, penalize: bool) -> Option<ChunkRange> {
+        let range = self.in_flight.remove(peer_id)?;
+        self.failed.push_back((range.from_chunk, range.to_chunk));
+        if penalize {
+            self.stats.entry(*peer_id).or_default().failures += 1;
+        }
+        Some(range)
+    }
+
+    /// Selects an idle peer to request chunks from the candidates of peer id, shard config and
+    /// download score in peer db. Peers with higher throughput measured in this file sync are
+    /// preferred, or higher download score if not measured yet. Peers never requested are
+    /// preferred so as to measure them, and peers that store more shards are preferred in case
+    /// of a tie.
+    pub fn select_peer(
+        &self,
+        candidates: Vec<(PeerId, ShardConfig, Option<f64>)>,
+    ) -> Option<PeerId> {
+        candidates
+            .into_iter()
+            .filter(|(peer_id, ..)| !self.in_flight.contains_key(peer_id))
+            .map(|(peer_id, shard_config, score)| {
+                let throughput = self
+                    .stats
+                    .get(&peer_id)
+                    .and_then(|stats| stats.throughput)
+                    .or(score)
+                    .unwrap_or(f64::INFINITY);
+                (peer_id, shard_config, throughput)
+            })
+            .max_by(|(_, a_shard, a_throughput), (_, b_shard, b_throughput)| {
+                a_throughput
+                    .partial_cmp(b_throughput)
+                    .unwrap_or(Ordering::Equal)
+                    .then_with(|| b_shard.num_shard.cmp(&a_shard.num_shard))
+            })
+            .map(|(peer_id, ..)| peer_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libp2p::identity;
+
+    fn random_peer() -> PeerId {
+        identity::Keypair::generate_ed25519().public().to_peer_id()
+    }
+
+    #[test]
+    fn test_select_peer_by_throughput() {
+        let mut downloads = ParallelDownloads::default();
+        let (slow, fast, unknown) = (random_peer(), random_peer(), random_peer());
+        let shard_config = ShardConfig::default();
+
+        downloads.start(slow, 0, 1024);
+        downloads.complete(&slow, 1);
+        downloads.start(fast, 1024, 2048);
+        downloads.complete(&fast, 1 << 30);
+
+        // peer not measured yet takes precedence
+        let candidates = vec![
+            (slow, shard_config, None),
+            (fast, shard_config, None),
+            (unknown, shard_config, None),
+        ];
+        assert_eq!(downloads.select_peer(candidates.clone()), Some(unknown));
+
+        // busy peer will not be selected
+        downloads.start(unknown, 2048, 3072);
+        assert_eq!(downloads.select_peer(candidates.clone()), Some(fast));
+
+        downloads.start(fast, 3072, 4096);
+        downloads.start(slow, 4096, 5120);
+        assert_eq!(downloads.select_peer(candidates), None);
+    }
+
+    #[test]
+    fn test_select_peer_by_shard_coverage() {
+        let downloads = ParallelDownloads::default();
+        let (full, half) = (random_peer(), random_peer());
+        let half_config = ShardConfig {
+            num_shard: 2,
+            shard_id: 0,
+        };
+
+        let candidates = vec![
+            (half, half_config, None),
+            (full, ShardConfig::default(), None),
+        ];
+        assert_eq!(downloads.select_peer(candidates), Some(full));
+    }
+
+    #[test]
+    fn test_select_peer_by_download_score() {
+        let mut downloads = ParallelDownloads::default();
+        let (measured, scored) = (random_peer(), random_peer());
+        let shard_config = ShardConfig::default();
+
+        downloads.start(measured, 0, 1024);
+        downloads.complete(&measured, 1024);
+
+        // download score in peer db is used if not measured in this file sync
+        let candidates = vec![
+            (measured, shard_config, Some(0.0)),
+            (scored, shard_config, Some(f64::MAX)),
+        ];
+        assert_eq!(downloads.select_peer(candidates), Some(scored));
+
+        let candidates = vec![
+            (measured, shard_config, Some(f64::MAX)),
+            (scored, shard_config, Some(0.0)),
+        ];
+        assert_eq!(downloads.select_peer(candidates), Some(measured));
+    }
+
+    #[test]
+    fn test_fail_and_request_again() {
+        let mut downloads = ParallelDownloads::default();
+        let peer = random_peer();
+
+        downloads.start(peer, 0, 1024);
+        assert!(downloads.has_pending());
+        assert!(downloads.fail(&peer, true).is_some());
+        assert_eq!(downloads.failures(&peer), 1);
+        assert_eq!(downloads.num_in_flight(), 0);
+
+        assert_eq!(downloads.pop_failed(), Some((0, 1024)));
+        assert!(!downloads.has_pending());
+
+        // failures reset once succeeded
+        downloads.start(peer, 0, 1024);
+        downloads.complete(&peer, 1024);
+        assert_eq!(downloads.failures(&peer), 0);
+        assert!(downloads.fail(&peer, true).is_none());
+    }
+
+    #[test]
+    fn test_timed_out_peers() {
+        let mut downloads = ParallelDownloads::default();
+        let peer = random_peer();
+
+        downloads.start(peer, 0, 1024);
+        assert!(downloads
+            .timed_out_peers(Duration::from_secs(60))
+            .is_empty());
+        assert_eq!(downloads.timed_out_peers(Duration::ZERO), vec![peer]);
+    }
+}
@@ -1,9 +1,10 @@
 use file_location_cache::FileLocationCache;
 use network::{Multiaddr, PeerAction, PeerId};
-use rand::seq::IteratorRandom;
+use rand::seq::SliceRandom;
 use serde::{Deserialize, Serialize};
 use shared_types::TxID;
 
+use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::fmt::Debug;
 use std::sync::Arc;
@@ -139,12 +140,26 @@ impl SyncPeers {
         self.peers.get(peer_id).map(|info| info.shard_config)
     }
 
-    pub fn random_peer(&self, state: PeerState) -> Option<(PeerId, Multiaddr)> {
-        self.peers
-            .iter()
-            .filter(|(_, info)| info.state == state)
-            .map(|(peer_id, info)| (*peer_id, info.addr.clone()))
-            .choose(&mut rand::thread_rng())
+    /// Returns the download score of the peer, or `None` if never requested.
+    pub fn download_score(&self, peer_id: &PeerId) -> Option<f64> {
+        self.ctx.as_ref()?.download_score(peer_id)
+    }
+
+    /// Selects the peer with the highest download score. Peers never requested are preferred
+    /// so as to measure them, and ties are broken randomly.
+    pub fn best_peer(&self, mut peers: Vec<PeerId>) -> Option<PeerId> {
+        peers.shuffle(&mut rand::thread_rng());
+
+        let score = |peer_id: &PeerId| self.download_score(peer_id).unwrap_or(f64::INFINITY);
+        peers
+            .into_iter()
+            .max_by(|a, b| score(a).partial_cmp(&score(b)).unwrap_or(Ordering::Equal))
+    }
+
+    /// Selects a peer in the specified state by download score.
+    pub fn select_peer(&self, state: PeerState) -> Option<(PeerId, Multiaddr)> {
+        let peer_id = self.best_peer(self.filter_peers(vec![state]))?;
+        Some((peer_id, self.peers.get(&peer_id)?.addr.clone()))
     }
 
     pub fn filter_peers(&self, state: Vec<PeerState>) -> Vec<PeerId> {
@@ -289,7 +304,7 @@ mod tests {
     }
 
     #[test]
-    fn test_random_peer() {
+    fn test_select_peer() {
         let count = 10;
         let mut sync_peers: SyncPeers = Default::default();
         let addr: Multiaddr = "/ip4/127.0.0.1/tcp/10000".parse().unwrap();
@@ -324,15 +339,15 @@ mod tests {
             );
         }
 
-        // random pick
+        // random pick without download statistics
         for _ in 0..30 {
-            let peer = sync_peers.random_peer(PeerState::Found).unwrap();
+            let peer = sync_peers.select_peer(PeerState::Found).unwrap();
             assert!(peers_found.contains(&peer.0));
             assert_eq!(peer.1, addr);
-            let peer = sync_peers.random_peer(PeerState::Connecting).unwrap();
+            let peer = sync_peers.select_peer(PeerState::Connecting).unwrap();
             assert!(peers_connecting.contains(&peer.0));
             assert_eq!(peer.1, addr);
-            assert!(sync_peers.random_peer(PeerState::Disconnected).is_none());
+            assert!(sync_peers.select_peer(PeerState::Disconnected).is_none());
         }
     }
 
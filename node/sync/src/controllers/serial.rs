@@ -1,4 +1,5 @@
 use crate::context::SyncNetworkContext;
+use crate::controllers::parallel::ParallelDownloads;
 use crate::controllers::peers::{PeerState, SyncPeers};
 use crate::controllers::{metrics, FileSyncGoal, FileSyncInfo};
 use crate::{Config, InstantWrapper};
@@ -6,16 +7,19 @@ use file_location_cache::FileLocationCache;
 use libp2p::swarm::DialError;
 use network::types::FindChunks;
 use network::{
-    multiaddr::Protocol, rpc::GetChunksRequest, types::FindFile, Multiaddr, NetworkMessage,
-    PeerAction, PeerId, PubsubMessage, SyncId as RequestId,
+    multiaddr::Protocol, rpc::GetChunksRequest, types::FindFile, DownloadEvent, Multiaddr,
+    NetworkMessage, PeerAction, PeerId, PubsubMessage, SyncId as RequestId,
 };
-use rand::Rng;
 use shared_types::{ChunkArrayWithProof, ShardedFile, TxID, CHUNK_SIZE};
 use ssz::Encode;
 use std::{sync::Arc, time::Instant};
 use storage::log_store::log_manager::{sector_to_segment, segment_to_sector, PORA_CHUNK_SIZE};
 use storage_async::{ShardConfig, Store};
 
+/// The number of peers to discover that store data of the local shard, when no peers found to
+/// sync a file.
+const NUM_DISCOVER_SHARD_PEERS: usize = 16;
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum FailureReason {
     DBError(String),
@@ -47,6 +51,9 @@ pub enum SyncState {
         to_chunk: u64,
         since: InstantWrapper,
     },
+    DownloadingParallel {
+        num_requests: usize,
+    },
     Completed,
     Failed {
         reason: FailureReason,
@@ -75,6 +82,9 @@ pub struct SerialSyncController {
     /// Continuous RPC failures to request chunks.
     failures: usize,
 
+    /// Chunk ranges in downloading from multiple peers if `max_parallel_requests` > 1.
+    downloads: ParallelDownloads,
+
     /// Current state of this request.
     state: SyncState,
 
@@ -110,6 +120,7 @@ impl SerialSyncController {
             goal,
             next_chunk: goal.index_start,
             failures: 0,
+            downloads: Default::default(),
             state: SyncState::Idle,
             peers: SyncPeers::new(config, ctx.clone(), tx_id, file_location_cache.clone()),
             ctx,
@@ -153,6 +164,7 @@ impl SerialSyncController {
         }
 
         self.failures = 0;
+        self.downloads = Default::default();
         self.state = SyncState::Idle;
         // remove disconnected peers
         self.peers.transition();
@@ -182,10 +194,10 @@ impl SerialSyncController {
         // try from cache
         let mut num_new_peers = 0;
 
-        for announcement in self.file_location_cache.get_all(self.tx_id) {
+        for location in self.file_location_cache.get_all(self.tx_id) {
             // make sure peer_id is part of the address
-            let peer_id: PeerId = announcement.peer_id.clone().into();
-            let mut addr: Multiaddr = announcement.at.clone().into();
+            let peer_id = location.peer_id;
+            let mut addr = location.at;
             addr.push(Protocol::P2p(peer_id.into()));
 
             if self.on_peer_found(peer_id, addr) {
@@ -209,6 +221,17 @@ impl SerialSyncController {
     }
 
     fn do_publish_find_file(&self) {
+        if self.config.find_file_by_dht {
+            self.ctx
+                .send(NetworkMessage::FindProviders { tx_id: self.tx_id });
+
+            // neighbors are still asked, which is cheap and finds recently synced files that
+            // may not be published in DHT yet
+            if !self.config.neighbors_only {
+                return;
+            }
+        }
+
         let shard_config = self.store.get_store().get_shard_config();
 
         let msg = if self.config.neighbors_only {
@@ -253,11 +276,16 @@ impl SerialSyncController {
             .peers
             .all_shards_available(vec![PeerState::Connecting, PeerState::Connected])
         {
-            let (peer_id, address) = match self.peers.random_peer(PeerState::Found) {
+            let (peer_id, address) = match self.peers.select_peer(PeerState::Found) {
                 Some((peer_id, address)) => (peer_id, address),
                 None => {
                     // peer may be disconnected by remote node and need to find peers again
                     warn!(%self.tx_seq, "No peers available to connect");
+                    // connect to more peers that store data of the local shard via discovery
+                    self.ctx.send(NetworkMessage::DiscoverShardPeers {
+                        shard_config: self.store.get_store().get_shard_config().into(),
+                        num_peers: NUM_DISCOVER_SHARD_PEERS,
+                    });
                     self.state = SyncState::Idle;
                     return;
                 }
@@ -281,19 +309,41 @@ impl SerialSyncController {
         };
     }
 
-    /// Randomly select a peer to sync the next segment.
-    fn try_request_next(&mut self) {
-        // limits network bandwidth if configured
+    fn is_parallel(&self) -> bool {
+        self.config.max_parallel_requests > 1
+    }
+
+    /// Returns the next chunk to download after the segment of `from_chunk` in local shard.
+    fn next_chunk_in_shard(&self, from_chunk: u64) -> u64 {
+        let shard_config = self.store.get_store().get_shard_config();
+        segment_to_sector(shard_config.next_segment_index(
+            sector_to_segment(from_chunk),
+            sector_to_segment(self.tx_start_chunk_in_flow),
+        )) as u64
+    }
+
+    /// Returns `true` and waits for a while if the network bandwidth limit exceeded.
+    fn wait_for_bandwidth(&mut self) -> bool {
         if self.config.max_bandwidth_bytes > 0 {
             let m1 = metrics::SERIAL_SYNC_SEGMENT_BANDWIDTH.rate1() as u64;
             if m1 > self.config.max_bandwidth_bytes {
                 self.state = SyncState::AwaitingDownload {
                     since: (Instant::now() + self.config.bandwidth_wait_timeout).into(),
                 };
-                return;
+                return true;
             }
         }
 
+        false
+    }
+
+    /// Randomly select a peer to sync the next segment.
+    fn try_request_next(&mut self) {
+        // limits network bandwidth if configured
+        if self.wait_for_bandwidth() {
+            return;
+        }
+
         // request next chunk array
         let from_chunk = self.next_chunk;
         let to_chunk = std::cmp::min(from_chunk + PORA_CHUNK_SIZE as u64, self.goal.index_end);
@@ -337,6 +387,86 @@ impl SerialSyncController {
         };
     }
 
+    /// Requests disjoint chunk ranges from multiple peers until `max_parallel_requests`
+    /// reached, or no more ranges to request.
+    fn try_request_parallel(&mut self) {
+        // limits network bandwidth if configured
+        if self.wait_for_bandwidth() {
+            return;
+        }
+
+        let committed_tx_seq = self.store.get_store().next_tx_seq().saturating_sub(1);
+
+        while self.downloads.num_in_flight() < self.config.max_parallel_requests {
+            let (from_chunk, to_chunk) = match self.downloads.pop_failed() {
+                Some(range) => range,
+                None if self.next_chunk < self.goal.index_end => {
+                    let from_chunk = self.next_chunk;
+                    let to_chunk =
+                        std::cmp::min(from_chunk + PORA_CHUNK_SIZE as u64, self.goal.index_end);
+                    self.next_chunk = self.next_chunk_in_shard(from_chunk);
+                    (from_chunk, to_chunk)
+                }
+                None => break,
+            };
+
+            let peer_id = match self.select_parallel_peer(from_chunk) {
+                Some(peer_id) => peer_id,
+                None => {
+                    // all available peers are busy
+                    self.downloads.push_failed(from_chunk, to_chunk);
+                    break;
+                }
+            };
+
+            self.ctx.send(NetworkMessage::SendRequest {
+                peer_id,
+                request_id: network::RequestId::Sync(
+                    Instant::now(),
+                    RequestId::SerialSync { tx_id: self.tx_id },
+                ),
+                request: network::Request::GetChunks(GetChunksRequest {
+                    tx_id: self.tx_id,
+                    index_start: from_chunk,
+                    index_end: to_chunk,
+                    merkle_tx_seq: committed_tx_seq,
+                }),
+            });
+
+            debug!(%self.tx_seq, %from_chunk, %to_chunk, %peer_id, "Sent request to get chunks");
+
+            self.downloads.start(peer_id, from_chunk, to_chunk);
+        }
+
+        let num_requests = self.downloads.num_in_flight();
+        if num_requests == 0 {
+            warn!(%self.tx_seq, "No peers available to request chunks");
+            self.state = SyncState::Idle;
+        } else {
+            self.state = SyncState::DownloadingParallel { num_requests };
+        }
+    }
+
+    /// Selects a `Connected` peer to sync the segment of `from_chunk` by throughput.
+    fn select_parallel_peer(&self, from_chunk: u64) -> Option<PeerId> {
+        let segment_index = sector_to_segment(from_chunk + self.tx_start_chunk_in_flow);
+        let candidates = self
+            .peers
+            .filter_peers(vec![PeerState::Connected])
+            .into_iter()
+            .filter_map(|peer_id| {
+                let shard_config = self.peers.shard_config(&peer_id)?;
+                if shard_config.in_range(segment_index as u64) {
+                    Some((peer_id, shard_config, self.peers.download_score(&peer_id)))
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        self.downloads.select_peer(candidates)
+    }
+
     fn ban_peer(&mut self, peer_id: PeerId, reason: &'static str) {
         debug!(%self.tx_seq, %peer_id, %reason, "Ban peer");
         self.ctx.ban_peer(peer_id, reason);
@@ -456,6 +586,11 @@ impl SerialSyncController {
     pub async fn on_response(&mut self, from_peer_id: PeerId, response: ChunkArrayWithProof) {
         metrics::SERIAL_SYNC_SEGMENT_BANDWIDTH.mark(response.ssz_bytes_len());
 
+        if self.is_parallel() {
+            self.on_parallel_response(from_peer_id, response).await;
+            return;
+        }
+
         if self.handle_on_response_mismatch(from_peer_id) {
             return;
         }
@@ -517,6 +652,8 @@ impl SerialSyncController {
             Err(err) => {
                 warn!(%err, %self.tx_seq, "Failed to validate chunks response");
                 metrics::SERIAL_SYNC_UNEXPECTED_ERRORS.inc(1);
+                self.ctx
+                    .report_download(from_peer_id, DownloadEvent::ProofFailed);
                 self.ban_peer(from_peer_id, "Chunk array validation failed");
                 self.state = SyncState::Idle;
                 return;
@@ -526,26 +663,139 @@ impl SerialSyncController {
         self.failures = 0;
 
         metrics::SERIAL_SYNC_SEGMENT_LATENCY.update_since(since.0);
+        self.ctx.report_download(
+            from_peer_id,
+            DownloadEvent::Completed {
+                num_bytes: data_len,
+                latency: since.elapsed(),
+            },
+        );
+
+        // store in db
+        if !self.store_chunks(response).await {
+            return;
+        }
+        self.next_chunk = self.next_chunk_in_shard(from_chunk);
+
+        // prepare to download next
+        if self.next_chunk < self.goal.index_end {
+            self.state = SyncState::Idle;
+            return;
+        }
+
+        self.on_all_chunks_downloaded().await;
+    }
+
+    /// Handles response of chunk ranges requested from multiple peers.
+    async fn on_parallel_response(&mut self, from_peer_id: PeerId, response: ChunkArrayWithProof) {
+        let range = match self.downloads.get(&from_peer_id) {
+            Some(range) => range,
+            None => {
+                // Delayed response can enter this.
+                warn!(%self.tx_seq, %from_peer_id, "Got response from unexpected peer");
+                self.ctx.report_peer(
+                    from_peer_id,
+                    PeerAction::LowToleranceError,
+                    "Peer id mismatch",
+                );
+                return;
+            }
+        };
+
+        let (from_chunk, to_chunk) = (range.from_chunk, range.to_chunk);
+        debug!(%self.tx_seq, %from_peer_id, %from_chunk, %to_chunk, "Received RPC response from expected peer");
+
+        // invalid chunk array size: ban and re-request
+        let data_len = response.chunks.data.len();
+        if data_len == 0 || data_len % CHUNK_SIZE > 0 {
+            warn!(%from_peer_id, %self.tx_seq, %data_len, "Invalid chunk response data length");
+            metrics::SERIAL_SYNC_UNEXPECTED_ERRORS.inc(1);
+            self.downloads.fail(&from_peer_id, true);
+            self.ban_peer(from_peer_id, "Invalid chunk response data length");
+            return;
+        }
+
+        // invalid chunk range: may be response timeout, just ignore it
+        let start_index = response.chunks.start_index;
+        let end_index = start_index + (data_len / CHUNK_SIZE) as u64;
+        if start_index != from_chunk || end_index != to_chunk {
+            warn!(%self.tx_seq, "Invalid chunk response range, expected={from_chunk}..{to_chunk}, actual={start_index}..{end_index}");
+            self.ctx.report_peer(
+                from_peer_id,
+                PeerAction::LowToleranceError,
+                "Got response with unexpected chunk range",
+            );
+            return;
+        }
+
+        // validate Merkle proofs
+        let validation_result = self
+            .store
+            .get_store()
+            .validate_and_insert_range_proof(self.tx_seq, &response);
+
+        match validation_result {
+            Ok(true) => {}
+            Ok(false) => {
+                // occurs when remote peer has higher block height
+                info!(%self.tx_seq, "Failed to validate chunks response due to no root found");
+                self.downloads.fail(&from_peer_id, false);
+                self.state = SyncState::AwaitingDownload {
+                    since: (Instant::now() + self.config.peer_next_chunks_request_wait_timeout)
+                        .into(),
+                };
+                return;
+            }
+            Err(err) => {
+                warn!(%err, %self.tx_seq, "Failed to validate chunks response");
+                metrics::SERIAL_SYNC_UNEXPECTED_ERRORS.inc(1);
+                self.ctx
+                    .report_download(from_peer_id, DownloadEvent::ProofFailed);
+                self.downloads.fail(&from_peer_id, true);
+                self.ban_peer(from_peer_id, "Chunk array validation failed");
+                return;
+            }
+        }
+
+        metrics::SERIAL_SYNC_SEGMENT_LATENCY.update_since(range.since);
+        self.ctx.report_download(
+            from_peer_id,
+            DownloadEvent::Completed {
+                num_bytes: data_len,
+                latency: range.since.elapsed(),
+            },
+        );
 
-        let shard_config = self.store.get_store().get_shard_config();
-        let next_chunk = segment_to_sector(shard_config.next_segment_index(
-            sector_to_segment(from_chunk),
-            sector_to_segment(self.tx_start_chunk_in_flow),
-        ));
         // store in db
+        if !self.store_chunks(response).await {
+            return;
+        }
+        self.downloads.complete(&from_peer_id, data_len);
+
+        // request more ranges
+        if self.next_chunk < self.goal.index_end || self.downloads.has_pending() {
+            self.try_request_parallel();
+            return;
+        }
+
+        self.on_all_chunks_downloaded().await;
+    }
+
+    /// Stores the validated chunks, and returns `false` if failed to sync file.
+    async fn store_chunks(&mut self, response: ChunkArrayWithProof) -> bool {
         match self
             .store
             .put_chunks_with_tx_hash(self.tx_id.seq, self.tx_id.hash, response.chunks, None)
             .await
         {
-            Ok(true) => self.next_chunk = next_chunk as u64,
+            Ok(true) => true,
             Ok(false) => {
                 warn!(%self.tx_seq, ?self.tx_id, "Transaction reverted while storing chunks");
                 metrics::SERIAL_SYNC_UNEXPECTED_ERRORS.inc(1);
                 self.state = SyncState::Failed {
                     reason: FailureReason::TxReverted(self.tx_id),
                 };
-                return;
+                false
             }
             Err(err) => {
                 error!(%err, %self.tx_seq, "Unexpected DB error while storing chunks");
@@ -553,16 +803,12 @@ impl SerialSyncController {
                 self.state = SyncState::Failed {
                     reason: FailureReason::DBError(err.to_string()),
                 };
-                return;
+                false
             }
         }
+    }
 
-        // prepare to download next
-        if self.next_chunk < self.goal.index_end {
-            self.state = SyncState::Idle;
-            return;
-        }
-
+    async fn on_all_chunks_downloaded(&mut self) {
         // completed to download chunks
         if !self.goal.is_all_chunks() {
             self.state = SyncState::Completed;
@@ -602,6 +848,13 @@ impl SerialSyncController {
     }
 
     pub fn on_request_failed(&mut self, peer_id: PeerId) {
+        if self.is_parallel() {
+            if self.downloads.get(&peer_id).is_some() {
+                self.handle_parallel_failure(peer_id, "RPC Error");
+            }
+            return;
+        }
+
         if self.handle_on_response_mismatch(peer_id) {
             return;
         }
@@ -611,6 +864,8 @@ impl SerialSyncController {
 
     fn handle_response_failure(&mut self, peer_id: PeerId, reason: &'static str) {
         info!(%peer_id, %self.tx_seq, %reason, "Chunks request failed");
+        self.ctx
+            .report_download(peer_id, DownloadEvent::RequestFailed);
 
         self.failures += 1;
 
@@ -626,7 +881,20 @@ impl SerialSyncController {
         }
     }
 
-    /// Randomly select a `Connected` peer to sync chunks.
+    /// Requests the failed range again later, and bans the peer if failed continuously.
+    fn handle_parallel_failure(&mut self, peer_id: PeerId, reason: &'static str) {
+        info!(%peer_id, %self.tx_seq, %reason, "Chunks request failed");
+        self.ctx
+            .report_download(peer_id, DownloadEvent::RequestFailed);
+
+        self.downloads.fail(&peer_id, true);
+
+        if self.downloads.failures(&peer_id) > self.config.max_request_failures {
+            self.ban_peer(peer_id, reason);
+        }
+    }
+
+    /// Select a `Connected` peer with the highest download score to sync chunks.
     fn select_peer_for_request(&self, request: &GetChunksRequest) -> Option<PeerId> {
         let segment_index = sector_to_segment(request.index_start + self.tx_start_chunk_in_flow);
         let mut peers = self.peers.filter_peers(vec![PeerState::Connected]);
@@ -636,13 +904,7 @@ impl SerialSyncController {
             None => false,
         });
 
-        let len = peers.len();
-        if len == 0 {
-            return None;
-        }
-
-        let index = rand::thread_rng().gen_range(0..len);
-        Some(peers[index])
+        self.peers.best_peer(peers)
     }
 
     pub fn transition(&mut self) {
@@ -732,6 +994,8 @@ impl SerialSyncController {
                     if Instant::now() < since.0 {
                         // retry seconds later
                         completed = true;
+                    } else if self.is_parallel() {
+                        self.try_request_parallel();
                     } else {
                         self.try_request_next();
                     }
@@ -750,6 +1014,26 @@ impl SerialSyncController {
                     }
                 }
 
+                SyncState::DownloadingParallel { .. } => {
+                    for peer_id in self.downloads.in_flight_peers() {
+                        if !matches!(self.peers.peer_state(&peer_id), Some(PeerState::Connected)) {
+                            // e.g. peer disconnected by remote node
+                            debug!(%self.tx_seq, %peer_id, "Peer disconnected while downloading");
+                            self.downloads.fail(&peer_id, false);
+                        }
+                    }
+
+                    let timeout = self.config.peer_chunks_download_timeout;
+                    for peer_id in self.downloads.timed_out_peers(timeout) {
+                        metrics::SERIAL_SYNC_SEGMENT_TIMEOUT.inc(1);
+                        self.handle_parallel_failure(peer_id, "RPC timeout");
+                    }
+
+                    // request failed ranges again or more ranges by idle peers
+                    self.try_request_parallel();
+                    completed = matches!(self.state, SyncState::DownloadingParallel { .. });
+                }
+
                 SyncState::Completed | SyncState::Failed { .. } => completed = true,
             }
         }
@@ -867,6 +1151,31 @@ mod tests {
         ));
     }
 
+    #[tokio::test]
+    async fn test_find_peers_by_dht() {
+        let runtime = TestRuntime::default();
+        let task_executor = runtime.task_executor.clone();
+        let (mut controller, mut network_recv) = create_default_controller(task_executor, None);
+
+        controller.config.find_file_by_dht = true;
+        controller.tx_seq = 1;
+        controller.tx_id = TxID {
+            seq: 1,
+            hash: H256::random(),
+        };
+        controller.try_find_peers();
+
+        match network_recv.recv().await {
+            Some(NetworkMessage::FindProviders { tx_id }) => {
+                assert_eq!(tx_id, controller.tx_id);
+            }
+            _ => panic!("Unexpected message type"),
+        }
+
+        // FindFile is not broadcast
+        assert!(network_recv.try_recv().is_err());
+    }
+
     #[tokio::test]
     async fn test_connect_peers() {
         let runtime = TestRuntime::default();
@@ -876,6 +1185,12 @@ mod tests {
         controller.state = SyncState::FoundPeers;
         controller.try_connect();
         assert_eq!(controller.state, SyncState::Idle);
+        match network_recv.try_recv().unwrap() {
+            NetworkMessage::DiscoverShardPeers { shard_config, .. } => {
+                assert_eq!(shard_config, Default::default());
+            }
+            _ => panic!("Not expected message: NetworkMessage::DiscoverShardPeers"),
+        }
         assert!(network_recv.try_recv().is_err());
 
         let new_peer_id = identity::Keypair::generate_ed25519().public().to_peer_id();
@@ -947,6 +1262,7 @@ mod tests {
                             network::SyncId::SerialSync { tx_id } => {
                                 assert_eq!(tx_id, controller.tx_id);
                             }
+                            _ => panic!("Not expected sync id: {:?}", sync_id),
                         },
                         _ => {
                             panic!("Not expected message: network::RequestId::Sync");
@@ -965,6 +1281,74 @@ mod tests {
         ));
     }
 
+    #[tokio::test]
+    async fn test_request_chunks_parallel() {
+        let runtime = TestRuntime::default();
+        let task_executor = runtime.task_executor.clone();
+        let tx_id = TxID {
+            seq: 0,
+            hash: H256::random(),
+        };
+        let store = Arc::new(LogManager::memorydb(LogConfig::default()).unwrap());
+        let (mut controller, mut network_recv) =
+            create_controller(task_executor, None, store, tx_id, 2 * PORA_CHUNK_SIZE + 100);
+        controller.config.max_parallel_requests = 2;
+
+        let addr: Multiaddr = "/ip4/127.0.0.1/tcp/10000".parse().unwrap();
+        for _ in 0..2 {
+            let peer_id = identity::Keypair::generate_ed25519().public().to_peer_id();
+            controller.peers.add_new_peer(peer_id, addr.clone());
+            controller
+                .peers
+                .update_state_force(&peer_id, PeerState::Connected);
+        }
+
+        controller.state = SyncState::AwaitingDownload {
+            since: Instant::now().into(),
+        };
+        controller.transition();
+        assert_eq!(
+            controller.state,
+            SyncState::DownloadingParallel { num_requests: 2 }
+        );
+
+        let mut requests = vec![];
+        while let Ok(msg) = network_recv.try_recv() {
+            match msg {
+                NetworkMessage::SendRequest {
+                    peer_id,
+                    request: Request::GetChunks(request),
+                    ..
+                } => requests.push((peer_id, request.index_start, request.index_end)),
+                _ => panic!("Not expected message: NetworkMessage::SendRequest"),
+            }
+        }
+        requests.sort_by_key(|(_, index_start, _)| *index_start);
+        assert_eq!(requests.len(), 2);
+        assert_ne!(requests[0].0, requests[1].0);
+        assert_eq!((requests[0].1, requests[0].2), (0, 1024));
+        assert_eq!((requests[1].1, requests[1].2), (1024, 2048));
+
+        // failed range is requested again prior to new ranges
+        let failed_peer = requests[0].0;
+        controller.on_request_failed(failed_peer);
+        assert_eq!(controller.downloads.num_in_flight(), 1);
+
+        controller.transition();
+        match network_recv.try_recv() {
+            Ok(NetworkMessage::SendRequest {
+                peer_id,
+                request: Request::GetChunks(request),
+                ..
+            }) => {
+                assert_eq!(peer_id, failed_peer);
+                assert_eq!((request.index_start, request.index_end), (0, 1024));
+            }
+            _ => panic!("Not expected message: NetworkMessage::SendRequest"),
+        }
+        assert_eq!(controller.next_chunk, 2048);
+    }
+
     #[tokio::test]
     async fn test_ban_peer() {
         let runtime = TestRuntime::default();
@@ -25,6 +25,10 @@ pub struct Config {
     /// This is to avoid flooding file announcements in the whole network,
     /// which leads to high latency or even timeout to sync files.
     pub neighbors_only: bool,
+    /// Indicates whether to find file locations by querying provider records in the DHT,
+    /// instead of broadcasting `FindFile` messages in the whole network. Requires the DHT to be
+    /// enabled in network config.
+    pub find_file_by_dht: bool,
     #[serde(deserialize_with = "deserialize_duration")]
     pub heartbeat_interval: Duration,
     pub auto_sync_enabled: bool,
@@ -35,6 +39,9 @@ pub struct Config {
     // serial sync config
     pub max_chunks_to_request: u64,
     pub max_request_failures: usize,
+    /// Maximum number of chunk ranges of a file to download from different peers
+    /// simultaneously. Chunks are downloaded in sequence from one peer at a time if 1.
+    pub max_parallel_requests: usize,
     #[serde(deserialize_with = "deserialize_duration")]
     pub peer_connect_timeout: Duration,
     #[serde(deserialize_with = "deserialize_duration")]
@@ -51,6 +58,34 @@ pub struct Config {
     #[serde(deserialize_with = "deserialize_duration")]
     pub bandwidth_wait_timeout: Duration,
 
+    // erasure sync config
+    /// Maximum number of batches to sync erasure pieces concurrently.
+    pub max_erasure_sync_batches: usize,
+    #[serde(deserialize_with = "deserialize_duration")]
+    pub peer_erasure_piece_timeout: Duration,
+    /// Interval to retry a failed batch, which is doubled on each failure.
+    #[serde(deserialize_with = "deserialize_duration")]
+    pub erasure_sync_retry_interval: Duration,
+
+    // storage challenge config
+    /// Indicates whether to challenge peers that announce files to prove that they really
+    /// store the files, by requesting random sectors with Merkle proofs.
+    pub storage_challenge_enabled: bool,
+    /// Minimum interval to challenge the same peer again.
+    #[serde(deserialize_with = "deserialize_duration")]
+    pub storage_challenge_interval: Duration,
+    /// Number of random sectors to request in a challenge, which is at most 64.
+    pub storage_challenge_sectors: usize,
+    #[serde(deserialize_with = "deserialize_duration")]
+    pub storage_challenge_timeout: Duration,
+
+    // transaction prefetch config
+    /// Indicates whether to prefetch transactions with proofs from peers when log sync from
+    /// blockchain falls behind. Prefetched transactions are confirmed by log sync later.
+    pub prefetch_transactions_enabled: bool,
+    #[serde(deserialize_with = "deserialize_duration")]
+    pub prefetch_transactions_timeout: Duration,
+
     // auto sync config
     #[serde(deserialize_with = "deserialize_duration")]
     pub auto_sync_idle_interval: Duration,
@@ -70,6 +105,7 @@ impl Default for Config {
         Self {
             // sync service config
             neighbors_only: true,
+            find_file_by_dht: false,
             heartbeat_interval: Duration::from_secs(3),
             auto_sync_enabled: false,
             max_sync_files: 16,
@@ -79,6 +115,7 @@ impl Default for Config {
             // serial sync config
             max_chunks_to_request: 2 * 1024,
             max_request_failures: 3,
+            max_parallel_requests: 1,
             peer_connect_timeout: Duration::from_secs(15),
             peer_disconnect_timeout: Duration::from_secs(15),
             peer_find_timeout: Duration::from_secs(5),
@@ -88,6 +125,21 @@ impl Default for Config {
             max_bandwidth_bytes: 0,
             bandwidth_wait_timeout: Duration::from_secs(5),
 
+            // erasure sync config
+            max_erasure_sync_batches: 4,
+            peer_erasure_piece_timeout: Duration::from_secs(15),
+            erasure_sync_retry_interval: Duration::from_secs(60),
+
+            // storage challenge config
+            storage_challenge_enabled: false,
+            storage_challenge_interval: Duration::from_secs(600),
+            storage_challenge_sectors: 8,
+            storage_challenge_timeout: Duration::from_secs(15),
+
+            // transaction prefetch config
+            prefetch_transactions_enabled: false,
+            prefetch_transactions_timeout: Duration::from_secs(15),
+
             // auto sync config
             auto_sync_idle_interval: Duration::from_secs(3),
             auto_sync_error_interval: Duration::from_secs(10),
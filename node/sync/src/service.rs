@@ -1,29 +1,40 @@
 use crate::auto_sync::manager::AutoSyncManager;
 use crate::context::SyncNetworkContext;
 use crate::controllers::{
-    FailureReason, FileSyncGoal, FileSyncInfo, SerialSyncController, SyncState,
+    local_piece_index, select_range_tx_seq, select_sectors, Challenge, ErasureRetries,
+    ErasureSyncController, ErasureSyncGoal, ErasureSyncState, FailureReason, FileSyncGoal,
+    FileSyncInfo, SerialSyncController, StorageChallenges, SyncState,
 };
 use crate::{Config, SyncServiceState};
 use anyhow::{anyhow, bail, Result};
 use file_location_cache::FileLocationCache;
 use libp2p::swarm::DialError;
-use log_entry_sync::LogSyncEvent;
-use network::types::{AnnounceChunks, FindFile};
+use log_entry_sync::{FlowReader, LogSyncEvent};
+use network::types::{AnnounceChunks, FindFile, SignedAnnounceFileRanges};
 use network::{
-    rpc::GetChunksRequest, rpc::RPCResponseErrorCode, Multiaddr, NetworkMessage, NetworkSender,
-    PeerId, PeerRequestId, PubsubMessage, SyncId as RequestId,
+    rpc::GetChunksRequest, rpc::GetErasurePieceRequest, rpc::GetTransactionsRequest,
+    rpc::RPCResponseErrorCode, rpc::StorageChallengeRequest, rpc::StorageChallengeResponse,
+    rpc::TransactionsResponse, rpc::MAX_CHALLENGE_SECTORS, rpc::MAX_REQUEST_TRANSACTIONS,
+    Multiaddr, NetworkGlobals, NetworkMessage, NetworkSender, PeerId, PeerRequestId, PubsubMessage,
+    SyncId as RequestId,
+};
+use rand::seq::IteratorRandom;
+use shared_types::{
+    bytes_to_chunks, ChunkArrayWithProof, ErasurePiece, ShardedFile, Transaction,
+    TransactionWithProof, TxID, CHUNK_SIZE,
 };
-use shared_types::{bytes_to_chunks, ChunkArrayWithProof, ShardedFile, Transaction, TxID};
 use std::sync::atomic::Ordering;
 use std::{
     cmp,
-    collections::{hash_map::Entry, HashMap},
+    collections::{hash_map::Entry, HashMap, HashSet},
     sync::Arc,
+    time::Instant,
 };
-use storage::config::ShardConfig;
+use storage::config::{ErasureConfig, ShardConfig};
 use storage::error::Result as StorageResult;
 use storage::log_store::log_manager::{sector_to_segment, segment_to_sector, PORA_CHUNK_SIZE};
 use storage::log_store::Store as LogStore;
+use storage::H256;
 use storage_async::Store;
 use tokio::sync::{broadcast, oneshot};
 
@@ -52,6 +63,36 @@ pub enum SyncMessage {
         request_id: RequestId,
         response: ChunkArrayWithProof,
     },
+    RequestErasurePiece {
+        peer_id: PeerId,
+        request_id: PeerRequestId,
+        request: GetErasurePieceRequest,
+    },
+    ErasurePieceResponse {
+        peer_id: PeerId,
+        request_id: RequestId,
+        response: ErasurePiece,
+    },
+    RequestStorageChallenge {
+        peer_id: PeerId,
+        request_id: PeerRequestId,
+        request: StorageChallengeRequest,
+    },
+    StorageChallengeResponse {
+        peer_id: PeerId,
+        request_id: RequestId,
+        response: StorageChallengeResponse,
+    },
+    RequestTransactions {
+        peer_id: PeerId,
+        request_id: PeerRequestId,
+        request: GetTransactionsRequest,
+    },
+    TransactionsResponse {
+        peer_id: PeerId,
+        request_id: RequestId,
+        response: TransactionsResponse,
+    },
     RpcError {
         peer_id: PeerId,
         request_id: RequestId,
@@ -61,6 +102,9 @@ pub enum SyncMessage {
         peer_id: PeerId,
         addr: Multiaddr,
     },
+    AnnounceFileRangesGossip {
+        msg: SignedAnnounceFileRanges,
+    },
     AnnounceShardConfig {
         shard_config: ShardConfig,
         peer_id: PeerId,
@@ -102,6 +146,9 @@ pub enum SyncRequest {
         tx_seq: u64,
         is_reverted: bool,
     },
+    RebuildBatch {
+        batch_index: u64,
+    },
 }
 
 #[derive(Debug)]
@@ -112,6 +159,7 @@ pub enum SyncResponse {
     FileSyncInfo { result: HashMap<u64, FileSyncInfo> },
     FindFile { err: String },
     TerminateFileSync { count: usize },
+    RebuildBatch { err: String },
 }
 
 pub struct SyncService {
@@ -132,6 +180,28 @@ pub struct SyncService {
     /// A collection of file sync controllers.
     controllers: HashMap<u64, SerialSyncController>,
 
+    /// A collection of erasure piece sync controllers by batch index.
+    erasure_controllers: HashMap<u64, ErasureSyncController>,
+
+    /// The next batch to check whether the erasure piece of this node is missing.
+    next_erasure_batch: u64,
+
+    /// Batches that failed to sync erasure pieces, which are retried later.
+    erasure_retries: ErasureRetries,
+
+    /// Peers connected with status exchanged, which are requested for erasure pieces.
+    connected_peers: HashSet<PeerId>,
+
+    /// Pending storage challenges to peers that announced files.
+    challenges: StorageChallenges,
+
+    /// Pending request to prefetch transactions from a peer, `(peer_id, from_seq, since)`.
+    pending_tx_request: Option<(PeerId, u64, Instant)>,
+
+    /// Reads the flow contract on chain to verify prefetched transactions, which are not
+    /// prefetched if not available.
+    flow_reader: Option<FlowReader>,
+
     auto_sync_manager: Option<AutoSyncManager>,
 }
 
@@ -148,6 +218,8 @@ impl SyncService {
             Config::default(),
             executor,
             network_send,
+            None,
+            None,
             store,
             file_location_cache,
             event_recv,
@@ -160,6 +232,8 @@ impl SyncService {
         config: Config,
         executor: task_executor::TaskExecutor,
         network_send: NetworkSender,
+        network_globals: Option<Arc<NetworkGlobals>>,
+        flow_reader: Option<FlowReader>,
         store: Arc<dyn LogStore>,
         file_location_cache: Arc<FileLocationCache>,
         event_recv: broadcast::Receiver<LogSyncEvent>,
@@ -185,13 +259,26 @@ impl SyncService {
             None
         };
 
+        let mut ctx = SyncNetworkContext::new(network_send);
+        if let Some(network_globals) = network_globals {
+            ctx = ctx.with_network_globals(network_globals);
+        }
+
         let mut sync = SyncService {
             config,
             msg_recv: sync_recv,
-            ctx: Arc::new(SyncNetworkContext::new(network_send)),
+            ctx: Arc::new(ctx),
             store,
             file_location_cache,
             controllers: Default::default(),
+            erasure_controllers: Default::default(),
+            // the first batch is not erasure coded
+            next_erasure_batch: 1,
+            erasure_retries: Default::default(),
+            connected_peers: Default::default(),
+            challenges: Default::default(),
+            pending_tx_request: None,
+            flow_reader,
             auto_sync_manager,
         };
 
@@ -215,7 +302,12 @@ impl SyncService {
                 }
 
                 // heartbeat
-                _ = heartbeat.tick() => self.on_heartbeat(),
+                _ = heartbeat.tick() => {
+                    self.on_heartbeat();
+                    self.on_erasure_heartbeat().await;
+                    self.on_challenge_heartbeat();
+                    self.on_prefetch_heartbeat().await;
+                }
             }
         }
     }
@@ -252,6 +344,59 @@ impl SyncService {
                 self.on_chunks_response(peer_id, request_id, response).await;
             }
 
+            SyncMessage::RequestErasurePiece {
+                peer_id,
+                request_id,
+                request,
+            } => {
+                self.on_get_erasure_piece_request(peer_id, request_id, request)
+                    .await;
+            }
+
+            SyncMessage::ErasurePieceResponse {
+                peer_id,
+                request_id,
+                response,
+            } => {
+                self.on_erasure_piece_response(peer_id, request_id, response)
+                    .await;
+            }
+
+            SyncMessage::RequestStorageChallenge {
+                peer_id,
+                request_id,
+                request,
+            } => {
+                self.on_storage_challenge_request(peer_id, request_id, request)
+                    .await;
+            }
+
+            SyncMessage::StorageChallengeResponse {
+                peer_id,
+                request_id,
+                response,
+            } => {
+                self.on_storage_challenge_response(peer_id, request_id, response);
+            }
+
+            SyncMessage::RequestTransactions {
+                peer_id,
+                request_id,
+                request,
+            } => {
+                self.on_get_transactions_request(peer_id, request_id, request)
+                    .await;
+            }
+
+            SyncMessage::TransactionsResponse {
+                peer_id,
+                request_id,
+                response,
+            } => {
+                self.on_transactions_response(peer_id, request_id, response)
+                    .await;
+            }
+
             SyncMessage::RpcError {
                 peer_id,
                 request_id,
@@ -267,6 +412,10 @@ impl SyncService {
                 self.on_announce_file_gossip(tx_id, peer_id, addr).await;
             }
 
+            SyncMessage::AnnounceFileRangesGossip { msg } => {
+                self.on_announce_file_ranges_gossip(msg).await;
+            }
+
             SyncMessage::AnnounceChunksGossip { msg } => self.on_announce_chunks_gossip(msg).await,
             SyncMessage::AnnounceShardConfig { .. } => {
                 // FIXME: Check if controllers need to be reset?
@@ -359,6 +508,13 @@ impl SyncService {
                 let result = self.on_find_file_request(tx_seq).await;
                 let _ = sender.send(SyncResponse::FindFile { err: result });
             }
+            SyncRequest::RebuildBatch { batch_index } => {
+                let result = match self.on_rebuild_batch(batch_index).await {
+                    Ok(()) => "".into(),
+                    Err(e) => e.to_string(),
+                };
+                let _ = sender.send(SyncResponse::RebuildBatch { err: result });
+            }
         }
     }
 
@@ -374,6 +530,8 @@ impl SyncService {
     fn on_peer_connected(&mut self, peer_id: PeerId) {
         info!(%peer_id, "Peer connected");
 
+        self.connected_peers.insert(peer_id);
+
         for controller in self.controllers.values_mut() {
             controller.on_peer_connected(peer_id);
             controller.transition();
@@ -383,6 +541,11 @@ impl SyncService {
     fn on_peer_disconnected(&mut self, peer_id: PeerId) {
         info!(%peer_id, "Peer disconnected");
 
+        self.connected_peers.remove(&peer_id);
+        for controller in self.erasure_controllers.values_mut() {
+            controller.on_request_failed(peer_id);
+        }
+
         for controller in self.controllers.values_mut() {
             controller.on_peer_disconnected(peer_id);
             controller.transition();
@@ -471,78 +634,755 @@ impl SyncService {
         //     return Ok(());
         // }
 
-        let result = self
-            .store
-            .get_chunks_with_proof_by_tx_and_index_range(
-                request.tx_id.seq,
-                request.index_start as usize,
-                request.index_end as usize,
-                Some(request.merkle_tx_seq),
-            )
-            .await?;
+        let result = self
+            .store
+            .get_chunks_with_proof_by_tx_and_index_range(
+                request.tx_id.seq,
+                request.index_start as usize,
+                request.index_end as usize,
+                Some(request.merkle_tx_seq),
+            )
+            .await?;
+
+        match result {
+            Some(chunks) => {
+                self.ctx.send(NetworkMessage::SendResponse {
+                    peer_id,
+                    id: request_id,
+                    response: network::Response::Chunks(chunks),
+                });
+            }
+            None => {
+                // file may be removed during downloading
+                warn!(%request.tx_id.seq, "Failed to handle chunks request due to chunks not found");
+                self.ctx.send(NetworkMessage::SendErrorResponse {
+                    peer_id,
+                    error: RPCResponseErrorCode::InvalidRequest,
+                    reason: "Chunks not found".into(),
+                    id: request_id,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn on_chunks_response(
+        &mut self,
+        peer_id: PeerId,
+        request_id: RequestId,
+        response: ChunkArrayWithProof,
+    ) {
+        debug!(%response.chunks, %peer_id, ?request_id, "Received chunks response");
+
+        let tx_seq = match request_id {
+            RequestId::SerialSync { tx_id } => tx_id.seq,
+            RequestId::ErasurePiece { .. } => unreachable!("Chunks response for erasure sync"),
+            RequestId::StorageChallenge { .. } => {
+                unreachable!("Chunks response for storage challenge")
+            }
+            RequestId::Transactions { .. } => {
+                unreachable!("Chunks response for transactions prefetch")
+            }
+        };
+
+        match self.controllers.get_mut(&tx_seq) {
+            Some(controller) => {
+                controller.on_response(peer_id, response).await;
+                controller.transition();
+            }
+            None => {
+                warn!("Received chunks response for non-existent controller tx_seq={tx_seq}");
+            }
+        }
+    }
+
+    fn on_rpc_error(&mut self, peer_id: PeerId, request_id: RequestId) {
+        info!(%peer_id, ?request_id, "Received RPC error");
+
+        let tx_seq = match request_id {
+            RequestId::SerialSync { tx_id } => tx_id.seq,
+            RequestId::ErasurePiece { batch_index } => {
+                if let Some(controller) = self.erasure_controllers.get_mut(&batch_index) {
+                    controller.on_request_failed(peer_id);
+                    controller.transition(&self.connected_peers);
+                }
+                return;
+            }
+            RequestId::StorageChallenge { tx_id } => {
+                // the RPC error has been scored by peer manager according to the error type
+                if self.challenges.complete(&peer_id, &tx_id).is_some() {
+                    info!(%peer_id, tx_seq = tx_id.seq, "Failed to challenge storage of peer");
+                    self.remove_file_location(&tx_id, &peer_id);
+                }
+                return;
+            }
+            RequestId::Transactions { from_seq } => {
+                if self.is_pending_tx_request(&peer_id, from_seq) {
+                    self.pending_tx_request = None;
+                }
+                return;
+            }
+        };
+
+        match self.controllers.get_mut(&tx_seq) {
+            Some(controller) => {
+                controller.on_request_failed(peer_id);
+                controller.transition();
+            }
+            None => {
+                warn!("Received rpc error for non-existent controller tx_seq={tx_seq}");
+            }
+        }
+    }
+
+    async fn on_get_erasure_piece_request(
+        &mut self,
+        peer_id: PeerId,
+        request_id: PeerRequestId,
+        request: GetErasurePieceRequest,
+    ) {
+        debug!(?request, %peer_id, ?request_id, "Received GetErasurePiece request");
+
+        let result = self
+            .store
+            .get_erasure_piece(request.batch_index, request.piece_index as usize)
+            .await;
+
+        match result {
+            Ok(Some(piece)) => {
+                self.ctx.send(NetworkMessage::SendResponse {
+                    peer_id,
+                    id: request_id,
+                    response: network::Response::ErasurePiece(piece),
+                });
+            }
+            Ok(None) => {
+                self.ctx.send(NetworkMessage::SendErrorResponse {
+                    peer_id,
+                    error: RPCResponseErrorCode::InvalidRequest,
+                    reason: "Erasure piece not found".into(),
+                    id: request_id,
+                });
+            }
+            Err(err) => {
+                error!(%err, "Failed to handle erasure piece request due to db error");
+                self.ctx.send(NetworkMessage::SendErrorResponse {
+                    peer_id,
+                    id: request_id,
+                    error: RPCResponseErrorCode::ServerError,
+                    reason: "DB error".into(),
+                });
+            }
+        }
+    }
+
+    async fn on_erasure_piece_response(
+        &mut self,
+        peer_id: PeerId,
+        request_id: RequestId,
+        response: ErasurePiece,
+    ) {
+        debug!(?response, %peer_id, ?request_id, "Received erasure piece response");
+
+        let batch_index = match request_id {
+            RequestId::ErasurePiece { batch_index } => batch_index,
+            RequestId::SerialSync { .. } => unreachable!("Erasure piece response for file sync"),
+            RequestId::StorageChallenge { .. } => {
+                unreachable!("Erasure piece response for storage challenge")
+            }
+            RequestId::Transactions { .. } => {
+                unreachable!("Erasure piece response for transactions prefetch")
+            }
+        };
+
+        match self.erasure_controllers.get_mut(&batch_index) {
+            Some(controller) => {
+                controller.on_response(peer_id, response).await;
+                controller.transition(&self.connected_peers);
+            }
+            None => {
+                warn!(%batch_index, "Received erasure piece response for non-existent controller");
+            }
+        }
+    }
+
+    async fn on_storage_challenge_request(
+        &mut self,
+        peer_id: PeerId,
+        request_id: PeerRequestId,
+        request: StorageChallengeRequest,
+    ) {
+        debug!(?request, %peer_id, ?request_id, "Received StorageChallenge request");
+
+        if let Err(err) = self
+            .handle_storage_challenge_with_db_err(peer_id, request_id, request)
+            .await
+        {
+            error!(%err, "Failed to handle storage challenge due to db error");
+            self.ctx.send(NetworkMessage::SendErrorResponse {
+                peer_id,
+                id: request_id,
+                error: RPCResponseErrorCode::ServerError,
+                reason: "DB error".into(),
+            });
+        }
+    }
+
+    async fn handle_storage_challenge_with_db_err(
+        &mut self,
+        peer_id: PeerId,
+        request_id: PeerRequestId,
+        request: StorageChallengeRequest,
+    ) -> StorageResult<()> {
+        // tx may be not synced yet or reverted
+        let tx = match self.store.get_tx_by_seq_number(request.tx_id.seq).await? {
+            Some(tx) if tx.id() == request.tx_id => tx,
+            _ => {
+                self.ctx.send(NetworkMessage::SendErrorResponse {
+                    peer_id,
+                    error: RPCResponseErrorCode::InvalidRequest,
+                    reason: "Tx not found".into(),
+                    id: request_id,
+                });
+                return Ok(());
+            }
+        };
+
+        // ban peer if sector index out of bound
+        let num_sectors = bytes_to_chunks(tx.size as usize) as u64;
+        if request.sectors.iter().any(|&sector| sector >= num_sectors) {
+            self.ctx.ban_peer(peer_id, "Sector index out of bound");
+            return Ok(());
+        }
+
+        let mut proofs = Vec::with_capacity(request.sectors.len());
+        for &sector in request.sectors.iter() {
+            let result = self
+                .store
+                .get_chunks_with_proof_by_tx_and_index_range(
+                    tx.seq,
+                    sector as usize,
+                    sector as usize + 1,
+                    Some(tx.seq),
+                )
+                .await?;
+
+            match result {
+                Some(proof) => proofs.push(proof),
+                None => {
+                    self.ctx.send(NetworkMessage::SendErrorResponse {
+                        peer_id,
+                        error: RPCResponseErrorCode::InvalidRequest,
+                        reason: "Sectors not found".into(),
+                        id: request_id,
+                    });
+                    return Ok(());
+                }
+            }
+        }
+
+        self.ctx.send(NetworkMessage::SendResponse {
+            peer_id,
+            id: request_id,
+            response: network::Response::StorageChallenge(StorageChallengeResponse {
+                tx_id: request.tx_id,
+                proofs,
+            }),
+        });
+
+        Ok(())
+    }
+
+    async fn on_get_transactions_request(
+        &mut self,
+        peer_id: PeerId,
+        request_id: PeerRequestId,
+        request: GetTransactionsRequest,
+    ) {
+        debug!(?request, %peer_id, ?request_id, "Received GetTransactions request");
+
+        if let Err(err) = self
+            .handle_transactions_request_with_db_err(peer_id, request_id, request)
+            .await
+        {
+            error!(%err, "Failed to handle transactions request due to db error");
+            self.ctx.send(NetworkMessage::SendErrorResponse {
+                peer_id,
+                id: request_id,
+                error: RPCResponseErrorCode::ServerError,
+                reason: "DB error".into(),
+            });
+        }
+    }
+
+    async fn handle_transactions_request_with_db_err(
+        &mut self,
+        peer_id: PeerId,
+        request_id: PeerRequestId,
+        request: GetTransactionsRequest,
+    ) -> StorageResult<()> {
+        // ban peer if requested too many transactions
+        if request.count == 0 || request.count > MAX_REQUEST_TRANSACTIONS {
+            self.ctx.ban_peer(peer_id, "Invalid transactions count");
+            return Ok(());
+        }
+
+        // return transactions synced so far, which may be empty
+        let end_seq = cmp::min(
+            request.from_seq.saturating_add(request.count),
+            self.store.get_store().next_tx_seq(),
+        );
+
+        // prove all transactions against the flow root after the last one, so that the
+        // requester could check the root once all transactions are put
+        let mut transactions = vec![];
+        for seq in request.from_seq..end_seq {
+            match self
+                .store
+                .get_tx_with_proof_by_seq_number(seq, Some(end_seq - 1))
+                .await?
+            {
+                Some(tx) => transactions.push(tx),
+                None => break,
+            }
+        }
+
+        self.ctx.send(NetworkMessage::SendResponse {
+            peer_id,
+            id: request_id,
+            response: network::Response::Transactions(TransactionsResponse { transactions }),
+        });
+
+        Ok(())
+    }
+
+    fn is_pending_tx_request(&self, peer_id: &PeerId, from_seq: u64) -> bool {
+        matches!(self.pending_tx_request, Some((p, seq, _)) if p == *peer_id && seq == from_seq)
+    }
+
+    /// Requests transactions after the latest one in store from a random peer, if log sync
+    /// from blockchain falls behind the transactions submitted on chain. Prefetched
+    /// transactions are confirmed by log sync later.
+    async fn on_prefetch_heartbeat(&mut self) {
+        if !self.config.prefetch_transactions_enabled {
+            return;
+        }
+
+        if let Some((peer_id, from_seq, since)) = self.pending_tx_request {
+            if since.elapsed() < self.config.prefetch_transactions_timeout {
+                return;
+            }
+            debug!(%peer_id, %from_seq, "GetTransactions request timeout");
+            self.pending_tx_request = None;
+        }
+
+        let num_submissions = match &self.flow_reader {
+            Some(reader) => match reader.num_submissions().await {
+                Ok(num) => num,
+                Err(err) => {
+                    debug!(%err, "Failed to get the number of transactions on chain");
+                    return;
+                }
+            },
+            None => return,
+        };
+
+        // log sync is not behind
+        let from_seq = self.store.get_store().next_tx_seq();
+        if num_submissions <= from_seq {
+            return;
+        }
+
+        let peer_id = match self.connected_peers.iter().choose(&mut rand::thread_rng()) {
+            Some(peer_id) => *peer_id,
+            None => return,
+        };
+
+        self.ctx.send(NetworkMessage::SendRequest {
+            peer_id,
+            request_id: network::RequestId::Sync(
+                Instant::now(),
+                RequestId::Transactions { from_seq },
+            ),
+            request: network::Request::GetTransactions(GetTransactionsRequest {
+                from_seq,
+                count: cmp::min(num_submissions - from_seq, MAX_REQUEST_TRANSACTIONS),
+            }),
+        });
+
+        self.pending_tx_request = Some((peer_id, from_seq, Instant::now()));
+    }
+
+    async fn on_transactions_response(
+        &mut self,
+        peer_id: PeerId,
+        request_id: RequestId,
+        response: TransactionsResponse,
+    ) {
+        debug!(%peer_id, ?request_id, count = response.transactions.len(), "Received transactions response");
+
+        let from_seq = match request_id {
+            RequestId::Transactions { from_seq } => from_seq,
+            _ => unreachable!("Transactions response for file sync"),
+        };
+
+        if !self.is_pending_tx_request(&peer_id, from_seq) {
+            debug!(%peer_id, %from_seq, "Received unexpected transactions response");
+            return;
+        }
+        self.pending_tx_request = None;
+
+        let num_txs = response.transactions.len() as u64;
+        if num_txs == 0 {
+            return;
+        }
+
+        // the flow root on chain after the last transaction, against which all transactions
+        // must be proved
+        let last_seq = from_seq + num_txs - 1;
+        let flow_root = match &self.flow_reader {
+            Some(reader) => match reader.flow_root_by_tx_seq(last_seq).await {
+                Ok(root) => root,
+                Err(err) => {
+                    debug!(%err, %last_seq, "Failed to get the flow root on chain");
+                    return;
+                }
+            },
+            None => return,
+        };
+
+        // zero for transactions submitted before the contract upgrade, or not on chain yet
+        if flow_root.is_zero() {
+            debug!(%last_seq, "No flow root on chain to verify prefetched transactions");
+            return;
+        }
+
+        match self.put_prefetched_transactions(from_seq, response.transactions, flow_root) {
+            Ok(0) => {}
+            Ok(count) => debug!(%peer_id, %from_seq, %count, "Prefetched transactions from peer"),
+            Err(err) => {
+                warn!(%err, %peer_id, %from_seq, "Peer responded invalid transactions");
+                self.ctx.ban_peer(peer_id, "Invalid transactions");
+            }
+        }
+    }
+
+    /// Verifies the transactions against the given flow root on chain, and puts those not in
+    /// store yet. The flow root in store must be the same once the transactions are put,
+    /// otherwise the put transactions are reverted.
+    ///
+    /// Returns the number of transactions put in store.
+    fn put_prefetched_transactions(
+        &self,
+        from_seq: u64,
+        transactions: Vec<TransactionWithProof>,
+        flow_root: H256,
+    ) -> Result<usize> {
+        for (seq, tx) in (from_seq..).zip(transactions.iter()) {
+            if tx.transaction.seq != seq {
+                bail!("Invalid tx seq, expected = {}", seq);
+            }
+
+            let root = tx.validate()?;
+            if root != flow_root {
+                bail!(
+                    "Mismatched flow root, expected = {:?}, proved = {:?}",
+                    flow_root,
+                    root
+                );
+            }
+        }
+
+        if transactions.is_empty() {
+            return Ok(0);
+        }
+        let last_seq = from_seq + transactions.len() as u64 - 1;
+
+        let store = self.store.get_store();
+        let mut first_put_seq = None;
+        let mut num_put = 0;
+        for tx in transactions.into_iter().map(|tx| tx.transaction) {
+            let seq = tx.seq;
+            if seq < store.next_tx_seq() {
+                // synced from blockchain or prefetched before
+                match store.get_tx_by_seq_number(seq)? {
+                    Some(local) if local != tx => {
+                        bail!("Mismatched with tx in store, seq = {}", seq)
+                    }
+                    _ => continue,
+                }
+            }
+
+            // the transaction may be put by log sync meanwhile
+            if let Err(err) = store.put_tx(tx) {
+                debug!(%err, %seq, "Failed to put prefetched transaction");
+                break;
+            }
+            first_put_seq.get_or_insert(seq);
+            num_put += 1;
+        }
+
+        // the flow root could only be checked against the latest version in store
+        if store.next_tx_seq() != last_seq + 1 {
+            return Ok(num_put);
+        }
+
+        let (local_root, _) = store.get_context()?;
+        if local_root != flow_root {
+            if let Some(seq) = first_put_seq {
+                store.revert_to(seq.wrapping_sub(1))?;
+            }
+            bail!(
+                "Mismatched flow root in store, expected = {:?}, local = {:?}",
+                flow_root,
+                local_root
+            );
+        }
+
+        Ok(num_put)
+    }
+
+    fn on_storage_challenge_response(
+        &mut self,
+        peer_id: PeerId,
+        request_id: RequestId,
+        response: StorageChallengeResponse,
+    ) {
+        debug!(%peer_id, ?request_id, "Received storage challenge response");
+
+        let tx_id = match request_id {
+            RequestId::StorageChallenge { tx_id } => tx_id,
+            _ => unreachable!("Storage challenge response for file sync"),
+        };
+
+        let challenge = match self.challenges.complete(&peer_id, &tx_id) {
+            Some(challenge) => challenge,
+            None => {
+                debug!(%peer_id, tx_seq = tx_id.seq, "Received unexpected storage challenge response");
+                return;
+            }
+        };
+
+        match self.verify_storage_challenge(&challenge, &response) {
+            Ok(true) => {
+                debug!(%peer_id, tx_seq = tx_id.seq, "Peer passed storage challenge");
+                self.challenges.pass(peer_id);
+                if let Some(ranges) = challenge.ranges {
+                    self.file_location_cache.insert_ranges(ranges);
+                }
+            }
+            Ok(false) => {
+                // occurs when remote peer has higher block height
+                info!(%peer_id, tx_seq = tx_id.seq, "Failed to verify storage challenge due to no root found");
+            }
+            Err(err) => {
+                warn!(%err, %peer_id, tx_seq = tx_id.seq, "Peer failed storage challenge");
+                self.remove_file_location(&tx_id, &peer_id);
+                self.ctx.ban_peer(peer_id, "Storage challenge failed");
+            }
+        }
+    }
+
+    /// Verifies that the response contains the challenged sectors with valid proofs.
+    fn verify_storage_challenge(
+        &self,
+        challenge: &Challenge,
+        response: &StorageChallengeResponse,
+    ) -> Result<bool> {
+        let sectors = &challenge.sectors;
+        if response.tx_id != challenge.tx_id {
+            bail!("Invalid tx id, expected = {:?}", challenge.tx_id);
+        }
 
-        match result {
-            Some(chunks) => {
-                self.ctx.send(NetworkMessage::SendResponse {
-                    peer_id,
-                    id: request_id,
-                    response: network::Response::Chunks(chunks),
-                });
+        if response.proofs.len() != sectors.len() {
+            bail!(
+                "Invalid number of sectors, expected = {}, actual = {}",
+                sectors.len(),
+                response.proofs.len()
+            );
+        }
+
+        for (&sector, proof) in sectors.iter().zip(response.proofs.iter()) {
+            if proof.chunks.start_index != sector || proof.chunks.data.len() != CHUNK_SIZE {
+                bail!("Invalid sector, expected = {}", sector);
             }
-            None => {
-                // file may be removed during downloading
-                warn!(%request.tx_id.seq, "Failed to handle chunks request due to chunks not found");
-                self.ctx.send(NetworkMessage::SendErrorResponse {
-                    peer_id,
-                    error: RPCResponseErrorCode::InvalidRequest,
-                    reason: "Chunks not found".into(),
-                    id: request_id,
-                });
+
+            if !self
+                .store
+                .get_store()
+                .validate_range_proof(challenge.tx_id.seq, proof)?
+            {
+                return Ok(false);
             }
         }
 
-        Ok(())
+        Ok(true)
     }
 
-    async fn on_chunks_response(
+    /// Challenges the peer that announced a file to prove that it really stores the file, unless
+    /// the peer has been challenged recently.
+    ///
+    /// The range announcement of the peer, if any, is accepted once the challenge passed.
+    async fn try_challenge_storage(
         &mut self,
+        tx_id: TxID,
         peer_id: PeerId,
-        request_id: RequestId,
-        response: ChunkArrayWithProof,
+        ranges: Option<SignedAnnounceFileRanges>,
     ) {
-        debug!(%response.chunks, %peer_id, ?request_id, "Received chunks response");
+        if !self.connected_peers.contains(&peer_id)
+            || !self
+                .challenges
+                .can_challenge(&peer_id, self.config.storage_challenge_interval)
+        {
+            return;
+        }
 
-        let tx_seq = match request_id {
-            RequestId::SerialSync { tx_id } => tx_id.seq,
+        let shard_config = match self.file_location_cache.get_peer_config(&peer_id) {
+            Some(config) => config,
+            None => return,
         };
 
-        match self.controllers.get_mut(&tx_seq) {
-            Some(controller) => {
-                controller.on_response(peer_id, response).await;
-                controller.transition();
-            }
-            None => {
-                warn!("Received chunks response for non-existent controller tx_seq={tx_seq}");
+        let tx = match self.store.get_tx_by_seq_number(tx_id.seq).await {
+            Ok(Some(tx)) if tx.id() == tx_id => tx,
+            Ok(_) => return,
+            Err(err) => {
+                error!(%err, tx_seq = tx_id.seq, "Failed to get tx");
+                return;
             }
+        };
+
+        let count = cmp::min(
+            self.config.storage_challenge_sectors,
+            MAX_CHALLENGE_SECTORS as usize,
+        );
+        let sectors = select_sectors(
+            tx.start_entry_index,
+            bytes_to_chunks(tx.size as usize) as u64,
+            &shard_config,
+            count,
+        );
+        if sectors.is_empty() {
+            return;
         }
+
+        debug!(%peer_id, tx_seq = tx_id.seq, ?sectors, "Challenge peer to prove file storage");
+
+        self.ctx.send(NetworkMessage::SendRequest {
+            peer_id,
+            request_id: network::RequestId::Sync(
+                Instant::now(),
+                RequestId::StorageChallenge { tx_id },
+            ),
+            request: network::Request::StorageChallenge(StorageChallengeRequest {
+                tx_id,
+                sectors: sectors.clone().into(),
+            }),
+        });
+
+        self.challenges.start(peer_id, tx_id, sectors, ranges);
     }
 
-    fn on_rpc_error(&mut self, peer_id: PeerId, request_id: RequestId) {
-        info!(%peer_id, ?request_id, "Received RPC error");
+    /// Accepts the range announcement of a peer if the peer passed a storage challenge recently,
+    /// or otherwise challenges the peer on a random file in the announced ranges first.
+    async fn on_announce_file_ranges_gossip(&mut self, msg: SignedAnnounceFileRanges) {
+        let peer_id: PeerId = msg.peer_id.clone().into();
+        trace!(%peer_id, num_ranges = msg.ranges.len(), "Received AnnounceFileRanges gossip");
 
-        let tx_seq = match request_id {
-            RequestId::SerialSync { tx_id } => tx_id.seq,
+        if !self.config.storage_challenge_enabled
+            || self
+                .challenges
+                .is_verified(&peer_id, self.config.storage_challenge_interval)
+        {
+            self.file_location_cache.insert_ranges(msg);
+            return;
+        }
+
+        let next_tx_seq = self.store.get_store().next_tx_seq();
+        let tx_seq = match select_range_tx_seq(&msg.ranges, next_tx_seq) {
+            Some(tx_seq) => tx_seq,
+            None => return,
         };
 
-        match self.controllers.get_mut(&tx_seq) {
-            Some(controller) => {
-                controller.on_request_failed(peer_id);
-                controller.transition();
+        let tx_id = match self.store.get_tx_by_seq_number(tx_seq).await {
+            Ok(Some(tx)) => tx.id(),
+            Ok(None) => return,
+            Err(err) => {
+                error!(%err, %tx_seq, "Failed to get tx");
+                return;
             }
-            None => {
-                warn!("Received rpc error for non-existent controller tx_seq={tx_seq}");
+        };
+
+        // the announced shard config is required to select sectors to challenge
+        if let Ok(shard_config) = ShardConfig::try_from(msg.shard_config) {
+            self.file_location_cache
+                .insert_peer_config(peer_id, shard_config);
+        }
+
+        self.try_challenge_storage(tx_id, peer_id, Some(msg)).await;
+    }
+
+    /// Removes the peer from file location cache, including its announcement in tx_seq ranges.
+    fn remove_file_location(&self, tx_id: &TxID, peer_id: &PeerId) {
+        self.file_location_cache.remove(tx_id, peer_id);
+        self.file_location_cache.remove_ranges(peer_id);
+    }
+
+    fn on_challenge_heartbeat(&mut self) {
+        let timed_out = self.challenges.garbage_collect(
+            self.config.storage_challenge_timeout,
+            self.config.storage_challenge_interval,
+        );
+
+        for (peer_id, tx_id) in timed_out {
+            info!(%peer_id, tx_seq = tx_id.seq, "Storage challenge timeout");
+            self.remove_file_location(&tx_id, &peer_id);
+        }
+    }
+
+    /// Starts to rebuild a batch in shard of this node from erasure pieces of peers.
+    async fn on_rebuild_batch(&mut self, batch_index: u64) -> Result<()> {
+        let erasure_config = match self.store.get_store().get_erasure_config() {
+            Some(config) => config,
+            None => bail!("Erasure coding disabled"),
+        };
+
+        if batch_index == 0
+            || !self
+                .store
+                .get_store()
+                .get_shard_config()
+                .in_range(batch_index)
+        {
+            bail!("Batch not in shard");
+        }
+
+        if let Some(controller) = self.erasure_controllers.get(&batch_index) {
+            if !controller.is_completed_or_failed() {
+                bail!("Batch already in rebuild");
             }
         }
+
+        info!(%batch_index, "Start to rebuild batch from erasure pieces");
+
+        let mut controller = ErasureSyncController::new(
+            self.config,
+            batch_index,
+            ErasureSyncGoal::RebuildBatch,
+            erasure_config,
+            self.ctx.clone(),
+            self.store.clone(),
+            self.file_location_cache.clone(),
+        );
+        controller.transition(&self.connected_peers);
+
+        if let ErasureSyncState::Failed { reason } = controller.get_status() {
+            bail!("{}", reason);
+        }
+
+        self.erasure_controllers.insert(batch_index, controller);
+
+        Ok(())
     }
 
     async fn on_sync_file_request(
@@ -698,6 +1538,10 @@ impl SyncService {
         let tx_seq = tx_id.seq;
         trace!(%tx_seq, %peer_id, %addr, "Received AnnounceFile gossip");
 
+        if self.config.storage_challenge_enabled {
+            self.try_challenge_storage(tx_id, peer_id, None).await;
+        }
+
         if let Some(manager) = &self.auto_sync_manager {
             let _ = manager.file_announcement_send.send(tx_seq);
         }
@@ -836,6 +1680,112 @@ impl SyncService {
         }
     }
 
+    /// Drives the erasure piece sync controllers, and starts to fetch the missing erasure
+    /// pieces of this node in sequence, while the failed batches are retried with backoff.
+    async fn on_erasure_heartbeat(&mut self) {
+        let connected_peers = &self.connected_peers;
+        let retry_interval = self.config.erasure_sync_retry_interval;
+        let erasure_retries = &mut self.erasure_retries;
+        self.erasure_controllers.retain(|&batch_index, controller| {
+            controller.transition(connected_peers);
+
+            match controller.get_status() {
+                ErasureSyncState::Requesting { .. } => true,
+                ErasureSyncState::Completed => {
+                    erasure_retries.remove(batch_index);
+                    false
+                }
+                ErasureSyncState::Failed { reason } => {
+                    debug!(%batch_index, %reason, "Failed to sync erasure pieces");
+                    erasure_retries.on_failed(batch_index, retry_interval);
+                    false
+                }
+            }
+        });
+
+        if let Err(err) = self.start_erasure_piece_sync().await {
+            error!(%err, "Failed to start erasure piece sync");
+        }
+    }
+
+    async fn start_erasure_piece_sync(&mut self) -> Result<()> {
+        let erasure_config = match self.store.get_store().get_erasure_config() {
+            Some(config) => config,
+            None => return Ok(()),
+        };
+        let shard_config = self.store.get_store().get_shard_config();
+
+        // only complete batches are erasure coded
+        let (_, flow_length) = self.store.get_store().get_context()?;
+        let num_batches = flow_length / PORA_CHUNK_SIZE as u64;
+
+        for batch_index in self.erasure_retries.ready() {
+            if self.erasure_controllers.len() >= self.config.max_erasure_sync_batches {
+                return Ok(());
+            }
+
+            if batch_index >= num_batches
+                || !self
+                    .start_erasure_batch_sync(batch_index, &erasure_config, &shard_config)
+                    .await?
+            {
+                self.erasure_retries.remove(batch_index);
+            }
+        }
+
+        while self.erasure_controllers.len() < self.config.max_erasure_sync_batches
+            && self.next_erasure_batch < num_batches
+        {
+            let batch_index = self.next_erasure_batch;
+            self.next_erasure_batch += 1;
+            self.start_erasure_batch_sync(batch_index, &erasure_config, &shard_config)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Starts to fetch the erasure piece of this node for the batch if missing. Returns `false`
+    /// if no piece is missing.
+    async fn start_erasure_batch_sync(
+        &mut self,
+        batch_index: u64,
+        erasure_config: &ErasureConfig,
+        shard_config: &ShardConfig,
+    ) -> Result<bool> {
+        if self.erasure_controllers.contains_key(&batch_index) {
+            return Ok(true);
+        }
+
+        let piece_index = match local_piece_index(erasure_config, shard_config, batch_index) {
+            Some(index) => index,
+            None => return Ok(false),
+        };
+
+        if self
+            .store
+            .get_erasure_piece(batch_index, piece_index)
+            .await?
+            .is_some()
+        {
+            return Ok(false);
+        }
+
+        let mut controller = ErasureSyncController::new(
+            self.config,
+            batch_index,
+            ErasureSyncGoal::FetchPiece(piece_index),
+            *erasure_config,
+            self.ctx.clone(),
+            self.store.clone(),
+            self.file_location_cache.clone(),
+        );
+        controller.transition(&self.connected_peers);
+        self.erasure_controllers.insert(batch_index, controller);
+
+        Ok(true)
+    }
+
     async fn tx_sync_start_index(store: &Store, tx: &Transaction) -> Result<Option<u64>> {
         let shard_config = store.get_store().get_shard_config();
         let start_segment = sector_to_segment(tx.start_entry_index());
@@ -966,6 +1916,8 @@ mod tests {
                 config,
                 self.runtime.task_executor.clone(),
                 self.network_send.clone(),
+                None,
+                None,
                 store,
                 self.file_location_cache.clone(),
                 self.event_send.subscribe(),
@@ -998,6 +1950,13 @@ mod tests {
             store,
             file_location_cache,
             controllers: Default::default(),
+            erasure_controllers: Default::default(),
+            next_erasure_batch: 1,
+            erasure_retries: Default::default(),
+            connected_peers: Default::default(),
+            challenges: Default::default(),
+            pending_tx_request: None,
+            flow_reader: None,
             auto_sync_manager: None,
         };
 
@@ -1027,6 +1986,13 @@ mod tests {
             store,
             file_location_cache,
             controllers: Default::default(),
+            erasure_controllers: Default::default(),
+            next_erasure_batch: 1,
+            erasure_retries: Default::default(),
+            connected_peers: Default::default(),
+            challenges: Default::default(),
+            pending_tx_request: None,
+            flow_reader: None,
             auto_sync_manager: None,
         };
 
@@ -1343,6 +2309,8 @@ mod tests {
             Config::default(),
             runtime.task_executor.clone(),
             network_send,
+            None,
+            None,
             store.clone(),
             file_location_cache,
             event_recv,
@@ -1599,6 +2567,305 @@ mod tests {
         assert!(runtime.network_recv.try_recv().is_err());
     }
 
+    #[tokio::test]
+    async fn test_request_storage_challenge() {
+        let mut runtime = TestSyncRuntime::default();
+        let sync_send = runtime.spawn_sync_service(true).await;
+
+        let tx_id = runtime.txs[0].id();
+        let sectors = vec![0, 100, runtime.chunk_count as u64 - 1];
+        sync_send
+            .notify(SyncMessage::RequestStorageChallenge {
+                request_id: (ConnectionId::new(0), SubstreamId(0)),
+                peer_id: runtime.init_peer_id,
+                request: StorageChallengeRequest {
+                    tx_id,
+                    sectors: sectors.clone().into(),
+                },
+            })
+            .unwrap();
+
+        match runtime.network_recv.recv().await {
+            Some(NetworkMessage::SendResponse {
+                peer_id,
+                response: network::Response::StorageChallenge(response),
+                ..
+            }) => {
+                assert_eq!(peer_id, runtime.init_peer_id);
+                assert_eq!(response.tx_id, tx_id);
+                assert_eq!(response.proofs.len(), sectors.len());
+
+                for (sector, proof) in sectors.into_iter().zip(response.proofs.iter()) {
+                    assert_eq!(proof.chunks.start_index, sector);
+                    runtime
+                        .peer_store
+                        .validate_range_proof(0, proof)
+                        .expect("validate proof");
+                }
+            }
+            _ => panic!("Not expected message: Response::StorageChallenge"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_storage_challenge_index_out_bound() {
+        let mut runtime = TestSyncRuntime::default();
+        let sync_send = runtime.spawn_sync_service(true).await;
+
+        sync_send
+            .notify(SyncMessage::RequestStorageChallenge {
+                request_id: (ConnectionId::new(0), SubstreamId(0)),
+                peer_id: runtime.init_peer_id,
+                request: StorageChallengeRequest {
+                    tx_id: runtime.txs[0].id(),
+                    sectors: vec![0, runtime.chunk_count as u64].into(),
+                },
+            })
+            .unwrap();
+
+        match runtime.network_recv.recv().await {
+            Some(NetworkMessage::ReportPeer {
+                peer_id, action, ..
+            }) => {
+                assert_eq!(peer_id, runtime.init_peer_id);
+                assert!(matches!(action, network::PeerAction::Fatal));
+            }
+            _ => panic!("Not expected message: NetworkMessage::ReportPeer"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_transactions() {
+        let mut runtime = TestSyncRuntime::new(vec![1023, 2048], 1);
+        let sync_send = runtime.spawn_sync_service(true).await;
+
+        sync_send
+            .notify(SyncMessage::RequestTransactions {
+                request_id: (ConnectionId::new(0), SubstreamId(0)),
+                peer_id: runtime.init_peer_id,
+                request: GetTransactionsRequest {
+                    from_seq: 0,
+                    count: MAX_REQUEST_TRANSACTIONS,
+                },
+            })
+            .unwrap();
+
+        match runtime.network_recv.recv().await {
+            Some(NetworkMessage::SendResponse {
+                peer_id,
+                response: network::Response::Transactions(response),
+                ..
+            }) => {
+                assert_eq!(peer_id, runtime.init_peer_id);
+                assert_eq!(response.transactions.len(), runtime.txs.len());
+
+                let (flow_root, _) = runtime.peer_store.get_context().unwrap();
+                for (tx, tx_with_proof) in runtime.txs.iter().zip(response.transactions.iter()) {
+                    assert_eq!(&tx_with_proof.transaction, tx);
+                    assert_eq!(tx_with_proof.validate().unwrap(), flow_root);
+                }
+            }
+            _ => panic!("Not expected message: Response::Transactions"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_transactions_invalid_count() {
+        let mut runtime = TestSyncRuntime::default();
+        let sync_send = runtime.spawn_sync_service(true).await;
+
+        sync_send
+            .notify(SyncMessage::RequestTransactions {
+                request_id: (ConnectionId::new(0), SubstreamId(0)),
+                peer_id: runtime.init_peer_id,
+                request: GetTransactionsRequest {
+                    from_seq: 0,
+                    count: MAX_REQUEST_TRANSACTIONS + 1,
+                },
+            })
+            .unwrap();
+
+        match runtime.network_recv.recv().await {
+            Some(NetworkMessage::ReportPeer {
+                peer_id, action, ..
+            }) => {
+                assert_eq!(peer_id, runtime.init_peer_id);
+                assert!(matches!(action, network::PeerAction::Fatal));
+            }
+            _ => panic!("Not expected message: NetworkMessage::ReportPeer"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_put_prefetched_transactions() {
+        let runtime = TestRuntime::default();
+        let (_, peer_store, txs, _) = create_2_store(vec![1023, 2048]);
+        let store = Arc::new(LogManager::memorydb(LogConfig::default()).unwrap());
+
+        let (network_send, _network_recv) = new_network_channel();
+        let (_, sync_recv) = channel::Channel::unbounded("test");
+        let sync = SyncService {
+            config: Config::default(),
+            msg_recv: sync_recv,
+            ctx: Arc::new(SyncNetworkContext::new(network_send)),
+            store: Store::new(store.clone(), runtime.task_executor.clone()),
+            file_location_cache: Arc::new(FileLocationCache::default()),
+            controllers: Default::default(),
+            erasure_controllers: Default::default(),
+            next_erasure_batch: 1,
+            erasure_retries: Default::default(),
+            connected_peers: Default::default(),
+            challenges: Default::default(),
+            pending_tx_request: None,
+            flow_reader: None,
+            auto_sync_manager: None,
+        };
+
+        let last_seq = txs.len() as u64 - 1;
+        let transactions: Vec<_> = txs
+            .iter()
+            .map(|tx| {
+                peer_store
+                    .get_tx_with_proof_by_seq_number(tx.seq, Some(last_seq))
+                    .unwrap()
+                    .unwrap()
+            })
+            .collect();
+        // flow root on chain after the last transaction
+        let (flow_root, _) = peer_store.get_context().unwrap();
+
+        // invalid seq
+        let mut tampered = transactions.clone();
+        tampered[1].transaction.seq += 1;
+        assert!(sync
+            .put_prefetched_transactions(0, tampered, flow_root)
+            .is_err());
+        assert!(sync
+            .put_prefetched_transactions(1, transactions.clone(), flow_root)
+            .is_err());
+        assert_eq!(store.next_tx_seq(), 0);
+
+        // not proved against the flow root on chain
+        assert!(sync
+            .put_prefetched_transactions(0, transactions.clone(), H256::random())
+            .is_err());
+        assert_eq!(store.next_tx_seq(), 0);
+
+        // proved against different flow roots
+        let mut tampered = transactions.clone();
+        tampered[0] = peer_store
+            .get_tx_with_proof_by_seq_number(0, Some(0))
+            .unwrap()
+            .unwrap();
+        assert!(sync
+            .put_prefetched_transactions(0, tampered, flow_root)
+            .is_err());
+        assert_eq!(store.next_tx_seq(), 0);
+
+        // valid transactions are put in store
+        assert_eq!(
+            sync.put_prefetched_transactions(0, transactions.clone(), flow_root)
+                .unwrap(),
+            txs.len()
+        );
+        assert_eq!(store.next_tx_seq(), txs.len() as u64);
+        assert_eq!(
+            store.get_context().unwrap(),
+            peer_store.get_context().unwrap()
+        );
+        for tx in txs.iter() {
+            assert_eq!(&store.get_tx_by_seq_number(tx.seq).unwrap().unwrap(), tx);
+        }
+
+        // transactions already in store are skipped
+        assert_eq!(
+            sync.put_prefetched_transactions(0, transactions, flow_root)
+                .unwrap(),
+            0
+        );
+        assert_eq!(
+            sync.put_prefetched_transactions(0, vec![], flow_root)
+                .unwrap(),
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn test_storage_challenge_failed() {
+        let mut runtime = TestSyncRuntime::default();
+        let config = Config {
+            storage_challenge_enabled: true,
+            ..Default::default()
+        };
+        let sync_send = runtime.spawn_sync_service_with_config(false, config).await;
+
+        let tx_id = runtime.txs[0].id();
+        let peer_id = runtime.init_peer_id;
+        sync_send
+            .notify(SyncMessage::PeerConnected { peer_id })
+            .unwrap();
+        sync_send
+            .notify(SyncMessage::AnnounceFileGossip {
+                tx_id,
+                peer_id,
+                addr: "/ip4/127.0.0.1/tcp/10000".parse().unwrap(),
+            })
+            .unwrap();
+
+        let (request_id, sectors) = match runtime.network_recv.recv().await {
+            Some(NetworkMessage::SendRequest {
+                peer_id: to,
+                request_id: network::RequestId::Sync(_, request_id),
+                request: network::Request::StorageChallenge(request),
+            }) => {
+                assert_eq!(to, peer_id);
+                assert_eq!(request.tx_id, tx_id);
+                (request_id, request.sectors.to_vec())
+            }
+            _ => panic!("Not expected message: Request::StorageChallenge"),
+        };
+
+        // respond with tampered sectors
+        let proofs = sectors
+            .into_iter()
+            .map(|sector| {
+                let mut proof = runtime
+                    .peer_store
+                    .get_chunks_with_proof_by_tx_and_index_range(
+                        0,
+                        sector as usize,
+                        sector as usize + 1,
+                        Some(0),
+                    )
+                    .unwrap()
+                    .unwrap();
+                proof.chunks.data[0] ^= 1;
+                proof
+            })
+            .collect();
+        sync_send
+            .notify(SyncMessage::StorageChallengeResponse {
+                peer_id,
+                request_id,
+                response: StorageChallengeResponse { tx_id, proofs },
+            })
+            .unwrap();
+
+        match runtime.network_recv.recv().await {
+            Some(NetworkMessage::ReportPeer {
+                peer_id: reported,
+                action,
+                ..
+            }) => {
+                assert_eq!(reported, peer_id);
+                assert!(matches!(action, network::PeerAction::Fatal));
+            }
+            _ => panic!("Not expected message: NetworkMessage::ReportPeer"),
+        }
+
+        assert!(runtime.file_location_cache.get_all(tx_id).is_empty());
+    }
+
     #[tokio::test]
     async fn test_announce_file() {
         let mut runtime = TestSyncRuntime::new(vec![1023], 0);
@@ -3,7 +3,7 @@ use shared_types::{compute_padded_chunk_size, ChunkArray, Transaction, CHUNK_SIZ
 use std::{cmp, sync::Arc};
 use storage::{
     log_store::{
-        log_manager::{sub_merkle_tree, tx_subtree_root_list_padded, LogConfig, PORA_CHUNK_SIZE},
+        log_manager::{tx_subtree_root_list_padded, LogConfig, PORA_CHUNK_SIZE},
         LogStoreChunkWrite, LogStoreWrite,
     },
     LogManager,
@@ -61,11 +61,10 @@ fn generate_data(
         (offset / first_tree_size + 1) * first_tree_size
     };
 
-    let merkle = sub_merkle_tree(&data).unwrap();
     let tx = Transaction {
         stream_ids: vec![],
         size: data_size as u64,
-        data_merkle_root: merkle.root().into(),
+        data_merkle_root: Transaction::data_root_of_nodes(&merkel_nodes).unwrap(),
         seq,
         data: vec![],
         start_entry_index: start_offset,